@@ -0,0 +1,80 @@
+/*
+The runtime classes (RIMPInt, RIMPFloat, RIMPBigInt) are shipped precompiled in the default package.
+When a program is compiled into a package, they need to move with it, so we rewrite the
+class name in their constant pool rather than requiring a java compiler at build time.
+
+Only CONSTANT_Utf8 entries are changed, any entry that is exactly the old internal name
+is replaced, as are field/method descriptors referring to it (L<old>;).
+*/
+
+fn read_u16(bytes: &[u8], index: usize) -> Result<u16, String> {
+    match bytes.get(index..index + 2) {
+        Some(slice) => Ok(u16::from_be_bytes([slice[0], slice[1]])),
+        None => Err(String::from("Unexpected end of class file")),
+    }
+}
+
+pub fn rename_class(class_file: &[u8], old_name: &str, new_name: &str) -> Result<Vec<u8>, String> {
+    if class_file.get(0..4) != Some(&[0xCA, 0xFE, 0xBA, 0xBE]) {
+        return Err(String::from("Not a class file"));
+    }
+
+    // magic, minor and major version come before the constant pool count
+    let constant_pool_count = read_u16(class_file, 8)? as usize;
+
+    let old_descriptor = format!("L{};", old_name);
+    let new_descriptor = format!("L{};", new_name);
+
+    let mut output = class_file[0..10].to_vec();
+    let mut index = 10;
+    let mut entry = 1;
+
+    while entry < constant_pool_count {
+        let tag = *class_file.get(index).ok_or("Unexpected end of class file")?;
+
+        let size = match tag {
+            1 => {
+                let length = read_u16(class_file, index + 1)? as usize;
+                let bytes = class_file
+                    .get(index + 3..index + 3 + length)
+                    .ok_or("Unexpected end of class file")?;
+
+                // class files use modified utf8, but class names here are plain ascii
+                let value = String::from_utf8_lossy(bytes);
+                let value = if value == old_name {
+                    new_name.to_string()
+                } else if value.contains(&old_descriptor) {
+                    value.replace(&old_descriptor, &new_descriptor)
+                } else {
+                    value.to_string()
+                };
+
+                output.push(1);
+                output.extend_from_slice(&(value.len() as u16).to_be_bytes());
+                output.extend_from_slice(value.as_bytes());
+
+                index += 3 + length;
+                entry += 1;
+                continue;
+            }
+            7 | 8 | 16 | 19 | 20 => 3,
+            15 => 4,
+            3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 5,
+            5 | 6 => 9,
+            _ => return Err(format!("Unknown constant pool tag {}", tag)),
+        };
+
+        let bytes = class_file
+            .get(index..index + size)
+            .ok_or("Unexpected end of class file")?;
+        output.extend_from_slice(bytes);
+        index += size;
+
+        // longs and doubles take up two entries in the constant pool
+        entry += if tag == 5 || tag == 6 { 2 } else { 1 };
+    }
+
+    output.extend_from_slice(&class_file[index..]);
+
+    Ok(output)
+}
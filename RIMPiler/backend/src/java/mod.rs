@@ -1,4 +1,9 @@
-use std::path::Path;
+mod class_file;
+mod zip;
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
 
 fn find_kratatau2() -> Option<String> {
     let mut path = std::env::current_exe().unwrap();
@@ -83,22 +88,39 @@ fn find_kratatau2() -> Option<String> {
     }
 }
 
-pub fn assemble_byte_code(byte_code: String, output_folder: String) {
+// the folder within the output folder that classes in the given package are written to
+fn package_folder(output_folder: &Path, package: Option<&str>) -> PathBuf {
+    match package {
+        Some(package) => package.split('.').fold(output_folder.to_path_buf(), |path, part| path.join(part)),
+        None => output_folder.to_path_buf(),
+    }
+}
+
+fn internal_name(class_name: &str, package: Option<&str>) -> String {
+    match package {
+        Some(package) => format!("{}/{}", package.replace('.', "/"), class_name),
+        None => class_name.to_string(),
+    }
+}
+
+pub fn assemble_byte_code(byte_code: String, output_folder: String, class_name: &str, package: Option<&str>) {
     std::fs::create_dir_all(&output_folder).expect("Unable to create directory");
     let output_folder = Path::new(&output_folder).canonicalize().expect("Unable to canonicalize output folder path");
 
-    if !output_folder.exists() {
-        std::fs::create_dir_all(&output_folder)
+    let class_folder = package_folder(&output_folder, package);
+
+    if !class_folder.exists() {
+        std::fs::create_dir_all(&class_folder)
             .expect("Unable to create output directory");
     }
 
-    let mut temp_file = output_folder.to_path_buf();
-    temp_file.push("Main.j");
+    let mut temp_file = class_folder.to_path_buf();
+    temp_file.push(format!("{}.j", class_name));
     std::fs::write(&temp_file, &byte_code).expect("Unable to write file");
 
     let krak2 = find_kratatau2().expect("Unable to find krak2");
 
-    let output_file = output_folder.join("Main.class");
+    let output_file = class_folder.join(format!("{}.class", class_name));
     let output = std::process::Command::new(krak2)
         .arg("asm")
         .arg("--out")
@@ -113,10 +135,66 @@ pub fn assemble_byte_code(byte_code: String, output_folder: String) {
 
     let rimp_int = include_bytes!("../../../../data/RIMPInt.class");
     let rimp_float = include_bytes!("../../../../data/RIMPFloat.class");
+    let rimp_big_int = include_bytes!("../../../../data/RIMPBigInt.class");
+
+    let rimp_int = class_file::rename_class(rimp_int, "RIMPInt", &internal_name("RIMPInt", package))
+        .expect("Unable to move RIMPInt into package");
+    let rimp_float = class_file::rename_class(rimp_float, "RIMPFloat", &internal_name("RIMPFloat", package))
+        .expect("Unable to move RIMPFloat into package");
+    let rimp_big_int = class_file::rename_class(rimp_big_int, "RIMPBigInt", &internal_name("RIMPBigInt", package))
+        .expect("Unable to move RIMPBigInt into package");
 
-    let rimp_int_file = output_folder.join("RIMPInt.class");
+    let rimp_int_file = class_folder.join("RIMPInt.class");
     std::fs::write(&rimp_int_file, rimp_int).expect("Unable to write RIMPInt.class file");
 
-    let rimp_float_file = output_folder.join("RIMPFloat.class");
+    let rimp_float_file = class_folder.join("RIMPFloat.class");
     std::fs::write(&rimp_float_file, rimp_float).expect("Unable to write RIMPFloat.class file");
+
+    let rimp_big_int_file = class_folder.join("RIMPBigInt.class");
+    std::fs::write(&rimp_big_int_file, rimp_big_int).expect("Unable to write RIMPBigInt.class file");
+}
+
+// Writes transpiled java source to <output_folder>/<package folders>/<class_name>.java
+pub fn write_java_source(source: String, output_folder: String, class_name: &str, package: Option<&str>) -> PathBuf {
+    std::fs::create_dir_all(&output_folder).expect("Unable to create directory");
+    let output_folder = Path::new(&output_folder).canonicalize().expect("Unable to canonicalize output folder path");
+
+    let class_folder = package_folder(&output_folder, package);
+    std::fs::create_dir_all(&class_folder).expect("Unable to create output directory");
+
+    let source_file = class_folder.join(format!("{}.java", class_name));
+    std::fs::write(&source_file, source).expect("Unable to write java source file");
+
+    source_file
+}
+
+// Packages the classes written by assemble_byte_code into <output_folder>/<class_name>.jar,
+// with a manifest so that it can be run with java -jar
+pub fn write_jar(output_folder: String, class_name: &str, package: Option<&str>) -> PathBuf {
+    let output_folder = Path::new(&output_folder).canonicalize().expect("Unable to canonicalize output folder path");
+    let class_folder = package_folder(&output_folder, package);
+
+    let main_class = match package {
+        Some(package) => format!("{}.{}", package, class_name),
+        None => class_name.to_string(),
+    };
+
+    let manifest = format!(
+        "Manifest-Version: 1.0\r\nMain-Class: {}\r\nCreated-By: RIMPiler\r\n\r\n",
+        main_class
+    );
+
+    let mut jar = zip::ZipWriter::new();
+    jar.add_file("META-INF/MANIFEST.MF", manifest.as_bytes());
+
+    for class in [class_name, "RIMPInt", "RIMPFloat", "RIMPBigInt"] {
+        let class_file = class_folder.join(format!("{}.class", class));
+        let bytes = std::fs::read(&class_file).expect("Unable to read class file");
+        jar.add_file(&format!("{}.class", internal_name(class, package)), &bytes);
+    }
+
+    let jar_file = output_folder.join(format!("{}.jar", class_name));
+    std::fs::write(&jar_file, jar.finish()).expect("Unable to write jar file");
+
+    jar_file
 }
@@ -0,0 +1,49 @@
+use super::class_file::rename_class;
+use super::zip::{crc32, ZipWriter};
+
+#[test]
+fn crc() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn zip_layout() {
+    let mut zip = ZipWriter::new();
+    zip.add_file("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\r\n\r\n");
+    zip.add_file("Main.class", &[0xCA, 0xFE, 0xBA, 0xBE]);
+    let bytes = zip.finish();
+
+    // local file header for the first entry
+    assert_eq!(&bytes[0..4], &[0x50, 0x4b, 0x03, 0x04]);
+    assert_eq!(&bytes[30..50], b"META-INF/MANIFEST.MF");
+
+    // end of central directory record
+    let end = &bytes[bytes.len() - 22..];
+    assert_eq!(&end[0..4], &[0x50, 0x4b, 0x05, 0x06]);
+    assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+
+    let central_directory_size = u32::from_le_bytes([end[12], end[13], end[14], end[15]]) as usize;
+    let central_directory_offset = u32::from_le_bytes([end[16], end[17], end[18], end[19]]) as usize;
+    assert_eq!(central_directory_offset + central_directory_size, bytes.len() - 22);
+    assert_eq!(
+        &bytes[central_directory_offset..central_directory_offset + 4],
+        &[0x50, 0x4b, 0x01, 0x02]
+    );
+}
+
+#[test]
+fn relocate_runtime_classes() {
+    let rimp_int = include_bytes!("../../../../data/RIMPInt.class");
+
+    let relocated = rename_class(rimp_int, "RIMPInt", "com/example/RIMPInt").unwrap();
+    assert_ne!(relocated.as_slice(), rimp_int.as_slice());
+    assert!(relocated
+        .windows("com/example/RIMPInt".len())
+        .any(|window| window == b"com/example/RIMPInt"));
+
+    let restored = rename_class(&relocated, "com/example/RIMPInt", "RIMPInt").unwrap();
+    assert_eq!(restored.as_slice(), rimp_int.as_slice());
+
+    assert!(rename_class(b"not a class", "RIMPInt", "RIMPInt").is_err());
+}
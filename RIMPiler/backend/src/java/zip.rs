@@ -0,0 +1,124 @@
+/*
+A minimal zip writer, this only supports stored (uncompressed) entries, which is
+all that is needed to package class files into a jar.
+
+Every entry is given the same timestamp (1980-01-01 00:00) so that the output is
+reproducible.
+*/
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+
+const VERSION_NEEDED: u16 = 10;
+const VERSION_MADE_BY: u16 = 20;
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            if crc & 1 == 1 {
+                crc = (crc >> 1) ^ 0xEDB88320;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+pub struct ZipWriter {
+    buffer: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        ZipWriter {
+            buffer: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn add_file(&mut self, name: &str, data: &[u8]) {
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc32(data),
+            size: data.len() as u32,
+            offset: self.buffer.len() as u32,
+        };
+
+        self.write_u32(LOCAL_FILE_HEADER_SIGNATURE);
+        self.write_u16(VERSION_NEEDED);
+        self.write_u16(0); // flags
+        self.write_u16(0); // compression method, stored
+        self.write_u16(DOS_TIME);
+        self.write_u16(DOS_DATE);
+        self.write_u32(entry.crc);
+        self.write_u32(entry.size); // compressed size
+        self.write_u32(entry.size); // uncompressed size
+        self.write_u16(entry.name.len() as u16);
+        self.write_u16(0); // extra field length
+        self.buffer.extend_from_slice(entry.name.as_bytes());
+        self.buffer.extend_from_slice(data);
+
+        self.entries.push(entry);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let central_directory_offset = self.buffer.len() as u32;
+
+        let entries = std::mem::take(&mut self.entries);
+        for entry in &entries {
+            self.write_u32(CENTRAL_DIRECTORY_SIGNATURE);
+            self.write_u16(VERSION_MADE_BY);
+            self.write_u16(VERSION_NEEDED);
+            self.write_u16(0); // flags
+            self.write_u16(0); // compression method, stored
+            self.write_u16(DOS_TIME);
+            self.write_u16(DOS_DATE);
+            self.write_u32(entry.crc);
+            self.write_u32(entry.size);
+            self.write_u32(entry.size);
+            self.write_u16(entry.name.len() as u16);
+            self.write_u16(0); // extra field length
+            self.write_u16(0); // comment length
+            self.write_u16(0); // disk number
+            self.write_u16(0); // internal attributes
+            self.write_u32(0); // external attributes
+            self.write_u32(entry.offset);
+            self.buffer.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let central_directory_size = self.buffer.len() as u32 - central_directory_offset;
+
+        self.write_u32(END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        self.write_u16(0); // this disk
+        self.write_u16(0); // disk with the central directory
+        self.write_u16(entries.len() as u16);
+        self.write_u16(entries.len() as u16);
+        self.write_u32(central_directory_size);
+        self.write_u32(central_directory_offset);
+        self.write_u16(0); // comment length
+
+        self.buffer
+    }
+}
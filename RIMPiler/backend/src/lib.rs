@@ -1 +1,2 @@
-pub mod java;
\ No newline at end of file
+pub mod java;
+pub mod source;
@@ -0,0 +1,12 @@
+use std::path::{Path, PathBuf};
+
+// Writes the output of a source-to-source backend (C, WebAssembly text, ...) to <output_folder>/<file_name>
+pub fn write_source(source: String, output_folder: String, file_name: &str) -> PathBuf {
+    std::fs::create_dir_all(&output_folder).expect("Unable to create directory");
+    let output_folder = Path::new(&output_folder).canonicalize().expect("Unable to canonicalize output folder path");
+
+    let source_file = output_folder.join(file_name);
+    std::fs::write(&source_file, source).expect("Unable to write source file");
+
+    source_file
+}
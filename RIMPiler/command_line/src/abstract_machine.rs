@@ -1,14 +1,42 @@
 use std::io::Write;
 use RIMPiler_frontend::abstract_machine::engine::{Engine, Rules};
+use RIMPiler_frontend::optimiser::optimise;
+use RIMPiler_frontend::overflow::Overflow;
+use RIMPiler_frontend::post_parse::name_generator::labels;
+use RIMPiler_frontend::post_parse::transformer::transform_if_only;
 use super::ast::create_ast_without_transform;
 
 pub struct AbstractMachine {
     input_file: String,
+    optimise: bool,
+    overflow: Overflow,
+    max_steps: Option<usize>,
+    max_history: Option<usize>,
 }
 
 impl AbstractMachine {
     pub(crate) fn new(input_file: String) -> AbstractMachine {
-        AbstractMachine { input_file }
+        AbstractMachine { input_file, optimise: false, overflow: Overflow::default(), max_steps: None, max_history: None }
+    }
+
+    pub(crate) fn optimise(mut self, optimise: bool) -> AbstractMachine {
+        self.optimise = optimise;
+        self
+    }
+
+    pub(crate) fn overflow(mut self, overflow: Overflow) -> AbstractMachine {
+        self.overflow = overflow;
+        self
+    }
+
+    pub(crate) fn max_steps(mut self, max_steps: Option<usize>) -> AbstractMachine {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub(crate) fn max_history(mut self, max_history: Option<usize>) -> AbstractMachine {
+        self.max_history = max_history;
+        self
     }
 
     pub(crate) fn run(&self) -> Result<(), String> {
@@ -18,7 +46,15 @@ impl AbstractMachine {
             return Err(ast.unwrap_err().to_string());
         }
 
-        let mut engine = Engine::new(ast.unwrap());
+        let ast = if self.optimise { optimise(&ast.unwrap(), self.overflow) } else { ast.unwrap() };
+
+        // the engine makes the same copies for ifs as transform_if_only
+        let labels = labels(&transform_if_only(&ast));
+
+        let mut engine = Engine::new(ast)
+            .overflow(self.overflow)
+            .max_steps(self.max_steps)
+            .max_history(self.max_history);
 
         println!("control stack: \n{}", engine.get_control_stack());
 
@@ -45,7 +81,9 @@ impl AbstractMachine {
                         println!("Program has finished executing, reverse to continue");
                         continue;
                     }
-                    engine.step();
+                    if let Err(error) = engine.step() {
+                        println!("{}", error);
+                    }
                 }
                 "back" | "b" => {
                     engine.reverse();
@@ -54,7 +92,9 @@ impl AbstractMachine {
                         engine.reverse();
                         continue;
                     }
-                    engine.step();
+                    if let Err(error) = engine.step() {
+                        println!("{}", error);
+                    }
                     engine.reverse();
                 }
                 "print control stack" | "pcs" => {
@@ -67,13 +107,13 @@ impl AbstractMachine {
                     println!("result stack: \n{}", engine.get_result_stack());
                 }
                 "print store" | "ps" => {
-                    println!("store: \n{}", engine.get_store());
+                    println!("store: \n{}", engine.get_store().labelled(&labels));
                 }
                 "print all" | "pa" => {
                     println!("Rule {} has been applied", engine.get_next_rule());
                     println!("control stack: \n{}", engine.get_control_stack());
                     println!("result stack: \n{}", engine.get_result_stack());
-                    println!("store: \n{}", engine.get_store());
+                    println!("store: \n{}", engine.get_store().labelled(&labels));
                     println!("back stack: \n{}", engine.get_back_stack());
                 }
                 "step and print" | "sp" => {
@@ -81,48 +121,68 @@ impl AbstractMachine {
                         println!("Program has finished executing, reverse to continue");
                         continue;
                     }
-                    engine.step();
+                    if let Err(error) = engine.step() {
+                        println!("{}", error);
+                        continue;
+                    }
                     println!("Rule {} has been applied", engine.get_next_rule());
                     println!("control stack: \n{}", engine.get_control_stack());
                     println!("result stack: \n{}", engine.get_result_stack());
-                    println!("store: \n{}", engine.get_store());
+                    println!("store: \n{}", engine.get_store().labelled(&labels));
                     println!("back stack: \n{}", engine.get_back_stack());
                 }
                 "run" | "r" => {
-                    while !engine.is_done() {
-                        engine.step();
+                    if let Err(error) = engine.run() {
+                        println!("{}", error);
+                        println!("store: \n{}", engine.get_store().labelled(&labels));
                     }
                 }
                 "run and print" | "rp" => {
                     while !engine.is_done() {
-                        engine.step();
+                        if let Err(error) = engine.check_budget().and_then(|_| engine.step()) {
+                            println!("{}", error);
+                            break;
+                        }
                         println!("Rule {} has been applied", engine.get_next_rule());
                         println!("control stack: \n{}", engine.get_control_stack());
                         println!("result stack: \n{}", engine.get_result_stack());
-                        println!("store: \n{}", engine.get_store());
+                        println!("store: \n{}", engine.get_store().labelled(&labels));
                         println!("back stack: \n{}", engine.get_back_stack());
                         println!();
                     }
                 }
                 "run print reverse" | "rpr" => {
+                    let mut exhausted = false;
                     while !engine.is_done() {
-                        engine.step();
+                        if let Err(error) = engine.check_budget().and_then(|_| engine.step()) {
+                            println!("{}", error);
+                            exhausted = true;
+                            break;
+                        }
                         println!("Rule {} has been applied", engine.get_next_rule());
                         println!("control stack: \n{}", engine.get_control_stack());
                         println!("result stack: \n{}", engine.get_result_stack());
-                        println!("store: \n{}", engine.get_store());
+                        println!("store: \n{}", engine.get_store().labelled(&labels));
                         println!("back stack: \n{}", engine.get_back_stack());
                     }
 
+                    // the program never got to the end, so there's nothing to run back from
+                    if exhausted {
+                        continue;
+                    }
+
                     engine.reverse();
                     is_forward = !is_forward;
 
                     while !engine.is_done() {
-                        engine.step();
+                        if let Err(error) = engine.check_budget().and_then(|_| engine.step()) {
+                            println!("{}", error);
+                            break;
+                        }
                         println!("Rule {} has been applied", engine.get_next_rule());
                         println!("control stack: \n{}", engine.get_control_stack());
                         println!("result stack: \n{}", engine.get_result_stack());
-                        println!("store: \n{}", engine.get_store());
+                        println!("store: \n{}", engine.get_store().labelled(&labels));
                         println!("back stack: \n{}", engine.get_back_stack());
                     }
                 }
@@ -149,12 +209,24 @@ impl AbstractMachine {
                     if rule.is_none() {
                         continue;
                     } else {
+                        let mut exhausted = Ok(());
                         while !engine.is_done() && engine.get_next_rule() != rule.clone().unwrap() {
+                            exhausted = engine.check_budget();
+                            if exhausted.is_err() {
+                                break;
+                            }
                             print!("{} -> ", engine.get_next_rule());
-                            engine.step();
+                            exhausted = engine.step();
+                            if exhausted.is_err() {
+                                break;
+                            }
                         }
 
-                        if engine.is_done() {
+                        if let Err(error) = exhausted {
+                            println!();
+                            println!("{}", error);
+                            println!("store: \n{}", engine.get_store().labelled(&labels));
+                        } else if engine.is_done() {
                             print!("done");
                             println!("Program has finished executing");
                         } else {
@@ -163,14 +235,17 @@ impl AbstractMachine {
                             println!("Previous state:");
                             println!("\tcontrol stack: \n\t{}", engine.get_control_stack());
                             println!("\tresult stack: \n\t{}", engine.get_result_stack());
-                            println!("\tstore: \n\t{}", engine.get_store());
+                            println!("\tstore: \n\t{}", engine.get_store().labelled(&labels));
                             println!("\tback stack: \n\t{}", engine.get_back_stack());
-                            engine.step();
+                            if let Err(error) = engine.step() {
+                                println!("{}", error);
+                                continue;
+                            }
                             println!();
                             println!("Next state:");
                             println!("\tcontrol stack: \n\t{}", engine.get_control_stack());
                             println!("\tresult stack: \n\t{}", engine.get_result_stack());
-                            println!("\tstore: \n\t{}", engine.get_store());
+                            println!("\tstore: \n\t{}", engine.get_store().labelled(&labels));
                             println!("\tback stack: \n\t{}", engine.get_back_stack());
                         }
                     }
@@ -216,7 +291,7 @@ impl AbstractMachine {
                     println!("print store (ps) - print the store");
                     println!("print all (pa) - print all stacks and the store");
                     println!("step and print (sp) - step through the program and print all stacks and the store");
-                    println!("run (r) - run the program to completion");
+                    println!("run (r) - run the program to completion, or until it runs out of steps or history");
                     println!("run and print (rp) - run the program to completion and print all stacks and the store");
                     println!("run print reverse (rpr) - run the program to completion, reverse, then repeat");
                     println!("run until rule (rr) - run the program until a specific rule is applied");
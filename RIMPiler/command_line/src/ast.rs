@@ -1,5 +1,5 @@
 use RIMPiler_frontend::lexer::lexer::Tokeniser;
-use RIMPiler_frontend::parser::parser::{parse, parse_without_transform};
+use RIMPiler_frontend::parser::parser::{parse, parse_keeping_counters, parse_without_transform};
 use RIMPiler_frontend::AST::Program;
 use utilities::debug::Result;
 
@@ -14,6 +14,17 @@ pub fn create_ast(input_file: &str) -> Result<Program> {
     parse(&mut tokens.into())
 }
 
+pub fn create_ast_keeping_counters(input_file: &str) -> Result<Program> {
+    let input_file_contents = utilities::files::load_file(input_file).unwrap();
+
+    let tokens = Tokeniser::new()
+        .initialise()
+        .tokenise(input_file_contents)
+        .unwrap();
+
+    parse_keeping_counters(&mut tokens.into())
+}
+
 pub fn create_ast_without_transform(input_file: &str) -> Result<Program> {
     let input_file_contents = utilities::files::load_file(input_file).unwrap();
 
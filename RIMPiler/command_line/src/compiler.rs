@@ -1,36 +1,191 @@
-use RIMPiler_backend::java::assemble_byte_code;
-use RIMPiler_frontend::Backend;
-use RIMPiler_frontend::JVM::code_gen::JVMCompiler;
-use RIMPiler_frontend::post_parse::inverter::invert_and_combine;
-use super::ast::create_ast;
+use std::path::Path;
+use RIMPiler_backend::java::{assemble_byte_code, write_jar, write_java_source};
+use RIMPiler_backend::source::write_source;
+use RIMPiler_frontend::C::code_gen::CCompiler;
+use RIMPiler_frontend::WAT::code_gen::WATCompiler;
+use RIMPiler_frontend::PISA::code_gen::PISACompiler;
+use RIMPiler_frontend::Circuit::code_gen::{CircuitCompiler, DEFAULT_UNROLL, DEFAULT_WIDTH};
+use RIMPiler_frontend::X86::code_gen::X86Compiler;
+use RIMPiler_frontend::Java::code_gen::JavaCompiler;
+use RIMPiler_frontend::JVM::code_gen::{ClassDetails, JVMCompiler};
+use RIMPiler_frontend::optimiser::optimise;
+use RIMPiler_frontend::overflow::Overflow;
+use RIMPiler_frontend::post_parse::inverter::invert_and_combine_with;
+use RIMPiler_frontend::IR::lower::lower;
+use RIMPiler_frontend::IR::Type;
+use super::ast::{create_ast, create_ast_keeping_counters};
+
+// what the compiler produces, selected with --target
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Target {
+    // class files assembled with krakatau
+    JVM,
+    // a single self-contained .java file
+    Java,
+    // a standalone C99 program
+    C,
+    // a WebAssembly text module
+    WAT,
+    // assembly for the Pendulum reversible processor
+    PISA,
+    // a reversible circuit in the RevLib .real format
+    Circuit { width: u32, unroll: u32 },
+    // x86-64 assembly for a static Linux executable
+    X86,
+}
+
+impl Target {
+    pub(crate) fn from_name(name: &str) -> Result<Target, String> {
+        match name {
+            "jvm" => Ok(Target::JVM),
+            "java" => Ok(Target::Java),
+            "c" => Ok(Target::C),
+            "wat" => Ok(Target::WAT),
+            "pisa" => Ok(Target::PISA),
+            "circuit" => Ok(Target::Circuit { width: DEFAULT_WIDTH, unroll: DEFAULT_UNROLL }),
+            "x86" => Ok(Target::X86),
+            _ => Err(format!("Unknown target {}, expected one of: jvm, java, c, wat, pisa, circuit, x86", name)),
+        }
+    }
+}
 
 pub struct Compiler {
     input_file: String,
     output: String,
+    class_name: String,
+    package: Option<String>,
+    jar: bool,
+    target: Target,
+    // variables whose first assignment is replaced by primary inputs of the circuit
+    inputs: Vec<String>,
+    // run the optimisation passes before inverting, -O
+    optimise: bool,
+    // what int arithmetic does when it overflows, the other targets only wrap
+    overflow: Overflow,
 }
 
 impl Compiler {
-    pub(crate) fn new(input_file: String, output: String) -> Compiler {
+    pub(crate) fn new(input_file: String, output: String, class_name: String, package: Option<String>, jar: bool, target: Target) -> Compiler {
         Compiler {
             input_file,
             output,
+            class_name,
+            package,
+            jar,
+            target,
+            inputs: Vec::new(),
+            optimise: false,
+            overflow: Overflow::default(),
         }
     }
 
+    pub(crate) fn inputs(mut self, inputs: Vec<String>) -> Compiler {
+        self.inputs = inputs;
+        self
+    }
+
+    pub(crate) fn optimise(mut self, optimise: bool) -> Compiler {
+        self.optimise = optimise;
+        self
+    }
+
+    pub(crate) fn overflow(mut self, overflow: Overflow) -> Compiler {
+        self.overflow = overflow;
+        self
+    }
+
+    // backends that don't produce a class are named after the input file, e.g. collatz.rimp -> collatz.c
+    fn file_stem(&self) -> String {
+        Path::new(&self.input_file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or(String::from("main"))
+    }
+
     pub(crate) fn compile(&self) -> Result<(), String> {
-        let ast = create_ast(&self.input_file);
+        let source_file = Path::new(&self.input_file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(self.input_file.clone());
+
+        let class = ClassDetails::new(&self.class_name, self.package.as_deref(), &source_file);
+
+        class.validate()?;
+
+        if self.overflow != Overflow::Wrapping && self.target != Target::JVM {
+            return Err(format!("{} overflow is only supported by the jvm target", self.overflow.name()));
+        }
+
+        // PISA and circuits run the forward program backwards, which finds where a loop was entered from its counter
+        let ast = match self.target {
+            Target::PISA | Target::Circuit { .. } => create_ast_keeping_counters(&self.input_file),
+            _ => create_ast(&self.input_file),
+        };
 
         if ast.is_err() {
             return Err(ast.unwrap_err().to_string());
         }
 
-        let ast = ast.unwrap();
+        let ast = if self.optimise { optimise(&ast.unwrap(), self.overflow) } else { ast.unwrap() };
+
+        let inverted = invert_and_combine_with(&ast, self.overflow);
+        let program = lower(&inverted)?;
+
+        // bigints are java.math.BigIntegers, PISA and circuits reject them with the rest of what they can't compile
+        if matches!(self.target, Target::Java | Target::C | Target::WAT | Target::X86) && program.uses(&Type::BigInteger) {
+            return Err(String::from("bigint is only supported by the jvm target"));
+        }
+
+        match self.target {
+            Target::JVM => {
+                let byte_code = JVMCompiler::new().overflow(self.overflow)._compile(&program, &class);
+
+                assemble_byte_code(byte_code, self.output.clone(), &class.name, class.package.as_deref());
+
+                if self.jar {
+                    let jar_file = write_jar(self.output.clone(), &class.name, class.package.as_deref());
+                    println!("Runnable jar written to {}", jar_file.display());
+                }
+            }
+            Target::Java => {
+                let source = JavaCompiler::new()._compile(&program, &class);
+
+                let source_file = write_java_source(source, self.output.clone(), &class.name, class.package.as_deref());
+                println!("Java source written to {}", source_file.display());
+            }
+            Target::C => {
+                let source = CCompiler::new()._compile(&program, &source_file);
 
-        let inverted = invert_and_combine(&ast);
+                let source_file = write_source(source, self.output.clone(), &format!("{}.c", self.file_stem()));
+                println!("C source written to {}", source_file.display());
+            }
+            Target::WAT => {
+                let module = WATCompiler::new()._compile(&program);
 
-        let byte_code = JVMCompiler::compile(&inverted);
+                let module_file = write_source(module, self.output.clone(), &format!("{}.wat", self.file_stem()));
+                println!("WebAssembly module written to {}", module_file.display());
+            }
+            Target::PISA => {
+                let assembly = PISACompiler::new()._compile(&program)?;
 
-        assemble_byte_code(byte_code, self.output.clone());
+                let assembly_file = write_source(assembly, self.output.clone(), &format!("{}.pisa", self.file_stem()));
+                println!("PISA assembly written to {}", assembly_file.display());
+            }
+            Target::Circuit { width, unroll } => {
+                let mut compiler = CircuitCompiler::new(width, unroll, self.inputs.clone());
+                let circuit = compiler._compile(&program)?;
+
+                let circuit_file = write_source(circuit, self.output.clone(), &format!("{}.real", self.file_stem()));
+                println!("Reversible circuit written to {}", circuit_file.display());
+                println!("{}", compiler.statistics());
+            }
+            Target::X86 => {
+                let assembly = X86Compiler::new()._compile(&program, &source_file);
+
+                let assembly_file = write_source(assembly, self.output.clone(), &format!("{}.s", self.file_stem()));
+                println!("x86-64 assembly written to {}", assembly_file.display());
+            }
+        }
 
         Ok(())
     }
@@ -0,0 +1,75 @@
+use RIMPiler_frontend::optimiser::optimise;
+use RIMPiler_frontend::overflow::Overflow;
+use RIMPiler_frontend::post_parse::inverter::invert_and_combine_with;
+use RIMPiler_frontend::post_parse::invertibility::analyse_with;
+use RIMPiler_frontend::post_parse::loop_analysis::decisions;
+use RIMPiler_frontend::IR::lower::lower;
+use super::ast::create_ast;
+
+// an intermediate form of the program that can be printed with --emit
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Stage {
+    // the program after the semantic transformer, with how each loop is run backwards
+    Transformed,
+    // the typed intermediate representation the backends are compiled from
+    IR,
+    // every assignment of the program, and whether it needs history to be undone
+    History,
+}
+
+impl Stage {
+    pub(crate) fn from_name(name: &str) -> Result<Stage, String> {
+        match name {
+            "transformed" => Ok(Stage::Transformed),
+            "ir" => Ok(Stage::IR),
+            "history" => Ok(Stage::History),
+            _ => Err(format!("Unknown stage {}, expected one of: transformed, ir, history", name)),
+        }
+    }
+}
+
+pub struct Emitter {
+    input_file: String,
+    stage: Stage,
+    optimise: bool,
+    overflow: Overflow,
+}
+
+impl Emitter {
+    pub(crate) fn new(input_file: String, stage: Stage) -> Emitter {
+        Emitter { input_file, stage, optimise: false, overflow: Overflow::default() }
+    }
+
+    pub(crate) fn optimise(mut self, optimise: bool) -> Emitter {
+        self.optimise = optimise;
+        self
+    }
+
+    pub(crate) fn overflow(mut self, overflow: Overflow) -> Emitter {
+        self.overflow = overflow;
+        self
+    }
+
+    pub(crate) fn emit(&self) -> Result<(), String> {
+        let ast = create_ast(&self.input_file).map_err(|error| error.to_string())?;
+
+        let ast = if self.optimise { optimise(&ast, self.overflow) } else { ast };
+
+        match self.stage {
+            Stage::Transformed => {
+                print!("{}", ast);
+                for decision in decisions(&ast) {
+                    println!("{}", decision);
+                }
+            }
+            Stage::IR => print!("{}", lower(&invert_and_combine_with(&ast, self.overflow))?),
+            Stage::History => {
+                for classification in analyse_with(&ast, self.overflow) {
+                    println!("{}", classification);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,58 @@
 use super::ast::create_ast;
-use RIMPiler_frontend::{interpreter::interpreter::InterpreterEngine, post_parse::inverter::invert_and_combine};
+use RIMPiler_frontend::{interpreter::{compiled::compile, interpreter::InterpreterEngine}, optimiser::optimise, post_parse::inverter::invert_and_combine_with};
+use RIMPiler_frontend::overflow::Overflow;
+use RIMPiler_frontend::post_parse::name_generator::labels;
 
 pub struct Interpreter {
     input_file: String,
+    optimise: bool,
+    overflow: Overflow,
+    max_steps: Option<usize>,
+    max_history: Option<usize>,
+    checkpoints: Option<usize>,
+    profile: bool,
+    // where the folded stacks are written, see RIMPiler_frontend::interpreter::profile
+    folded: Option<String>,
 }
 
 impl Interpreter {
     pub(crate) fn new(input_file: String) -> Interpreter {
-        Interpreter { input_file }
+        Interpreter { input_file, optimise: false, overflow: Overflow::default(), max_steps: None, max_history: None, checkpoints: None, profile: false, folded: None }
+    }
+
+    pub(crate) fn optimise(mut self, optimise: bool) -> Interpreter {
+        self.optimise = optimise;
+        self
+    }
+
+    pub(crate) fn overflow(mut self, overflow: Overflow) -> Interpreter {
+        self.overflow = overflow;
+        self
+    }
+
+    pub(crate) fn max_steps(mut self, max_steps: Option<usize>) -> Interpreter {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub(crate) fn max_history(mut self, max_history: Option<usize>) -> Interpreter {
+        self.max_history = max_history;
+        self
+    }
+
+    pub(crate) fn checkpoints(mut self, checkpoints: Option<usize>) -> Interpreter {
+        self.checkpoints = checkpoints;
+        self
+    }
+
+    pub(crate) fn profile(mut self, profile: bool) -> Interpreter {
+        self.profile = profile;
+        self
+    }
+
+    pub(crate) fn folded(mut self, folded: Option<String>) -> Interpreter {
+        self.folded = folded;
+        self
     }
 
     pub(crate) fn interpret(&self) -> Result<(), String> {
@@ -17,21 +62,46 @@ impl Interpreter {
             return Err(ast.unwrap_err().to_string());
         }
 
-        let inverted = invert_and_combine(&ast.clone().unwrap());
+        let ast = if self.optimise { optimise(&ast.unwrap(), self.overflow) } else { ast.unwrap() };
+
+        let inverted = invert_and_combine_with(&ast, self.overflow);
 
         println!("statements: \n{}", inverted);
 
-        let mut interpreter = InterpreterEngine::new();
+        let mut interpreter = InterpreterEngine::new()
+            .overflow(self.overflow)
+            .max_steps(self.max_steps)
+            .max_history(self.max_history)
+            .checkpoints(self.checkpoints)
+            .profile(self.profile || self.folded.is_some());
 
-        let result = interpreter.interpret(&inverted);
+        // only interpret profiles, so the program is only compiled when it isn't profiled
+        let result = if interpreter.get_profile().is_some() {
+            interpreter.interpret(&inverted)
+        } else {
+            interpreter.run(&compile(&inverted))
+        };
+
+        if let Some(profile) = interpreter.get_profile() {
+            if self.profile {
+                println!("profile: \n{}", profile.labelled(&labels(&inverted)));
+            }
+            if let Some(folded) = &self.folded {
+                std::fs::write(folded, profile.folded()).map_err(|error| format!("Couldn't write {}: {}", folded, error))?;
+            }
+        }
 
         if result.is_err() {
-            return Err(result.unwrap_err().to_string());
+            return Err(format!(
+                "{}\nstore when it stopped: \n\t{}",
+                result.unwrap_err(),
+                interpreter.get_memory_store().labelled(&labels(&inverted))
+            ));
         }
 
         println!(
             "result: \n\t{}",
-            interpreter.get_reverse_point_snapshot().as_ref().unwrap()
+            interpreter.get_reverse_point_snapshot().as_ref().unwrap().labelled(&labels(&inverted))
         );
 
         Ok(())
@@ -1,9 +1,12 @@
 mod ast;
 mod compiler;
+mod emit;
 mod interpreter;
 mod abstract_machine;
+mod verify;
 
 use utilities::args_parser::*;
+use RIMPiler_frontend::overflow::Overflow;
 
 const LOGO: &str = r#"
 ██████╗ ██╗███╗   ███╗██████╗ ██╗██╗     ███████╗██████╗
@@ -32,6 +35,108 @@ fn get_args() -> CommandLineResult {
                 .description("The output file/folder")
                 .optional(),
         )
+        .add_argument(
+            ArgumentBuilder::new("class name")
+                .short_name("n")
+                .long_name("class-name")
+                .string()
+                .description("The name of the generated class (default Main)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("package")
+                .short_name("p")
+                .long_name("package")
+                .string()
+                .description("The java package of the generated classes, e.g. com.example")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("target")
+                .short_name("t")
+                .long_name("target")
+                .string()
+                .description("What to compile to: jvm (class files, default), java (a .java source file), c (a C99 program), wat (a WebAssembly text module), pisa (Pendulum reversible assembly), circuit (a RevLib reversible circuit) or x86 (x86-64 Linux assembly)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("width")
+                .short_name("w")
+                .long_name("width")
+                .integer()
+                .description("The number of bits in an integer of the circuit target (default 16)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("unroll")
+                .short_name("u")
+                .long_name("unroll")
+                .integer()
+                .description("How many times loops are unrolled for the circuit target (default 16)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("inputs")
+                .short_name("v")
+                .long_name("inputs")
+                .string()
+                .description("Comma separated variables that are inputs of the circuit target instead of their first assignment")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("emit")
+                .short_name("e")
+                .long_name("emit")
+                .string()
+                .description("Print an intermediate form of the program instead of compiling it: transformed (the program after the semantic transformer, and how each loop is run backwards), ir (the typed intermediate representation the backends are compiled from) or history (which assignments need history to be undone)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("overflow")
+                .short_name("f")
+                .long_name("overflow")
+                .string()
+                .description("What int arithmetic does when the result doesn't fit in 32 bits: wrapping (keep the lowest 32 bits, default), checked (stop with an error) or saturating (clamp to the smallest or largest int). Compiling only supports checked and saturating for the jvm target")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("max steps")
+                .short_name("s")
+                .long_name("max-steps")
+                .integer()
+                .description("Stop interpreting or running the abstract machine after this many steps, for programs that might not terminate (default no limit)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("max history")
+                .short_name("H")
+                .long_name("max-history")
+                .integer()
+                .description("Stop interpreting or running the abstract machine once the histories hold more than this many entries (default no limit)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("checkpoints")
+                .short_name("k")
+                .long_name("checkpoints")
+                .integer()
+                .description("When interpreting, record the whole value of a variable in its history every this many assignments rather than only the differences (default never)")
+                .optional(),
+        )
+        .add_argument(
+            ArgumentBuilder::new("folded")
+                .short_name("F")
+                .long_name("folded")
+                .string()
+                .description("When interpreting, also write the profile to this file as folded stacks, which flamegraph tools read")
+                .optional(),
+        )
+        .add_flag(
+            FlagBuilder::new("jar")
+                .short_name("j")
+                .long_name("jar")
+                .description("Also package the compiled classes into a runnable jar"),
+        )
         .add_flag(
             FlagBuilder::new("compile")
                 .short_name("c")
@@ -50,6 +155,24 @@ fn get_args() -> CommandLineResult {
                 .long_name("abstract")
                 .description("Run the abstract machine"),
         )
+        .add_flag(
+            FlagBuilder::new("verify")
+                .short_name("V")
+                .long_name("verify")
+                .description("Check that running the program forward and then backward puts every variable back to 0 with an empty history, on the interpreter and the abstract machine"),
+        )
+        .add_flag(
+            FlagBuilder::new("optimise")
+                .short_name("O")
+                .long_name("optimise")
+                .description("Fold constants, propagate copies and remove dead assignments before inverting the program"),
+        )
+        .add_flag(
+            FlagBuilder::new("profile")
+                .short_name("P")
+                .long_name("profile")
+                .description("When interpreting, count how often each statement runs, how many times each loop goes round and how much history each variable records, in the forward and backward halves, and print them sorted"),
+        )
         .add_flag(
             FlagBuilder::new("help")
                 .short_name("h")
@@ -67,30 +190,88 @@ fn get_args() -> CommandLineResult {
             std::process::exit(0);
         }
         Ok(args) => {
-            if (args.flags.contains("compile") && args.flags.contains("interpret"))
-                || (args.flags.contains("compile") && args.flags.contains("abstract machine"))
-                || (args.flags.contains("interpret") && args.flags.contains("abstract machine"))
-            {
+            if ["compile", "interpret", "abstract machine", "verify"].iter().filter(|mode| args.flags.contains(**mode)).count() > 1 {
+                println!("{}", LOGO);
+                println!("Error: Only one of the flags compile, interpret, abstract machine or verify can be used at a time");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
+            } else if args.arguments.get("emit").is_some()
+                && (args.flags.contains("abstract machine")
+                    || args.flags.contains("interpret")
+                    || args.flags.contains("verify")
+                    || args.flags.contains("jar")
+                    || args.arguments.get("output").is_some()) {
                 println!("{}", LOGO);
-                println!("Error: Only one of the flags compile, interpret, or abstract machine can be used at a time");
+                println!("Error: The emit option prints to the terminal, so it can't be used with output, jar, interpret, abstract machine or verify");
                 println!();
                 println!("{}", parser);
                 std::process::exit(1);
             } else if args.arguments.get("output").is_none()
+                && args.arguments.get("emit").is_none()
                 && !args.flags.contains("abstract machine")
-                && !args.flags.contains("interpret") {
+                && !args.flags.contains("interpret")
+                && !args.flags.contains("verify") {
                 println!("{}", LOGO);
                 println!("Error: The output flag is required when compiling a file");
                 println!();
                 println!("{}", parser);
                 std::process::exit(1);
             } else if args.arguments.get("output").is_some()
-                && (args.flags.contains("abstract machine") || args.flags.contains("interpret")) {
+                && (args.flags.contains("abstract machine") || args.flags.contains("interpret") || args.flags.contains("verify")) {
                 println!("{}", LOGO);
                 println!("Error: The output flag is only required when running the compiler");
                 println!();
                 println!("{}", parser);
                 std::process::exit(1);
+            } else if (args.arguments.get("class name").is_some()
+                || args.arguments.get("package").is_some()
+                || args.arguments.get("target").is_some()
+                || args.flags.contains("jar"))
+                && (args.flags.contains("abstract machine") || args.flags.contains("interpret") || args.flags.contains("verify")) {
+                println!("{}", LOGO);
+                println!("Error: The class name, package, target and jar options can only be used when running the compiler");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
+            } else if (args.arguments.get("max steps").is_some() || args.arguments.get("max history").is_some())
+                && !args.flags.contains("abstract machine")
+                && !args.flags.contains("interpret")
+                && !args.flags.contains("verify") {
+                println!("{}", LOGO);
+                println!("Error: The max steps and max history options can only be used with interpret, abstract machine or verify");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
+            } else if (args.flags.contains("profile") || args.arguments.contains_key("folded")) && !args.flags.contains("interpret") {
+                println!("{}", LOGO);
+                println!("Error: The profile and folded options can only be used with interpret");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
+            } else if args.flags.contains("jar")
+                && args.arguments.get("target").is_some_and(|target| target != "jvm") {
+                println!("{}", LOGO);
+                println!("Error: A jar can only be built for the jvm target");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
+            } else if (args.arguments.get("class name").is_some() || args.arguments.get("package").is_some())
+                && args.arguments.get("target").is_some_and(|target| target != "jvm" && target != "java") {
+                println!("{}", LOGO);
+                println!("Error: The class name and package options can only be used with the jvm and java targets");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
+            } else if (args.arguments.get("width").is_some()
+                || args.arguments.get("unroll").is_some()
+                || args.arguments.get("inputs").is_some())
+                && args.arguments.get("target").is_none_or(|target| target != "circuit") {
+                println!("{}", LOGO);
+                println!("Error: The width, unroll and inputs options can only be used with the circuit target");
+                println!();
+                println!("{}", parser);
+                std::process::exit(1);
             } else {
                 args
             }
@@ -105,6 +286,20 @@ fn get_args() -> CommandLineResult {
 }
 
 static DEFAULT_OUTPUT_FILE: &str = "Main";
+static DEFAULT_CLASS_NAME: &str = "Main";
+static DEFAULT_TARGET: &str = "jvm";
+static DEFAULT_OVERFLOW: &str = "wrapping";
+
+// a program that doesn't parse or stops with an error ends RIMPiler with a non-zero status, not a panic
+fn exit_on_error<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
 
 fn main() {
     let args = get_args();
@@ -112,20 +307,111 @@ fn main() {
     let input_file = args.arguments.get("input").unwrap();
     let mut output_file = DEFAULT_OUTPUT_FILE.to_string();
 
-    if args.flags.is_empty() || args.flags.contains("compile") {
+    let overflow = match Overflow::from_name(args.arguments.get("overflow").map(String::as_str).unwrap_or(DEFAULT_OVERFLOW)) {
+        Ok(overflow) => overflow,
+        Err(error) => {
+            println!("Error: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let budget = |name: &str| match args.arguments.get(name).map(|value| value.parse::<usize>()) {
+        None => None,
+        Some(Ok(value)) => Some(value),
+        Some(Err(_)) => {
+            println!("Error: The {} can't be negative", name);
+            std::process::exit(1);
+        }
+    };
+    let max_steps = budget("max steps");
+    let max_history = budget("max history");
+    let checkpoints = budget("checkpoints");
+
+    if let Some(stage) = args.arguments.get("emit") {
+        let stage = match emit::Stage::from_name(stage) {
+            Ok(stage) => stage,
+            Err(error) => {
+                println!("Error: {}", error);
+                std::process::exit(1);
+            }
+        };
+
+        let emitter = emit::Emitter::new(input_file.to_string(), stage).optimise(args.flags.contains("optimise")).overflow(overflow);
+        exit_on_error(emitter.emit());
+    } else if !args.flags.contains("abstract machine") && !args.flags.contains("interpret") && !args.flags.contains("verify") {
         let output_file_opt = args.arguments.get("output");
         if output_file_opt.is_some() {
             output_file = output_file_opt.unwrap().to_string();
         }
 
-        let compiler = compiler::Compiler::new(input_file.to_string(), output_file.to_string());
-        compiler.compile().unwrap();
+        let class_name = args.arguments.get("class name")
+            .cloned()
+            .unwrap_or(DEFAULT_CLASS_NAME.to_string());
+        let package = args.arguments.get("package").cloned();
+
+        let target = args.arguments.get("target").map(String::as_str).unwrap_or(DEFAULT_TARGET);
+        let target = match compiler::Target::from_name(target) {
+            Ok(target) => target,
+            Err(error) => {
+                println!("Error: {}", error);
+                std::process::exit(1);
+            }
+        };
+
+        let target = match target {
+            compiler::Target::Circuit { width, unroll } => {
+                let option = |name: &str, default: u32| match args.arguments.get(name).map(|value| value.parse::<u32>()) {
+                    None => default,
+                    Some(Ok(value)) => value,
+                    Some(Err(_)) => {
+                        println!("Error: The {} can't be negative", name);
+                        std::process::exit(1);
+                    }
+                };
+                compiler::Target::Circuit { width: option("width", width), unroll: option("unroll", unroll) }
+            }
+            target => target,
+        };
+
+        let inputs = args.arguments.get("inputs")
+            .map(|inputs| inputs.split(',').map(|input| input.trim().to_string()).filter(|input| !input.is_empty()).collect())
+            .unwrap_or_default();
+
+        let compiler = compiler::Compiler::new(
+            input_file.to_string(),
+            output_file.to_string(),
+            class_name,
+            package,
+            args.flags.contains("jar"),
+            target,
+        ).inputs(inputs).optimise(args.flags.contains("optimise")).overflow(overflow);
+        exit_on_error(compiler.compile());
     } else if args.flags.contains("abstract machine") {
-        let abstract_machine = abstract_machine::AbstractMachine::new(input_file.to_string());
-        abstract_machine.run().unwrap();
+        let abstract_machine = abstract_machine::AbstractMachine::new(input_file.to_string())
+            .optimise(args.flags.contains("optimise"))
+            .overflow(overflow)
+            .max_steps(max_steps)
+            .max_history(max_history);
+        exit_on_error(abstract_machine.run());
     } else if args.flags.contains("interpret") {
-        let interpreter = interpreter::Interpreter::new(input_file.to_string());
-        interpreter.interpret().unwrap();
+        let interpreter = interpreter::Interpreter::new(input_file.to_string())
+            .optimise(args.flags.contains("optimise"))
+            .overflow(overflow)
+            .max_steps(max_steps)
+            .max_history(max_history)
+            .checkpoints(checkpoints)
+            .profile(args.flags.contains("profile"))
+            .folded(args.arguments.get("folded").cloned());
+        exit_on_error(interpreter.interpret());
+    } else if args.flags.contains("verify") {
+        let verifier = verify::Verifier::new(input_file.to_string())
+            .optimise(args.flags.contains("optimise"))
+            .overflow(overflow)
+            .max_steps(max_steps)
+            .max_history(max_history);
+        if !exit_on_error(verifier.verify()) {
+            std::process::exit(1);
+        }
     } else {
         unreachable!("Invalid flag combination")
     }
@@ -0,0 +1,106 @@
+use super::ast::{create_ast, create_ast_without_transform};
+use RIMPiler_frontend::abstract_machine::engine::Engine;
+use RIMPiler_frontend::interpreter::interpreter::InterpreterEngine;
+use RIMPiler_frontend::optimiser::optimise;
+use RIMPiler_frontend::overflow::Overflow;
+use RIMPiler_frontend::post_parse::inverter::invert_and_combine_with;
+use RIMPiler_frontend::post_parse::name_generator::labels;
+use RIMPiler_frontend::post_parse::transformer::transform_if_only;
+use RIMPiler_frontend::verify::{verify_abstract_machine, verify_interpreter, Unrestored};
+use std::collections::HashMap;
+
+pub struct Verifier {
+    input_file: String,
+    optimise: bool,
+    overflow: Overflow,
+    max_steps: Option<usize>,
+    max_history: Option<usize>,
+}
+
+impl Verifier {
+    pub(crate) fn new(input_file: String) -> Verifier {
+        Verifier { input_file, optimise: false, overflow: Overflow::default(), max_steps: None, max_history: None }
+    }
+
+    pub(crate) fn optimise(mut self, optimise: bool) -> Verifier {
+        self.optimise = optimise;
+        self
+    }
+
+    pub(crate) fn overflow(mut self, overflow: Overflow) -> Verifier {
+        self.overflow = overflow;
+        self
+    }
+
+    pub(crate) fn max_steps(mut self, max_steps: Option<usize>) -> Verifier {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub(crate) fn max_history(mut self, max_history: Option<usize>) -> Verifier {
+        self.max_history = max_history;
+        self
+    }
+
+    // runs the program forward and back on the interpreter and the abstract machine, false if either didn't
+    // restore every variable
+    pub(crate) fn verify(&self) -> Result<bool, String> {
+        let ast = create_ast(&self.input_file);
+
+        if ast.is_err() {
+            return Err(ast.unwrap_err().to_string());
+        }
+
+        let ast = if self.optimise { optimise(&ast.unwrap(), self.overflow) } else { ast.unwrap() };
+
+        let inverted = invert_and_combine_with(&ast, self.overflow);
+
+        let mut interpreter = InterpreterEngine::new()
+            .overflow(self.overflow)
+            .max_steps(self.max_steps)
+            .max_history(self.max_history);
+
+        let interpreted = verify_interpreter(&mut interpreter, &inverted);
+
+        if interpreted.is_err() {
+            return Err(interpreted.unwrap_err().to_string());
+        }
+
+        let interpreted = report("interpreter", &interpreted.unwrap(), &labels(&inverted));
+
+        let ast = create_ast_without_transform(&self.input_file);
+
+        if ast.is_err() {
+            return Err(ast.unwrap_err().to_string());
+        }
+
+        let ast = if self.optimise { optimise(&ast.unwrap(), self.overflow) } else { ast.unwrap() };
+
+        // the engine makes the same copies for ifs as transform_if_only
+        let labels = labels(&transform_if_only(&ast));
+
+        let mut engine = Engine::new(ast)
+            .overflow(self.overflow)
+            .max_steps(self.max_steps)
+            .max_history(self.max_history);
+
+        let machine = verify_abstract_machine(&mut engine)?;
+
+        let machine = report("abstract machine", &machine, &labels);
+
+        Ok(interpreted && machine)
+    }
+}
+
+fn report(engine: &str, unrestored: &[Unrestored], labels: &HashMap<String, String>) -> bool {
+    if unrestored.is_empty() {
+        println!("{}: every variable was restored", engine);
+        return true;
+    }
+
+    println!("{}: {} variables weren't restored", engine, unrestored.len());
+    for variable in unrestored {
+        println!("\t{}", variable.labelled(labels));
+    }
+    false
+}
@@ -26,6 +26,8 @@ impl Display for Variable {
         match self {
             Variable::Integer(integer) => write!(f, "{}", integer),
             Variable::Float(float) => write!(f, "{}f", float),
+            Variable::BigInteger(integer) => write!(f, "{}n", integer),
+            Variable::Rational(rational) => write!(f, "{}r", rational),
         }
     }
 }
@@ -35,6 +37,8 @@ impl Display for ArithmeticExpression {
         match self {
             ArithmeticExpression::Integer(integer) => write!(f, "{}", integer),
             ArithmeticExpression::Float(float) => write!(f, "{}f", float),
+            ArithmeticExpression::BigInteger(integer) => write!(f, "{}n", integer),
+            ArithmeticExpression::Rational(rational) => write!(f, "{}r", rational),
             ArithmeticExpression::Variable(variable) => write!(f, "{}", variable),
             ArithmeticExpression::Operation(operation, left_hand_side, right_hand_side) => {
                 write!(f, "({} {} {})", operation, left_hand_side, right_hand_side)
@@ -119,6 +123,7 @@ impl Display for Statement {
             }
             Statement::Assignment(variable, exp) => write!(f, "{} = {}\n", variable, exp),
             Statement::ReverseAssignment(variable, exp) => write!(f, "({} = {})'\n", variable, exp),
+            Statement::DirectAssignment(variable, exp, _) => write!(f, "{} := {}\n", variable, exp),
             Statement::ReversePoint => write!(f, "---------------rp---------------'\n"),
         }
     }
@@ -1,8 +1,11 @@
 use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
+use utilities::debug::Meta;
 
 mod display;
 
-pub type Block = Vec<Statement>;
+pub type Block = Vec<Meta<Statement>>;
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Program {
@@ -13,12 +16,18 @@ pub enum Program {
 pub enum Type {
     Integer,
     Float,
+    // arbitrary precision, see utilities::bigint
+    BigInteger,
+    // exact, see utilities::rational
+    Rational,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum Variable {
     Integer(String),
     Float(String),
+    BigInteger(String),
+    Rational(String),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -28,6 +37,8 @@ pub enum Statement {
     While(Box<BooleanExpression>, Box<Block>),
     Assignment(Variable, ArithmeticExpression),
     ReverseAssignment(Variable, ArithmeticExpression),
+    // an assignment that doesn't record history, as the second expression undoes it, see post_parse::invertibility
+    DirectAssignment(Variable, ArithmeticExpression, ArithmeticExpression),
     ReversePoint,
 }
 
@@ -50,6 +61,8 @@ pub enum ArithmeticExpression {
     Variable(Variable),
     Integer(i32),
     Float(NotNan<f32>),
+    BigInteger(BigInt),
+    Rational(Rational),
     Unary(UnaryArithmeticOperator, Box<ArithmeticExpression>),
     Operation(
         ArithmeticOperator,
@@ -0,0 +1,283 @@
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Slot, Type};
+use super::super::Backend;
+
+/*
+Compiles a RIMP program into a standalone C99 program.
+
+Each variable is a struct holding its value and a growable array of the differences made by every
+assignment, the same history the JVM runtime classes keep. The forward program is followed by printing
+every variable (in the same format as RIMPInt/RIMPFloat print) and then the backward program, which pops
+the history to restore the initial state.
+
+Integer arithmetic goes through the rimp_* helpers in data/main.c, so that overflow wraps rather than being
+undefined behaviour, and division by zero is reported rather than crashing. Ints that the IR converts to floats
+are left to C's usual arithmetic conversions, which do the same.
+*/
+
+const INDENT: &str = "    ";
+
+// C99 keywords and the names the generated main relies on
+const RESERVED: [&str; 39] = [
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum",
+    "extern", "float", "for", "goto", "if", "inline", "int", "long", "register", "restrict", "return",
+    "short", "signed", "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void",
+    "volatile", "while", "main", "free", "NULL", "INT32_MIN", "INT32_MAX",
+];
+
+// binding strength of C's operators, function calls (all integer arithmetic) bind as tightly as a variable
+fn arithmetic_precedence(expression: &Expression) -> usize {
+    match expression {
+        Expression::Operation(Type::Integer, _, _, _) => 4,
+        Expression::Operation(Type::Float, operator, _, _) => match operator {
+            ArithmeticOperator::Exponentiation => 4,
+            ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => 1,
+            ArithmeticOperator::Multiplication | ArithmeticOperator::Division => 2,
+        },
+        Expression::Negation(Type::Float, _) => 3,
+        Expression::Integer(value) if *value < 0 => 3,
+        Expression::Float(value) if value.is_sign_negative() => 3,
+        // ints converted to floats are written as they are
+        Expression::Convert(Type::Float, operand) => arithmetic_precedence(operand),
+        _ => 4,
+    }
+}
+
+fn boolean_precedence(condition: &Condition) -> usize {
+    match condition {
+        Condition::Or(_, _) => 1,
+        Condition::And(_, _) => 2,
+        Condition::Comparison(_, _, _, _) => 3,
+        Condition::Not(_) => 4,
+    }
+}
+
+fn float_literal(value: f32) -> String {
+    // if number has no decimal point, add .0 to make it a float
+    if value.to_string().contains('.') {
+        format!("{}f", value)
+    } else {
+        format!("{}.0f", value)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CCompiler {}
+
+impl CCompiler {
+    pub fn new() -> Self {
+        CCompiler {}
+    }
+
+    // RIMP identifiers never contain an underscore, generated ones never end with one, so the suffix can't clash
+    fn local_name(program: &Program, slot: Slot) -> String {
+        let variable = &program.variable(slot).name;
+        if RESERVED.contains(&variable.as_str()) {
+            format!("{}_", variable)
+        } else {
+            variable.to_string()
+        }
+    }
+
+    fn runtime_type(variable_type: &Type) -> &'static str {
+        match variable_type {
+            Type::Integer => "rimp_int",
+            Type::Float => "rimp_float",
+            Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+            Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+        }
+    }
+
+    pub fn _compile(&mut self, program: &Program, source_file: &str) -> String {
+        let mut content = String::from("/* forward program */\n");
+        content.push_str(&self.compile_block(program, &program.forward, 0));
+        content.push_str("\n/* reverse point */\n");
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let runtime_type = Self::runtime_type(&variable.variable_type);
+            content.push_str(&format!("{}_print(&{});\n", runtime_type, Self::local_name(program, slot)));
+        }
+        content.push_str("\n/* backward program */\n");
+        content.push_str(&self.compile_block(program, &program.backward, 0));
+
+        let content = content
+            .lines()
+            .map(|line| if line.is_empty() { String::from("\n") } else { format!("{}{}\n", INDENT, line) })
+            .collect::<String>();
+
+        let mut declarations = String::new();
+        let mut cleanup = String::new();
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let runtime_type = Self::runtime_type(&variable.variable_type);
+            let name = Self::local_name(program, slot);
+            declarations.push_str(&format!("{}{} {};\n", INDENT, runtime_type, name));
+            cleanup.push_str(&format!("{}free({}.history);\n", INDENT, name));
+        }
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let runtime_type = Self::runtime_type(&variable.variable_type);
+            declarations.push_str(&format!("{}{}_init(&{}, \"{}\");\n", INDENT, runtime_type, Self::local_name(program, slot), variable.name));
+        }
+
+        let file = include_str!("../../../../data/main.c");
+
+        file.replace("<source>", source_file)
+            .replace("<declarations>\n", &declarations)
+            .replace("<code>\n", &content)
+            .replace("<cleanup>\n", &cleanup)
+    }
+
+    fn compile_block(&mut self, program: &Program, block: &Block, depth: usize) -> String {
+        block.iter().map(|instruction| self.compile_instruction(program, &instruction.value, depth)).collect()
+    }
+
+    fn compile_instruction(&mut self, program: &Program, instruction: &Instruction, depth: usize) -> String {
+        let indent = INDENT.repeat(depth);
+        match instruction {
+            Instruction::If(condition, then_block, else_block) => {
+                let condition = self.compile_condition(program, condition);
+                let then_code = self.compile_block(program, then_block, depth + 1);
+                let else_code = self.compile_block(program, else_block, depth + 1);
+
+                if else_code.is_empty() {
+                    format!("{}if ({}) {{\n{}{}}}\n", indent, condition, then_code, indent)
+                } else {
+                    format!("{}if ({}) {{\n{}{}}} else {{\n{}{}}}\n", indent, condition, then_code, indent, else_code, indent)
+                }
+            }
+            Instruction::While(condition, block, _) => {
+                let condition = self.compile_condition(program, condition);
+                let block_code = self.compile_block(program, block, depth + 1);
+
+                format!("{}while ({}) {{\n{}{}}}\n", indent, condition, block_code, indent)
+            }
+            Instruction::Push(slot, expression) => {
+                let (expression, _) = self.compile_expression(program, expression);
+                let runtime_type = Self::runtime_type(&program.variable(*slot).variable_type);
+                format!("{}{}_assign(&{}, {});\n", indent, runtime_type, Self::local_name(program, *slot), expression)
+            }
+            Instruction::Pop(slot) => {
+                let runtime_type = Self::runtime_type(&program.variable(*slot).variable_type);
+                format!("{}{}_unassign(&{});\n", indent, runtime_type, Self::local_name(program, *slot))
+            }
+            Instruction::Set(slot, expression) => {
+                let (expression, _) = self.compile_expression(program, expression);
+                format!("{}{}.value = {};\n", indent, Self::local_name(program, *slot), expression)
+            }
+        }
+    }
+
+    fn bracket_arithmetic(code: String, expression_precedence: usize, precedence: usize) -> String {
+        if expression_precedence < precedence {
+            format!("({})", code)
+        } else {
+            code
+        }
+    }
+
+    // the code for an expression and how tightly it binds
+    fn compile_expression(&mut self, program: &Program, expression: &Expression) -> (String, usize) {
+        let precedence = arithmetic_precedence(expression);
+        let code = match expression {
+            Expression::Variable(slot, _) => format!("{}.value", Self::local_name(program, *slot)),
+            Expression::Integer(value) => {
+                if *value == i32::MIN {
+                    String::from("INT32_MIN")
+                } else {
+                    value.to_string()
+                }
+            }
+            Expression::Float(value) => float_literal(value.into_inner()),
+            Expression::Convert(Type::Float, operand) => return self.compile_expression(program, operand),
+            Expression::Convert(Type::Integer, operand) => format!("rimp_to_int({})", self.compile_expression(program, operand).0),
+            Expression::Negation(Type::Integer, operand) => format!("rimp_neg({})", self.compile_expression(program, operand).0),
+            Expression::Negation(Type::Float, operand) => {
+                // anything that is not a plain operand is bracketed, so we never emit --x
+                let (code, operand_precedence) = self.compile_expression(program, operand);
+                format!("-{}", Self::bracket_arithmetic(code, operand_precedence, 4))
+            }
+            Expression::Operation(Type::Integer, operator, left, right) => {
+                let function = match operator {
+                    ArithmeticOperator::Addition => "rimp_add",
+                    ArithmeticOperator::Subtraction => "rimp_sub",
+                    ArithmeticOperator::Multiplication => "rimp_mul",
+                    ArithmeticOperator::Division => "rimp_div",
+                    ArithmeticOperator::Exponentiation => "rimp_pow",
+                };
+                let (lhs, _) = self.compile_expression(program, left);
+                let (rhs, _) = self.compile_expression(program, right);
+                format!("{}({}, {})", function, lhs, rhs)
+            }
+            Expression::Operation(Type::Float, ArithmeticOperator::Exponentiation, left, right) => {
+                let (lhs, _) = self.compile_expression(program, left);
+                let (rhs, _) = self.compile_expression(program, right);
+                format!("rimp_powf({}, {})", lhs, rhs)
+            }
+            Expression::Operation(Type::Float, operator, left, right) => {
+                let (lhs, lhs_precedence) = self.compile_expression(program, left);
+                let (rhs, rhs_precedence) = self.compile_expression(program, right);
+
+                // the left operand binds as tightly as this operator, the right has to bind tighter (a - (b - c))
+                let lhs = Self::bracket_arithmetic(lhs, lhs_precedence, precedence);
+                let rhs = Self::bracket_arithmetic(rhs, rhs_precedence, precedence + 1);
+
+                let operator = match operator {
+                    ArithmeticOperator::Addition => "+",
+                    ArithmeticOperator::Subtraction => "-",
+                    ArithmeticOperator::Multiplication => "*",
+                    ArithmeticOperator::Division => "/",
+                    ArithmeticOperator::Exponentiation => unreachable!(),
+                };
+
+                format!("{} {} {}", lhs, operator, rhs)
+            }
+            _ => unreachable!("bigints are only compiled by the jvm backend"),
+        };
+        (code, precedence)
+    }
+
+    fn bracket_boolean(&mut self, program: &Program, condition: &Condition, precedence: usize) -> String {
+        let code = self.compile_condition(program, condition);
+        if boolean_precedence(condition) < precedence {
+            format!("({})", code)
+        } else {
+            code
+        }
+    }
+
+    fn compile_condition(&mut self, program: &Program, condition: &Condition) -> String {
+        match condition {
+            Condition::Not(operand) => format!("!{}", self.bracket_boolean(program, operand, 4)),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let precedence = boolean_precedence(condition);
+                let lhs = self.bracket_boolean(program, left, precedence);
+                let rhs = self.bracket_boolean(program, right, precedence + 1);
+
+                match condition {
+                    Condition::And(_, _) => format!("{} && {}", lhs, rhs),
+                    _ => format!("{} || {}", lhs, rhs),
+                }
+            }
+            Condition::Comparison(_, operator, left, right) => {
+                let (lhs, _) = self.compile_expression(program, left);
+                let (rhs, _) = self.compile_expression(program, right);
+
+                let operator = match operator {
+                    RelationOperator::Equal => "==",
+                    RelationOperator::NotEqual => "!=",
+                    RelationOperator::LessThan => "<",
+                    RelationOperator::GreaterThan => ">",
+                };
+
+                format!("{} {} {}", lhs, operator, rhs)
+            }
+        }
+    }
+}
+
+impl Backend for CCompiler {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
+        let mut compiler = CCompiler::new();
+        compiler._compile(&program, "main.rimp")
+    }
+}
@@ -0,0 +1,4 @@
+pub mod code_gen;
+
+#[cfg(test)]
+mod tests;
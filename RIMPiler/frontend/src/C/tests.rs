@@ -0,0 +1,84 @@
+use std::process::Command;
+use super::code_gen::CCompiler;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::fixtures::{build, example, folder, installed, inverted_program, matches_printed, run, EXAMPLES};
+use super::super::Backend;
+
+#[test]
+fn readable_source() {
+    let inverted = inverted_program(r#"int n = 2;
+float free = 0.5;
+while n > 0 do {
+    n = n - 1;
+    free = (free - (n - 1)) * 2.5;
+};"#);
+
+    let source = CCompiler::compile(&inverted);
+
+    assert!(source.contains("    rimp_int n;\n    rimp_float free_;\n"));
+    assert!(source.contains("    rimp_float_init(&free_, \"free\");\n"));
+    // n = n - 1 is undone by adding 1 back, so n never needs its history
+    assert!(source.contains("    /* forward program */\n    n.value = 2;\n"));
+    assert!(source.contains("    while (n.value > 0) {\n        n.value = rimp_sub(n.value, 1);\n"));
+    assert!(source.contains("rimp_float_assign(&free_, (free_.value - rimp_sub(n.value, 1)) * 2.5f);\n"));
+    assert!(source.contains("    /* reverse point */\n    rimp_int_print(&n);\n    rimp_float_print(&free_);\n"));
+    assert!(source.contains("    /* backward program */\n"));
+    assert!(source.contains("rimp_float_unassign(&free_);\n        n.value = rimp_add(n.value, 1);\n"));
+    assert!(source.contains("    free(n.history);\n    free(free_.history);\n"));
+}
+
+fn run_c(name: &str, source: &str) -> String {
+    let folder = folder("c", name, "main.c", source);
+    build(&folder, "cc", &["-std=c99", "-pedantic", "-Wall", "-Werror", "-o", "main", "main.c", "-lm"]);
+
+    let output = run(&folder, &mut Command::new(folder.join("main")));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn matches_interpreter() {
+    if !installed("cc", "--version") {
+        println!("cc not found, skipping");
+        return;
+    }
+
+    for name in EXAMPLES {
+        let inverted = inverted_program(&example(name));
+
+        let mut interpreter = InterpreterEngine::new();
+        interpreter.interpret(&inverted).unwrap();
+
+        matches_printed(name, &run_c(name, &CCompiler::compile(&inverted)), &interpreter);
+    }
+}
+
+#[test]
+fn java_float_format() {
+    if !installed("cc", "--version") {
+        println!("cc not found, skipping");
+        return;
+    }
+
+    // assigned inside an if, so each history keeps the 0.0 the variable held before
+    let inverted = inverted_program(r#"if 0 < 1 then {
+    float a = 7;
+    float b = 0.1;
+    float c = 12345678;
+    float d = 0.0001;
+    float e = 0 - 2.5;
+    float f = 100;
+} else {
+    skip;
+};"#);
+
+    let stdout = run_c("float_format", &CCompiler::compile(&inverted));
+
+    assert!(stdout.contains("a: 7.0\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("b: 0.1\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("c: 1.2345678E7\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("d: 1.0E-4\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("e: -2.5\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("f: 100.0\t [0.0 0.0 ]\n"));
+}
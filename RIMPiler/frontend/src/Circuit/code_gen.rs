@@ -0,0 +1,1009 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Slot, Type};
+use super::super::Backend;
+
+/*
+Compiles a RIMP program over fixed-width integers into a reversible circuit of NOT, CNOT and Toffoli gates, written
+in the RevLib .real format.
+
+Every variable is a register of `width` lines, least significant bit first, and arithmetic wraps around at that
+width. Like on PISA only the forward program is compiled, the circuit run backwards is the inverted program.
+
+- expressions are computed into registers of ancilla lines holding 0, and uncomputed by running the same gates
+  backwards once the result has been used (Bennett's trick), so ancillas always go back to 0. + and - are Cuccaro
+  ripple-carry adders, * is shift-and-add, / is restoring division on the magnitudes, and ^ is square-and-multiply.
+- assignments that are already reversible (x = x + e, x = x - e, x = -x and first assignments, where x still holds
+  0) update the variable in place. Any other assignment swaps the old value out onto fresh garbage lines, which are
+  never cleaned up and are marked as garbage in the output.
+- a circuit can't branch, so every statement is controlled by a line that says whether it runs. if computes a line
+  for each branch from the condition, which the semantic transformer makes sure doesn't change inside the if, and
+  the lines are uncomputed afterwards with the same condition.
+- while loops are unrolled `unroll` times, iteration i running while the line of iteration i - 1 and the condition
+  are both set. Once the loop is over the line of iteration i is set exactly when the counter added by the semantic
+  transformer is at least i, which is how they are cleaned up. If the condition still holds after the last
+  iteration, the loop needed more iterations than it was unrolled for, and its exceeded line is set.
+
+Without inputs the circuit always computes the same thing. The variables named as inputs have their first
+assignment left out, and their lines are primary inputs instead of constant 0.
+*/
+
+pub const DEFAULT_WIDTH: u32 = 16;
+pub const DEFAULT_UNROLL: u32 = 16;
+
+// a register is a list of lines, least significant bit first
+type Register = Vec<usize>;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum Gate {
+    Not(usize),
+    // control, target
+    ControlledNot(usize, usize),
+    // control, control, target
+    Toffoli(usize, usize, usize),
+}
+
+impl Gate {
+    fn lines(&self) -> Vec<usize> {
+        match self {
+            Gate::Not(target) => vec![*target],
+            Gate::ControlledNot(control, target) => vec![*control, *target],
+            Gate::Toffoli(first, second, target) => vec![*first, *second, *target],
+        }
+    }
+}
+
+// what a line is used for, which decides its name and whether its output is garbage
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Line {
+    // holds 0 whenever it isn't being used
+    Ancilla,
+    // bit of a variable
+    Variable(String, u32),
+    // old value of a variable
+    Garbage,
+    // set if a loop needed more iterations than it was unrolled for
+    Exceeded,
+}
+
+// the size of a compiled circuit, reported by the command line
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct CircuitStatistics {
+    pub gates: usize,
+    pub nots: usize,
+    pub controlled_nots: usize,
+    pub toffolis: usize,
+
+    pub lines: usize,
+    pub variable_lines: usize,
+    pub ancillas: usize,
+    pub garbage: usize,
+    pub exceeded: usize,
+}
+
+impl Display for CircuitStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} gates ({} NOT, {} CNOT, {} Toffoli), {} lines ({} variable lines, {} ancillas, {} garbage lines, {} loop bound lines)",
+            self.gates,
+            self.nots,
+            self.controlled_nots,
+            self.toffolis,
+            self.lines,
+            self.variable_lines,
+            self.ancillas,
+            self.garbage,
+            self.exceeded,
+        )
+    }
+}
+
+fn expression_uses(expression: &Expression, slot: Slot) -> bool {
+    match expression {
+        Expression::Variable(variable, _) => *variable == slot,
+        Expression::Integer(_) | Expression::Float(_) | Expression::BigInteger(_) => false,
+        Expression::Convert(_, expression) | Expression::Negation(_, expression) => expression_uses(expression, slot),
+        Expression::Operation(_, _, left, right) => expression_uses(left, slot) || expression_uses(right, slot),
+    }
+}
+
+fn condition_uses(condition: &Condition, slot: Slot) -> bool {
+    match condition {
+        Condition::Comparison(_, _, left, right) => expression_uses(left, slot) || expression_uses(right, slot),
+        Condition::And(left, right) | Condition::Or(left, right) => condition_uses(left, slot) || condition_uses(right, slot),
+        Condition::Not(condition) => condition_uses(condition, slot),
+    }
+}
+
+fn instruction_uses(instruction: &Instruction, slot: Slot) -> bool {
+    match instruction {
+        Instruction::Push(variable, expression) | Instruction::Set(variable, expression) => {
+            *variable == slot || expression_uses(expression, slot)
+        }
+        Instruction::Pop(variable) => *variable == slot,
+        Instruction::If(condition, then_block, else_block) => {
+            condition_uses(condition, slot)
+                || then_block.iter().any(|instruction| instruction_uses(&instruction.value, slot))
+                || else_block.iter().any(|instruction| instruction_uses(&instruction.value, slot))
+        }
+        Instruction::While(condition, block, _) => {
+            condition_uses(condition, slot) || block.iter().any(|instruction| instruction_uses(&instruction.value, slot))
+        }
+    }
+}
+
+fn check_expression(program: &Program, expression: &Expression) -> Result<(), String> {
+    match expression {
+        Expression::Variable(slot, Type::Float) => Err(format!(
+            "Reversible circuits only have fixed-width integers, but {} is a float",
+            program.variable(*slot).name
+        )),
+        Expression::Float(value) => Err(format!("Reversible circuits only have fixed-width integers, but {} is a float", value)),
+        Expression::Variable(slot, Type::BigInteger) => Err(format!(
+            "Reversible circuits only have fixed-width integers, but {} is a bigint",
+            program.variable(*slot).name
+        )),
+        Expression::BigInteger(value) => Err(format!("Reversible circuits only have fixed-width integers, but {} is a bigint", value)),
+        Expression::Variable(_, _) | Expression::Integer(_) => Ok(()),
+        Expression::Convert(_, expression) | Expression::Negation(_, expression) => check_expression(program, expression),
+        Expression::Operation(_, _, left, right) => check_expression(program, left).and(check_expression(program, right)),
+    }
+}
+
+fn check_condition(program: &Program, condition: &Condition) -> Result<(), String> {
+    match condition {
+        Condition::Comparison(_, _, left, right) => check_expression(program, left).and(check_expression(program, right)),
+        Condition::And(left, right) | Condition::Or(left, right) => {
+            check_condition(program, left).and(check_condition(program, right))
+        }
+        Condition::Not(condition) => check_condition(program, condition),
+    }
+}
+
+// everything that can't be compiled is found before any gates are generated
+fn check_block(program: &Program, block: &Block) -> Result<(), String> {
+    for instruction in block {
+        match &instruction.value {
+            Instruction::Push(slot, _) | Instruction::Set(slot, _) if program.variable(*slot).variable_type == Type::Float => {
+                return Err(format!(
+                    "Reversible circuits only have fixed-width integers, but {} is a float",
+                    program.variable(*slot).name
+                ));
+            }
+            Instruction::Push(slot, _) | Instruction::Set(slot, _) if program.variable(*slot).variable_type == Type::BigInteger => {
+                return Err(format!(
+                    "Reversible circuits only have fixed-width integers, but {} is a bigint",
+                    program.variable(*slot).name
+                ));
+            }
+            Instruction::Push(_, expression) | Instruction::Set(_, expression) => check_expression(program, expression)?,
+            Instruction::If(condition, then_block, else_block) => {
+                check_condition(program, condition)?;
+                check_block(program, then_block)?;
+                check_block(program, else_block)?;
+            }
+            Instruction::While(condition, block, counter) => {
+                check_condition(program, condition)?;
+                if counter.is_none() {
+                    return Err(String::from("Loops need the counter added by the semantic transformer to be unrolled"));
+                }
+                check_block(program, block)?;
+            }
+            Instruction::Pop(_) => {
+                return Err(String::from("Reverse assignments can't be compiled, the circuit is run backwards instead"));
+            }
+        }
+    }
+    Ok(())
+}
+
+// a line that is only set when control is (or always, without a control)
+fn and(control: Option<usize>, line: usize, target: usize) -> Gate {
+    match control {
+        None => Gate::ControlledNot(line, target),
+        Some(control) => toffoli(control, line, target),
+    }
+}
+
+// a Toffoli gate can't use the same line as both controls, which happens when squaring
+fn toffoli(first: usize, second: usize, target: usize) -> Gate {
+    if first == second {
+        Gate::ControlledNot(first, target)
+    } else {
+        Gate::Toffoli(first, second, target)
+    }
+}
+
+fn flip(control: Option<usize>, target: usize) -> Gate {
+    match control {
+        None => Gate::Not(target),
+        Some(control) => Gate::ControlledNot(control, target),
+    }
+}
+
+fn copy(control: Option<usize>, from: &[usize], to: &[usize]) -> Vec<Gate> {
+    from.iter().zip(to).map(|(from, to)| and(control, *from, *to)).collect()
+}
+
+fn inverse(gates: &[Gate]) -> Vec<Gate> {
+    // every gate is its own inverse
+    gates.iter().rev().copied().collect()
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CircuitCompiler {
+    width: u32,
+    unroll: u32,
+    inputs: Vec<String>,
+
+    lines: Vec<Line>,
+    // ancilla lines that hold 0 and aren't being used, taken from the back
+    free: Vec<usize>,
+
+    // variable names by slot
+    names: Vec<String>,
+    // variable name -> its register, and the names in the order they were first used
+    variables: HashMap<String, Register>,
+    declarations: Vec<String>,
+    // variables that may no longer hold 0 at the current point of the program
+    assigned: HashSet<Slot>,
+
+    statistics: CircuitStatistics,
+}
+
+impl CircuitCompiler {
+    pub fn new(width: u32, unroll: u32, inputs: Vec<String>) -> Self {
+        CircuitCompiler {
+            width,
+            unroll,
+            inputs,
+            lines: Vec::new(),
+            free: Vec::new(),
+            names: Vec::new(),
+            variables: HashMap::new(),
+            declarations: Vec::new(),
+            assigned: HashSet::new(),
+            statistics: CircuitStatistics::default(),
+        }
+    }
+
+    // the size of the last circuit compiled
+    pub fn statistics(&self) -> &CircuitStatistics {
+        &self.statistics
+    }
+
+    pub fn _compile(&mut self, program: &Program) -> Result<String, String> {
+        if !(2..=64).contains(&self.width) {
+            return Err(format!("The width has to be between 2 and 64 bits, not {}", self.width));
+        }
+        // loop counters have to be able to count every iteration
+        if u64::from(self.unroll) >= 1 << (self.width - 1) {
+            return Err(format!("Loops can't be unrolled {} times with {}-bit counters", self.unroll, self.width));
+        }
+
+        // the backward program is the forward one run backwards, so it is left out
+        let mut forward = program.forward.clone();
+
+        check_block(program, &forward)?;
+
+        self.names = program.variables.iter().map(|variable| variable.name.clone()).collect();
+
+        for input in self.inputs.clone() {
+            // the first instruction using an input has to be the assignment that is replaced by the input lines
+            let slot = program.slot(&input);
+            let first = slot.and_then(|slot| forward.iter().position(|instruction| instruction_uses(&instruction.value, slot)));
+            match first.map(|index| &forward[index].value) {
+                Some(Instruction::Push(variable, expression) | Instruction::Set(variable, expression))
+                    if Some(*variable) == slot && !expression_uses(expression, *variable) =>
+                {
+                    forward.remove(first.unwrap());
+                }
+                Some(_) => {
+                    return Err(format!("The input {} has to be assigned a value outside of any if or while before it is used", input));
+                }
+                None => return Err(format!("The input {} isn't used by the program", input)),
+            }
+            self.variable(&input);
+            self.assigned.insert(slot.unwrap());
+        }
+
+        let gates = self.compile_block(&forward, None);
+
+        Ok(self.render(&gates))
+    }
+
+    fn allocate(&mut self, size: u32) -> Register {
+        (0..size)
+            .map(|_| match self.free.pop() {
+                Some(line) => line,
+                None => {
+                    self.lines.push(Line::Ancilla);
+                    self.lines.len() - 1
+                }
+            })
+            .collect()
+    }
+
+    // the lines have to hold 0 again
+    fn release(&mut self, register: &[usize]) {
+        self.free.extend(register.iter().rev());
+    }
+
+    fn garbage(&mut self) -> Register {
+        let register = self.allocate(self.width);
+        for line in &register {
+            self.lines[*line] = Line::Garbage;
+        }
+        register
+    }
+
+    fn variable(&mut self, name: &str) -> Register {
+        if let Some(register) = self.variables.get(name) {
+            return register.clone();
+        }
+
+        let register = self.allocate(self.width);
+        for (bit, line) in register.iter().enumerate() {
+            self.lines[*line] = Line::Variable(name.to_string(), bit as u32);
+        }
+        self.variables.insert(name.to_string(), register.clone());
+        self.declarations.push(name.to_string());
+        register
+    }
+
+    fn slot_variable(&mut self, slot: Slot) -> Register {
+        let name = self.names[slot].clone();
+        self.variable(&name)
+    }
+
+    // runs gates, then the gates returned by body, then gates backwards, so whatever gates compute is only there
+    // while body runs. The ancillas gates use are kept from body, or they might not hold 0 when gates are undone
+    fn around(&mut self, gates: Vec<Gate>, body: impl FnOnce(&mut Self) -> Vec<Gate>) -> Vec<Gate> {
+        let used = gates.iter().flat_map(Gate::lines).collect::<HashSet<usize>>();
+        let (reserved, free): (Vec<usize>, Vec<usize>) = self.free.drain(..).partition(|line| used.contains(line));
+        self.free = free;
+
+        let mut result = gates.clone();
+        result.extend(body(self));
+        result.extend(inverse(&gates));
+
+        self.free.extend(reserved);
+        result
+    }
+
+    fn compile_block(&mut self, block: &Block, control: Option<usize>) -> Vec<Gate> {
+        block.iter().flat_map(|instruction| self.compile_instruction(&instruction.value, control)).collect()
+    }
+
+    fn compile_instruction(&mut self, instruction: &Instruction, control: Option<usize>) -> Vec<Gate> {
+        match instruction {
+            // whether an assignment can be undone without garbage is worked out again for the circuit
+            Instruction::Push(slot, expression) | Instruction::Set(slot, expression) => self.compile_assignment(*slot, expression, control),
+            Instruction::If(condition, then_block, else_block) => {
+                let branches = self.allocate(2);
+                let (then_line, else_line) = (branches[0], branches[1]);
+
+                let mut gates = self.select(condition, control, then_line, else_line);
+
+                // a variable first assigned in one branch still holds 0 in the other
+                let before = self.assigned.clone();
+                gates.extend(self.compile_block(then_block, Some(then_line)));
+                let after_then = std::mem::replace(&mut self.assigned, before);
+                gates.extend(self.compile_block(else_block, Some(else_line)));
+                self.assigned.extend(after_then);
+
+                // the condition is the same as before the branches, so selecting again clears the lines
+                gates.extend(self.select(condition, control, then_line, else_line));
+                self.release(&branches);
+                gates
+            }
+            Instruction::While(condition, block, counter) => {
+                let counter = self.slot_variable(counter.unwrap());
+
+                let mut gates = vec![];
+                let mut iterations = vec![];
+                let mut running = control;
+                for _ in 0..self.unroll {
+                    let iteration = self.allocate(1)[0];
+                    gates.extend(self.step(condition, running, iteration));
+                    gates.extend(self.compile_block(block, Some(iteration)));
+                    iterations.push(iteration);
+                    running = Some(iteration);
+                }
+
+                let exceeded = self.allocate(1)[0];
+                self.lines[exceeded] = Line::Exceeded;
+                gates.extend(self.step(condition, running, exceeded));
+
+                // iteration i ran exactly when the loop was run and the counter is at least i
+                for (index, iteration) in iterations.iter().enumerate() {
+                    let at_least = self.allocate(1)[0];
+                    let number = self.allocate(self.width);
+
+                    let mut compare = self.constant(index as i64 + 1, &number, None);
+                    compare.extend(self.less_than(&counter, &number, true, at_least));
+                    compare.push(Gate::Not(at_least));
+                    gates.extend(self.around(compare, |_| vec![and(control, at_least, *iteration)]));
+
+                    self.release(&number);
+                    self.release(&[at_least, *iteration]);
+                }
+                gates
+            }
+            Instruction::Pop(_) => unreachable!("Reverse assignments are rejected by check_block"),
+        }
+    }
+
+    fn compile_assignment(&mut self, slot: Slot, expression: &Expression, control: Option<usize>) -> Vec<Gate> {
+        let name = self.names[slot].clone();
+        let register = self.variable(&name);
+
+        if let Some(gates) = self.reversible_update(slot, &register, expression, control) {
+            self.assigned.insert(slot);
+            return gates;
+        }
+
+        let self_referential = expression_uses(expression, slot);
+
+        // the variable still holds 0, so the value can be put straight into it
+        if !self.assigned.contains(&slot) && !self_referential {
+            self.assigned.insert(slot);
+            return match control {
+                None => self.compute(expression, &register),
+                Some(_) => {
+                    let value = self.allocate(self.width);
+                    let compute = self.compute(expression, &value);
+                    let gates = self.around(compute, |_| copy(control, &value, &register));
+                    self.release(&value);
+                    gates
+                }
+            };
+        }
+
+        let mut gates = vec![];
+
+        // the new value is computed from a copy of the old one, as the variable is cleared before it is set
+        let old = if self_referential {
+            let old = self.allocate(self.width);
+            gates.extend(copy(None, &register, &old));
+            Some(old)
+        } else {
+            None
+        };
+
+        let garbage = self.garbage();
+        let value = self.allocate(self.width);
+        let compute = match &old {
+            Some(old) => {
+                let variable = self.variables.insert(name.clone(), old.clone()).unwrap();
+                let compute = self.compute(expression, &value);
+                self.variables.insert(name, variable);
+                compute
+            }
+            None => self.compute(expression, &value),
+        };
+        gates.extend(self.around(compute, |_| {
+            // the old value is swapped onto the garbage lines, leaving 0 behind
+            let mut gates = copy(control, &register, &garbage);
+            gates.extend(copy(None, &garbage, &register));
+            gates.extend(copy(control, &value, &register));
+            gates
+        }));
+        self.release(&value);
+
+        if let Some(old) = old {
+            // the old value is either on the garbage lines, or still in the variable if the assignment didn't run
+            gates.extend(copy(None, &garbage, &old));
+            if let Some(control) = control {
+                gates.push(Gate::Not(control));
+                gates.extend(copy(Some(control), &register, &old));
+                gates.push(Gate::Not(control));
+            }
+            self.release(&old);
+        }
+
+        self.assigned.insert(slot);
+        gates
+    }
+
+    // assignments that can be done in place: x = x, x = -x, x = x + e, x = e + x and x = x - e, where e doesn't use x
+    fn reversible_update(
+        &mut self,
+        slot: Slot,
+        register: &Register,
+        expression: &Expression,
+        control: Option<usize>,
+    ) -> Option<Vec<Gate>> {
+        let is_variable = |expression: &Expression| matches!(expression, Expression::Variable(variable, _) if *variable == slot);
+
+        match expression {
+            _ if is_variable(expression) => Some(vec![]),
+            Expression::Negation(_, expression) if is_variable(expression) => Some(self.negate(control, register)),
+            Expression::Operation(_, operator, left, right) => {
+                let (other, subtract) = match operator {
+                    ArithmeticOperator::Addition if is_variable(left) => (right, false),
+                    ArithmeticOperator::Addition if is_variable(right) => (left, false),
+                    ArithmeticOperator::Subtraction if is_variable(left) => (right, true),
+                    _ => return None,
+                };
+                if expression_uses(other, slot) {
+                    return None;
+                }
+
+                let (value, compute, temporary) = self.operand(other);
+                let gates = self.around(compute, |compiler| compiler.controlled_add(control, &value, register, subtract));
+                if temporary {
+                    self.release(&value);
+                }
+                Some(gates)
+            }
+            _ => None,
+        }
+    }
+
+    // puts value into a register holding 0
+    fn constant(&self, value: i64, register: &[usize], control: Option<usize>) -> Vec<Gate> {
+        register
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| (value >> (*bit).min(63)) & 1 == 1)
+            .map(|(_, line)| flip(control, *line))
+            .collect()
+    }
+
+    // a variable is used as it is, anything else is computed into ancillas, which are returned with true
+    fn operand(&mut self, expression: &Expression) -> (Register, Vec<Gate>, bool) {
+        if let Expression::Variable(slot, _) = expression {
+            return (self.slot_variable(*slot), vec![], false);
+        }
+
+        let register = self.allocate(self.width);
+        let gates = self.compute(expression, &register);
+        (register, gates, true)
+    }
+
+    // the value of expression xor-ed into target, which holds 0
+    fn compute(&mut self, expression: &Expression, target: &Register) -> Vec<Gate> {
+        match expression {
+            Expression::Integer(value) => self.constant(i64::from(*value), target, None),
+            Expression::Float(_) | Expression::BigInteger(_) | Expression::Convert(_, _) => {
+                unreachable!("Floats and bigints are rejected by check_block")
+            }
+            Expression::Variable(slot, _) => {
+                let register = self.slot_variable(*slot);
+                copy(None, &register, target)
+            }
+            Expression::Negation(_, expression) => {
+                let mut gates = self.compute(expression, target);
+                gates.extend(self.negate(None, target));
+                gates
+            }
+            Expression::Operation(_, operator @ (ArithmeticOperator::Addition | ArithmeticOperator::Subtraction), left, right) => {
+                let mut gates = self.compute(left, target);
+                let (value, compute, temporary) = self.operand(right);
+                let subtract = *operator == ArithmeticOperator::Subtraction;
+                gates.extend(self.around(compute, |compiler| compiler.controlled_add(None, &value, target, subtract)));
+                if temporary {
+                    self.release(&value);
+                }
+                gates
+            }
+            Expression::Operation(_, operator, left, right) => {
+                let (x, mut compute, x_temporary) = self.operand(left);
+                let (y, compute_y, y_temporary) = self.operand(right);
+                compute.extend(compute_y);
+
+                let gates = self.around(compute, |compiler| match operator {
+                    ArithmeticOperator::Multiplication => compiler.multiply(&x, &y, target),
+                    ArithmeticOperator::Division => compiler.divide(&x, &y, target),
+                    ArithmeticOperator::Exponentiation => compiler.power(&x, &y, target),
+                    ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => unreachable!(),
+                });
+
+                if y_temporary {
+                    self.release(&y);
+                }
+                if x_temporary {
+                    self.release(&x);
+                }
+                gates
+            }
+        }
+    }
+
+    // b += a (or b -= a) with a Cuccaro ripple-carry adder, using one ancilla for the carry in
+    fn add(&mut self, a: &[usize], b: &[usize], subtract: bool) -> Vec<Gate> {
+        let bits = b.len();
+        if bits == 1 {
+            return vec![Gate::ControlledNot(a[0], b[0])];
+        }
+
+        let carry = self.allocate(1)[0];
+        // the carry into bit i ends up on the line before it
+        let before = |bit: usize| if bit == 0 { carry } else { a[bit - 1] };
+
+        let mut gates = vec![];
+        for bit in 0..bits - 1 {
+            // majority
+            gates.push(Gate::ControlledNot(a[bit], b[bit]));
+            gates.push(Gate::ControlledNot(a[bit], before(bit)));
+            gates.push(Gate::Toffoli(before(bit), b[bit], a[bit]));
+        }
+        // the carry out of the top bit is thrown away
+        gates.push(Gate::ControlledNot(a[bits - 1], b[bits - 1]));
+        gates.push(Gate::ControlledNot(before(bits - 1), b[bits - 1]));
+        for bit in (0..bits - 1).rev() {
+            // unmajority and add
+            gates.push(Gate::Toffoli(before(bit), b[bit], a[bit]));
+            gates.push(Gate::ControlledNot(a[bit], before(bit)));
+            gates.push(Gate::ControlledNot(before(bit), b[bit]));
+        }
+        self.release(&[carry]);
+
+        if subtract {
+            inverse(&gates)
+        } else {
+            gates
+        }
+    }
+
+    // the adder is uncontrolled, so a controlled addition adds a copy of a that is 0 when control isn't set
+    fn controlled_add(&mut self, control: Option<usize>, a: &[usize], b: &[usize], subtract: bool) -> Vec<Gate> {
+        match control {
+            None => self.add(a, b, subtract),
+            Some(_) => {
+                let masked = self.allocate(a.len() as u32);
+                let mask = copy(control, a, &masked);
+                let gates = self.around(mask, |compiler| compiler.add(&masked, b, subtract));
+                self.release(&masked);
+                gates
+            }
+        }
+    }
+
+    // two's complement: flip every bit and add 1
+    fn negate(&mut self, control: Option<usize>, register: &[usize]) -> Vec<Gate> {
+        let mut gates = register.iter().map(|line| flip(control, *line)).collect::<Vec<Gate>>();
+
+        let one = self.allocate(register.len() as u32);
+        let set = self.constant(1, &one, control);
+        gates.extend(self.around(set, |compiler| compiler.add(&one, register, false)));
+        self.release(&one);
+        gates
+    }
+
+    // target += x shifted left by i, for every bit i of y that is set
+    fn multiply(&mut self, x: &[usize], y: &[usize], target: &[usize]) -> Vec<Gate> {
+        let bits = target.len();
+        (0..bits)
+            .flat_map(|bit| self.controlled_add(Some(y[bit]), &x[..bits - bit], &target[bit..], false))
+            .collect()
+    }
+
+    // the quotient, rounded towards 0. The divisions of the magnitudes leave a remainder behind at every step,
+    // so everything is run backwards once the quotient has been copied out
+    fn divide(&mut self, x: &[usize], y: &[usize], target: &[usize]) -> Vec<Gate> {
+        let bits = target.len();
+        let mut forward = vec![];
+        let mut held = vec![];
+
+        let mut magnitude = |compiler: &mut Self, register: &[usize], forward: &mut Vec<Gate>| {
+            let copied = compiler.allocate(bits as u32);
+            forward.extend(copy(None, register, &copied));
+            forward.extend(compiler.negate(Some(register[bits - 1]), &copied));
+            held.push(copied.clone());
+            copied
+        };
+        let dividend = magnitude(self, x, &mut forward);
+        let divisor = magnitude(self, y, &mut forward);
+
+        // the divisor with an extra 0 on top, as the partial remainders are a bit wider
+        let zero = self.allocate(1);
+        let wide_divisor = [divisor.as_slice(), zero.as_slice()].concat();
+
+        let quotient = self.allocate(bits as u32);
+        let mut remainder: Option<Register> = None;
+        for bit in (0..bits).rev() {
+            // the next partial remainder is the last one shifted left with the next bit of the dividend
+            let shifted = self.allocate(bits as u32 + 1);
+            if let Some(remainder) = &remainder {
+                forward.extend(copy(None, &remainder[..bits], &shifted[1..]));
+            }
+            forward.push(Gate::ControlledNot(dividend[bit], shifted[0]));
+
+            // the divisor is subtracted when it fits, and the bit of the quotient says whether it did
+            let less = self.allocate(1)[0];
+            forward.extend(self.less_than(&shifted, &wide_divisor, false, less));
+            forward.push(Gate::Not(quotient[bit]));
+            forward.push(Gate::ControlledNot(less, quotient[bit]));
+            forward.push(Gate::ControlledNot(quotient[bit], less));
+            forward.push(Gate::Not(less));
+            self.release(&[less]);
+            forward.extend(self.controlled_add(Some(quotient[bit]), &wide_divisor, &shifted, true));
+
+            if let Some(remainder) = remainder.replace(shifted) {
+                held.push(remainder);
+            }
+        }
+        held.extend(remainder);
+
+        // the quotient is negative when exactly one of x and y is
+        let sign = self.allocate(1)[0];
+        forward.push(Gate::ControlledNot(x[bits - 1], sign));
+        forward.push(Gate::ControlledNot(y[bits - 1], sign));
+        forward.extend(self.negate(Some(sign), &quotient));
+
+        let gates = self.around(forward, |_| copy(None, &quotient, target));
+
+        self.release(&[sign]);
+        self.release(&quotient);
+        self.release(&zero);
+        for register in held.iter().rev() {
+            self.release(register);
+        }
+        gates
+    }
+
+    // square-and-multiply, keeping every power of x and partial product until the result has been copied out
+    fn power(&mut self, x: &[usize], y: &[usize], target: &[usize]) -> Vec<Gate> {
+        let bits = target.len();
+        let mut forward = vec![];
+        let mut held = vec![];
+
+        let mut product = self.allocate(bits as u32);
+        forward.extend(self.constant(1, &product, None));
+        let mut square = x.to_vec();
+
+        // the sign bit of y is left out, negative powers aren't defined
+        for (bit, set) in y[..bits - 1].iter().enumerate() {
+            let multiplied = self.allocate(bits as u32);
+            forward.extend(self.multiply(&product, &square, &multiplied));
+
+            // the next product is the multiplied one if the bit is set, or the last one otherwise
+            let next = self.allocate(bits as u32);
+            forward.extend(copy(Some(*set), &multiplied, &next));
+            forward.push(Gate::Not(*set));
+            forward.extend(copy(Some(*set), &product, &next));
+            forward.push(Gate::Not(*set));
+
+            held.push(multiplied);
+            held.push(std::mem::replace(&mut product, next));
+
+            if bit < bits - 2 {
+                let squared = self.allocate(bits as u32);
+                forward.extend(self.multiply(&square, &square, &squared));
+                held.push(std::mem::replace(&mut square, squared));
+            }
+        }
+        // x itself isn't an ancilla
+        held.retain(|register| register.as_slice() != x);
+        if square.as_slice() != x {
+            held.push(square);
+        }
+
+        let gates = self.around(forward, |_| copy(None, &product, target));
+
+        held.push(product);
+        for register in held.iter().rev() {
+            self.release(register);
+        }
+        gates
+    }
+
+    // flag ^= x < y, from the sign of x - y one bit wider than x and y so it can't overflow
+    fn less_than(&mut self, x: &[usize], y: &[usize], signed: bool, flag: usize) -> Vec<Gate> {
+        let bits = x.len().max(y.len()) + 1;
+
+        let difference = self.allocate(bits as u32);
+        let subtrahend = self.allocate(bits as u32);
+        let mut forward = vec![];
+        for (register, wide) in [(x, &difference), (y, &subtrahend)] {
+            forward.extend(copy(None, register, wide));
+            if signed {
+                for line in &wide[register.len()..] {
+                    forward.push(Gate::ControlledNot(register[register.len() - 1], *line));
+                }
+            }
+        }
+        forward.extend(self.add(&subtrahend, &difference, true));
+
+        let gates = self.around(forward, |_| vec![Gate::ControlledNot(difference[bits - 1], flag)]);
+        self.release(&subtrahend);
+        self.release(&difference);
+        gates
+    }
+
+    // flag ^= x == y, by and-ing together the negated bits of x ^ y
+    fn equal(&mut self, x: &[usize], y: &[usize], flag: usize) -> Vec<Gate> {
+        let bits = x.len();
+
+        let different = self.allocate(bits as u32);
+        let mut forward = copy(None, x, &different);
+        forward.extend(copy(None, y, &different));
+        forward.extend(different.iter().map(|line| Gate::Not(*line)));
+
+        // all the bits but the last are and-ed together
+        let chain = self.allocate(bits as u32 - 2);
+        let mut all = different[0];
+        for (line, next) in different[1..bits - 1].iter().zip(&chain) {
+            forward.push(Gate::Toffoli(all, *line, *next));
+            all = *next;
+        }
+
+        let gates = self.around(forward, |_| vec![Gate::Toffoli(all, different[bits - 1], flag)]);
+        self.release(&chain);
+        self.release(&different);
+        gates
+    }
+
+    // flag ^= condition
+    fn condition(&mut self, condition: &Condition, flag: usize) -> Vec<Gate> {
+        match condition {
+            Condition::Comparison(_, operator, left, right) => {
+                let (x, mut compute, x_temporary) = self.operand(left);
+                let (y, compute_y, y_temporary) = self.operand(right);
+                compute.extend(compute_y);
+
+                let gates = self.around(compute, |compiler| match operator {
+                    RelationOperator::LessThan => compiler.less_than(&x, &y, true, flag),
+                    RelationOperator::GreaterThan => compiler.less_than(&y, &x, true, flag),
+                    RelationOperator::Equal => compiler.equal(&x, &y, flag),
+                    RelationOperator::NotEqual => {
+                        let mut gates = compiler.equal(&x, &y, flag);
+                        gates.push(Gate::Not(flag));
+                        gates
+                    }
+                });
+
+                if y_temporary {
+                    self.release(&y);
+                }
+                if x_temporary {
+                    self.release(&x);
+                }
+                gates
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let operands = self.allocate(2);
+                let (a, b) = (operands[0], operands[1]);
+                let mut compute = self.condition(left, a);
+                compute.extend(self.condition(right, b));
+
+                let gates = self.around(compute, |_| match condition {
+                    Condition::And(_, _) => vec![Gate::Toffoli(a, b, flag)],
+                    _ => vec![Gate::ControlledNot(a, flag), Gate::ControlledNot(b, flag), Gate::Toffoli(a, b, flag)],
+                });
+                self.release(&operands);
+                gates
+            }
+            Condition::Not(condition) => {
+                let mut gates = self.condition(condition, flag);
+                gates.push(Gate::Not(flag));
+                gates
+            }
+        }
+    }
+
+    // flag ^= running && condition
+    fn step(&mut self, condition: &Condition, running: Option<usize>, flag: usize) -> Vec<Gate> {
+        let value = self.allocate(1)[0];
+        let compute = self.condition(condition, value);
+        let gates = self.around(compute, |_| vec![and(running, value, flag)]);
+        self.release(&[value]);
+        gates
+    }
+
+    // then_line ^= control && condition, else_line ^= control && !condition
+    fn select(&mut self, condition: &Condition, control: Option<usize>, then_line: usize, else_line: usize) -> Vec<Gate> {
+        let value = self.allocate(1)[0];
+        let compute = self.condition(condition, value);
+        let gates = self.around(compute, |_| {
+            vec![
+                and(control, value, then_line),
+                Gate::Not(value),
+                and(control, value, else_line),
+                Gate::Not(value),
+            ]
+        });
+        self.release(&[value]);
+        gates
+    }
+
+    fn render(&mut self, gates: &[Gate]) -> String {
+        // variables first, bit by bit, then everything else
+        let mut order = vec![];
+        for name in &self.declarations {
+            order.extend(self.variables[name].iter().copied());
+        }
+        for kind in [Line::Exceeded, Line::Garbage, Line::Ancilla] {
+            order.extend((0..self.lines.len()).filter(|line| self.lines[*line] == kind));
+        }
+
+        let mut counts = HashMap::new();
+        let mut names = vec![String::new(); self.lines.len()];
+        for line in &order {
+            let kind = match &self.lines[*line] {
+                Line::Variable(name, bit) => {
+                    names[*line] = format!("{}_{}", name, bit);
+                    continue;
+                }
+                Line::Exceeded => "exceeded",
+                Line::Garbage => "garbage",
+                Line::Ancilla => "ancilla",
+            };
+            let count = counts.entry(kind).or_insert(0);
+            names[*line] = format!("{}{}", kind, count);
+            *count += 1;
+        }
+
+        self.statistics = CircuitStatistics {
+            gates: gates.len(),
+            nots: gates.iter().filter(|gate| matches!(gate, Gate::Not(_))).count(),
+            controlled_nots: gates.iter().filter(|gate| matches!(gate, Gate::ControlledNot(_, _))).count(),
+            toffolis: gates.iter().filter(|gate| matches!(gate, Gate::Toffoli(_, _, _))).count(),
+            lines: self.lines.len(),
+            variable_lines: self.declarations.len() * self.width as usize,
+            ancillas: counts.get("ancilla").copied().unwrap_or(0),
+            garbage: counts.get("garbage").copied().unwrap_or(0),
+            exceeded: counts.get("exceeded").copied().unwrap_or(0),
+        };
+
+        let inputs = self
+            .inputs
+            .iter()
+            .flat_map(|input| self.variables[input].iter())
+            .collect::<HashSet<&usize>>();
+
+        let mut output = String::new();
+        output.push_str("# Generated by RIMPiler\n");
+        output.push_str(&format!("# {}-bit integers, loops unrolled {} times\n", self.width, self.unroll));
+        output.push_str(&format!("# {}\n", self.statistics));
+        if !self.inputs.is_empty() {
+            output.push_str(&format!("# inputs: {}\n", self.inputs.join(", ")));
+        }
+        output.push_str("# variables are stored least significant bit first, <name>_0 is the lowest bit\n");
+        if self.statistics.exceeded > 0 {
+            output.push_str("# an exceeded line is set when a loop needed more iterations than it was unrolled for\n");
+        }
+
+        let listed = order.iter().map(|line| names[*line].as_str()).collect::<Vec<&str>>().join(" ");
+        output.push_str(".version 1.0\n");
+        output.push_str(&format!(".numvars {}\n", order.len()));
+        output.push_str(&format!(".variables {}\n", listed));
+        output.push_str(&format!(".inputs {}\n", listed));
+        output.push_str(&format!(".outputs {}\n", listed));
+        output.push_str(&format!(
+            ".constants {}\n",
+            order.iter().map(|line| if inputs.contains(line) { '-' } else { '0' }).collect::<String>()
+        ));
+        // ancillas always end up holding 0, so they aren't outputs either
+        output.push_str(&format!(
+            ".garbage {}\n",
+            order
+                .iter()
+                .map(|line| match self.lines[*line] {
+                    Line::Garbage | Line::Ancilla => '1',
+                    Line::Variable(_, _) | Line::Exceeded => '-',
+                })
+                .collect::<String>()
+        ));
+        output.push_str(".begin\n");
+        for gate in gates {
+            let lines = gate.lines().iter().map(|line| names[*line].as_str()).collect::<Vec<&str>>();
+            output.push_str(&format!("t{} {}\n", lines.len(), lines.join(" ")));
+        }
+        output.push_str(".end\n");
+        output
+    }
+}
+
+impl Backend for CircuitCompiler {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
+        let mut compiler = CircuitCompiler::new(DEFAULT_WIDTH, DEFAULT_UNROLL, vec![]);
+        compiler._compile(&program).unwrap()
+    }
+}
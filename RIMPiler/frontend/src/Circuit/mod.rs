@@ -0,0 +1,6 @@
+pub mod code_gen;
+
+#[cfg(test)]
+mod simulator;
+#[cfg(test)]
+mod tests;
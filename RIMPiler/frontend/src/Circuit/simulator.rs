@@ -0,0 +1,109 @@
+/*
+A small simulator for the .real netlists the circuit backend emits, so the tests don't need RevKit.
+
+Netlist::new reads the header and the gates, and initial sets the primary inputs (the lines marked - in .constants)
+to the given values and every other line to its constant. run then applies the gates in order, or in reverse order when
+running backwards. Only NOT, CNOT and Toffoli gates (t1, t2 and t3) are understood.
+*/
+
+use std::collections::HashMap;
+
+pub struct Netlist {
+    pub names: Vec<String>,
+    pub constants: Vec<char>,
+    pub garbage: Vec<char>,
+    // controls, target
+    gates: Vec<(Vec<usize>, usize)>,
+}
+
+impl Netlist {
+    pub fn new(source: &str) -> Result<Netlist, String> {
+        let mut names = vec![];
+        let mut constants = vec![];
+        let mut garbage = vec![];
+        let mut gates = vec![];
+        let mut numvars = None;
+
+        let mut lines = source.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for line in lines.by_ref() {
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match keyword {
+                ".version" | ".inputs" | ".outputs" => {}
+                ".numvars" => numvars = Some(rest.parse::<usize>().map_err(|error| error.to_string())?),
+                ".variables" => names = rest.split_whitespace().map(str::to_string).collect(),
+                ".constants" => constants = rest.chars().collect(),
+                ".garbage" => garbage = rest.chars().collect(),
+                ".begin" => break,
+                _ => return Err(format!("Unexpected line in the header: {}", line)),
+            }
+        }
+
+        if numvars != Some(names.len()) || constants.len() != names.len() || garbage.len() != names.len() {
+            return Err(String::from("The header doesn't describe every line"));
+        }
+        let index = names.iter().enumerate().map(|(index, name)| (name.as_str(), index)).collect::<HashMap<&str, usize>>();
+
+        for line in lines.by_ref() {
+            if line == ".end" {
+                return Ok(Netlist { names, constants, garbage, gates });
+            }
+
+            let mut tokens = line.split_whitespace();
+            let size = match tokens.next() {
+                Some("t1") => 1,
+                Some("t2") => 2,
+                Some("t3") => 3,
+                _ => return Err(format!("Unknown gate {}", line)),
+            };
+            let mut operands = tokens
+                .map(|name| index.get(name).copied().ok_or(format!("Unknown line {}", name)))
+                .collect::<Result<Vec<usize>, String>>()?;
+            if operands.len() != size {
+                return Err(format!("Wrong number of lines in {}", line));
+            }
+
+            let target = operands.pop().unwrap();
+            if operands.contains(&target) || operands.windows(2).any(|pair| pair[0] == pair[1]) {
+                return Err(format!("A line is used twice in {}", line));
+            }
+            gates.push((operands, target));
+        }
+
+        Err(String::from("Missing .end"))
+    }
+
+    pub fn gates(&self) -> usize {
+        self.gates.len()
+    }
+
+    // the value of every line before the circuit runs, given the values of the primary inputs
+    pub fn initial(&self, inputs: &HashMap<String, bool>) -> Result<HashMap<String, bool>, String> {
+        self.names
+            .iter()
+            .zip(&self.constants)
+            .map(|(name, constant)| match constant {
+                '0' => Ok((name.clone(), false)),
+                '1' => Ok((name.clone(), true)),
+                _ => inputs.get(name).map(|value| (name.clone(), *value)).ok_or(format!("No value for the input {}", name)),
+            })
+            .collect()
+    }
+
+    // runs the gates on the values of every line, in reverse order when running backwards
+    pub fn run(&self, state: &HashMap<String, bool>, forwards: bool) -> HashMap<String, bool> {
+        let mut values = self.names.iter().map(|name| state[name]).collect::<Vec<bool>>();
+
+        let mut apply = |(controls, target): &(Vec<usize>, usize)| {
+            if controls.iter().all(|control| values[*control]) {
+                values[*target] = !values[*target];
+            }
+        };
+        if forwards {
+            self.gates.iter().for_each(&mut apply);
+        } else {
+            self.gates.iter().rev().for_each(&mut apply);
+        }
+
+        self.names.iter().cloned().zip(values).collect()
+    }
+}
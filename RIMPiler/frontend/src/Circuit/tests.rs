@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use super::code_gen::{CircuitCompiler, DEFAULT_UNROLL, DEFAULT_WIDTH};
+use super::simulator::Netlist;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::interpreter::memory_store::Value;
+use super::super::fixtures::{example, inverted_program_keeping_counters as inverted_program};
+use super::super::AST::{ArithmeticExpression, Program, Statement, Variable};
+use super::super::IR::lower::lower;
+use super::super::Backend;
+
+// the examples without floats
+const EXAMPLES: [&str; 5] = ["basic", "collatz", "fibonacci", "if", "while"];
+
+// the program the interpreter runs, with the first assignment to each input replaced by its value
+fn with_inputs(program: &Program, inputs: &[(&str, i32)]) -> Program {
+    let Program::Statements(block) = program;
+    let mut block = block.clone();
+    for (input, value) in inputs {
+        let assignment = block
+            .iter_mut()
+            .find(|statement| match &statement.value {
+                Statement::Assignment(Variable::Integer(name), _) | Statement::DirectAssignment(Variable::Integer(name), _, _) => name == input,
+                _ => false,
+            })
+            .unwrap();
+        let variable = Variable::Integer(input.to_string());
+        // a first assignment is undone by setting the input back to 0, whatever its value
+        assignment.value = match &assignment.value {
+            Statement::DirectAssignment(_, _, inverse) => Statement::DirectAssignment(variable, ArithmeticExpression::Integer(*value), inverse.clone()),
+            _ => Statement::Assignment(variable, ArithmeticExpression::Integer(*value)),
+        };
+    }
+    Program::Statements(block)
+}
+
+// the variables in the circuit, and the value of each one, sign extended
+fn variables(state: &HashMap<String, bool>, width: u32) -> HashMap<String, i64> {
+    let mut values = HashMap::new();
+    for (line, value) in state {
+        if let Some((name, bit)) = line.rsplit_once('_') {
+            let bit = bit.parse::<u32>().unwrap();
+            *values.entry(name.to_string()).or_insert(0) |= i64::from(*value) << bit;
+        }
+    }
+    values
+        .into_iter()
+        .map(|(name, value)| (name, value << (64 - width) >> (64 - width)))
+        .collect()
+}
+
+fn wrap(value: i32, width: u32) -> i64 {
+    i64::from(value) << (64 - width) >> (64 - width)
+}
+
+// runs the circuit on the inputs and checks it against the interpreter, that every ancilla is back to 0, that no
+// loop needed more iterations and that running it backwards gets back to the start
+fn check(name: &str, program: &Program, inputs: &[(&str, i32)], width: u32, unroll: u32) {
+    let mut interpreter = InterpreterEngine::new();
+    interpreter.interpret(&with_inputs(program, inputs)).unwrap();
+
+    let names = inputs.iter().map(|(input, _)| input.to_string()).collect();
+    let mut compiler = CircuitCompiler::new(width, unroll, names);
+    let netlist = Netlist::new(&compiler._compile(&lower(program).unwrap()).unwrap()).unwrap();
+    assert_eq!(compiler.statistics().gates, netlist.gates(), "{}", name);
+
+    let mut values = HashMap::new();
+    for (input, value) in inputs {
+        for bit in 0..width {
+            values.insert(format!("{}_{}", input, bit), (i64::from(*value) >> bit.min(63)) & 1 == 1);
+        }
+    }
+    let initial = netlist.initial(&values).unwrap();
+    let state = netlist.run(&initial, true);
+
+    let results = variables(&state, width);
+    assert!(!results.is_empty(), "{}", name);
+    for (variable, value) in &results {
+        // variables that are only assigned in code that didn't run still hold 0
+        let expected = match interpreter.get_result(variable).map(|element| element.get()) {
+            Some(Value::Integer(expected)) => wrap(expected, width),
+            Some(Value::Float(_)) | Some(Value::BigInteger(_)) | Some(Value::Rational(_)) => unreachable!(),
+            None => 0,
+        };
+        assert_eq!(*value, expected, "{} with {:?}: {}", name, inputs, variable);
+    }
+
+    for (line, value) in &state {
+        if line.starts_with("ancilla") || line.starts_with("exceeded") {
+            assert!(!value, "{} with {:?}: {}", name, inputs, line);
+        }
+    }
+
+    assert_eq!(netlist.run(&state, false), initial, "{}", name);
+}
+
+#[test]
+fn real_format() {
+    let program = inverted_program("int x = 3; int y = x * 2; while x > 0 do { x = x - 1; };");
+    let mut compiler = CircuitCompiler::new(8, 4, vec![String::from("x")]);
+    let circuit = compiler._compile(&lower(&program).unwrap()).unwrap();
+
+    assert!(circuit.starts_with("# Generated by RIMPiler\n# 8-bit integers, loops unrolled 4 times\n"));
+    assert!(circuit.contains("\n.version 1.0\n.numvars "));
+    assert!(circuit.contains("\n.variables x_0 x_1 x_2 x_3 x_4 x_5 x_6 x_7 y_0 "));
+    assert!(circuit.contains("\n.constants --------0000"));
+    assert!(circuit.contains("\n.begin\n"));
+    assert!(circuit.ends_with("\n.end\n"));
+    assert!(circuit.lines().skip_while(|line| *line != ".begin").skip(1).take_while(|line| *line != ".end").all(|gate| {
+        gate.starts_with("t1 ") || gate.starts_with("t2 ") || gate.starts_with("t3 ")
+    }));
+
+    // ancillas and garbage aren't outputs
+    let netlist = Netlist::new(&circuit).unwrap();
+    for (name, garbage) in netlist.names.iter().zip(&netlist.garbage) {
+        assert_eq!(*garbage == '1', name.starts_with("ancilla") || name.starts_with("garbage"), "{}", name);
+    }
+
+    let statistics = compiler.statistics();
+    assert!(circuit.contains(&format!("# {}\n", statistics)));
+    assert_eq!(statistics.gates, statistics.nots + statistics.controlled_nots + statistics.toffolis);
+    assert_eq!(statistics.lines, statistics.variable_lines + statistics.ancillas + statistics.garbage + statistics.exceeded);
+    assert_eq!(statistics.variable_lines, 8 * 3);
+    assert_eq!(statistics.exceeded, 1);
+
+    assert!(CircuitCompiler::compile(&program).contains("\n# 16-bit integers, loops unrolled 16 times\n"));
+}
+
+#[test]
+fn garbage_only_when_needed() {
+    let mut compiler = CircuitCompiler::new(8, 4, vec![]);
+    compiler._compile(&lower(&inverted_program("int x = 1; int y = 2; x = x + y * 2; y = -y; x = x - 3;")).unwrap()).unwrap();
+    assert_eq!(compiler.statistics().garbage, 0);
+
+    // the old value of x can't be recovered from x * 3 without division
+    let mut compiler = CircuitCompiler::new(8, 4, vec![]);
+    compiler._compile(&lower(&inverted_program("int x = 1; x = x * 3;")).unwrap()).unwrap();
+    assert_eq!(compiler.statistics().garbage, 8);
+}
+
+#[test]
+fn rejects_floats() {
+    let program = example("floats");
+    let result = CircuitCompiler::new(DEFAULT_WIDTH, DEFAULT_UNROLL, vec![])._compile(&lower(&inverted_program(&program)).unwrap());
+    assert!(result.unwrap_err().contains("only have fixed-width integers"));
+}
+
+#[test]
+fn rejects_bad_options() {
+    let program = inverted_program("int x = 1; if x > 0 then { int y = 2; } else { skip; };");
+
+    assert!(CircuitCompiler::new(1, 4, vec![])._compile(&lower(&program).unwrap()).is_err());
+    assert!(CircuitCompiler::new(65, 4, vec![])._compile(&lower(&program).unwrap()).is_err());
+    assert!(CircuitCompiler::new(4, 8, vec![])._compile(&lower(&program).unwrap()).is_err());
+    assert!(CircuitCompiler::new(8, 4, vec![String::from("z")])._compile(&lower(&program).unwrap()).is_err());
+    // y is only assigned inside the if
+    assert!(CircuitCompiler::new(8, 4, vec![String::from("y")])._compile(&lower(&program).unwrap()).is_err());
+}
+
+#[test]
+fn matches_interpreter() {
+    for name in EXAMPLES {
+        let program = example(name);
+        check(name, &inverted_program(&program), &[], 8, 8);
+    }
+}
+
+#[test]
+fn sample_inputs() {
+    let collatz = inverted_program(&example("collatz"));
+    for n in [1, 2, 3, 6, 8, 12] {
+        check("collatz", &collatz, &[("n", n)], 8, 10);
+    }
+
+    let fibonacci = inverted_program(&example("fibonacci"));
+    for n in [0, 1, 5, 9] {
+        check("fibonacci", &fibonacci, &[("n", n)], 16, 10);
+    }
+}
+
+#[test]
+fn arithmetic() {
+    let program = inverted_program(r#"
+        int a = 0;
+        int b = 0;
+        int c = a + b;
+        int d = a - b;
+        int e = a * b;
+        int f = a / b;
+        int g = -a + 3 * (b - 1);
+        int h = (a - b) / (0 - 2);
+        // wider than 12 bits, but * wraps around the same way
+        c = c * c;
+        a = b;
+        b = a + b;
+        a = -a;
+        b = 10 - b;
+    "#);
+    for a in [-100, -7, -1, 0, 1, 5, 13, 127] {
+        for b in [-9, -2, -1, 1, 3, 7, 50] {
+            check("arithmetic", &program, &[("a", a), ("b", b)], 12, 1);
+        }
+    }
+}
+
+#[test]
+fn powers() {
+    let program = inverted_program("int a = 0; int b = 0; int c = a ^ b; int d = (a - 1) ^ 3; a = a ^ 2;");
+    for a in [-3, -1, 0, 2, 5] {
+        for b in [1, 2, 3, 7] {
+            check("powers", &program, &[("a", a), ("b", b)], 12, 1);
+        }
+    }
+}
+
+#[test]
+fn conditions() {
+    let program = inverted_program(r#"
+        int x = 0;
+        int y = 0;
+        int less = 0;
+        if x < y && !(y < x) then { less = 1; } else { less = 2; };
+        if x > 3 && !(x == 4) || y != 0 then { y = x * x; } else { y = 1; };
+        if x == 5 then { x = y - x; } else { skip; };
+        if y > x then { if x < 0 then { y = 0; } else { x = x + y; }; } else { less = less * 3; };
+    "#);
+    for x in [-6, -1, 0, 4, 5, 9] {
+        for y in [-2, 0, 3, 5] {
+            check("conditions", &program, &[("x", x), ("y", y)], 8, 1);
+        }
+    }
+}
+
+#[test]
+fn nested_loops() {
+    let program = inverted_program(r#"
+        int i = 0;
+        int total = 0;
+        while i > 0 do {
+            int j = i;
+            while j > 0 do {
+                total = total + i * j;
+                j = j - 1;
+            };
+            i = i - 1;
+        };
+    "#);
+    for i in [0, 1, 2, 4] {
+        check("nested_loops", &program, &[("i", i)], 8, 4);
+    }
+}
+
+#[test]
+fn loop_bound() {
+    let program = inverted_program("int n = 0; int total = 0; while n > 0 do { total = total + n; n = n - 1; };");
+
+    let mut compiler = CircuitCompiler::new(8, 3, vec![String::from("n")]);
+    let netlist = Netlist::new(&compiler._compile(&lower(&program).unwrap()).unwrap()).unwrap();
+
+    // the loop runs 3 times for n = 3, but needs a 4th for n = 4
+    for (n, exceeded) in [(3, false), (4, true)] {
+        let inputs = (0..8).map(|bit| (format!("n_{}", bit), (n >> bit) & 1 == 1)).collect();
+        let state = netlist.run(&netlist.initial(&inputs).unwrap(), true);
+        assert_eq!(state["exceeded0"], exceeded);
+    }
+
+    check("loop_bound", &program, &[("n", 3)], 8, 3);
+}
@@ -0,0 +1,126 @@
+use std::fmt::{Display, Formatter};
+use super::{Block, Condition, Expression, Instruction, Program, Type};
+
+/*
+The text form of the IR, e.g.
+
+    0: int n
+    1: int generated_name_semantic_transformer0 (loop counter for while at line 2)
+
+    forward:
+        set n = 10
+        push generated_name_semantic_transformer0 = 0
+        while (n > 0) counted by generated_name_semantic_transformer0 {
+            push n = (n / 2)
+            push generated_name_semantic_transformer0 = (generated_name_semantic_transformer0 + 1)
+        }
+
+    reverse point
+
+    backward:
+        while (generated_name_semantic_transformer0 > 0) counted by generated_name_semantic_transformer0 {
+            pop generated_name_semantic_transformer0
+            pop n
+        }
+        pop generated_name_semantic_transformer0
+        set n = 0
+
+Conversions are written as calls, float(n) or int(x).
+*/
+
+const INDENT: &str = "    ";
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Type::Integer => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::BigInteger => write!(f, "bigint"),
+            Type::Rational => write!(f, "rational"),
+        }
+    }
+}
+
+impl Program {
+    fn name(&self, slot: usize) -> &str {
+        &self.variables[slot].name
+    }
+
+    fn format_expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Integer(value) => value.to_string(),
+            Expression::Float(value) => format!("{:?}", value.into_inner()),
+            Expression::BigInteger(value) => format!("{}n", value),
+            Expression::Variable(slot, _) => self.name(*slot).to_string(),
+            Expression::Convert(target, operand) => format!("{}({})", target, self.format_expression(operand)),
+            Expression::Negation(_, operand) => format!("(-{})", self.format_expression(operand)),
+            Expression::Operation(_, operator, left, right) => {
+                format!("({} {} {})", self.format_expression(left), operator, self.format_expression(right))
+            }
+        }
+    }
+
+    fn format_condition(&self, condition: &Condition) -> String {
+        match condition {
+            Condition::Comparison(_, operator, left, right) => {
+                format!("({} {} {})", self.format_expression(left), operator, self.format_expression(right))
+            }
+            Condition::And(left, right) => format!("({} && {})", self.format_condition(left), self.format_condition(right)),
+            Condition::Or(left, right) => format!("({} || {})", self.format_condition(left), self.format_condition(right)),
+            Condition::Not(operand) => format!("!{}", self.format_condition(operand)),
+        }
+    }
+
+    fn format_block(&self, block: &Block, depth: usize) -> String {
+        let indent = INDENT.repeat(depth);
+        let mut text = String::new();
+        for instruction in block {
+            match &instruction.value {
+                Instruction::Push(slot, value) => {
+                    text.push_str(&format!("{}push {} = {}\n", indent, self.name(*slot), self.format_expression(value)));
+                }
+                Instruction::Pop(slot) => text.push_str(&format!("{}pop {}\n", indent, self.name(*slot))),
+                Instruction::Set(slot, value) => {
+                    text.push_str(&format!("{}set {} = {}\n", indent, self.name(*slot), self.format_expression(value)));
+                }
+                Instruction::If(condition, then_block, else_block) => {
+                    text.push_str(&format!("{}if {} {{\n", indent, self.format_condition(condition)));
+                    text.push_str(&self.format_block(then_block, depth + 1));
+                    if !else_block.is_empty() {
+                        text.push_str(&format!("{}}} else {{\n", indent));
+                        text.push_str(&self.format_block(else_block, depth + 1));
+                    }
+                    text.push_str(&format!("{}}}\n", indent));
+                }
+                Instruction::While(condition, body, counter) => {
+                    text.push_str(&format!("{}while {}", indent, self.format_condition(condition)));
+                    if let Some(counter) = counter {
+                        text.push_str(&format!(" counted by {}", self.name(*counter)));
+                    }
+                    text.push_str(" {\n");
+                    text.push_str(&self.format_block(body, depth + 1));
+                    text.push_str(&format!("{}}}\n", indent));
+                }
+            }
+        }
+        text
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for (slot, variable) in self.variables.iter().enumerate() {
+            write!(f, "{}: {} {}", slot, variable.variable_type, variable.name)?;
+            match &variable.label {
+                Some(label) => write!(f, " ({})", label)?,
+                None if variable.generated => write!(f, " (generated)")?,
+                None => {}
+            }
+            writeln!(f)?;
+        }
+
+        write!(f, "\nforward:\n{}", self.format_block(&self.forward, 1))?;
+        write!(f, "\nreverse point\n")?;
+        write!(f, "\nbackward:\n{}", self.format_block(&self.backward, 1))
+    }
+}
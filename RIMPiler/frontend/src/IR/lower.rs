@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::debug::Meta;
+use super::super::post_parse::name_generator::{is_generated, labels};
+use super::super::AST;
+use super::super::AST::{ArithmeticExpression, BooleanExpression, BooleanOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator};
+use super::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Slot, Type, Variable};
+
+// lowers an inverted and combined program, see invert_and_combine
+pub fn lower(program: &AST::Program) -> Result<Program, String> {
+    let AST::Program::Statements(statements) = program;
+
+    let mut lowerer = Lowerer::new(labels(program));
+
+    // the inverter places the reverse point between the forward and backward programs
+    let split = statements
+        .iter()
+        .position(|statement| statement.value == Statement::ReversePoint)
+        .unwrap_or(statements.len());
+    let forward = lowerer.lower_block(&statements[..split])?;
+    let backward = lowerer.lower_block(statements.get(split + 1..).unwrap_or(&[]))?;
+
+    Ok(Program { variables: lowerer.variables, forward, backward })
+}
+
+fn variable_name(variable: &AST::Variable) -> &String {
+    match variable {
+        AST::Variable::Integer(name) => name,
+        AST::Variable::Float(name) => name,
+        AST::Variable::BigInteger(name) => name,
+        AST::Variable::Rational(name) => name,
+    }
+}
+
+// int literals used as floats or bigints are written as float or bigint literals
+fn convert(expression: Expression, target: &Type) -> Expression {
+    match (expression, target) {
+        (expression, _) if expression.expression_type() == *target => expression,
+        (Expression::Integer(value), Type::Float) => Expression::Float(NotNan::new(value as f32).unwrap()),
+        (Expression::Integer(value), Type::BigInteger) => Expression::BigInteger(BigInt::from(value)),
+        (expression, _) => Expression::Convert(target.clone(), Box::new(expression)),
+    }
+}
+
+// ints are only kept when both operands are ints, otherwise both become floats if either is a float, or bigints
+fn unify(left: Expression, right: Expression) -> (Type, Expression, Expression) {
+    let operation_type = match (left.expression_type(), right.expression_type()) {
+        (Type::Integer, Type::Integer) => Type::Integer,
+        (Type::Float, _) | (_, Type::Float) => Type::Float,
+        _ => Type::BigInteger,
+    };
+    let left = convert(left, &operation_type);
+    let right = convert(right, &operation_type);
+    (operation_type, left, right)
+}
+
+// rationals have no fixed size representation in any of the targets, so they are only interpreted
+fn rational(value: impl std::fmt::Display) -> String {
+    format!("Rationals can only be interpreted, but {} is a rational", value)
+}
+
+struct Lowerer {
+    variables: Vec<Variable>,
+    slots: HashMap<String, Slot>,
+    labels: HashMap<String, String>,
+}
+
+impl Lowerer {
+    fn new(labels: HashMap<String, String>) -> Self {
+        Lowerer { variables: Vec::new(), slots: HashMap::new(), labels }
+    }
+
+    // a variable is declared by its first assignment, with the type it was declared with
+    fn declare(&mut self, variable: &AST::Variable) -> Result<Slot, String> {
+        let (name, variable_type) = match variable {
+            AST::Variable::Integer(name) => (name, Type::Integer),
+            AST::Variable::Float(name) => (name, Type::Float),
+            AST::Variable::BigInteger(name) => (name, Type::BigInteger),
+            AST::Variable::Rational(name) => return Err(rational(name)),
+        };
+
+        if let Some(slot) = self.slots.get(name) {
+            return Ok(*slot);
+        }
+
+        self.variables.push(Variable {
+            name: name.clone(),
+            variable_type,
+            generated: is_generated(name),
+            label: self.labels.get(name).cloned(),
+        });
+        self.slots.insert(name.clone(), self.variables.len() - 1);
+        Ok(self.variables.len() - 1)
+    }
+
+    fn lookup(&self, name: &str) -> Result<Slot, String> {
+        self.slots.get(name).copied().ok_or(format!("Variable {} used before assignment", name))
+    }
+
+    // the generated variable a loop is counted with, from the increment at the end of a forward loop's body or
+    // the test of a backward loop
+    fn loop_counter(&self, condition: &Condition, body: &Block) -> Option<Slot> {
+        let generated = |slot: &Slot| self.variables[*slot].generated;
+
+        if let Some(Meta { value: Instruction::Push(slot, Expression::Operation(Type::Integer, ArithmeticOperator::Addition, left, right)), .. }) = body.last() {
+            if **left == Expression::Variable(*slot, Type::Integer) && **right == Expression::Integer(1) && generated(slot) {
+                return Some(*slot);
+            }
+        }
+
+        match condition {
+            Condition::Comparison(Type::Integer, RelationOperator::GreaterThan, Expression::Variable(slot, _), Expression::Integer(0))
+                if generated(slot) => Some(*slot),
+            _ => None,
+        }
+    }
+
+    fn lower_block(&mut self, block: &[Meta<Statement>]) -> Result<Block, String> {
+        let mut instructions = Vec::new();
+        for statement in block {
+            if let Some(instruction) = self.lower_statement(&statement.value)? {
+                instructions.push(Meta::new(instruction, statement.location));
+            }
+        }
+        Ok(instructions)
+    }
+
+    fn lower_statement(&mut self, statement: &Statement) -> Result<Option<Instruction>, String> {
+        let instruction = match statement {
+            Statement::Skip => return Ok(None),
+            Statement::Assignment(variable, expression) => {
+                let expression = self.lower_arithmetic(expression)?;
+                let slot = self.declare(variable)?;
+                Instruction::Push(slot, convert(expression, &self.variables[slot].variable_type))
+            }
+            Statement::DirectAssignment(variable, expression, _) => {
+                let expression = self.lower_arithmetic(expression)?;
+                let slot = self.declare(variable)?;
+                Instruction::Set(slot, convert(expression, &self.variables[slot].variable_type))
+            }
+            Statement::ReverseAssignment(variable, _) => {
+                let name = variable_name(variable);
+                let slot = self.slots.get(name).ok_or(format!("Variable {} being unassigned before assignment", name))?;
+                Instruction::Pop(*slot)
+            }
+            Statement::If(condition, then_block, else_block) => Instruction::If(
+                self.lower_boolean(condition)?,
+                self.lower_block(then_block)?,
+                self.lower_block(else_block)?,
+            ),
+            Statement::While(condition, block) => {
+                let condition = self.lower_boolean(condition)?;
+                let body = self.lower_block(block)?;
+                let counter = self.loop_counter(&condition, &body);
+                Instruction::While(condition, body, counter)
+            }
+            Statement::ReversePoint => return Err(String::from("A program can only have one reverse point, at the top level")),
+        };
+        Ok(Some(instruction))
+    }
+
+    fn lower_arithmetic(&self, expression: &ArithmeticExpression) -> Result<Expression, String> {
+        match expression {
+            ArithmeticExpression::Variable(variable) => {
+                let slot = self.lookup(variable_name(variable))?;
+                Ok(Expression::Variable(slot, self.variables[slot].variable_type.clone()))
+            }
+            ArithmeticExpression::Integer(value) => Ok(Expression::Integer(*value)),
+            ArithmeticExpression::Float(value) => Ok(Expression::Float(*value)),
+            ArithmeticExpression::BigInteger(value) => Ok(Expression::BigInteger(value.clone())),
+            ArithmeticExpression::Rational(_) => Err(rational(expression)),
+            ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, operand) => {
+                let operand = self.lower_arithmetic(operand)?;
+                Ok(Expression::Negation(operand.expression_type(), Box::new(operand)))
+            }
+            ArithmeticExpression::Operation(operator, left, right) => {
+                let (operation_type, left, right) = unify(self.lower_arithmetic(left)?, self.lower_arithmetic(right)?);
+                Ok(Expression::Operation(operation_type, operator.clone(), Box::new(left), Box::new(right)))
+            }
+        }
+    }
+
+    fn lower_boolean(&self, expression: &BooleanExpression) -> Result<Condition, String> {
+        match expression {
+            BooleanExpression::Relational(operator, left, right) => {
+                let (comparison_type, left, right) = unify(self.lower_arithmetic(left)?, self.lower_arithmetic(right)?);
+                Ok(Condition::Comparison(comparison_type, operator.clone(), left, right))
+            }
+            BooleanExpression::Logical(operator, left, right) => {
+                let left = Box::new(self.lower_boolean(left)?);
+                let right = Box::new(self.lower_boolean(right)?);
+                match operator {
+                    BooleanOperator::And => Ok(Condition::And(left, right)),
+                    BooleanOperator::Or => Ok(Condition::Or(left, right)),
+                }
+            }
+            BooleanExpression::Unary(UnaryBooleanOperator::Negation, operand) => {
+                Ok(Condition::Not(Box::new(self.lower_boolean(operand)?)))
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+/*
+A typed, flattened intermediate representation of a program, between the AST and the backends.
+
+The inverted and combined AST is lowered once (lower::lower), resolving what each backend used to work out
+for itself:
+    - every variable gets a slot, its index in Program::variables, with the type it was declared with, and the
+      variables the transformer generated are marked as such
+    - every conversion between ints, bigints and floats is explicit, so each expression has a single type
+    - assignments become history operations: Push stores a value and pushes the difference from the old one
+      onto the variable's history, Pop takes the last difference off again and subtracts it. Floats push the
+      old value itself and Pop restores it, as subtracting a float difference can round. Assignments that
+      are undone by another assignment (see post_parse::invertibility) become Set, which keeps no history
+    - the program is split at the reverse point into its forward and backward halves, and skips are dropped
+
+If and While are still structured blocks. The backward half undoes the forward half block by block, and the
+loops that are driven by one of the transformer's counters (which is incremented at the end of the forward
+body and tested against 0 by the backward loop) name it, so backends can treat it specially.
+
+The text form (Display) is for reading what the backends are given, e.g. with --emit ir.
+*/
+
+use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::debug::Meta;
+
+pub use super::AST::{ArithmeticOperator, RelationOperator, Type};
+
+mod display;
+pub mod lower;
+
+#[cfg(test)]
+mod tests;
+
+// an index into Program::variables
+pub type Slot = usize;
+
+pub type Block = Vec<Meta<Instruction>>;
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Variable {
+    pub name: String,
+    pub variable_type: Type,
+    // created by the semantic transformer rather than written in the program
+    pub generated: bool,
+    // what a generated variable was created for, see post_parse::name_generator::labels
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Expression {
+    Integer(i32),
+    Float(NotNan<f32>),
+    BigInteger(BigInt),
+    Variable(Slot, Type),
+    // converts to the given type, floats become ints by truncating, saturating and turning NaN into 0, bigints
+    // become ints by keeping their lowest 32 bits, and floats become bigints by truncating
+    Convert(Type, Box<Expression>),
+    Negation(Type, Box<Expression>),
+    // both operands have the type of the operation
+    Operation(Type, ArithmeticOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Condition {
+    // both operands have the given type
+    Comparison(Type, RelationOperator, Expression, Expression),
+    // both sides are always evaluated
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub enum Instruction {
+    // pushes the value minus the variable (the variable itself for floats) onto the variable's history, then
+    // stores the value
+    Push(Slot, Expression),
+    // pops the last difference off the variable's history and subtracts it from the variable, or for floats
+    // restores the popped value
+    Pop(Slot),
+    // stores the value without touching the history, the inverse is another Set
+    Set(Slot, Expression),
+    If(Condition, Block, Block),
+    // the counter that records how many times the loop ran, if it has one
+    While(Condition, Block, Option<Slot>),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Program {
+    pub variables: Vec<Variable>,
+    pub forward: Block,
+    // runs after every variable is printed at the reverse point
+    pub backward: Block,
+}
+
+impl Expression {
+    pub fn expression_type(&self) -> Type {
+        match self {
+            Expression::Integer(_) => Type::Integer,
+            Expression::Float(_) => Type::Float,
+            Expression::BigInteger(_) => Type::BigInteger,
+            Expression::Variable(_, expression_type)
+            | Expression::Convert(expression_type, _)
+            | Expression::Negation(expression_type, _)
+            | Expression::Operation(expression_type, _, _, _) => expression_type.clone(),
+        }
+    }
+}
+
+impl Program {
+    pub fn variable(&self, slot: Slot) -> &Variable {
+        &self.variables[slot]
+    }
+
+    pub fn uses(&self, variable_type: &Type) -> bool {
+        self.variables.iter().any(|variable| variable.variable_type == *variable_type)
+    }
+
+    // the slot of a variable, by name
+    pub fn slot(&self, name: &str) -> Option<Slot> {
+        self.variables.iter().position(|variable| variable.name == name)
+    }
+}
@@ -0,0 +1,166 @@
+use super::lower::lower;
+use super::{Condition, Expression, Instruction, Program, RelationOperator, Type};
+use super::super::fixtures::{example, inverted_program, inverted_program_keeping_counters};
+use super::super::AST;
+
+fn lowered(program: &str) -> Program {
+    lower(&inverted_program(program)).unwrap()
+}
+
+#[test]
+fn text_form() {
+    let program = lowered("int n = 10; float x = 0.5; while n > 0 do { n = n - 1; x = x * n; };");
+
+    assert_eq!(
+        program.to_string(),
+        r#"0: int n
+1: float x
+
+forward:
+    set n = 10
+    set x = 0.5
+    while (n > 0) {
+        set n = (n - 1)
+        push x = (x * float(n))
+    }
+
+reverse point
+
+backward:
+    while (n != 10) {
+        pop x
+        set n = (n + 1)
+    }
+    set x = 0.0
+    set n = 0
+"#
+    );
+}
+
+#[test]
+fn explicit_conversions() {
+    let program = lowered("int n = 3; float x = n; int m = x / 2; float y = n ^ 2; if n < x && !(x == 1.5) then { skip; } else { m = -m; };");
+
+    let x = program.slot("x").unwrap();
+    let m = program.slot("m").unwrap();
+    let n = program.slot("n").unwrap();
+    assert_eq!(program.variable(m).variable_type, Type::Integer);
+    assert_eq!(program.forward[1].value, Instruction::Push(x, Expression::Convert(Type::Float, Box::new(Expression::Variable(n, Type::Integer)))));
+
+    let text = program.to_string();
+    assert!(text.contains("    push m = int((x / 2.0))\n"));
+    // int ^ int stays an int
+    assert!(text.contains("    push y = float((n ^ 2))\n"));
+    assert!(text.contains("    if ((float(n) < x) && !(x == 1.5)) {\n    } else {\n        set m = (-m)\n    }\n"));
+
+    // skips are dropped, along with the else block they were in
+    let Instruction::If(Condition::And(comparison, _), then_block, else_block) = &program.forward[4].value else {
+        panic!("{}", text);
+    };
+    assert!(matches!(**comparison, Condition::Comparison(Type::Float, RelationOperator::LessThan, _, _)));
+    assert!(then_block.is_empty());
+    assert_eq!(else_block.len(), 1);
+}
+
+#[test]
+fn bigint_conversions() {
+    let program = lowered("bigint b = 2; int n = 3; float x = 0.5; b = b * n; x = b + x; n = b; b = x; if b < 4 then { skip; } else { b = -b; };");
+
+    assert!(program.uses(&Type::BigInteger));
+    assert!(!lowered("int n = 3;").uses(&Type::BigInteger));
+
+    let text = program.to_string();
+    assert!(text.contains("0: bigint b\n"));
+    // int literals become bigint literals, and int operands are converted
+    assert!(text.contains("    set b = 2n\n"));
+    assert!(text.contains("    push b = (b * bigint(n))\n"));
+    // floats win over bigints
+    assert!(text.contains("    push x = (float(b) + x)\n"));
+    assert!(text.contains("    push n = int(b)\n"));
+    assert!(text.contains("    push b = bigint(x)\n"));
+    assert!(text.contains(" < 4n) {\n"));
+}
+
+#[test]
+fn rationals_are_only_interpreted() {
+    let inverted = inverted_program("rational q = 1r / 3;");
+    assert_eq!(lower(&inverted), Err(String::from("Rationals can only be interpreted, but 1r is a rational")));
+}
+
+#[test]
+fn loop_counters() {
+    for name in ["collatz", "fibonacci", "while"] {
+        let program = lower(&inverted_program_keeping_counters(&example(name))).unwrap();
+
+        // the counters the transformer added are the only generated variables, and every loop has one
+        fn loops(block: &super::Block, counters: &mut Vec<Option<usize>>) {
+            for instruction in block {
+                match &instruction.value {
+                    Instruction::While(_, body, counter) => {
+                        counters.push(*counter);
+                        loops(body, counters);
+                    }
+                    Instruction::If(_, then_block, else_block) => {
+                        loops(then_block, counters);
+                        loops(else_block, counters);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let mut forward = Vec::new();
+        loops(&program.forward, &mut forward);
+        let mut backward = Vec::new();
+        loops(&program.backward, &mut backward);
+
+        assert!(!forward.is_empty(), "{}", name);
+        assert!(forward.iter().all(|counter| counter.is_some_and(|slot| program.variable(slot).generated)), "{}", name);
+        backward.reverse();
+        assert_eq!(forward, backward, "{}", name);
+    }
+}
+
+#[test]
+fn every_example() {
+    for name in ["basic", "bigfibonacci", "collatz", "exponentials", "fibonacci", "floats", "if", "while"] {
+        let inverted = inverted_program(&example(name));
+        let program = lower(&inverted).unwrap();
+
+        // the forward and backward halves are the same size, a pop for every push
+        fn count(block: &super::Block) -> (usize, usize) {
+            block.iter().fold((0, 0), |(pushes, pops), instruction| {
+                let (inner_pushes, inner_pops) = match &instruction.value {
+                    Instruction::Push(_, _) => (1, 0),
+                    Instruction::Pop(_) => (0, 1),
+                    // undone by another set, without the history
+                    Instruction::Set(_, _) => (0, 0),
+                    Instruction::If(_, then_block, else_block) => {
+                        let (a, b) = count(then_block);
+                        let (c, d) = count(else_block);
+                        (a + c, b + d)
+                    }
+                    Instruction::While(_, body, _) => count(body),
+                };
+                (pushes + inner_pushes, pops + inner_pops)
+            })
+        }
+        assert_eq!(count(&program.forward), (count(&program.backward).1, 0), "{}", name);
+        assert_eq!(count(&program.backward).0, 0, "{}", name);
+
+        let mut names = program.variables.iter().map(|variable| variable.name.clone()).collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), program.variables.len(), "{}", name);
+    }
+}
+
+#[test]
+fn single_reverse_point() {
+    let inverted = inverted_program("int n = 1;");
+    let twice = super::super::post_parse::inverter::combine(&inverted, &inverted);
+    assert!(lower(&twice).is_err());
+
+    // without a reverse point everything is forward
+    let program = lower(&AST::Program::Statements(vec![])).unwrap();
+    assert!(program.forward.is_empty() && program.backward.is_empty());
+}
@@ -1,48 +1,101 @@
-use std::collections::HashMap;
-use std::io::Read;
-use super::super::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
+use utilities::debug::Meta;
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Slot, Type};
 use super::super::Backend;
+use super::super::overflow::Overflow;
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
-enum Type {
-    Integer,
-    Float,
-}
+const BIG_INTEGER: &str = "java/math/BigInteger";
 
-fn conversion(source: &Type, target: &Type) -> String {
-    match (source, target) {
+fn conversion(from: &Type, to: &Type, bigint_class: &str) -> String {
+    match (from, to) {
         (Type::Integer, Type::Float) => String::from("i2f\n"),
         (Type::Float, Type::Integer) => String::from("f2i\n"),
-        _ => unreachable!(),
+        (Type::Integer, Type::BigInteger) => format!("i2l\ninvokestatic Method {} valueOf (J)L{};\n", BIG_INTEGER, BIG_INTEGER),
+        (Type::BigInteger, Type::Integer) => format!("invokevirtual Method {} intValue ()I\n", BIG_INTEGER),
+        (Type::BigInteger, Type::Float) => format!("invokevirtual Method {} floatValue ()F\n", BIG_INTEGER),
+        (Type::Float, Type::BigInteger) => format!("invokestatic Method {} fromFloat (F)L{};\n", bigint_class, BIG_INTEGER),
+        _ => unreachable!("Conversions are only lowered between different types"),
     }
 }
 
-fn compile_relational_operator(operator: &RelationOperator, target_type: &Type) -> String {
-    match target_type {
-        Type::Integer => match operator {
+// the branch taken when the comparison is (or isn't) true, fcmpl and fcmpg push -1 and 1 for NaN respectively,
+// so they're chosen to make every comparison with NaN false
+fn compile_relational_operator(operator: &RelationOperator, target_type: &Type, jump_when: bool) -> String {
+    match (target_type, jump_when) {
+        (Type::Integer, true) => match operator {
+            RelationOperator::Equal => "if_icmpeq".to_string(),
+            RelationOperator::NotEqual => "if_icmpne".to_string(),
+            RelationOperator::LessThan => "if_icmplt".to_string(),
+            RelationOperator::GreaterThan => "if_icmpgt".to_string(),
+        },
+        (Type::Integer, false) => match operator {
             RelationOperator::Equal => "if_icmpne".to_string(),
             RelationOperator::NotEqual => "if_icmpeq".to_string(),
             RelationOperator::LessThan => "if_icmpge".to_string(),
             RelationOperator::GreaterThan => "if_icmple".to_string(),
         },
-        Type::Float => match operator {
-            RelationOperator::Equal => "fcmpg\nifne".to_string(),
-            RelationOperator::NotEqual => "fcmpg\neq".to_string(),
+        (Type::Float, true) => match operator {
+            RelationOperator::Equal => "fcmpl\nifeq".to_string(),
+            RelationOperator::NotEqual => "fcmpl\nifne".to_string(),
+            RelationOperator::LessThan => "fcmpg\niflt".to_string(),
+            RelationOperator::GreaterThan => "fcmpl\nifgt".to_string(),
+        },
+        (Type::Float, false) => match operator {
+            RelationOperator::Equal => "fcmpl\nifne".to_string(),
+            RelationOperator::NotEqual => "fcmpl\nifeq".to_string(),
             RelationOperator::LessThan => "fcmpg\nifge".to_string(),
             RelationOperator::GreaterThan => "fcmpl\nifle".to_string(),
         },
+        // compareTo gives -1, 0 or 1
+        (Type::BigInteger, _) => {
+            let branch = match (operator, jump_when) {
+                (RelationOperator::Equal, true) | (RelationOperator::NotEqual, false) => "ifeq",
+                (RelationOperator::NotEqual, true) | (RelationOperator::Equal, false) => "ifne",
+                (RelationOperator::LessThan, true) => "iflt",
+                (RelationOperator::LessThan, false) => "ifge",
+                (RelationOperator::GreaterThan, true) => "ifgt",
+                (RelationOperator::GreaterThan, false) => "ifle",
+            };
+            format!("invokevirtual Method {} compareTo (L{};)I\n{}", BIG_INTEGER, BIG_INTEGER, branch)
+        }
+        (Type::Rational, _) => unreachable!("rationals are only interpreted, see IR::lower"),
+    }
+}
+
+// jvm int arithmetic wraps, checked mode uses Math's exact methods, which throw an ArithmeticException, and the
+// rest are helpers of RIMPInt
+fn compile_int_operation(operator: &ArithmeticOperator, overflow: Overflow, int_class: &str) -> String {
+    match (overflow, operator) {
+        (Overflow::Wrapping, ArithmeticOperator::Addition) => String::from("iadd"),
+        (Overflow::Wrapping, ArithmeticOperator::Subtraction) => String::from("isub"),
+        (Overflow::Wrapping, ArithmeticOperator::Multiplication) => String::from("imul"),
+        (Overflow::Wrapping, ArithmeticOperator::Division) => String::from("idiv"),
+        (Overflow::Wrapping, ArithmeticOperator::Exponentiation) => format!("invokestatic Method {} pow (II)I", int_class),
+        (Overflow::Checked, ArithmeticOperator::Addition) => String::from("invokestatic Method java/lang/Math addExact (II)I"),
+        (Overflow::Checked, ArithmeticOperator::Subtraction) => String::from("invokestatic Method java/lang/Math subtractExact (II)I"),
+        (Overflow::Checked, ArithmeticOperator::Multiplication) => String::from("invokestatic Method java/lang/Math multiplyExact (II)I"),
+        (Overflow::Checked, ArithmeticOperator::Division) => format!("invokestatic Method {} divideExact (II)I", int_class),
+        (Overflow::Checked, ArithmeticOperator::Exponentiation) => format!("invokestatic Method {} powExact (II)I", int_class),
+        (Overflow::Saturating, ArithmeticOperator::Addition) => format!("invokestatic Method {} addSaturating (II)I", int_class),
+        (Overflow::Saturating, ArithmeticOperator::Subtraction) => format!("invokestatic Method {} subtractSaturating (II)I", int_class),
+        (Overflow::Saturating, ArithmeticOperator::Multiplication) => format!("invokestatic Method {} multiplySaturating (II)I", int_class),
+        (Overflow::Saturating, ArithmeticOperator::Division) => format!("invokestatic Method {} divideSaturating (II)I", int_class),
+        (Overflow::Saturating, ArithmeticOperator::Exponentiation) => format!("invokestatic Method {} powSaturating (II)I", int_class),
+    }
+}
+
+fn compile_int_negation(overflow: Overflow, int_class: &str) -> String {
+    match overflow {
+        Overflow::Wrapping => String::from("ineg"),
+        Overflow::Checked => String::from("invokestatic Method java/lang/Math negateExact (I)I"),
+        Overflow::Saturating => format!("invokestatic Method {} negateSaturating (I)I", int_class),
     }
 }
 
 fn compile_arithmetic_operation(operator: &ArithmeticOperator, target_type: &Type) -> String {
     match target_type {
-        Type::Integer => match operator {
-            ArithmeticOperator::Addition => String::from("iadd"),
-            ArithmeticOperator::Subtraction => String::from("isub"),
-            ArithmeticOperator::Multiplication => String::from("imul"),
-            ArithmeticOperator::Division => String::from("idiv"),
-            ArithmeticOperator::Exponentiation => unreachable!("Exponentiation is not supported by jvm"),
-        }
+        Type::Integer => unreachable!("int arithmetic depends on the overflow mode, see compile_int_operation"),
         Type::Float => match operator {
             ArithmeticOperator::Addition => String::from("fadd"),
             ArithmeticOperator::Subtraction => String::from("fsub"),
@@ -50,36 +103,132 @@ fn compile_arithmetic_operation(operator: &ArithmeticOperator, target_type: &Typ
             ArithmeticOperator::Division => String::from("fdiv"),
             ArithmeticOperator::Exponentiation => unreachable!("Exponentiation is not supported by jvm"),
         }
+        Type::BigInteger => {
+            let method = match operator {
+                ArithmeticOperator::Addition => "add",
+                ArithmeticOperator::Subtraction => "subtract",
+                ArithmeticOperator::Multiplication => "multiply",
+                ArithmeticOperator::Division => "divide",
+                ArithmeticOperator::Exponentiation => unreachable!("Exponentiation is not supported by jvm"),
+            };
+            format!("invokevirtual Method {} {} (L{};)L{};", BIG_INTEGER, method, BIG_INTEGER, BIG_INTEGER)
+        }
+        Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
     }
 }
 
+// Describes the class that is generated, the package is in java's dotted form (e.g. com.example)
+// and the runtime classes (RIMPInt, RIMPFloat, RIMPBigInt) are expected to live in the same package.
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct JVMCompiler {
-    // variable name -> (index, type)
-    variables: HashMap<String, (usize, Type)>,
-    // index used by the last variable
-    last_variable_index: usize,
+pub struct ClassDetails {
+    pub name: String,
+    pub package: Option<String>,
+    pub source_file: String,
+}
+
+impl Default for ClassDetails {
+    fn default() -> Self {
+        ClassDetails {
+            name: String::from("Main"),
+            package: None,
+            source_file: String::from("Main.rimp"),
+        }
+    }
+}
+
+impl ClassDetails {
+    pub fn new(name: &str, package: Option<&str>, source_file: &str) -> Self {
+        ClassDetails {
+            name: name.to_string(),
+            package: package.map(|package| package.to_string()),
+            source_file: source_file.to_string(),
+        }
+    }
+
+    // the jvm internal name of a class in this package, e.g. com/example/RIMPInt
+    pub fn internal_name(&self, class: &str) -> String {
+        match &self.package {
+            Some(package) => format!("{}/{}", package.replace('.', "/"), class),
+            None => class.to_string(),
+        }
+    }
+
+    // the binary name of a class in this package, as used by the java launcher, e.g. com.example.Main
+    pub fn binary_name(&self, class: &str) -> String {
+        match &self.package {
+            Some(package) => format!("{}.{}", package, class),
+            None => class.to_string(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        fn is_java_identifier(identifier: &str) -> bool {
+            let mut characters = identifier.chars();
+            match characters.next() {
+                Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+                    characters.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+                }
+                _ => false,
+            }
+        }
+
+        if !is_java_identifier(&self.name) {
+            return Err(format!("Invalid class name {}", self.name));
+        }
+
+        if self.name == "RIMPInt" || self.name == "RIMPFloat" || self.name == "RIMPBigInt" {
+            return Err(format!("Class name {} is reserved for the runtime", self.name));
+        }
+
+        if let Some(package) = &self.package {
+            if !package.split('.').all(is_java_identifier) {
+                return Err(format!("Invalid package name {}", package));
+            }
+        }
+
+        Ok(())
+    }
+}
 
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct JVMCompiler {
     // index for fresh labels
     index: usize,
 
     // stack size
     max_stack: usize,
     current_stack: usize,
+
+    // (label, source line) pairs for the line number table
+    line_numbers: Vec<(String, usize)>,
+
+    // internal names of the runtime classes
+    int_class: String,
+    float_class: String,
+    bigint_class: String,
+
+    overflow: Overflow,
 }
 
 impl JVMCompiler {
-    // Stack is less optimal to ensure it is more than enough, could reorder increments/decrements to be more optimal
     pub fn new() -> Self {
         JVMCompiler {
-            variables: HashMap::new(),
-            last_variable_index: 0,
             index: 0,
             max_stack: 0,
             current_stack: 0,
+            line_numbers: Vec::new(),
+            int_class: String::from("RIMPInt"),
+            float_class: String::from("RIMPFloat"),
+            bigint_class: String::from("RIMPBigInt"),
+            overflow: Overflow::default(),
         }
     }
 
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
     fn increment_stack(&mut self) {
         self.current_stack += 1;
         if self.current_stack > self.max_stack {
@@ -97,351 +246,310 @@ impl JVMCompiler {
         label
     }
 
-    fn load_variable(&mut self, variable: &String, variable_type: &Type) -> String {
-        self.increment_stack();
-        self.increment_stack();
-        self.decrement_stack();
-
-        let index = self.variables.get(variable);
+    // slot 0 is main's args, so variables start at 1
+    fn local(slot: Slot) -> usize {
+        slot + 1
+    }
 
-        if index.is_none() {
-            panic!("Variable {} used before assignment", variable);
+    fn runtime_class(&self, variable_type: &Type) -> &str {
+        match variable_type {
+            Type::Integer => &self.int_class,
+            Type::Float => &self.float_class,
+            Type::BigInteger => &self.bigint_class,
+            Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
         }
+    }
 
-        let (index, _) = index.unwrap();
+    fn load_variable(&mut self, slot: Slot, variable_type: &Type) -> String {
+        self.increment_stack();
 
         match variable_type {
-            Type::Integer => format!("aload {}\ninvokevirtual Method RIMPInt get ()I\n", index),
-            Type::Float => format!("aload {}\ninvokevirtual Method RIMPFloat get ()F\n", index),
+            Type::Integer => format!("aload {}\ninvokevirtual Method {} get ()I\n", Self::local(slot), self.int_class),
+            Type::Float => format!("aload {}\ninvokevirtual Method {} get ()F\n", Self::local(slot), self.float_class),
+            Type::BigInteger => format!("aload {}\ninvokevirtual Method {} get ()L{};\n", Self::local(slot), self.bigint_class, BIG_INTEGER),
+            Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
         }
     }
 
-    pub fn _compile(&mut self, program: &Program) -> String {
-        let content = self.compile_program(program);
+    pub fn _compile(&mut self, program: &Program, class: &ClassDetails) -> String {
+        self.int_class = class.internal_name("RIMPInt");
+        self.float_class = class.internal_name("RIMPFloat");
+        self.bigint_class = class.internal_name("RIMPBigInt");
+
+        let mut content = self.compile_block(program, &program.forward);
+        content.push_str(&self.insert_reverse_point(program));
+        content.push_str(&self.compile_block(program, &program.backward));
+
         let file = include_str!("../../../../data/Main.j");
 
-        let content = self.compile_assignments() + &content;
+        let content = self.compile_assignments(program) + "LBODY:\n" + &content;
 
         let file_content = file.replace("<code>", &content);
-        
+
         let max_stack = self.max_stack + 1;
-        let max_locals = self.last_variable_index + 1;
+        let max_locals = program.variables.len() + 1;
 
         let file_content = file_content.replace("<stack>", &max_stack.to_string()).replace("<locals>", &max_locals.to_string());
 
+        let file_content = file_content
+            .replace("<debug>", &self.compile_debug_tables(program))
+            .replace("<source>", &format!("\"{}\"", class.source_file))
+            .replace("<class>", &class.internal_name(&class.name));
+
         file_content
     }
 
-    fn compile_assignments(&self) -> String {
-        let mut variables: Vec<(&String, &(usize, Type))> = self.variables.iter().collect();
-        variables.sort_by(|(_, (index1, _)), (_, (index2, _))| index1.cmp(index2));
-
+    fn compile_debug_tables(&self, program: &Program) -> String {
         let mut code = String::new();
-        for (variable, (index, variable_type)) in variables {
-            match variable_type {
-                Type::Integer => {
-                    code.push_str(&format!("new RIMPInt\ndup\nldc \"{}\"\ninvokespecial Method RIMPInt <init> (Ljava/lang/String;)V\nastore {}\n", variable, index));
-                }
-                Type::Float => {
-                    code.push_str(&format!("new RIMPFloat\ndup\nldc \"{}\"\ninvokespecial Method RIMPFloat <init> (Ljava/lang/String;)V\nastore {}\n", variable, index));
-                }
+
+        if !self.line_numbers.is_empty() {
+            code.push_str(".linenumbertable\n");
+            for (label, line) in &self.line_numbers {
+                code.push_str(&format!("{} {}\n", label, line));
             }
+            code.push_str(".end linenumbertable\n");
+        }
+
+        code.push_str(".localvariabletable\n");
+        code.push_str("0 is args [Ljava/lang/String; from LSTART to LEND\n");
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let class = self.runtime_class(&variable.variable_type);
+            code.push_str(&format!("{} is {} L{}; from LBODY to LEND\n", Self::local(slot), variable.name, class));
         }
+        code.push_str(".end localvariabletable\n");
+
         code
     }
 
-    fn compile_program(&mut self, program: &Program) -> String {
-        match program {
-            Program::Statements(block) => self.compile_block(block),
+    fn compile_assignments(&mut self, program: &Program) -> String {
+        // new, dup and the name
+        self.increment_stack();
+        self.increment_stack();
+        self.increment_stack();
+        self.decrement_stack();
+        self.decrement_stack();
+        self.decrement_stack();
+
+        let mut code = String::new();
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let class = self.runtime_class(&variable.variable_type);
+            code.push_str(&format!(
+                "new {}\ndup\nldc \"{}\"\ninvokespecial Method {} <init> (Ljava/lang/String;)V\nastore {}\n",
+                class, variable.name, class, Self::local(slot)
+            ));
         }
+        code
+    }
+
+    fn compile_block(&mut self, program: &Program, block: &Block) -> String {
+        block.iter().map(|instruction| self.compile_located_instruction(program, instruction)).collect()
     }
 
-    fn compile_block(&mut self, block: &Block) -> String {
-        block.iter().map(|statement| self.compile_statement(statement)).collect()
+    // instructions with a known location are labelled so they can be mapped back to their line in the .rimp file
+    fn compile_located_instruction(&mut self, program: &Program, instruction: &Meta<Instruction>) -> String {
+        if instruction.location.is_unknown() {
+            return self.compile_instruction(program, &instruction.value);
+        }
+
+        let label = self.new_label("LINE");
+        self.line_numbers.push((label.clone(), instruction.location.line));
+
+        format!("{}:\n{}", label, self.compile_instruction(program, &instruction.value))
     }
 
-    fn compile_statement(&mut self, statement: &Statement) -> String {
-        match statement {
-            Statement::Skip => String::from(""),
-            Statement::If(condition, then_block, else_block) => {
-                self.compile_if(condition, then_block, else_block)
+    fn compile_instruction(&mut self, program: &Program, instruction: &Instruction) -> String {
+        match instruction {
+            Instruction::If(condition, then_block, else_block) => {
+                self.compile_if(program, condition, then_block, else_block)
             }
-            Statement::While(condition, block) => {
-                self.compile_while(condition, block)
+            Instruction::While(condition, block, _) => {
+                self.compile_while(program, condition, block)
             }
-            Statement::Assignment(variable, expression) => {
-                self.compile_assignment(variable, expression)
+            Instruction::Push(slot, expression) => {
+                self.compile_assignment(program, *slot, expression, "assign")
             }
-            Statement::ReverseAssignment(variable, expression) => {
-                self.compile_reverse_assignment(variable, expression)
+            Instruction::Set(slot, expression) => {
+                self.compile_assignment(program, *slot, expression, "set")
             }
-            Statement::ReversePoint => {
-                self.insert_reverse_point()
+            Instruction::Pop(slot) => {
+                let class = self.runtime_class(&program.variable(*slot).variable_type);
+                format!("aload {}\ninvokevirtual Method {} unAssign ()V\n", Self::local(*slot), class)
             }
         }
     }
 
-    fn insert_reverse_point(&mut self) -> String {
+    fn insert_reverse_point(&mut self, program: &Program) -> String {
         let mut code = String::new();
         self.increment_stack();
-        self.increment_stack();
-        self.decrement_stack();
         self.decrement_stack();
-        for (_, (index, variable_type)) in self.variables.iter() {
-            match variable_type {
-                Type::Integer => {
-                    code.push_str(&format!("aload {}\ninvokevirtual Method RIMPInt print ()V\n", index));
-                }
-                Type::Float => {
-                    code.push_str(&format!("aload {}\ninvokevirtual Method RIMPFloat print ()V\n", index));
-                }
-            }
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let class = self.runtime_class(&variable.variable_type);
+            code.push_str(&format!("aload {}\ninvokevirtual Method {} print ()V\n", Self::local(slot), class));
         }
         code
     }
 
-    fn compile_if(&mut self, condition: &BooleanExpression, then_block: &Block, else_block: &Block) -> String {
+    fn compile_if(&mut self, program: &Program, condition: &Condition, then_block: &Block, else_block: &Block) -> String {
         let else_label = self.new_label("ELSE");
         let end_label = self.new_label("ENDELSE");
 
-        let condition_code = self.compile_boolean_expression(condition, &else_label);
-        let then_code = self.compile_block(then_block);
-        let else_code = self.compile_block(else_block);
+        let condition_code = self.compile_condition(condition, &else_label, false);
+        let then_code = self.compile_block(program, then_block);
+        let else_code = self.compile_block(program, else_block);
 
         format!("{}{}goto {}\n{}:\n{}\n{}:\n", condition_code, then_code, end_label, else_label, else_code, end_label)
     }
 
-    fn compile_while(&mut self, condition: &BooleanExpression, block: &Block) -> String {
+    fn compile_while(&mut self, program: &Program, condition: &Condition, block: &Block) -> String {
         let start_label = self.new_label("START");
         let end_label = self.new_label("ENDLOOP");
 
-        let condition_code = self.compile_boolean_expression(condition, &end_label);
-        let block_code = self.compile_block(block);
+        let condition_code = self.compile_condition(condition, &end_label, false);
+        let block_code = self.compile_block(program, block);
 
         format!("{}:\n{}{}goto {}\n{}:\n", start_label, condition_code, block_code, start_label, end_label)
     }
 
-    fn compile_assignment(&mut self, variable: &Variable, expression: &ArithmeticExpression) -> String {
-        match variable {
-            Variable::Integer(variable) => {
-                self.increment_stack();
-                self.decrement_stack();
-
-                let (expr_code, expr_type) = self.compile_arithmetic_expression(expression);
-
-                let var = self.variables.get(variable);
-
-                if var.is_none() {
-                    self.last_variable_index += 1;
-                    self.variables.insert(variable.clone(), (self.last_variable_index, Type::Integer));
-                    if expr_type == Type::Float {
-                        let conversion = conversion(&expr_type, &Type::Integer);
-                        format!("aload {}\n{}{}invokevirtual Method RIMPInt assign (I)V\n", self.last_variable_index, expr_code, conversion)
-                    } else {
-                        format!("aload {}\n{}invokevirtual Method RIMPInt assign (I)V\n", self.last_variable_index, expr_code)
-                    }
-                } else {
-                    let (index, _) = var.unwrap();
-                    if expr_type == Type::Float {
-                        let conversion = conversion(&expr_type, &Type::Integer);
-                        format!("aload {}\n{}{}invokevirtual Method RIMPInt assign (I)V\n", index, expr_code, conversion)
-                    } else {
-                        format!("aload {}\n{}invokevirtual Method RIMPInt assign (I)V\n", index, expr_code)
-                    }
-                }
-            },
-            Variable::Float(variable) => {
-                self.increment_stack();
-                self.decrement_stack();
-
-                let (expr_code, expr_type) = self.compile_arithmetic_expression(expression);
-
-                let var = self.variables.get(variable);
+    // method is assign, which records the history, or set, which doesn't
+    fn compile_assignment(&mut self, program: &Program, slot: Slot, expression: &Expression, method: &str) -> String {
+        self.increment_stack();
+        let expr_code = self.compile_expression(expression);
+        self.decrement_stack();
+        self.decrement_stack();
 
-                if var.is_none() {
-                    self.last_variable_index += 1;
-                    self.variables.insert(variable.clone(), (self.last_variable_index, Type::Float));
-                    if expr_type == Type::Integer {
-                        let conversion = conversion(&expr_type, &Type::Float);
-                        format!("aload {}\n{}{}invokevirtual Method RIMPFloat assign (F)V\n", self.last_variable_index, expr_code, conversion)
-                    } else {
-                        format!("aload {}\n{}invokevirtual Method RIMPFloat assign (F)V\n", self.last_variable_index, expr_code)
-                    }
-                } else {
-                    let (index, _) = var.unwrap();
-                    if expr_type == Type::Integer {
-                        let conversion = conversion(&expr_type, &Type::Float);
-                        format!("aload {}\n{}{}invokevirtual Method RIMPFloat assign (F)V\n", index, expr_code, conversion)
-                    } else {
-                        format!("aload {}\n{}invokevirtual Method RIMPFloat assign (F)V\n", index, expr_code)
-                    }
-                }
-            }
+        match program.variable(slot).variable_type {
+            Type::Integer => format!("aload {}\n{}invokevirtual Method {} {} (I)V\n", Self::local(slot), expr_code, self.int_class, method),
+            Type::Float => format!("aload {}\n{}invokevirtual Method {} {} (F)V\n", Self::local(slot), expr_code, self.float_class, method),
+            Type::BigInteger => format!("aload {}\n{}invokevirtual Method {} {} (L{};)V\n", Self::local(slot), expr_code, self.bigint_class, method, BIG_INTEGER),
+            Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
         }
     }
 
-    fn compile_reverse_assignment(&self, variable: &Variable, _: &ArithmeticExpression) -> String {
-        match variable {
-            Variable::Integer(variable) => {
-                let var = self.variables.get(variable);
-
-                if var.is_none() {
-                    panic!("Variable {} being unassigned before assignment", variable);
-                }
-
-                let (index, _) = var.unwrap();
-
-                format!("aload {}\ninvokevirtual Method RIMPInt unAssign ()V\n", index)
-            },
-            Variable::Float(variable) => {
-                let var = self.variables.get(variable);
-
-                if var.is_none() {
-                    panic!("Variable {} being unassigned before assignment", variable);
-                }
-
-                let (index, _) = var.unwrap();
-
-                format!("aload {}\ninvokevirtual Method RIMPFloat unAssign ()V\n", index)
-            }
-        }
-    }
-
-    fn compile_arithmetic_expression(&mut self, arithmetic_expression: &ArithmeticExpression) -> (String, Type) {
-        match arithmetic_expression {
-            ArithmeticExpression::Variable(variable) => {
-                match variable {
-                    Variable::Integer(variable) => {
-                        (self.load_variable(variable, &Type::Integer), Type::Integer)
-                    },
-                    Variable::Float(variable) => {
-                        (self.load_variable(variable, &Type::Float), Type::Float)
-                    }
-                }
-            }
-            ArithmeticExpression::Integer(value) => {
+    // leaves the value of the expression on the stack
+    fn compile_expression(&mut self, expression: &Expression) -> String {
+        match expression {
+            Expression::Variable(slot, variable_type) => self.load_variable(*slot, variable_type),
+            Expression::Integer(value) => {
                 self.increment_stack();
-                (format!("ldc {}\n", value), Type::Integer)
+                format!("ldc {}\n", value)
             }
-            ArithmeticExpression::Float(value) => {
+            Expression::Float(value) => {
                 self.increment_stack();
 
                 // if number has no decimal point, add .0 to make it a float
                 let value = if value.to_string().contains(".") {
-                    format!("{}f", value.to_string())
+                    format!("{}f", value)
                 } else {
-                    format!("{}.0f", value.to_string())
+                    format!("{}.0f", value)
                 };
 
-                (format!("ldc {}\n", value), Type::Float)
+                format!("ldc {}\n", value)
             }
-            ArithmeticExpression::Unary(operator, expression) => {
-                match operator {
-                    UnaryArithmeticOperator::Negation => {
-                        let (expr_code, type_) = self.compile_arithmetic_expression(expression);
-                        self.increment_stack();
-                        self.decrement_stack();
-                        match type_ {
-                            Type::Integer => (format!("{}ineg\n", expr_code), Type::Integer),
-                            Type::Float => (format!("{}fneg\n", expr_code), Type::Float),
-                        }
-                    }
-                }
+            Expression::BigInteger(value) => {
+                // new, dup and the digits
+                self.increment_stack();
+                self.increment_stack();
+                self.increment_stack();
+                self.decrement_stack();
+                self.decrement_stack();
+
+                format!("new {}\ndup\nldc \"{}\"\ninvokespecial Method {} <init> (Ljava/lang/String;)V\n", BIG_INTEGER, value, BIG_INTEGER)
             }
-            ArithmeticExpression::Operation(operator, left, right) => {
-                let (lhs, lhs_type) = self.compile_arithmetic_expression(left);
-                let (rhs, rhs_type) = self.compile_arithmetic_expression(right);
-                match operator {
-                    ArithmeticOperator::Exponentiation => {
-                        self.decrement_stack();
-
-                        let new_lhs = match lhs_type {
-                            Type::Integer => format!("{}i2d\n", lhs),
-                            Type::Float => format!("{}f2d\n", lhs),
-                        };
-
-                        let new_rhs = match rhs_type {
-                            Type::Integer => format!("{}i2d\n", rhs),
-                            Type::Float => format!("{}f2d\n", rhs),
-                        };
-
-                        (format!("{}{}invokestatic java/lang/Math/pow(DD)D\nd2f\n", new_lhs, new_rhs), Type::Float)
-                    }
-                    _ => {
-                        // Check if types are the same, if not, we convert the right hand side to the left hand side type
-                        if lhs_type != rhs_type {
-                            let conversion = conversion(&rhs_type, &lhs_type);
-                            let operator = compile_arithmetic_operation(operator, &lhs_type);
-                            (format!("{}{}{}{}\n", lhs, rhs, conversion, operator), lhs_type)
-                        } else {
-                            let operator = compile_arithmetic_operation(operator, &lhs_type);
-                            (format!("{}{}{}\n", lhs, rhs, operator), lhs_type)
-                        }
-                    }
+            Expression::Convert(target, operand) => {
+                let expr_code = self.compile_expression(operand);
+
+                // an int is widened to a long, which takes two stack entries
+                if operand.expression_type() == Type::Integer && *target == Type::BigInteger {
+                    self.increment_stack();
+                    self.decrement_stack();
                 }
-            }
-        }
-    }
 
-    fn compile_boolean_expression(&mut self, boolean_expression: &BooleanExpression, jump_if_false: &String) -> String {
-        match boolean_expression {
-            BooleanExpression::Unary(operator, expression) => {
-                self.compile_boolean_unary(operator, expression, jump_if_false)
+                format!("{}{}", expr_code, conversion(&operand.expression_type(), target, &self.bigint_class))
             }
-            BooleanExpression::Logical(operator, left, right) => {
-                match operator {
-                    BooleanOperator::And => {
-                        let lhs = self.compile_boolean_expression(left, jump_if_false);
-                        let rhs = self.compile_boolean_expression(right, jump_if_false);
-                        format!("{}{}\n", lhs, rhs)
-                    }
-                    BooleanOperator::Or => {
-                        let next_or_label = self.new_label("OR");
-                        let lhs = self.compile_boolean_expression(left, &next_or_label);
-                        let rhs = self.compile_boolean_expression(right, jump_if_false);
-
-                        format!("{}{}:\n{}\n", lhs, next_or_label, rhs)
-                    }
-                }
+            Expression::Negation(Type::Integer, operand) => {
+                format!("{}{}\n", self.compile_expression(operand), compile_int_negation(self.overflow, &self.int_class))
             }
-            BooleanExpression::Relational(operator, left, right) => {
-                let (lhs, lhs_type) = self.compile_arithmetic_expression(left);
-                let (rhs, rhs_type) = self.compile_arithmetic_expression(right);
-
-                // Check if types are the same, if not, we convert the right hand side to the left hand side type
-                if lhs_type != rhs_type {
-                    let conversion = conversion(&rhs_type, &lhs_type);
-                    let operator = compile_relational_operator(operator, &lhs_type);
-                    self.decrement_stack();
-                    self.decrement_stack();
+            Expression::Negation(Type::Float, operand) => format!("{}fneg\n", self.compile_expression(operand)),
+            Expression::Negation(Type::BigInteger, operand) => {
+                format!("{}invokevirtual Method {} negate ()L{};\n", self.compile_expression(operand), BIG_INTEGER, BIG_INTEGER)
+            }
+            Expression::Negation(Type::Rational, _) => unreachable!("rationals are only interpreted, see IR::lower"),
+            Expression::Operation(Type::BigInteger, ArithmeticOperator::Exponentiation, left, right) => {
+                let lhs = self.compile_expression(left);
+                let rhs = self.compile_expression(right);
+                self.decrement_stack();
 
-                    format!("{}\n{}\n{}\n{} {}\n", lhs, rhs, conversion, operator, jump_if_false)
-                } else {
-                    let operator = compile_relational_operator(operator, &lhs_type);
-                    self.decrement_stack();
-                    self.decrement_stack();
+                // the exponent has to fit in an int, intValueExact throws otherwise
+                format!(
+                    "{}{}invokevirtual Method {} intValueExact ()I\ninvokevirtual Method {} pow (I)L{};\n",
+                    lhs, rhs, BIG_INTEGER, BIG_INTEGER, BIG_INTEGER
+                )
+            }
+            Expression::Operation(Type::Float, ArithmeticOperator::Exponentiation, left, right) => {
+                // both operands are widened to doubles, which take two stack entries each
+                let lhs = self.compile_expression(left);
+                self.increment_stack();
+                let rhs = self.compile_expression(right);
+                self.increment_stack();
+                self.decrement_stack();
+                self.decrement_stack();
+                self.decrement_stack();
 
-                    format!("{}\n{}\n{} {}\n", lhs, rhs, operator, jump_if_false)
-                }
+                format!("{}f2d\n{}f2d\ninvokestatic java/lang/Math/pow(DD)D\nd2f\n", lhs, rhs)
+            }
+            Expression::Operation(operation_type, operator, left, right) => {
+                let lhs = self.compile_expression(left);
+                let rhs = self.compile_expression(right);
+                self.decrement_stack();
+
+                let operator = match operation_type {
+                    Type::Integer => compile_int_operation(operator, self.overflow, &self.int_class),
+                    _ => compile_arithmetic_operation(operator, operation_type),
+                };
+                format!("{}{}{}\n", lhs, rhs, operator)
             }
         }
     }
 
-    fn compile_boolean_unary(&mut self, operator: &UnaryBooleanOperator, expression: &BooleanExpression, jump_if_false: &String) -> String {
-        match operator {
-            UnaryBooleanOperator::Negation => {
-                let expr_code = self.compile_boolean_expression(expression, jump_if_false);
+    // jumps to the label when the condition is jump_when, otherwise falls through
+    fn compile_condition(&mut self, condition: &Condition, label: &String, jump_when: bool) -> String {
+        match condition {
+            Condition::Not(operand) => self.compile_condition(operand, label, !jump_when),
+            // both sides jump to the same place
+            Condition::And(left, right) if !jump_when => {
+                let lhs = self.compile_condition(left, label, false);
+                let rhs = self.compile_condition(right, label, false);
+                format!("{}{}", lhs, rhs)
+            }
+            Condition::Or(left, right) if jump_when => {
+                let lhs = self.compile_condition(left, label, true);
+                let rhs = self.compile_condition(right, label, true);
+                format!("{}{}", lhs, rhs)
+            }
+            // the left side decides it alone when it's false for && or true for ||, so it skips over the right side
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let skip_label = self.new_label("SKIP");
+                let lhs = self.compile_condition(left, &skip_label, !jump_when);
+                let rhs = self.compile_condition(right, label, jump_when);
+                format!("{}{}{}:\n", lhs, rhs, skip_label)
+            }
+            Condition::Comparison(comparison_type, operator, left, right) => {
+                let lhs = self.compile_expression(left);
+                let rhs = self.compile_expression(right);
+                self.decrement_stack();
                 self.decrement_stack();
 
-                format!("{}ifeq {}\n", expr_code, jump_if_false)
+                let operator = compile_relational_operator(operator, comparison_type, jump_when);
+                format!("{}{}{} {}\n", lhs, rhs, operator, label)
             }
         }
     }
 }
 
 impl Backend for JVMCompiler {
-    fn compile(program: &Program) -> String {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
         let mut compiler = JVMCompiler::new();
-        compiler._compile(program)
+        compiler._compile(&program, &ClassDetails::default())
     }
 }
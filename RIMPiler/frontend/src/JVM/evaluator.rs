@@ -0,0 +1,546 @@
+/*
+A small evaluator for the Jasmin (Krakatau syntax) the JVM backend emits, so the tests can run the compiled
+program without Krakatau or a JVM.
+
+Class::new reads the instructions of the main method, resolving labels to indices, and Evaluator::run executes
+them. Only the instructions and methods the backend uses are supported: the runtime classes (RIMPInt, RIMPFloat
+and RIMPBigInt, in any package) are modelled directly, along with the BigInteger and Math methods they are used
+with. Anything the JVM would throw, like dividing an int by 0 or unassigning an empty history, is an error.
+*/
+
+use std::collections::HashMap;
+use utilities::bigint::BigInt;
+use super::super::interpreter::error::RuntimeError;
+use super::super::overflow::Overflow;
+use super::super::AST::ArithmeticOperator;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Int(i32),
+    Float(f32),
+    BigInteger(BigInt),
+}
+
+// an instance of one of the runtime classes, as print shows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub value: Number,
+    pub history: Vec<Number>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Text(String),
+    Reference(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Object {
+    Uninitialised(String),
+    Variable(Variable),
+    BigInteger(BigInt),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Instruction {
+    opcode: String,
+    operands: Vec<String>,
+}
+
+pub struct Class {
+    code: Vec<Instruction>,
+    labels: HashMap<String, usize>,
+}
+
+impl Class {
+    pub fn new(source: &str) -> Result<Class, String> {
+        let start = source.find(".method public static main").ok_or("No main method")?;
+        let end = source[start..].find(".end method").ok_or("Main method isn't closed")? + start;
+
+        let mut code = Vec::new();
+        let mut labels = HashMap::new();
+        let mut in_table = false;
+
+        // skips the header, .method and .code
+        for line in source[start..end].lines().skip(2) {
+            let line = line.trim();
+
+            // the entries of the debug tables aren't directives, so the whole table is skipped
+            if line == ".linenumbertable" || line == ".localvariabletable" {
+                in_table = true;
+            } else if line.starts_with(".end") {
+                in_table = false;
+            }
+            if in_table || line.is_empty() || line.starts_with('.') {
+                continue;
+            }
+
+            if let Some(label) = line.strip_suffix(':') {
+                if labels.insert(label.to_string(), code.len()).is_some() {
+                    return Err(format!("Label {} is defined twice", label));
+                }
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace().map(|token| token.to_string());
+            let opcode = tokens.next().unwrap();
+            code.push(Instruction { opcode, operands: tokens.collect() });
+        }
+
+        for instruction in &code {
+            if instruction.opcode == "goto" || instruction.opcode.starts_with("if") {
+                let label = instruction.operands.first().ok_or(format!("{} without a label", instruction.opcode))?;
+                if !labels.contains_key(label) {
+                    return Err(format!("Undefined label {}", label));
+                }
+            }
+        }
+
+        Ok(Class { code, labels })
+    }
+}
+
+pub struct Evaluator<'a> {
+    class: &'a Class,
+    fuel: usize,
+
+    stack: Vec<Value>,
+    locals: HashMap<usize, Value>,
+    heap: Vec<Object>,
+
+    // each variable as it was printed at the reverse point
+    pub printed: Vec<Variable>,
+}
+
+// the class of an internal name, without its package
+fn simple_name(class: &str) -> &str {
+    class.rsplit('/').next().unwrap()
+}
+
+fn int_operation(overflow: Overflow, operator: ArithmeticOperator, lhs: i32, rhs: i32) -> Result<i32, String> {
+    overflow.apply(&operator, lhs, rhs).map_err(exception)
+}
+
+// what the JVM throws for an error of int arithmetic
+fn exception(error: RuntimeError) -> String {
+    match error {
+        RuntimeError::DivisionByZero { .. } => String::from("ArithmeticException: / by zero"),
+        RuntimeError::Overflow { .. } => String::from("ArithmeticException: integer overflow"),
+        error => format!("ArithmeticException: {}", error.message()),
+    }
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(class: &'a Class, fuel: usize) -> Evaluator<'a> {
+        Evaluator { class, fuel, stack: Vec::new(), locals: HashMap::new(), heap: Vec::new(), printed: Vec::new() }
+    }
+
+    // the variables in the order of their locals, as they are now
+    pub fn variables(&self) -> Vec<Variable> {
+        let mut locals: Vec<(&usize, &Value)> = self.locals.iter().collect();
+        locals.sort_by_key(|(local, _)| **local);
+
+        locals
+            .into_iter()
+            .filter_map(|(_, value)| match value {
+                Value::Reference(reference) => match &self.heap[*reference] {
+                    Object::Variable(variable) => Some(variable.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    // runs main to its return, returning the number of instructions executed
+    pub fn run(&mut self) -> Result<usize, String> {
+        let mut pc = 0;
+        let mut steps = 0;
+
+        loop {
+            if steps == self.fuel {
+                return Err(String::from("Out of fuel"));
+            }
+            steps += 1;
+
+            let instruction = self.class.code.get(pc).ok_or("Fell off the end of main")?;
+            pc += 1;
+
+            match self.execute(instruction)? {
+                Jump::Next => {}
+                Jump::To(label) => pc = self.class.labels[&label],
+                Jump::Return => return Ok(steps),
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Result<Value, String> {
+        self.stack.pop().ok_or(String::from("Stack underflow"))
+    }
+
+    fn pop_int(&mut self) -> Result<i32, String> {
+        match self.pop()? {
+            Value::Int(value) => Ok(value),
+            value => Err(format!("Expected an int, found {:?}", value)),
+        }
+    }
+
+    fn pop_float(&mut self) -> Result<f32, String> {
+        match self.pop()? {
+            Value::Float(value) => Ok(value),
+            value => Err(format!("Expected a float, found {:?}", value)),
+        }
+    }
+
+    fn pop_double(&mut self) -> Result<f64, String> {
+        match self.pop()? {
+            Value::Double(value) => Ok(value),
+            value => Err(format!("Expected a double, found {:?}", value)),
+        }
+    }
+
+    fn pop_reference(&mut self) -> Result<usize, String> {
+        match self.pop()? {
+            Value::Reference(reference) => Ok(reference),
+            value => Err(format!("Expected a reference, found {:?}", value)),
+        }
+    }
+
+    fn pop_big_integer(&mut self) -> Result<BigInt, String> {
+        let reference = self.pop_reference()?;
+        match &self.heap[reference] {
+            Object::BigInteger(value) => Ok(value.clone()),
+            object => Err(format!("Expected a BigInteger, found {:?}", object)),
+        }
+    }
+
+    fn pop_variable(&mut self) -> Result<&mut Variable, String> {
+        let reference = self.pop_reference()?;
+        match &mut self.heap[reference] {
+            Object::Variable(variable) => Ok(variable),
+            object => Err(format!("Expected a runtime variable, found {:?}", object)),
+        }
+    }
+
+    fn push_big_integer(&mut self, value: BigInt) {
+        self.heap.push(Object::BigInteger(value));
+        self.stack.push(Value::Reference(self.heap.len() - 1));
+    }
+
+    fn branch(&self, taken: bool, instruction: &Instruction) -> Jump {
+        if taken {
+            Jump::To(instruction.operands[0].clone())
+        } else {
+            Jump::Next
+        }
+    }
+
+    fn execute(&mut self, instruction: &Instruction) -> Result<Jump, String> {
+        let operand = |index: usize| {
+            instruction.operands.get(index).map(String::as_str).ok_or(format!("{} is missing an operand", instruction.opcode))
+        };
+
+        match instruction.opcode.as_str() {
+            "ldc" => {
+                let constant = operand(0)?;
+                let value = if let Some(text) = constant.strip_prefix('"').and_then(|text| text.strip_suffix('"')) {
+                    Value::Text(text.to_string())
+                } else if let Some(float) = constant.strip_suffix('f') {
+                    Value::Float(float.parse().map_err(|_| format!("Bad float constant {}", constant))?)
+                } else {
+                    Value::Int(constant.parse().map_err(|_| format!("Bad int constant {}", constant))?)
+                };
+                self.stack.push(value);
+            }
+            "new" => {
+                self.heap.push(Object::Uninitialised(operand(0)?.to_string()));
+                self.stack.push(Value::Reference(self.heap.len() - 1));
+            }
+            "dup" => {
+                let value = self.pop()?;
+                self.stack.push(value.clone());
+                self.stack.push(value);
+            }
+            "astore" | "aload" => {
+                let local: usize = operand(0)?.parse().map_err(|_| format!("Bad local {}", operand(0).unwrap()))?;
+                if instruction.opcode == "astore" {
+                    let value = self.pop()?;
+                    self.locals.insert(local, value);
+                } else {
+                    let value = self.locals.get(&local).ok_or(format!("Local {} isn't set", local))?.clone();
+                    self.stack.push(value);
+                }
+            }
+            "invokespecial" => {
+                let argument = self.pop()?;
+                let reference = self.pop_reference()?;
+                let class = match &self.heap[reference] {
+                    Object::Uninitialised(class) => class.clone(),
+                    object => return Err(format!("{:?} is already initialised", object)),
+                };
+                let text = match argument {
+                    Value::Text(text) => text,
+                    value => return Err(format!("Expected a string, found {:?}", value)),
+                };
+
+                self.heap[reference] = match simple_name(&class) {
+                    "BigInteger" => Object::BigInteger(text.parse().map_err(|_| format!("NumberFormatException: {}", text))?),
+                    name => {
+                        let zero = match name {
+                            "RIMPInt" => Number::Int(0),
+                            "RIMPFloat" => Number::Float(0.0),
+                            "RIMPBigInt" => Number::BigInteger(BigInt::zero()),
+                            _ => return Err(format!("Unknown class {}", class)),
+                        };
+                        Object::Variable(Variable { name: text, value: zero.clone(), history: vec![zero] })
+                    }
+                };
+            }
+            "invokevirtual" => self.invoke_virtual(simple_name(operand(1)?), operand(2)?)?,
+            "invokestatic" if operand(0)? == "java/lang/Math/pow(DD)D" => {
+                let exponent = self.pop_double()?;
+                let base = self.pop_double()?;
+                self.stack.push(Value::Double(base.powf(exponent)));
+            }
+            "invokestatic" => self.invoke_static(simple_name(operand(1)?), operand(2)?)?,
+            "iadd" | "isub" | "imul" | "idiv" => {
+                let rhs = self.pop_int()?;
+                let lhs = self.pop_int()?;
+                let operator = match instruction.opcode.as_str() {
+                    "iadd" => ArithmeticOperator::Addition,
+                    "isub" => ArithmeticOperator::Subtraction,
+                    "imul" => ArithmeticOperator::Multiplication,
+                    _ => ArithmeticOperator::Division,
+                };
+                self.stack.push(Value::Int(int_operation(Overflow::Wrapping, operator, lhs, rhs)?));
+            }
+            "ineg" => {
+                let value = self.pop_int()?;
+                self.stack.push(Value::Int(value.wrapping_neg()));
+            }
+            "fadd" | "fsub" | "fmul" | "fdiv" => {
+                let rhs = self.pop_float()?;
+                let lhs = self.pop_float()?;
+                self.stack.push(Value::Float(match instruction.opcode.as_str() {
+                    "fadd" => lhs + rhs,
+                    "fsub" => lhs - rhs,
+                    "fmul" => lhs * rhs,
+                    _ => lhs / rhs,
+                }));
+            }
+            "fneg" => {
+                let value = self.pop_float()?;
+                self.stack.push(Value::Float(-value));
+            }
+            // like java, casting a float to an int saturates and NaN becomes 0
+            "i2f" => {
+                let value = self.pop_int()?;
+                self.stack.push(Value::Float(value as f32));
+            }
+            "f2i" => {
+                let value = self.pop_float()?;
+                self.stack.push(Value::Int(value as i32));
+            }
+            "i2l" => {
+                let value = self.pop_int()?;
+                self.stack.push(Value::Long(value as i64));
+            }
+            "f2d" => {
+                let value = self.pop_float()?;
+                self.stack.push(Value::Double(value as f64));
+            }
+            "d2f" => {
+                let value = self.pop_double()?;
+                self.stack.push(Value::Float(value as f32));
+            }
+            // fcmpl gives -1 and fcmpg 1 when either is NaN
+            "fcmpl" | "fcmpg" => {
+                let rhs = self.pop_float()?;
+                let lhs = self.pop_float()?;
+                let result = match lhs.partial_cmp(&rhs) {
+                    Some(ordering) => ordering as i32,
+                    None if instruction.opcode == "fcmpl" => -1,
+                    None => 1,
+                };
+                self.stack.push(Value::Int(result));
+            }
+            "if_icmpeq" | "if_icmpne" | "if_icmplt" | "if_icmpge" | "if_icmpgt" | "if_icmple" => {
+                let rhs = self.pop_int()?;
+                let lhs = self.pop_int()?;
+                let taken = match instruction.opcode.as_str() {
+                    "if_icmpeq" => lhs == rhs,
+                    "if_icmpne" => lhs != rhs,
+                    "if_icmplt" => lhs < rhs,
+                    "if_icmpge" => lhs >= rhs,
+                    "if_icmpgt" => lhs > rhs,
+                    _ => lhs <= rhs,
+                };
+                return Ok(self.branch(taken, instruction));
+            }
+            "ifeq" | "ifne" | "iflt" | "ifge" | "ifgt" | "ifle" => {
+                let value = self.pop_int()?;
+                let taken = match instruction.opcode.as_str() {
+                    "ifeq" => value == 0,
+                    "ifne" => value != 0,
+                    "iflt" => value < 0,
+                    "ifge" => value >= 0,
+                    "ifgt" => value > 0,
+                    _ => value <= 0,
+                };
+                return Ok(self.branch(taken, instruction));
+            }
+            "goto" => return Ok(Jump::To(operand(0)?.to_string())),
+            "return" => return Ok(Jump::Return),
+            opcode => return Err(format!("Unsupported instruction {}", opcode)),
+        }
+
+        Ok(Jump::Next)
+    }
+
+    fn invoke_virtual(&mut self, class: &str, method: &str) -> Result<(), String> {
+        match (class, method) {
+            ("RIMPInt" | "RIMPFloat" | "RIMPBigInt", "assign" | "set") => {
+                let value = match class {
+                    "RIMPInt" => Number::Int(self.pop_int()?),
+                    "RIMPFloat" => Number::Float(self.pop_float()?),
+                    _ => Number::BigInteger(self.pop_big_integer()?),
+                };
+                let assign = method == "assign";
+                let variable = self.pop_variable()?;
+
+                // ints and bigints remember the difference, floats the old value
+                if assign {
+                    let entry = match (&value, &variable.value) {
+                        (Number::Int(new), Number::Int(old)) => Number::Int(new.wrapping_sub(*old)),
+                        (Number::BigInteger(new), Number::BigInteger(old)) => Number::BigInteger(new - old),
+                        (Number::Float(_), old @ Number::Float(_)) => old.clone(),
+                        _ => return Err(format!("{:?} assigned to {}", value, variable.name)),
+                    };
+                    variable.history.push(entry);
+                }
+                variable.value = value;
+            }
+            ("RIMPInt" | "RIMPFloat" | "RIMPBigInt", "unAssign") => {
+                let variable = self.pop_variable()?;
+                let entry = variable.history.pop().ok_or(format!("EmptyStackException: the history of {}", variable.name))?;
+                variable.value = match (&variable.value, entry) {
+                    (Number::Int(value), Number::Int(difference)) => Number::Int(value.wrapping_sub(difference)),
+                    (Number::BigInteger(value), Number::BigInteger(difference)) => Number::BigInteger(value - &difference),
+                    (_, old) => old,
+                };
+            }
+            ("RIMPInt" | "RIMPFloat" | "RIMPBigInt", "get") => {
+                let value = self.pop_variable()?.value.clone();
+                match value {
+                    Number::Int(value) => self.stack.push(Value::Int(value)),
+                    Number::Float(value) => self.stack.push(Value::Float(value)),
+                    Number::BigInteger(value) => self.push_big_integer(value),
+                }
+            }
+            ("RIMPInt" | "RIMPFloat" | "RIMPBigInt", "print") => {
+                let variable = self.pop_variable()?.clone();
+                self.printed.push(variable);
+            }
+            ("BigInteger", "add" | "subtract" | "multiply" | "divide") => {
+                let rhs = self.pop_big_integer()?;
+                let lhs = self.pop_big_integer()?;
+                let result = match method {
+                    "add" => lhs + rhs,
+                    "subtract" => lhs - rhs,
+                    "multiply" => lhs * rhs,
+                    _ => lhs.checked_div(&rhs).ok_or("ArithmeticException: BigInteger divide by zero")?,
+                };
+                self.push_big_integer(result);
+            }
+            ("BigInteger", "negate") => {
+                let value = self.pop_big_integer()?;
+                self.push_big_integer(-value);
+            }
+            ("BigInteger", "pow") => {
+                let exponent = self.pop_int()?;
+                let value = self.pop_big_integer()?;
+                if exponent < 0 {
+                    return Err(String::from("ArithmeticException: Negative exponent"));
+                }
+                self.push_big_integer(value.pow(exponent as u32));
+            }
+            ("BigInteger", "compareTo") => {
+                let rhs = self.pop_big_integer()?;
+                let lhs = self.pop_big_integer()?;
+                self.stack.push(Value::Int(lhs.cmp(&rhs) as i32));
+            }
+            ("BigInteger", "intValue") => {
+                let value = self.pop_big_integer()?;
+                self.stack.push(Value::Int(value.wrapping_i32()));
+            }
+            ("BigInteger", "intValueExact") => {
+                let value = self.pop_big_integer()?;
+                self.stack.push(Value::Int(value.to_i32().ok_or("ArithmeticException: BigInteger out of int range")?));
+            }
+            ("BigInteger", "floatValue") => {
+                let value = self.pop_big_integer()?;
+                self.stack.push(Value::Float(value.to_f32()));
+            }
+            _ => return Err(format!("Unsupported method {}.{}", class, method)),
+        }
+
+        Ok(())
+    }
+
+    fn invoke_static(&mut self, class: &str, method: &str) -> Result<(), String> {
+        let binary = |method: &str| match method {
+            "pow" | "powExact" | "powSaturating" => Some(ArithmeticOperator::Exponentiation),
+            "addExact" | "addSaturating" => Some(ArithmeticOperator::Addition),
+            "subtractExact" | "subtractSaturating" => Some(ArithmeticOperator::Subtraction),
+            "multiplyExact" | "multiplySaturating" => Some(ArithmeticOperator::Multiplication),
+            "divideExact" | "divideSaturating" => Some(ArithmeticOperator::Division),
+            _ => None,
+        };
+        let overflow = if method == "pow" {
+            Overflow::Wrapping
+        } else if method.ends_with("Saturating") {
+            Overflow::Saturating
+        } else {
+            Overflow::Checked
+        };
+
+        match (class, method) {
+            ("RIMPInt" | "Math", method) if binary(method).is_some() => {
+                let rhs = self.pop_int()?;
+                let lhs = self.pop_int()?;
+                self.stack.push(Value::Int(int_operation(overflow, binary(method).unwrap(), lhs, rhs)?));
+            }
+            ("RIMPInt" | "Math", "negateExact" | "negateSaturating") => {
+                let value = self.pop_int()?;
+                self.stack.push(Value::Int(overflow.negate(value).map_err(exception)?));
+            }
+            ("BigInteger", "valueOf") => {
+                let value = match self.pop()? {
+                    Value::Long(value) => value,
+                    value => return Err(format!("Expected a long, found {:?}", value)),
+                };
+                self.push_big_integer(BigInt::from(value));
+            }
+            // new BigDecimal(value).toBigInteger(), which truncates
+            ("RIMPBigInt", "fromFloat") => {
+                let value = self.pop_float()?;
+                let value = BigInt::from_f32(value).ok_or(format!("NumberFormatException: {}", value))?;
+                self.push_big_integer(value);
+            }
+            _ => return Err(format!("Unsupported static method {}.{}", class, method)),
+        }
+
+        Ok(())
+    }
+}
+
+enum Jump {
+    Next,
+    To(String),
+    Return,
+}
@@ -1 +1,6 @@
-pub mod code_gen;
\ No newline at end of file
+pub mod code_gen;
+#[cfg(test)]
+pub(crate) mod evaluator;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
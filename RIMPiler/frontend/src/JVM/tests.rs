@@ -0,0 +1,109 @@
+use super::code_gen::{ClassDetails, JVMCompiler};
+use super::super::lexer::lexer::Tokeniser;
+use super::super::parser::parser::parse;
+use super::super::post_parse::inverter::invert_and_combine;
+use super::super::IR::lower::lower;
+
+#[test]
+fn debug_information() {
+    let program = r#"int n = 2;
+while n > 0 do {
+    n = n / 2;
+};"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = lower(&invert_and_combine(&ast)).unwrap();
+
+    let byte_code = JVMCompiler::new()._compile(&inverted, &ClassDetails::new("Main", None, "while.rimp"));
+
+    assert!(byte_code.contains(".sourcefile \"while.rimp\""));
+    assert!(byte_code.contains(".linenumbertable"));
+    assert!(byte_code.contains("LLINE0 1\n"));
+    assert!(byte_code.contains("LLINE5 3\n"));
+    assert!(byte_code.contains("is n LRIMPInt; from LBODY to LEND"));
+    assert!(byte_code.contains("is generated_name_semantic_transformer0 LRIMPInt; from LBODY to LEND"));
+}
+
+#[test]
+fn packaged_class() {
+    let program = "float x = 1.5; x = x * 2;";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = lower(&invert_and_combine(&ast)).unwrap();
+
+    let class = ClassDetails::new("Floats", Some("com.example"), "floats.rimp");
+    assert_eq!(class.validate(), Ok(()));
+    assert_eq!(class.binary_name("Floats"), "com.example.Floats");
+
+    let byte_code = JVMCompiler::new()._compile(&inverted, &class);
+
+    assert!(byte_code.contains(".class public super com/example/Floats\n"));
+    assert!(byte_code.contains("new com/example/RIMPFloat\n"));
+    assert!(byte_code.contains("invokevirtual Method com/example/RIMPFloat set (F)V"));
+    assert!(byte_code.contains("invokevirtual Method com/example/RIMPFloat assign (F)V"));
+    assert!(!byte_code.contains(" RIMPFloat "));
+
+    assert!(ClassDetails::new("1Main", None, "main.rimp").validate().is_err());
+    assert!(ClassDetails::new("Main", Some("com..example"), "main.rimp").validate().is_err());
+    assert!(ClassDetails::new("RIMPInt", None, "main.rimp").validate().is_err());
+}
+
+#[test]
+fn bigints() {
+    let program = "bigint x = 12345678901234567890; int n = 3; x = x * n; if x > 0 then { n = x; } else { skip; };";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = lower(&invert_and_combine(&ast)).unwrap();
+
+    let byte_code = JVMCompiler::new()._compile(&inverted, &ClassDetails::default());
+
+    assert!(byte_code.contains("new RIMPBigInt\n"));
+    assert!(byte_code.contains("ldc \"12345678901234567890\"\ninvokespecial Method java/math/BigInteger <init> (Ljava/lang/String;)V\n"));
+    assert!(byte_code.contains("i2l\ninvokestatic Method java/math/BigInteger valueOf (J)Ljava/math/BigInteger;\n"));
+    assert!(byte_code.contains("invokevirtual Method java/math/BigInteger multiply (Ljava/math/BigInteger;)Ljava/math/BigInteger;\n"));
+    assert!(byte_code.contains("invokevirtual Method RIMPBigInt assign (Ljava/math/BigInteger;)V\n"));
+    assert!(byte_code.contains("invokevirtual Method java/math/BigInteger compareTo (Ljava/math/BigInteger;)I\nifle"));
+    assert!(byte_code.contains("invokevirtual Method java/math/BigInteger intValue ()I\n"));
+
+    assert!(ClassDetails::new("RIMPBigInt", None, "main.rimp").validate().is_err());
+}
+
+#[test]
+fn overflow_modes() {
+    use super::super::overflow::Overflow;
+
+    let program = "int n = 3; int m = 2; n = n + m; n = n / m; n = n ^ m; m = -n;";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = lower(&invert_and_combine(&ast)).unwrap();
+
+    let byte_code = JVMCompiler::new()._compile(&inverted, &ClassDetails::default());
+    assert!(byte_code.contains("iadd\n"));
+    assert!(byte_code.contains("idiv\n"));
+    assert!(byte_code.contains("invokestatic Method RIMPInt pow (II)I\n"));
+    assert!(byte_code.contains("ineg\n"));
+    assert!(!byte_code.contains("Math/pow"));
+
+    let byte_code = JVMCompiler::new().overflow(Overflow::Checked)._compile(&inverted, &ClassDetails::default());
+    assert!(byte_code.contains("invokestatic Method java/lang/Math addExact (II)I\n"));
+    assert!(byte_code.contains("invokestatic Method RIMPInt divideExact (II)I\n"));
+    assert!(byte_code.contains("invokestatic Method RIMPInt powExact (II)I\n"));
+    assert!(byte_code.contains("invokestatic Method java/lang/Math negateExact (I)I\n"));
+    assert!(!byte_code.contains("iadd\n"));
+
+    let byte_code = JVMCompiler::new().overflow(Overflow::Saturating)._compile(&inverted, &ClassDetails::default());
+    assert!(byte_code.contains("invokestatic Method RIMPInt addSaturating (II)I\n"));
+    assert!(byte_code.contains("invokestatic Method RIMPInt negateSaturating (I)I\n"));
+}
@@ -0,0 +1,266 @@
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Slot, Type};
+use super::super::JVM::code_gen::ClassDetails;
+use super::super::Backend;
+
+/*
+Transpiles a RIMP program into a single, self-contained java source file.
+
+Every variable is backed by the same runtime classes as the JVM backend (RIMPInt, RIMPFloat), which are
+nested in the generated class so nothing else is needed to build it. Assignments push the difference
+onto the variable's history and reverse assignments pop it, so the inverted half of the program restores
+the initial state exactly as it does in the interpreter.
+*/
+
+const INDENT: &str = "    ";
+
+// words that cannot be used as local variable names in java, plus the parameter of main
+const RESERVED: [&str; 54] = [
+    "abstract", "assert", "boolean", "break", "byte", "case", "catch", "char", "class", "const",
+    "continue", "default", "do", "double", "else", "enum", "extends", "final", "finally", "float",
+    "for", "goto", "if", "implements", "import", "instanceof", "int", "interface", "long", "native",
+    "new", "package", "private", "protected", "public", "return", "short", "static", "strictfp", "super",
+    "switch", "synchronized", "this", "throw", "throws", "transient", "try", "void", "volatile", "while",
+    "true", "false", "null", "args",
+];
+
+// binding strength of java's operators, used to only emit the brackets that are needed
+fn arithmetic_precedence(expression: &Expression) -> usize {
+    match expression {
+        Expression::Operation(_, operator, _, _) => match operator {
+            ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => 1,
+            ArithmeticOperator::Multiplication | ArithmeticOperator::Division => 2,
+            // exponentiation is a call to pow, which binds as tightly as a variable
+            ArithmeticOperator::Exponentiation => 4,
+        },
+        // casts bind as tightly as a unary minus
+        Expression::Negation(_, _) | Expression::Convert(Type::Integer, _) => 3,
+        Expression::Integer(value) if *value < 0 => 3,
+        Expression::Float(value) if value.is_sign_negative() => 3,
+        // ints converted to floats are written as they are, java widens them
+        Expression::Convert(Type::Float, operand) => arithmetic_precedence(operand),
+        _ => 4,
+    }
+}
+
+fn boolean_precedence(condition: &Condition) -> usize {
+    match condition {
+        Condition::Or(_, _) => 1,
+        Condition::And(_, _) => 2,
+        Condition::Comparison(_, _, _, _) => 3,
+        Condition::Not(_) => 4,
+    }
+}
+fn float_literal(value: f32) -> String {
+    // if number has no decimal point, add .0 to make it a float
+    if value.to_string().contains('.') {
+        format!("{}f", value)
+    } else {
+        format!("{}.0f", value)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct JavaCompiler {}
+
+impl JavaCompiler {
+    pub fn new() -> Self {
+        JavaCompiler {}
+    }
+
+    // RIMP identifiers never contain an underscore, generated ones never end with one, so the suffix can't clash
+    fn local_name(program: &Program, slot: Slot) -> String {
+        let variable = &program.variable(slot).name;
+        if RESERVED.contains(&variable.as_str()) {
+            format!("{}_", variable)
+        } else {
+            variable.to_string()
+        }
+    }
+
+    pub fn _compile(&mut self, program: &Program, class: &ClassDetails) -> String {
+        let mut content = String::from("// forward program\n");
+        content.push_str(&self.compile_block(program, &program.forward, 0));
+        content.push_str("\n// reverse point\n");
+        for slot in 0..program.variables.len() {
+            content.push_str(&format!("{}.print();\n", Self::local_name(program, slot)));
+        }
+        content.push_str("\n// inverted program\n");
+        content.push_str(&self.compile_block(program, &program.backward, 0));
+
+        // the body of main is two levels deep in the class
+        let content = content
+            .lines()
+            .map(|line| if line.is_empty() { String::from("\n") } else { format!("{}{}{}\n", INDENT, INDENT, line) })
+            .collect::<String>();
+
+        let declarations = Self::compile_declarations(program);
+
+        let package = match &class.package {
+            Some(package) => format!("package {};\n\n", package),
+            None => String::new(),
+        };
+
+        let file = include_str!("../../../../data/Main.java");
+
+        file.replace("<package>", &package)
+            .replace("<source>", &class.source_file)
+            .replace("<class>", &class.name)
+            .replace("<declarations>\n", &declarations)
+            .replace("<code>\n", &content)
+    }
+
+    fn compile_declarations(program: &Program) -> String {
+        let mut code = String::new();
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let class = match variable.variable_type {
+                Type::Integer => "RIMPInt",
+                Type::Float => "RIMPFloat",
+                Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+            };
+            code.push_str(&format!(
+                "{}{}{} {} = new {}(\"{}\");\n",
+                INDENT, INDENT, class, Self::local_name(program, slot), class, variable.name
+            ));
+        }
+        code
+    }
+
+    fn compile_block(&mut self, program: &Program, block: &Block, depth: usize) -> String {
+        block.iter().map(|instruction| self.compile_instruction(program, &instruction.value, depth)).collect()
+    }
+
+    fn compile_instruction(&mut self, program: &Program, instruction: &Instruction, depth: usize) -> String {
+        let indent = INDENT.repeat(depth);
+        match instruction {
+            Instruction::If(condition, then_block, else_block) => {
+                let condition = self.compile_condition(program, condition);
+                let then_code = self.compile_block(program, then_block, depth + 1);
+                let else_code = self.compile_block(program, else_block, depth + 1);
+
+                if else_code.is_empty() {
+                    format!("{}if ({}) {{\n{}{}}}\n", indent, condition, then_code, indent)
+                } else {
+                    format!("{}if ({}) {{\n{}{}}} else {{\n{}{}}}\n", indent, condition, then_code, indent, else_code, indent)
+                }
+            }
+            Instruction::While(condition, block, _) => {
+                let condition = self.compile_condition(program, condition);
+                let block_code = self.compile_block(program, block, depth + 1);
+
+                format!("{}while ({}) {{\n{}{}}}\n", indent, condition, block_code, indent)
+            }
+            Instruction::Push(slot, expression) => {
+                let (expression, _) = self.compile_expression(program, expression);
+                format!("{}{}.assign({});\n", indent, Self::local_name(program, *slot), expression)
+            }
+            Instruction::Pop(slot) => format!("{}{}.unAssign();\n", indent, Self::local_name(program, *slot)),
+            Instruction::Set(slot, expression) => {
+                let (expression, _) = self.compile_expression(program, expression);
+                format!("{}{}.set({});\n", indent, Self::local_name(program, *slot), expression)
+            }
+        }
+    }
+
+    fn bracket_arithmetic(code: String, expression_precedence: usize, precedence: usize) -> String {
+        if expression_precedence < precedence {
+            format!("({})", code)
+        } else {
+            code
+        }
+    }
+
+    // the code for an expression and how tightly it binds
+    fn compile_expression(&mut self, program: &Program, expression: &Expression) -> (String, usize) {
+        let precedence = arithmetic_precedence(expression);
+        let code = match expression {
+            Expression::Variable(slot, _) => format!("{}.get()", Self::local_name(program, *slot)),
+            Expression::Integer(value) => value.to_string(),
+            Expression::Float(value) => float_literal(value.into_inner()),
+            Expression::Convert(Type::Float, operand) => return self.compile_expression(program, operand),
+            Expression::Convert(Type::Integer, operand) => {
+                // a java cast truncates, saturates and turns NaN into 0, as the conversion does
+                let (code, operand_precedence) = self.compile_expression(program, operand);
+                format!("(int) {}", Self::bracket_arithmetic(code, operand_precedence, 4))
+            }
+            Expression::Negation(_, operand) => {
+                // anything that is not a plain operand is bracketed, so we never emit --x
+                let (code, operand_precedence) = self.compile_expression(program, operand);
+                format!("-{}", Self::bracket_arithmetic(code, operand_precedence, 4))
+            }
+            Expression::Operation(_, ArithmeticOperator::Exponentiation, left, right) => {
+                let (lhs, _) = self.compile_expression(program, left);
+                let (rhs, _) = self.compile_expression(program, right);
+                format!("pow({}, {})", lhs, rhs)
+            }
+            Expression::Operation(_, operator, left, right) => {
+                let (lhs, lhs_precedence) = self.compile_expression(program, left);
+                let (rhs, rhs_precedence) = self.compile_expression(program, right);
+
+                // the left operand binds as tightly as this operator, the right has to bind tighter (a - (b - c))
+                let lhs = Self::bracket_arithmetic(lhs, lhs_precedence, precedence);
+                let rhs = Self::bracket_arithmetic(rhs, rhs_precedence, precedence + 1);
+
+                let operator = match operator {
+                    ArithmeticOperator::Addition => "+",
+                    ArithmeticOperator::Subtraction => "-",
+                    ArithmeticOperator::Multiplication => "*",
+                    ArithmeticOperator::Division => "/",
+                    ArithmeticOperator::Exponentiation => unreachable!(),
+                };
+
+                format!("{} {} {}", lhs, operator, rhs)
+            }
+            _ => unreachable!("bigints are only compiled by the jvm backend"),
+        };
+        (code, precedence)
+    }
+
+    fn bracket_boolean(&mut self, program: &Program, condition: &Condition, precedence: usize) -> String {
+        let code = self.compile_condition(program, condition);
+        if boolean_precedence(condition) < precedence {
+            format!("({})", code)
+        } else {
+            code
+        }
+    }
+
+    fn compile_condition(&mut self, program: &Program, condition: &Condition) -> String {
+        match condition {
+            Condition::Not(operand) => format!("!{}", self.bracket_boolean(program, operand, 4)),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let precedence = boolean_precedence(condition);
+                let lhs = self.bracket_boolean(program, left, precedence);
+                let rhs = self.bracket_boolean(program, right, precedence + 1);
+
+                match condition {
+                    Condition::And(_, _) => format!("{} && {}", lhs, rhs),
+                    _ => format!("{} || {}", lhs, rhs),
+                }
+            }
+            Condition::Comparison(_, operator, left, right) => {
+                let (lhs, _) = self.compile_expression(program, left);
+                let (rhs, _) = self.compile_expression(program, right);
+
+                let operator = match operator {
+                    RelationOperator::Equal => "==",
+                    RelationOperator::NotEqual => "!=",
+                    RelationOperator::LessThan => "<",
+                    RelationOperator::GreaterThan => ">",
+                };
+
+                format!("{} {} {}", lhs, operator, rhs)
+            }
+        }
+    }
+}
+
+impl Backend for JavaCompiler {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
+        let mut compiler = JavaCompiler::new();
+        compiler._compile(&program, &ClassDetails::default())
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::process::Command;
+use super::code_gen::JavaCompiler;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::fixtures::{build, example, folder, installed, inverted_program, run, EXAMPLES};
+use super::super::IR::lower::lower;
+use super::super::JVM::code_gen::ClassDetails;
+use super::super::Backend;
+
+#[test]
+fn readable_source() {
+    let inverted = inverted_program(r#"int n = 2;
+int class = 0;
+while n > 0 do {
+    n = n - 1;
+    class = (class - (n - 1)) * 2;
+};"#);
+
+    let source = JavaCompiler::compile(&inverted);
+
+    assert!(source.contains("public class Main {\n"));
+    assert!(source.contains("        RIMPInt n = new RIMPInt(\"n\");\n"));
+    assert!(source.contains("        RIMPInt class_ = new RIMPInt(\"class\");\n"));
+    assert!(source.contains("        // forward program\n        n.set(2);\n"));
+    assert!(source.contains("        while (n.get() > 0) {\n            n.set(n.get() - 1);\n"));
+    assert!(source.contains("class_.assign((class_.get() - (n.get() - 1)) * 2);\n"));
+    assert!(source.contains("        // reverse point\n        n.print();\n        class_.print();\n"));
+    assert!(source.contains("        // inverted program\n"));
+    assert!(source.contains("class_.unAssign();\n            n.set(n.get() + 1);\n"));
+    assert!(!source.contains("<code>") && !source.contains("<declarations>"));
+}
+
+#[test]
+fn packaged_class() {
+    let inverted = inverted_program("float x = 1.5; int y = x * 2; x = -(-x) ^ 2; x = 0 - -2;");
+
+    let class = ClassDetails::new("Floats", Some("com.example"), "floats.rimp");
+    let source = JavaCompiler::new()._compile(&lower(&inverted).unwrap(), &class);
+
+    assert!(source.starts_with("package com.example;\n\nimport java.util.Stack;\n"));
+    assert!(source.contains("// Generated by RIMPiler from floats.rimp\npublic class Floats {\n"));
+    assert!(source.contains("x.set(1.5f);"));
+    assert!(source.contains("y.assign((int) (x.get() * 2.0f));"));
+    assert!(source.contains("x.assign(-pow(-x.get(), 2.0f));"));
+    assert!(source.contains("x.assign(0 - -2);"));
+}
+
+// the values printed at the reverse point, by variable name
+fn run_java(name: &str, source: &str) -> HashMap<String, String> {
+    let folder = folder("java", name, "Main.java", source);
+    build(&folder, "javac", &["Main.java"]);
+
+    let output = run(&folder, Command::new("java").args(["-cp", ".", "Main"]));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (variable, rest) = line.split_once(": ").unwrap();
+            let (value, _) = rest.split_once('\t').unwrap();
+            (variable.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn matches_interpreter() {
+    if !installed("javac", "-version") {
+        println!("javac not found, skipping");
+        return;
+    }
+
+    for name in EXAMPLES {
+        let inverted = inverted_program(&example(name));
+
+        let mut interpreter = InterpreterEngine::new();
+        interpreter.interpret(&inverted).unwrap();
+
+        let printed = run_java(name, &JavaCompiler::compile(&inverted));
+
+        for (variable, value) in &printed {
+            let expected = interpreter.get_result(variable).unwrap().get();
+            assert_eq!(
+                value.parse::<f32>().unwrap(),
+                expected.to_string().parse::<f32>().unwrap(),
+                "{}: {}",
+                name,
+                variable
+            );
+        }
+        assert!(!printed.is_empty(), "{}", name);
+    }
+}
@@ -0,0 +1,1154 @@
+use std::collections::{HashMap, HashSet};
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Program, RelationOperator, Slot, Type};
+use super::super::IR::Instruction as IR;
+use super::super::Backend;
+
+/*
+Compiles a RIMP program into assembly for PISA, the instruction set of the Pendulum reversible processor.
+
+Only the forward program is compiled. Pendulum can run any PISA program backwards, so running the output from
+FINISH back to START restores the initial state, which is what the inverted program does on the other targets.
+That needs every instruction to be reversible:
+
+- an assignment that is already reversible (x = x + e, x = x - e, x = -x, or the first assignment to a variable,
+  which still holds 0) updates the variable in place. Any other assignment pushes the old value onto the history
+  stack before computing the new one.
+- expressions are computed into registers holding 0 and uncomputed once they have been used, so temporaries
+  never leave anything behind. PISA has no instructions for *, / and ^, they are inlined as loops over the bits
+  of an operand, and / and ^ clean up after themselves by running their loop backwards once the result is out.
+- if and while become pairs of branches pointing at each other, and the branch taken on the way in is checked
+  on the way out. The semantic transformer makes this possible: the condition of an if doesn't change inside
+  it, and every loop has a counter that is 0 exactly when the loop is entered.
+
+Branches follow the Pendulum machine: a taken branch adds its offset to the branch register, and the program
+counter moves by the branch register while it isn't 0, and by the direction otherwise.
+
+PISA only has 32-bit integers, so floats can't be compiled. Arithmetic wraps around, and as PISA code has no
+way of stopping with an error, dividing by 0 or raising to a power below 1 give an unspecified result.
+
+Variables live in registers, the most used ones first (weighted by how deeply they are nested in loops). When
+there aren't enough registers left over from the temporaries, the rest are kept in memory and exchanged into
+scratch registers around each instruction that uses them.
+*/
+
+const REGISTERS: usize = 32;
+
+// $0 is always 0 and $1 points at the top of the history stack
+const FIRST_FREE_REGISTER: usize = 2;
+
+// an instruction names at most two variables, each one kept in memory needs an address and a value register
+const SCRATCH_REGISTERS: usize = 4;
+
+const HISTORY_STACK: &str = "history_stack";
+
+// labels are written in a column of their own
+const LABEL_WIDTH: usize = 24;
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+enum Register {
+    Zero,
+    Stack,
+    Variable(String),
+    Temporary(usize),
+}
+
+// the instructions of PISA that are used, the ones ending in X xor their result into the first register
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Instruction {
+    Label(String),
+    Add(Register, Register),
+    AddImmediate(Register, i32),
+    Sub(Register, Register),
+    Neg(Register),
+    Xor(Register, Register),
+    XorImmediate(Register, i32),
+    AndX(Register, Register, Register),
+    AndImmediateX(Register, Register, i32),
+    OrX(Register, Register, Register),
+    ShiftLeftVariableX(Register, Register, Register),
+    ShiftRightArithmeticX(Register, Register, u32),
+    ShiftRightLogicalX(Register, Register, u32),
+    ShiftRightLogicalVariableX(Register, Register, Register),
+    RotateLeft(Register, u32),
+    RotateRight(Register, u32),
+    Exchange(Register, Register),
+    BranchEqual(Register, Register, String),
+    BranchNotEqual(Register, Register, String),
+    Branch(String),
+}
+
+impl Instruction {
+    fn inverse(&self) -> Instruction {
+        match self {
+            Instruction::Add(a, b) => Instruction::Sub(a.clone(), b.clone()),
+            Instruction::Sub(a, b) => Instruction::Add(a.clone(), b.clone()),
+            Instruction::AddImmediate(a, value) => Instruction::AddImmediate(a.clone(), value.wrapping_neg()),
+            Instruction::RotateLeft(a, amount) => Instruction::RotateRight(a.clone(), *amount),
+            Instruction::RotateRight(a, amount) => Instruction::RotateLeft(a.clone(), *amount),
+            // everything else undoes itself, including branches, which point at their partner either way
+            instruction => instruction.clone(),
+        }
+    }
+
+    fn registers(&self) -> Vec<&Register> {
+        match self {
+            Instruction::Label(_) | Instruction::Branch(_) => vec![],
+            Instruction::AddImmediate(a, _)
+            | Instruction::Neg(a)
+            | Instruction::XorImmediate(a, _)
+            | Instruction::RotateLeft(a, _)
+            | Instruction::RotateRight(a, _) => vec![a],
+            Instruction::Add(a, b)
+            | Instruction::Sub(a, b)
+            | Instruction::Xor(a, b)
+            | Instruction::AndImmediateX(a, b, _)
+            | Instruction::ShiftRightArithmeticX(a, b, _)
+            | Instruction::ShiftRightLogicalX(a, b, _)
+            | Instruction::Exchange(a, b)
+            | Instruction::BranchEqual(a, b, _)
+            | Instruction::BranchNotEqual(a, b, _) => vec![a, b],
+            Instruction::AndX(a, b, c)
+            | Instruction::OrX(a, b, c)
+            | Instruction::ShiftLeftVariableX(a, b, c)
+            | Instruction::ShiftRightLogicalVariableX(a, b, c) => vec![a, b, c],
+        }
+    }
+
+    fn rename_labels(&self, labels: &HashMap<String, String>) -> Instruction {
+        let rename = |label: &String| labels.get(label).cloned().unwrap_or(label.clone());
+        match self {
+            Instruction::Label(label) => Instruction::Label(rename(label)),
+            Instruction::BranchEqual(a, b, label) => Instruction::BranchEqual(a.clone(), b.clone(), rename(label)),
+            Instruction::BranchNotEqual(a, b, label) => Instruction::BranchNotEqual(a.clone(), b.clone(), rename(label)),
+            Instruction::Branch(label) => Instruction::Branch(rename(label)),
+            instruction => instruction.clone(),
+        }
+    }
+
+    fn render(&self, name: &dyn Fn(&Register) -> String) -> String {
+        match self {
+            Instruction::Label(label) => format!("{}:", label),
+            Instruction::Add(a, b) => format!("ADD {} {}", name(a), name(b)),
+            Instruction::AddImmediate(a, value) => format!("ADDI {} {}", name(a), value),
+            Instruction::Sub(a, b) => format!("SUB {} {}", name(a), name(b)),
+            Instruction::Neg(a) => format!("NEG {}", name(a)),
+            Instruction::Xor(a, b) => format!("XOR {} {}", name(a), name(b)),
+            Instruction::XorImmediate(a, value) => format!("XORI {} {}", name(a), value),
+            Instruction::AndX(a, b, c) => format!("ANDX {} {} {}", name(a), name(b), name(c)),
+            Instruction::AndImmediateX(a, b, value) => format!("ANDIX {} {} {}", name(a), name(b), value),
+            Instruction::OrX(a, b, c) => format!("ORX {} {} {}", name(a), name(b), name(c)),
+            Instruction::ShiftLeftVariableX(a, b, c) => format!("SLLVX {} {} {}", name(a), name(b), name(c)),
+            Instruction::ShiftRightArithmeticX(a, b, amount) => format!("SRAX {} {} {}", name(a), name(b), amount),
+            Instruction::ShiftRightLogicalX(a, b, amount) => format!("SRLX {} {} {}", name(a), name(b), amount),
+            Instruction::ShiftRightLogicalVariableX(a, b, c) => format!("SRLVX {} {} {}", name(a), name(b), name(c)),
+            Instruction::RotateLeft(a, amount) => format!("RL {} {}", name(a), amount),
+            Instruction::RotateRight(a, amount) => format!("RR {} {}", name(a), amount),
+            Instruction::Exchange(a, b) => format!("EXCH {} {}", name(a), name(b)),
+            Instruction::BranchEqual(a, b, label) => format!("BEQ {} {} {}", name(a), name(b), label),
+            Instruction::BranchNotEqual(a, b, label) => format!("BNE {} {} {}", name(a), name(b), label),
+            Instruction::Branch(label) => format!("BRA {}", label),
+        }
+    }
+}
+
+// where a variable ends up after register allocation
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Location {
+    Register(usize),
+    Memory(String),
+}
+
+fn expression_uses(expression: &Expression, slot: Slot) -> bool {
+    match expression {
+        Expression::Variable(variable, _) => *variable == slot,
+        Expression::Integer(_) | Expression::Float(_) | Expression::BigInteger(_) => false,
+        Expression::Convert(_, expression) | Expression::Negation(_, expression) => expression_uses(expression, slot),
+        Expression::Operation(_, _, left, right) => expression_uses(left, slot) || expression_uses(right, slot),
+    }
+}
+
+fn assigned_in(block: &Block, assigned: &mut HashSet<Slot>) {
+    for instruction in block {
+        match &instruction.value {
+            IR::Push(slot, _) | IR::Set(slot, _) => {
+                assigned.insert(*slot);
+            }
+            IR::If(_, then_block, else_block) => {
+                assigned_in(then_block, assigned);
+                assigned_in(else_block, assigned);
+            }
+            IR::While(_, block, _) => assigned_in(block, assigned),
+            IR::Pop(_) => {}
+        }
+    }
+}
+
+fn check_expression(program: &Program, expression: &Expression) -> Result<(), String> {
+    match expression {
+        Expression::Variable(slot, Type::Float) => {
+            Err(format!("PISA has no floats, but {} is a float", program.variable(*slot).name))
+        }
+        Expression::Float(value) => Err(format!("PISA has no floats, but {} is a float", value)),
+        Expression::Variable(slot, Type::BigInteger) => {
+            Err(format!("PISA has no bigints, but {} is a bigint", program.variable(*slot).name))
+        }
+        Expression::BigInteger(value) => Err(format!("PISA has no bigints, but {} is a bigint", value)),
+        Expression::Variable(_, _) | Expression::Integer(_) => Ok(()),
+        Expression::Convert(_, expression) | Expression::Negation(_, expression) => check_expression(program, expression),
+        Expression::Operation(_, _, left, right) => check_expression(program, left).and(check_expression(program, right)),
+    }
+}
+
+fn check_condition(program: &Program, condition: &Condition) -> Result<(), String> {
+    match condition {
+        Condition::Comparison(_, _, left, right) => check_expression(program, left).and(check_expression(program, right)),
+        Condition::And(left, right) | Condition::Or(left, right) => {
+            check_condition(program, left).and(check_condition(program, right))
+        }
+        Condition::Not(condition) => check_condition(program, condition),
+    }
+}
+
+// everything that can't be compiled is found before any code is generated
+fn check_block(program: &Program, block: &Block) -> Result<(), String> {
+    for instruction in block {
+        match &instruction.value {
+            IR::Push(slot, _) | IR::Set(slot, _) if program.variable(*slot).variable_type == Type::Float => {
+                return Err(format!("PISA has no floats, but {} is a float", program.variable(*slot).name));
+            }
+            IR::Push(slot, _) | IR::Set(slot, _) if program.variable(*slot).variable_type == Type::BigInteger => {
+                return Err(format!("PISA has no bigints, but {} is a bigint", program.variable(*slot).name));
+            }
+            IR::Push(_, expression) | IR::Set(_, expression) => check_expression(program, expression)?,
+            IR::If(condition, then_block, else_block) => {
+                check_condition(program, condition)?;
+                check_block(program, then_block)?;
+                check_block(program, else_block)?;
+            }
+            IR::While(condition, block, counter) => {
+                check_condition(program, condition)?;
+                if counter.is_none() {
+                    return Err(String::from("Loops need the counter added by the semantic transformer to be run backwards"));
+                }
+                check_block(program, block)?;
+            }
+            IR::Pop(_) => {
+                return Err(String::from("Reverse assignments can't be compiled, PISA runs the forward program backwards instead"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PISACompiler {
+    // variable names by slot
+    names: Vec<String>,
+    // variable names in the order they are first assigned
+    declarations: Vec<String>,
+    // variable name -> how often it is used, weighted by loop nesting
+    uses: HashMap<String, usize>,
+    // variables that may no longer hold 0 at the current point of the program
+    assigned: HashSet<Slot>,
+    // loops around the current point of the program
+    depth: u32,
+
+    // temporaries are allocated like a stack
+    temporaries: usize,
+    peak_temporaries: usize,
+
+    // index for fresh labels
+    index: usize,
+}
+
+impl PISACompiler {
+    pub fn new() -> Self {
+        PISACompiler {
+            names: Vec::new(),
+            declarations: Vec::new(),
+            uses: HashMap::new(),
+            assigned: HashSet::new(),
+            depth: 0,
+            temporaries: 0,
+            peak_temporaries: 0,
+            index: 0,
+        }
+    }
+
+    pub fn _compile(&mut self, program: &Program) -> Result<String, String> {
+        // the backward program is the forward one run backwards, so it is left out
+        check_block(program, &program.forward)?;
+
+        self.names = program.variables.iter().map(|variable| variable.name.clone()).collect();
+        let code = self.compile_block(&program.forward);
+
+        let locations = self.allocate()?;
+        Ok(self.render(&code, &locations))
+    }
+
+    // labels are <kind><index>_<part>, e.g. while0_entry
+    fn new_label(&mut self, kind: &str) -> String {
+        let label = format!("{}{}", kind, self.index);
+        self.index += 1;
+        label
+    }
+
+    fn temporary(&mut self) -> Register {
+        let register = Register::Temporary(self.temporaries);
+        self.temporaries += 1;
+        self.peak_temporaries = self.peak_temporaries.max(self.temporaries);
+        register
+    }
+
+    // temporaries are released in the opposite order they were allocated, holding 0 again
+    fn release(&mut self, register: &Register) {
+        if let Register::Temporary(index) = register {
+            assert_eq!(*index + 1, self.temporaries, "temporaries released out of order");
+            self.temporaries -= 1;
+        }
+    }
+
+    fn variable(&mut self, slot: Slot) -> Register {
+        let name = self.names[slot].clone();
+        *self.uses.entry(name.clone()).or_insert(0) += 8usize.saturating_pow(self.depth);
+        Register::Variable(name)
+    }
+
+    fn declare(&mut self, slot: Slot) {
+        let name = &self.names[slot];
+        if !self.declarations.contains(name) {
+            self.declarations.push(name.clone());
+        }
+        *self.uses.entry(name.clone()).or_insert(0) += 8usize.saturating_pow(self.depth);
+    }
+
+    // a copy of some code with fresh labels, so it can be placed more than once
+    fn relabel(&mut self, code: &[Instruction]) -> Vec<Instruction> {
+        let mut labels = HashMap::new();
+        let mut bases = HashMap::new();
+        for instruction in code {
+            if let Instruction::Label(label) = instruction {
+                let (base, part) = label.split_once('_').unwrap();
+                if !bases.contains_key(base) {
+                    let kind = base.trim_end_matches(|c: char| c.is_ascii_digit()).to_string();
+                    bases.insert(base.to_string(), self.new_label(&kind));
+                }
+                labels.insert(label.clone(), format!("{}_{}", bases.get(base).unwrap(), part));
+            }
+        }
+        code.iter().map(|instruction| instruction.rename_labels(&labels)).collect()
+    }
+
+    // code that undoes the given code, the reverse of each instruction in reverse order
+    fn invert(&mut self, code: &[Instruction]) -> Vec<Instruction> {
+        let mut inverted = vec![];
+        // a label stays in front of the instruction it names
+        for instruction in code.iter().rev() {
+            match instruction {
+                Instruction::Label(_) => {
+                    let named = inverted.pop().unwrap();
+                    inverted.push(instruction.clone());
+                    inverted.push(named);
+                }
+                instruction => inverted.push(instruction.inverse()),
+            }
+        }
+        self.relabel(&inverted)
+    }
+
+    fn compile_block(&mut self, block: &Block) -> Vec<Instruction> {
+        block.iter().flat_map(|instruction| self.compile_instruction(&instruction.value)).collect()
+    }
+
+    fn compile_instruction(&mut self, instruction: &IR) -> Vec<Instruction> {
+        match instruction {
+            // whether an assignment can be undone without the history is worked out again for PISA
+            IR::Push(slot, expression) | IR::Set(slot, expression) => self.compile_assignment(*slot, expression),
+            IR::If(condition, then_block, else_block) => {
+                let flag = self.temporary();
+                let entry = self.compute_condition(condition, &flag);
+                // the condition can't change inside the if, so the same test tells which branch was taken
+                let exit = self.relabel(&entry);
+
+                // a variable might have been assigned on either path
+                let before = self.assigned.clone();
+                let then_code = self.compile_block(then_block);
+                let after_then = std::mem::replace(&mut self.assigned, before);
+                let else_code = self.compile_block(else_block);
+                self.assigned.extend(after_then);
+
+                let code = self.conditional("if", &flag, entry, exit, then_code, else_code);
+                self.release(&flag);
+                code
+            }
+            IR::While(condition, block, counter) => {
+                let counter = counter.unwrap();
+
+                // the variables assigned in the loop aren't 0 anymore on the next iteration
+                assigned_in(block, &mut self.assigned);
+
+                self.depth += 1;
+                let flag = self.temporary();
+
+                // the loop is entered when the counter is 0, and left when the condition is false
+                let first_iteration = Condition::Comparison(
+                    Type::Integer,
+                    RelationOperator::Equal,
+                    Expression::Variable(counter, Type::Integer),
+                    Expression::Integer(0),
+                );
+                let entry = self.compute_condition(&first_iteration, &flag);
+                let mut exit = self.compute_condition(condition, &flag);
+                exit.push(Instruction::XorImmediate(flag.clone(), 1));
+
+                let body = self.compile_block(block);
+                let code = self.reversible_loop("while", &flag, entry, exit, body);
+
+                self.release(&flag);
+                self.depth -= 1;
+                code
+            }
+            IR::Pop(_) => unreachable!("only the forward program is compiled"),
+        }
+    }
+
+    fn compile_assignment(&mut self, slot: Slot, expression: &Expression) -> Vec<Instruction> {
+        let self_referential = expression_uses(expression, slot);
+
+        let code = if !self.assigned.contains(&slot) && !self_referential {
+            // the variable still holds 0, so the value is simply added to it
+            self.declare(slot);
+            self.add_expression(expression, &Register::Variable(self.names[slot].clone()))
+        } else if let Some(code) = self.reversible_update(slot, expression) {
+            code
+        } else if !self_referential {
+            self.declare(slot);
+            let variable = Register::Variable(self.names[slot].clone());
+            let mut code = self.push(&variable);
+            code.extend(self.add_expression(expression, &variable));
+            code
+        } else {
+            // the new value needs the old one, so it is computed on the side and swapped in
+            let value = self.temporary();
+            let mut code = self.add_expression(expression, &value);
+            let variable = self.variable(slot);
+            code.extend(Self::swap(&variable, &value));
+            code.extend(self.push(&value));
+            self.release(&value);
+            code
+        };
+
+        self.assigned.insert(slot);
+        code
+    }
+
+    // assignments that can be undone without remembering the old value
+    fn reversible_update(&mut self, slot: Slot, expression: &Expression) -> Option<Vec<Instruction>> {
+        let is_variable = |expression: &Expression| matches!(expression, Expression::Variable(variable, _) if *variable == slot);
+
+        match expression {
+            Expression::Variable(_, _) if is_variable(expression) => {
+                self.variable(slot);
+                Some(vec![])
+            }
+            Expression::Negation(_, operand) if is_variable(operand) => {
+                Some(vec![Instruction::Neg(self.variable(slot))])
+            }
+            Expression::Operation(_, ArithmeticOperator::Addition, left, right) => {
+                let operand = if is_variable(left) && !expression_uses(right, slot) {
+                    right
+                } else if is_variable(right) && !expression_uses(left, slot) {
+                    left
+                } else {
+                    return None;
+                };
+                let variable = self.variable(slot);
+                Some(self.add_expression(operand, &variable))
+            }
+            Expression::Operation(_, ArithmeticOperator::Subtraction, left, right)
+                if is_variable(left) && !expression_uses(right, slot) =>
+            {
+                let variable = self.variable(slot);
+                Some(self.subtract_expression(right, &variable))
+            }
+            _ => None,
+        }
+    }
+
+    // moves a register onto the history stack, leaving it 0
+    fn push(&mut self, register: &Register) -> Vec<Instruction> {
+        vec![
+            Instruction::Exchange(register.clone(), Register::Stack),
+            Instruction::AddImmediate(Register::Stack, 1),
+        ]
+    }
+
+    fn swap(a: &Register, b: &Register) -> Vec<Instruction> {
+        vec![
+            Instruction::Xor(a.clone(), b.clone()),
+            Instruction::Xor(b.clone(), a.clone()),
+            Instruction::Xor(a.clone(), b.clone()),
+        ]
+    }
+
+    /*
+    The paired branches of an if, where flag ^= the condition in entry and exit, and is 0 outside:
+
+          entry
+    test: BEQ flag $0 else     ; taken when the condition is false
+          XORI flag 1
+          then
+          XORI flag 1
+    done: BRA join
+    else: BRA test
+          else
+    join: BNE flag $0 done     ; taken when the then branch ran
+          exit
+    */
+    fn conditional(
+        &mut self,
+        kind: &str,
+        flag: &Register,
+        entry: Vec<Instruction>,
+        exit: Vec<Instruction>,
+        then_code: Vec<Instruction>,
+        else_code: Vec<Instruction>,
+    ) -> Vec<Instruction> {
+        let label = self.new_label(kind);
+        let [test, done, other, join] = ["test", "done", "else", "join"].map(|part| format!("{}_{}", label, part));
+
+        let mut code = entry;
+        code.push(Instruction::Label(test.clone()));
+        code.push(Instruction::BranchEqual(flag.clone(), Register::Zero, other.clone()));
+        code.push(Instruction::XorImmediate(flag.clone(), 1));
+        code.extend(then_code);
+        code.push(Instruction::XorImmediate(flag.clone(), 1));
+        code.push(Instruction::Label(done.clone()));
+        code.push(Instruction::Branch(join.clone()));
+        code.push(Instruction::Label(other));
+        code.push(Instruction::Branch(test));
+        code.extend(else_code);
+        code.push(Instruction::Label(join));
+        code.push(Instruction::BranchNotEqual(flag.clone(), Register::Zero, done));
+        code.extend(exit);
+        code
+    }
+
+    /*
+    A loop that is entered when the condition computed by entry is true, and left when the one computed by exit
+    is, which is what makes it possible to run backwards:
+
+            entry
+    entry:  BEQ flag $0 assert  ; coming back round from assert
+            entry
+            exit
+    test:   BNE flag $0 exit
+            body
+            entry               ; has to be false after the first iteration
+    assert: BEQ flag $0 entry
+    exit:   BNE flag $0 test
+            exit
+    */
+    fn reversible_loop(
+        &mut self,
+        kind: &str,
+        flag: &Register,
+        entry: Vec<Instruction>,
+        exit: Vec<Instruction>,
+        body: Vec<Instruction>,
+    ) -> Vec<Instruction> {
+        let label = self.new_label(kind);
+        let [entry_label, test, assert, exit_label] = ["entry", "test", "assert", "exit"].map(|part| format!("{}_{}", label, part));
+
+        let mut code = self.relabel(&entry);
+        code.push(Instruction::Label(entry_label.clone()));
+        code.push(Instruction::BranchEqual(flag.clone(), Register::Zero, assert.clone()));
+        code.extend(self.relabel(&entry));
+        code.extend(self.relabel(&exit));
+        code.push(Instruction::Label(test.clone()));
+        code.push(Instruction::BranchNotEqual(flag.clone(), Register::Zero, exit_label.clone()));
+        code.extend(body);
+        code.extend(entry);
+        code.push(Instruction::Label(assert));
+        code.push(Instruction::BranchEqual(flag.clone(), Register::Zero, entry_label));
+        code.push(Instruction::Label(exit_label));
+        code.push(Instruction::BranchNotEqual(flag.clone(), Register::Zero, test));
+        code.extend(exit);
+        code
+    }
+
+    // runs body 32 times, with counter going from 0 to 32, body has to add 1 to the counter
+    fn counting_loop(&mut self, kind: &str, flag: &Register, counter: &Register, body: Vec<Instruction>) -> Vec<Instruction> {
+        // counter - 1 is only negative when the counter is 0
+        let entry = vec![
+            Instruction::AddImmediate(counter.clone(), -1),
+            Instruction::ShiftRightLogicalX(flag.clone(), counter.clone(), 31),
+            Instruction::AddImmediate(counter.clone(), 1),
+        ];
+        // 32 is the only count up to 32 with bit 5 set
+        let exit = vec![Instruction::ShiftRightLogicalX(flag.clone(), counter.clone(), 5)];
+
+        self.reversible_loop(kind, flag, entry, exit, body)
+    }
+
+    // a register holding the value of the expression, and the code that computes it
+    fn operand(&mut self, expression: &Expression) -> (Register, Vec<Instruction>) {
+        match expression {
+            Expression::Variable(slot, _) => (self.variable(*slot), vec![]),
+            _ => {
+                let register = self.temporary();
+                let code = self.add_expression(expression, &register);
+                (register, code)
+            }
+        }
+    }
+
+    // register += expression, the register can't appear in the expression
+    fn add_expression(&mut self, expression: &Expression, register: &Register) -> Vec<Instruction> {
+        match expression {
+            Expression::Variable(slot, _) => {
+                vec![Instruction::Add(register.clone(), self.variable(*slot))]
+            }
+            Expression::Integer(0) => vec![],
+            Expression::Integer(value) => vec![Instruction::AddImmediate(register.clone(), *value)],
+            Expression::Float(_) | Expression::BigInteger(_) | Expression::Convert(_, _) => {
+                unreachable!("floats and bigints are rejected before compiling")
+            }
+            Expression::Negation(_, operand) => {
+                self.subtract_expression(operand, register)
+            }
+            Expression::Operation(_, operator, left, right) => match operator {
+                ArithmeticOperator::Addition => {
+                    let mut code = self.add_expression(left, register);
+                    code.extend(self.add_expression(right, register));
+                    code
+                }
+                ArithmeticOperator::Subtraction => {
+                    let mut code = self.add_expression(left, register);
+                    code.extend(self.subtract_expression(right, register));
+                    code
+                }
+                _ => self.operation(operator, left, right, register),
+            },
+        }
+    }
+
+    // register -= expression
+    fn subtract_expression(&mut self, expression: &Expression, register: &Register) -> Vec<Instruction> {
+        match expression {
+            Expression::Variable(slot, _) => {
+                vec![Instruction::Sub(register.clone(), self.variable(*slot))]
+            }
+            Expression::Integer(0) => vec![],
+            Expression::Integer(value) => vec![Instruction::AddImmediate(register.clone(), value.wrapping_neg())],
+            Expression::Float(_) | Expression::BigInteger(_) | Expression::Convert(_, _) => {
+                unreachable!("floats and bigints are rejected before compiling")
+            }
+            Expression::Negation(_, operand) => {
+                self.add_expression(operand, register)
+            }
+            Expression::Operation(_, operator, left, right) => match operator {
+                ArithmeticOperator::Addition => {
+                    let mut code = self.subtract_expression(left, register);
+                    code.extend(self.subtract_expression(right, register));
+                    code
+                }
+                ArithmeticOperator::Subtraction => {
+                    let mut code = self.subtract_expression(left, register);
+                    code.extend(self.add_expression(right, register));
+                    code
+                }
+                _ => {
+                    // r - x = -(-r + x)
+                    let mut code = vec![Instruction::Neg(register.clone())];
+                    code.extend(self.operation(operator, left, right, register));
+                    code.push(Instruction::Neg(register.clone()));
+                    code
+                }
+            },
+        }
+    }
+
+    // register += left operator right, for the operators PISA has no instruction for
+    fn operation(
+        &mut self,
+        operator: &ArithmeticOperator,
+        left: &Expression,
+        right: &Expression,
+        register: &Register,
+    ) -> Vec<Instruction> {
+        let (x, x_code) = self.operand(left);
+        let (y, y_code) = self.operand(right);
+
+        let mut code = x_code.clone();
+        code.extend(y_code.clone());
+        code.extend(match operator {
+            ArithmeticOperator::Multiplication => self.multiply(register, &x, &y),
+            ArithmeticOperator::Division => self.divide(register, &x, &y),
+            ArithmeticOperator::Exponentiation => self.power(register, &x, &y),
+            ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => unreachable!(),
+        });
+        code.extend(self.invert(&y_code));
+        code.extend(self.invert(&x_code));
+
+        self.release(&y);
+        self.release(&x);
+        code
+    }
+
+    // register += x * y, adding x << i for every bit i set in y
+    fn multiply(&mut self, register: &Register, x: &Register, y: &Register) -> Vec<Instruction> {
+        let flag = self.temporary();
+        let counter = self.temporary();
+        let shifted = self.temporary();
+        let mask = self.temporary();
+        let term = self.temporary();
+
+        // mask = -(bit i of y), so either 0 or all ones
+        let bit = vec![
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), y.clone(), counter.clone()),
+            Instruction::AndImmediateX(mask.clone(), shifted.clone(), 1),
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), y.clone(), counter.clone()),
+            Instruction::Neg(mask.clone()),
+        ];
+
+        let mut body = bit.clone();
+        body.extend([
+            Instruction::ShiftLeftVariableX(shifted.clone(), x.clone(), counter.clone()),
+            Instruction::AndX(term.clone(), shifted.clone(), mask.clone()),
+            Instruction::Add(register.clone(), term.clone()),
+            Instruction::AndX(term.clone(), shifted.clone(), mask.clone()),
+            Instruction::ShiftLeftVariableX(shifted.clone(), x.clone(), counter.clone()),
+        ]);
+        body.extend(self.invert(&bit));
+        body.push(Instruction::AddImmediate(counter.clone(), 1));
+
+        let mut code = self.counting_loop("mul", &flag, &counter, body);
+        code.push(Instruction::XorImmediate(counter.clone(), 32));
+
+        self.release(&term);
+        self.release(&mask);
+        self.release(&shifted);
+        self.release(&counter);
+        self.release(&flag);
+        code
+    }
+
+    // register += x / y, rounding towards 0, by long division of the magnitudes
+    fn divide(&mut self, register: &Register, x: &Register, y: &Register) -> Vec<Instruction> {
+        let magnitude_x = self.temporary();
+        let magnitude_y = self.temporary();
+        let quotient = self.temporary();
+        let remainder = self.temporary();
+        let flag = self.temporary();
+        let counter = self.temporary();
+        let position = self.temporary();
+        let shifted = self.temporary();
+        let bit = self.temporary();
+        let step = self.temporary();
+
+        // |v| = (v ^ sign) - sign, where sign is all ones for negative numbers, and |MIN| is 2^31 unsigned
+        let mut setup = vec![];
+        for (value, magnitude) in [(x, &magnitude_x), (y, &magnitude_y)] {
+            setup.extend([
+                Instruction::ShiftRightArithmeticX(shifted.clone(), value.clone(), 31),
+                Instruction::Xor(magnitude.clone(), value.clone()),
+                Instruction::Xor(magnitude.clone(), shifted.clone()),
+                Instruction::Sub(magnitude.clone(), shifted.clone()),
+                Instruction::ShiftRightArithmeticX(shifted.clone(), value.clone(), 31),
+            ]);
+        }
+
+        // the bits of |x| are brought down from the top, position = 31 - counter
+        let mut body = vec![
+            Instruction::XorImmediate(position.clone(), 31),
+            Instruction::Sub(position.clone(), counter.clone()),
+        ];
+        let next_bit = vec![
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), magnitude_x.clone(), position.clone()),
+            Instruction::AndImmediateX(bit.clone(), shifted.clone(), 1),
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), magnitude_x.clone(), position.clone()),
+        ];
+        // the remainder is below |y| <= 2^31, so its top bit is free
+        body.push(Instruction::RotateLeft(remainder.clone(), 1));
+        body.extend(next_bit.clone());
+        body.push(Instruction::Xor(remainder.clone(), bit.clone()));
+        body.extend(self.invert(&next_bit));
+
+        // when the remainder is at least |y| it is subtracted, and that is recorded in the quotient
+        let entry = self.unsigned_at_least(&remainder, &magnitude_y, &step);
+        let exit = vec![
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), quotient.clone(), position.clone()),
+            Instruction::AndImmediateX(step.clone(), shifted.clone(), 1),
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), quotient.clone(), position.clone()),
+        ];
+        let subtract = vec![
+            Instruction::Sub(remainder.clone(), magnitude_y.clone()),
+            Instruction::XorImmediate(shifted.clone(), 1),
+            Instruction::ShiftLeftVariableX(quotient.clone(), shifted.clone(), position.clone()),
+            Instruction::XorImmediate(shifted.clone(), 1),
+        ];
+        body.extend(self.conditional("div", &step, entry, exit, subtract, vec![]));
+
+        body.extend([
+            Instruction::Add(position.clone(), counter.clone()),
+            Instruction::XorImmediate(position.clone(), 31),
+            Instruction::AddImmediate(counter.clone(), 1),
+        ]);
+
+        let mut forward = setup;
+        forward.extend(self.counting_loop("div", &flag, &counter, body));
+
+        // the quotient is negated when exactly one of x and y is negative
+        let sign = vec![
+            Instruction::Xor(bit.clone(), x.clone()),
+            Instruction::Xor(bit.clone(), y.clone()),
+            Instruction::ShiftRightArithmeticX(shifted.clone(), bit.clone(), 31),
+            Instruction::Xor(bit.clone(), y.clone()),
+            Instruction::Xor(bit.clone(), x.clone()),
+        ];
+
+        let mut code = forward.clone();
+        code.extend(sign.clone());
+        code.extend([
+            Instruction::Xor(quotient.clone(), shifted.clone()),
+            Instruction::Add(register.clone(), quotient.clone()),
+            Instruction::Sub(register.clone(), shifted.clone()),
+            Instruction::Xor(quotient.clone(), shifted.clone()),
+        ]);
+        code.extend(self.invert(&sign));
+        code.extend(self.invert(&forward));
+
+        for temporary in [step, bit, shifted, position, counter, flag, remainder, quotient, magnitude_y, magnitude_x] {
+            self.release(&temporary);
+        }
+        code
+    }
+
+    // flag ^= x >= y, comparing them as unsigned numbers
+    fn unsigned_at_least(&mut self, x: &Register, y: &Register, flag: &Register) -> Vec<Instruction> {
+        let not_x = self.temporary();
+        let carry = self.temporary();
+        let either = self.temporary();
+        let difference = self.temporary();
+        let borrow = self.temporary();
+        let less = self.temporary();
+
+        // x < y = ((~x & y) | ((~x | y) & (x - y))) >>> 31, from Hacker's Delight
+        let compute = vec![
+            Instruction::Xor(not_x.clone(), x.clone()),
+            Instruction::XorImmediate(not_x.clone(), -1),
+            Instruction::AndX(carry.clone(), not_x.clone(), y.clone()),
+            Instruction::OrX(either.clone(), not_x.clone(), y.clone()),
+            Instruction::Add(difference.clone(), x.clone()),
+            Instruction::Sub(difference.clone(), y.clone()),
+            Instruction::AndX(borrow.clone(), either.clone(), difference.clone()),
+            Instruction::OrX(less.clone(), carry.clone(), borrow.clone()),
+        ];
+
+        let mut code = compute.clone();
+        code.push(Instruction::ShiftRightLogicalX(flag.clone(), less.clone(), 31));
+        code.push(Instruction::XorImmediate(flag.clone(), 1));
+        code.extend(self.invert(&compute));
+
+        for temporary in [less, borrow, difference, either, carry, not_x] {
+            self.release(&temporary);
+        }
+        code
+    }
+
+    // register += x ^ y, by square and multiply over the bits of y from the top
+    fn power(&mut self, register: &Register, x: &Register, y: &Register) -> Vec<Instruction> {
+        let result = self.temporary();
+        let next = self.temporary();
+        let factor = self.temporary();
+        let flag = self.temporary();
+        let counter = self.temporary();
+        let position = self.temporary();
+        let shifted = self.temporary();
+        let mask = self.temporary();
+        let other = self.temporary();
+
+        let mut body = vec![
+            Instruction::XorImmediate(position.clone(), 31),
+            Instruction::Sub(position.clone(), counter.clone()),
+        ];
+
+        // the result can't be updated in place, so each old value goes on the history stack
+        body.extend(self.multiply(&next, &result, &result));
+        body.extend(Self::swap(&result, &next));
+        body.extend(self.push(&next));
+
+        // factor = x when bit position of y is set, 1 otherwise, as 1 ^ ((x ^ 1) & mask)
+        let choose = vec![
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), y.clone(), position.clone()),
+            Instruction::AndImmediateX(mask.clone(), shifted.clone(), 1),
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), y.clone(), position.clone()),
+            Instruction::Neg(mask.clone()),
+            Instruction::Xor(other.clone(), x.clone()),
+            Instruction::XorImmediate(other.clone(), 1),
+            Instruction::XorImmediate(factor.clone(), 1),
+            Instruction::AndX(factor.clone(), other.clone(), mask.clone()),
+            Instruction::XorImmediate(other.clone(), 1),
+            Instruction::Xor(other.clone(), x.clone()),
+            Instruction::Neg(mask.clone()),
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), y.clone(), position.clone()),
+            Instruction::AndImmediateX(mask.clone(), shifted.clone(), 1),
+            Instruction::ShiftRightLogicalVariableX(shifted.clone(), y.clone(), position.clone()),
+        ];
+        body.extend(choose.clone());
+        body.extend(self.multiply(&next, &result, &factor));
+        body.extend(Self::swap(&result, &next));
+        body.extend(self.push(&next));
+        body.extend(self.invert(&choose));
+
+        body.extend([
+            Instruction::Add(position.clone(), counter.clone()),
+            Instruction::XorImmediate(position.clone(), 31),
+            Instruction::AddImmediate(counter.clone(), 1),
+        ]);
+
+        let mut forward = vec![Instruction::XorImmediate(result.clone(), 1)];
+        forward.extend(self.counting_loop("pow", &flag, &counter, body));
+
+        let mut code = forward.clone();
+        code.push(Instruction::Add(register.clone(), result.clone()));
+        code.extend(self.invert(&forward));
+
+        for temporary in [other, mask, shifted, position, counter, flag, factor, next, result] {
+            self.release(&temporary);
+        }
+        code
+    }
+
+    // flag ^= the condition, as 0 or 1
+    fn compute_condition(&mut self, condition: &Condition, flag: &Register) -> Vec<Instruction> {
+        match condition {
+            Condition::Not(operand) => {
+                let mut code = self.compute_condition(operand, flag);
+                code.push(Instruction::XorImmediate(flag.clone(), 1));
+                code
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let left_flag = self.temporary();
+                let left_code = self.compute_condition(left, &left_flag);
+                let right_flag = self.temporary();
+                let right_code = self.compute_condition(right, &right_flag);
+
+                let mut code = left_code.clone();
+                code.extend(right_code.clone());
+                code.push(match condition {
+                    Condition::And(_, _) => Instruction::AndX(flag.clone(), left_flag.clone(), right_flag.clone()),
+                    _ => Instruction::OrX(flag.clone(), left_flag.clone(), right_flag.clone()),
+                });
+                code.extend(self.invert(&right_code));
+                code.extend(self.invert(&left_code));
+
+                self.release(&right_flag);
+                self.release(&left_flag);
+                code
+            }
+            Condition::Comparison(_, operator, left, right) => {
+                let (x, x_code) = self.operand(left);
+                let (y, y_code) = self.operand(right);
+
+                let mut code = x_code.clone();
+                code.extend(y_code.clone());
+                code.extend(match operator {
+                    RelationOperator::Equal => self.equal(&x, &y, flag, true),
+                    RelationOperator::NotEqual => self.equal(&x, &y, flag, false),
+                    RelationOperator::LessThan => self.less_than(&x, &y, flag),
+                    RelationOperator::GreaterThan => self.less_than(&y, &x, flag),
+                });
+                code.extend(self.invert(&y_code));
+                code.extend(self.invert(&x_code));
+
+                self.release(&y);
+                self.release(&x);
+                code
+            }
+        }
+    }
+
+    // flag ^= x == y (or x != y), the top bit of d | -d is set when d = x ^ y isn't 0
+    fn equal(&mut self, x: &Register, y: &Register, flag: &Register, equal: bool) -> Vec<Instruction> {
+        let difference = self.temporary();
+        let negated = self.temporary();
+        let either = self.temporary();
+
+        let compute = vec![
+            Instruction::Xor(difference.clone(), x.clone()),
+            Instruction::Xor(difference.clone(), y.clone()),
+            Instruction::Xor(negated.clone(), difference.clone()),
+            Instruction::Neg(negated.clone()),
+            Instruction::OrX(either.clone(), difference.clone(), negated.clone()),
+        ];
+
+        let mut code = compute.clone();
+        code.push(Instruction::ShiftRightLogicalX(flag.clone(), either.clone(), 31));
+        if equal {
+            code.push(Instruction::XorImmediate(flag.clone(), 1));
+        }
+        code.extend(self.invert(&compute));
+
+        self.release(&either);
+        self.release(&negated);
+        self.release(&difference);
+        code
+    }
+
+    // flag ^= x < y = ((x - y) ^ ((x ^ y) & ((x - y) ^ x))) >>> 31, which is right even when x - y overflows
+    fn less_than(&mut self, x: &Register, y: &Register, flag: &Register) -> Vec<Instruction> {
+        let difference = self.temporary();
+        let signs = self.temporary();
+        let changed = self.temporary();
+        let less = self.temporary();
+
+        let compute = vec![
+            Instruction::Add(difference.clone(), x.clone()),
+            Instruction::Sub(difference.clone(), y.clone()),
+            Instruction::Xor(signs.clone(), x.clone()),
+            Instruction::Xor(signs.clone(), y.clone()),
+            Instruction::Xor(changed.clone(), difference.clone()),
+            Instruction::Xor(changed.clone(), x.clone()),
+            Instruction::AndX(less.clone(), signs.clone(), changed.clone()),
+            Instruction::Xor(less.clone(), difference.clone()),
+        ];
+
+        let mut code = compute.clone();
+        code.push(Instruction::ShiftRightLogicalX(flag.clone(), less.clone(), 31));
+        code.extend(self.invert(&compute));
+
+        self.release(&less);
+        self.release(&changed);
+        self.release(&signs);
+        self.release(&difference);
+        code
+    }
+
+    // gives the most used variables the registers the temporaries don't need, the rest go in memory
+    fn allocate(&self) -> Result<HashMap<String, Location>, String> {
+        let mut ranked = self.declarations.clone();
+        ranked.sort_by_key(|name| std::cmp::Reverse(self.uses.get(name).copied().unwrap_or(0)));
+
+        let free = REGISTERS - FIRST_FREE_REGISTER;
+        let scratch = if ranked.len() + self.peak_temporaries <= free { 0 } else { SCRATCH_REGISTERS };
+
+        if self.peak_temporaries + scratch > free {
+            return Err(format!(
+                "The expressions need {} registers at once, but PISA only has {}",
+                self.peak_temporaries,
+                free - scratch
+            ));
+        }
+
+        let first_variable = FIRST_FREE_REGISTER + scratch + self.peak_temporaries;
+
+        let mut locations = HashMap::new();
+        for (index, name) in ranked.iter().enumerate() {
+            if first_variable + index < REGISTERS {
+                locations.insert(name.clone(), Location::Register(first_variable + index));
+            } else {
+                locations.insert(name.clone(), Location::Memory(format!("data_{}", name)));
+            }
+        }
+        Ok(locations)
+    }
+
+    fn render(&self, code: &[Instruction], locations: &HashMap<String, Location>) -> String {
+        let spilled = locations.values().any(|location| matches!(location, Location::Memory(_)));
+        let scratch = if spilled { SCRATCH_REGISTERS } else { 0 };
+        let first_temporary = FIRST_FREE_REGISTER + scratch;
+
+        let mut output = String::from(";; Generated by RIMPiler\n");
+        output.push_str(";; Run forwards from START to FINISH, and backwards from FINISH to START to restore the initial state.\n");
+        output.push_str(";;\n;; variables:\n");
+        for name in &self.declarations {
+            let location = match locations.get(name).unwrap() {
+                Location::Register(register) => format!("${}", register),
+                Location::Memory(label) => label.clone(),
+            };
+            output.push_str(&format!(";;   {} {}\n", name, location));
+        }
+        output.push('\n');
+
+        let mut lines = vec![(String::new(), String::from("START")), (String::new(), format!("XORI $1 {}", HISTORY_STACK))];
+
+        let mut label = String::new();
+        for instruction in code {
+            if let Instruction::Label(name) = instruction {
+                assert!(label.is_empty(), "two labels on one instruction");
+                label = format!("{}:", name);
+                continue;
+            }
+
+            // variables in memory are exchanged into a scratch register for the instruction
+            let mut in_memory = vec![];
+            for register in instruction.registers() {
+                if let Register::Variable(name) = register {
+                    if let Location::Memory(address) = locations.get(name).unwrap() {
+                        if !in_memory.iter().any(|(spilled, _)| spilled == name) {
+                            in_memory.push((name.clone(), address.clone()));
+                        }
+                    }
+                }
+            }
+            assert!(in_memory.is_empty() || label.is_empty(), "branches never use variables");
+
+            let scratch_registers = |index: usize| (FIRST_FREE_REGISTER + 2 * index, FIRST_FREE_REGISTER + 2 * index + 1);
+
+            for (index, (_, address)) in in_memory.iter().enumerate() {
+                let (pointer, value) = scratch_registers(index);
+                lines.push((String::new(), format!("XORI ${} {}", pointer, address)));
+                lines.push((String::new(), format!("EXCH ${} ${}", value, pointer)));
+            }
+
+            let name = |register: &Register| match register {
+                Register::Zero => String::from("$0"),
+                Register::Stack => String::from("$1"),
+                Register::Temporary(index) => format!("${}", first_temporary + index),
+                Register::Variable(name) => match locations.get(name).unwrap() {
+                    Location::Register(register) => format!("${}", register),
+                    Location::Memory(_) => {
+                        let index = in_memory.iter().position(|(spilled, _)| spilled == name).unwrap();
+                        format!("${}", scratch_registers(index).1)
+                    }
+                },
+            };
+            lines.push((std::mem::take(&mut label), instruction.render(&name)));
+
+            for (index, (_, address)) in in_memory.iter().enumerate().rev() {
+                let (pointer, value) = scratch_registers(index);
+                lines.push((String::new(), format!("EXCH ${} ${}", value, pointer)));
+                lines.push((String::new(), format!("XORI ${} {}", pointer, address)));
+            }
+        }
+
+        lines.push((String::new(), String::from("FINISH")));
+
+        for name in &self.declarations {
+            if let Location::Memory(address) = locations.get(name).unwrap() {
+                lines.push((format!("{}:", address), String::from("DATA 0")));
+            }
+        }
+        // the stack grows upwards from here, past the end of the program
+        lines.push((format!("{}:", HISTORY_STACK), String::from("DATA 0")));
+
+        for (label, instruction) in lines {
+            let line = format!("{:<width$} {}", label, instruction, width = LABEL_WIDTH);
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl Backend for PISACompiler {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
+        let mut compiler = PISACompiler::new();
+        compiler._compile(&program).unwrap()
+    }
+}
@@ -0,0 +1,244 @@
+/*
+A small simulator for the PISA the PISA backend emits, so the tests don't need a Pendulum or PendVM.
+
+Machine::new assembles the program, resolving labels to addresses (instructions and data share one address
+space, one word each). run executes it forwards from START to FINISH, or backwards from FINISH to START, where
+every instruction is replaced by its inverse. Branches add the distance to their target to the branch register,
+and the program counter moves by the branch register while it isn't 0, and by the direction otherwise.
+
+Anything irreversible (writing to $0, ADD $r $r, XOR $r $r, ANDX $r $r ...) is an error rather than being
+executed, as is exchanging with an address that isn't data.
+*/
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Register(usize),
+    Immediate(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Word {
+    Instruction(String, Vec<Operand>),
+    Data,
+}
+
+pub struct Machine {
+    program: Vec<Word>,
+    labels: HashMap<String, i32>,
+    start: i32,
+    finish: i32,
+
+    pub registers: [i32; 32],
+    // the data words, and anything written past the end of the program
+    pub memory: HashMap<i32, i32>,
+    pub branch: i32,
+}
+
+impl Machine {
+    pub fn new(source: &str) -> Result<Machine, String> {
+        let mut lines = vec![];
+        let mut labels = HashMap::new();
+
+        for line in source.lines() {
+            let line = line.split(";;").next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace().collect::<Vec<&str>>();
+            if let Some(label) = tokens[0].strip_suffix(':') {
+                if labels.insert(label.to_string(), lines.len() as i32).is_some() {
+                    return Err(format!("Label {} is defined twice", label));
+                }
+                tokens.remove(0);
+            }
+            if tokens.is_empty() {
+                return Err(format!("Label without an instruction: {}", line));
+            }
+            lines.push(tokens.iter().map(|token| token.to_string()).collect::<Vec<String>>());
+        }
+
+        let mut program = vec![];
+        let mut memory = HashMap::new();
+        for (address, tokens) in lines.iter().enumerate() {
+            let operands = tokens[1..]
+                .iter()
+                .map(|operand| {
+                    if let Some(register) = operand.strip_prefix('$') {
+                        match register.parse::<usize>() {
+                            Ok(register) if register < 32 => Ok(Operand::Register(register)),
+                            _ => Err(format!("No register {}", operand)),
+                        }
+                    } else if let Ok(value) = operand.parse::<i32>() {
+                        Ok(Operand::Immediate(value))
+                    } else {
+                        match labels.get(operand) {
+                            Some(address) => Ok(Operand::Immediate(*address)),
+                            None => Err(format!("Undefined label {}", operand)),
+                        }
+                    }
+                })
+                .collect::<Result<Vec<Operand>, String>>()?;
+
+            if tokens[0] == "DATA" {
+                match operands.as_slice() {
+                    [Operand::Immediate(value)] => memory.insert(address as i32, *value),
+                    _ => return Err(format!("Bad data word at {}", address)),
+                };
+                program.push(Word::Data);
+            } else {
+                program.push(Word::Instruction(tokens[0].clone(), operands));
+            }
+        }
+
+        let find = |opcode: &str| {
+            let mut addresses = program
+                .iter()
+                .enumerate()
+                .filter(|(_, word)| matches!(word, Word::Instruction(name, _) if name == opcode));
+            match (addresses.next(), addresses.next()) {
+                (Some((address, _)), None) => Ok(address as i32),
+                _ => Err(format!("Expected exactly one {}", opcode)),
+            }
+        };
+        let start = find("START")?;
+        let finish = find("FINISH")?;
+
+        Ok(Machine {
+            program,
+            labels,
+            start,
+            finish,
+            registers: [0; 32],
+            memory,
+            branch: 0,
+        })
+    }
+
+    pub fn load(&self, label: &str) -> i32 {
+        let address = self.labels.get(label).unwrap();
+        self.memory.get(address).copied().unwrap_or(0)
+    }
+
+    // runs from START to FINISH, or from FINISH to START, returning the number of instructions executed
+    pub fn run(&mut self, forwards: bool, fuel: usize) -> Result<usize, String> {
+        let direction = if forwards { 1 } else { -1 };
+        let (mut pc, end) = if forwards { (self.start, self.finish) } else { (self.finish, self.start) };
+
+        let mut steps = 0;
+        loop {
+            if pc == end && self.branch == 0 && steps > 0 {
+                return Ok(steps);
+            }
+            if steps == fuel {
+                return Err(String::from("Out of fuel"));
+            }
+
+            let word = match self.program.get(pc as usize) {
+                Some(word) if pc >= 0 => word.clone(),
+                _ => return Err(format!("Jumped outside the program to {}", pc)),
+            };
+
+            match word {
+                Word::Data => return Err(format!("Executed data at {}", pc)),
+                Word::Instruction(opcode, operands) => self.execute(pc, &opcode, &operands, forwards)?,
+            }
+            steps += 1;
+
+            pc += if self.branch == 0 { direction } else { self.branch };
+        }
+    }
+
+    fn register(operand: &Operand) -> Result<usize, String> {
+        match operand {
+            Operand::Register(register) => Ok(*register),
+            Operand::Immediate(value) => Err(format!("Expected a register, found {}", value)),
+        }
+    }
+
+    fn immediate(operand: &Operand) -> Result<i32, String> {
+        match operand {
+            Operand::Immediate(value) => Ok(*value),
+            Operand::Register(register) => Err(format!("Expected an immediate, found ${}", register)),
+        }
+    }
+
+    fn execute(&mut self, pc: i32, opcode: &str, operands: &[Operand], forwards: bool) -> Result<(), String> {
+        let error = |message: &str| Err(format!("{} at {}: {} {:?}", message, pc, opcode, operands));
+
+        // the inverse of each instruction, when running backwards
+        let opcode = match (opcode, forwards) {
+            ("ADD", false) => "SUB",
+            ("SUB", false) => "ADD",
+            ("RL", false) => "RR",
+            ("RR", false) => "RL",
+            (opcode, _) => opcode,
+        };
+
+        let registers = operands.iter().filter_map(|operand| Self::register(operand).ok()).collect::<Vec<usize>>();
+        if let Some(first) = registers.first() {
+            // the first register is updated, so it can't be $0 or read by the update
+            let writes = !matches!(opcode, "BEQ" | "BNE" | "EXCH");
+            if writes && (*first == 0 || registers[1..].contains(first)) {
+                return error("Irreversible instruction");
+            }
+        }
+
+        let values = self.registers;
+        let r = |index: usize| -> Result<i32, String> { Ok(values[Self::register(&operands[index])?]) };
+
+        match opcode {
+            "START" | "FINISH" => {}
+            "ADD" => self.registers[registers[0]] = r(0)?.wrapping_add(r(1)?),
+            "SUB" => self.registers[registers[0]] = r(0)?.wrapping_sub(r(1)?),
+            "ADDI" => {
+                let value = Self::immediate(&operands[1])?;
+                let value = if forwards { value } else { value.wrapping_neg() };
+                self.registers[registers[0]] = r(0)?.wrapping_add(value);
+            }
+            "NEG" => self.registers[registers[0]] = r(0)?.wrapping_neg(),
+            "XOR" => self.registers[registers[0]] = r(0)? ^ r(1)?,
+            "XORI" => self.registers[registers[0]] = r(0)? ^ Self::immediate(&operands[1])?,
+            "ANDX" => self.registers[registers[0]] = r(0)? ^ (r(1)? & r(2)?),
+            "ANDIX" => self.registers[registers[0]] = r(0)? ^ (r(1)? & Self::immediate(&operands[2])?),
+            "ORX" => self.registers[registers[0]] = r(0)? ^ (r(1)? | r(2)?),
+            "SLLVX" => self.registers[registers[0]] = r(0)? ^ r(1)?.wrapping_shl(r(2)? as u32),
+            "SRAX" => self.registers[registers[0]] = r(0)? ^ r(1)?.wrapping_shr(Self::immediate(&operands[2])? as u32),
+            "SRLX" => {
+                let shifted = (r(1)? as u32).wrapping_shr(Self::immediate(&operands[2])? as u32) as i32;
+                self.registers[registers[0]] = r(0)? ^ shifted;
+            }
+            "SRLVX" => {
+                let shifted = (r(1)? as u32).wrapping_shr(r(2)? as u32) as i32;
+                self.registers[registers[0]] = r(0)? ^ shifted;
+            }
+            "RL" => self.registers[registers[0]] = r(0)?.rotate_left(Self::immediate(&operands[1])? as u32),
+            "RR" => self.registers[registers[0]] = r(0)?.rotate_right(Self::immediate(&operands[1])? as u32),
+            "EXCH" => {
+                let address = r(1)?;
+                if registers[0] == 0 || registers[0] == registers[1] {
+                    return error("Irreversible instruction");
+                }
+                // data words and the space after the program can be exchanged with, instructions can't
+                match self.program.get(address as usize) {
+                    Some(Word::Data) | None if address >= 0 => {}
+                    _ => return error("Exchange with an instruction"),
+                }
+                let value = self.memory.insert(address, r(0)?).unwrap_or(0);
+                self.registers[registers[0]] = value;
+            }
+            "BEQ" | "BNE" => {
+                let target = Self::immediate(&operands[2])?;
+                if (r(0)? == r(1)?) == (opcode == "BEQ") {
+                    self.branch += target - pc;
+                }
+            }
+            "BRA" => self.branch += Self::immediate(&operands[0])? - pc,
+            _ => return error("Unknown instruction"),
+        }
+
+        Ok(())
+    }
+}
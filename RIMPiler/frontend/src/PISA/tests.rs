@@ -0,0 +1,167 @@
+use super::code_gen::PISACompiler;
+use super::simulator::Machine;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::interpreter::memory_store::Value;
+use super::super::fixtures::{example, inverted_program_keeping_counters as inverted_program};
+use super::super::AST::Program;
+use super::super::IR::lower::lower;
+use super::super::Backend;
+
+// the examples without floats
+const EXAMPLES: [&str; 5] = ["basic", "collatz", "fibonacci", "if", "while"];
+
+const FUEL: usize = 50_000_000;
+
+// where each variable ends up, from the table at the top of the output
+fn locations(assembly: &str) -> Vec<(String, String)> {
+    assembly
+        .lines()
+        .skip_while(|line| *line != ";; variables:")
+        .skip(1)
+        .map_while(|line| line.strip_prefix(";;   "))
+        .map(|line| {
+            let (name, location) = line.split_once(' ').unwrap();
+            (name.to_string(), location.to_string())
+        })
+        .collect()
+}
+
+fn value(machine: &Machine, location: &str) -> i32 {
+    match location.strip_prefix('$') {
+        Some(register) => machine.registers[register.parse::<usize>().unwrap()],
+        None => machine.load(location),
+    }
+}
+
+// runs the program forwards, checks it against the interpreter, then backwards, checking everything is 0 again
+fn check(name: &str, inverted: &Program) {
+    let mut interpreter = InterpreterEngine::new();
+    interpreter.interpret(inverted).unwrap();
+
+    let assembly = PISACompiler::compile(inverted);
+    let mut machine = Machine::new(&assembly).unwrap();
+
+    machine.run(true, FUEL).unwrap_or_else(|error| panic!("{}: {}", name, error));
+
+    let locations = locations(&assembly);
+    assert!(!locations.is_empty(), "{}", name);
+    for (variable, location) in &locations {
+        let expected = interpreter.get_result(variable).unwrap().get();
+        assert_eq!(Value::Integer(value(&machine, location)), expected, "{}: {}", name, variable);
+    }
+
+    machine.run(false, FUEL).unwrap_or_else(|error| panic!("{}: {}", name, error));
+
+    assert_eq!(machine.registers, [0; 32], "{}", name);
+    assert_eq!(machine.branch, 0, "{}", name);
+    assert!(machine.memory.values().all(|value| *value == 0), "{}", name);
+}
+
+#[test]
+fn readable_assembly() {
+    let assembly = PISACompiler::compile(&inverted_program("int n = 3; while n > 0 do { n = n - 1; };"));
+
+    assert!(assembly.starts_with(";; Generated by RIMPiler\n"));
+    assert!(assembly.contains(";; variables:\n;;   n $"));
+    assert!(assembly.contains("\n                         START\n                         XORI $1 history_stack\n"));
+    assert!(assembly.contains("\nwhile"));
+    assert!(assembly.contains("_entry:"));
+    assert!(assembly.contains("\nhistory_stack:           DATA 0\n"));
+
+    // the first assignments, the loop and the counter are all reversible, so nothing goes on the history stack
+    assert!(!assembly.contains("EXCH"));
+}
+
+#[test]
+fn history_only_when_needed() {
+    let reversible = PISACompiler::compile(&inverted_program("int x = 1; int y = 2; x = x + y * 2; y = -y; x = x - 3;"));
+    assert!(!reversible.contains("EXCH"));
+
+    // the old value of x can't be recovered from x * 3 without division
+    let destructive = PISACompiler::compile(&inverted_program("int x = 1; x = x * 3;"));
+    assert_eq!(destructive.matches("EXCH").count(), 1);
+}
+
+#[test]
+fn rejects_floats() {
+    let program = example("floats");
+    let result = PISACompiler::new()._compile(&lower(&inverted_program(&program)).unwrap());
+    assert!(result.unwrap_err().contains("PISA has no floats"));
+}
+
+#[test]
+fn matches_interpreter() {
+    for name in EXAMPLES {
+        let program = example(name);
+        check(name, &inverted_program(&program));
+    }
+}
+
+#[test]
+fn arithmetic() {
+    check("arithmetic", &inverted_program(r#"
+        int a = -7;
+        int b = 2;
+        int c = a * b;
+        int d = a / b;
+        int e = b ^ 5;
+        int f = -(a - b) * (c / -3);
+        int g = (a + 100) / (b - 9);
+        int h = (0 - 3) ^ 3 - a ^ 2;
+        int min = -2147483647 - 1;
+        int i = min / 3 + min / -1000;
+        int j = 46340 * 46340 / -7;
+        a = b;
+        b = a + b;
+        a = -a;
+        b = 10 - b;
+    "#));
+}
+
+#[test]
+fn conditions() {
+    check("conditions", &inverted_program(r#"
+        int big = 2147483647;
+        int small = -2147483647;
+        int x = 5;
+        int y = 0;
+        int less = 0;
+        if small < big && !(big < small) then { less = 1; } else { less = 2; };
+        if x > 3 && !(x == 4) || y != 0 then { y = x * x; } else { y = 1; };
+        if x == 5 then { x = y - x; } else { skip; };
+        if big > small then { if x < 0 then { big = 0; } else { small = small + x; }; } else { skip; };
+    "#));
+}
+
+#[test]
+fn nested_loops() {
+    check("nested_loops", &inverted_program(r#"
+        int i = 3;
+        int total = 0;
+        while i > 0 do {
+            int j = i;
+            while j > 0 do {
+                total = total + i * j;
+                j = j - 1;
+            };
+            i = i - 1;
+        };
+    "#));
+}
+
+#[test]
+fn spills_to_memory() {
+    // more variables than registers, the least used ones are kept in memory
+    let mut program = String::from("int v0 = 1;\n");
+    for index in 1..40 {
+        program.push_str(&format!("int v{} = v{} + {};\n", index, index - 1, index));
+    }
+    program.push_str("while v0 < 5 do { v0 = v0 + v1; v39 = v39 - v0; };\n");
+
+    let inverted = inverted_program(&program);
+    let assembly = PISACompiler::compile(&inverted);
+    assert!(locations(&assembly).iter().any(|(_, location)| location.starts_with("data_")));
+    assert!(locations(&assembly).iter().any(|(variable, location)| variable == "v0" && location.starts_with('$')));
+
+    check("spills_to_memory", &inverted);
+}
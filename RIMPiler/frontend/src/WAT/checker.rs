@@ -0,0 +1,831 @@
+/*
+A small checker for the subset of the WebAssembly text format that the WAT backend emits, so the tests
+don't need an external toolchain.
+
+parse reads the s-expressions, Module::new collects the imports, globals, memory, data and functions,
+validate type checks every function body (in folded form) and run interprets the module, recording the
+calls made to the imported print function.
+*/
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Atom(String),
+    Text(String),
+    List(Vec<Node>),
+}
+
+impl Node {
+    fn atom(&self) -> Option<&str> {
+        match self {
+            Node::Atom(atom) => Some(atom),
+            _ => None,
+        }
+    }
+
+    fn head(&self) -> Option<&str> {
+        match self {
+            Node::List(items) => items.first().and_then(|item| item.atom()),
+            _ => None,
+        }
+    }
+
+    fn items(&self) -> &[Node] {
+        match self {
+            Node::List(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Node, String> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut index = 0;
+    let mut stack: Vec<Vec<Node>> = vec![vec![]];
+
+    while index < characters.len() {
+        let character = characters[index];
+        if character.is_whitespace() {
+            index += 1;
+        } else if character == ';' && characters.get(index + 1) == Some(&';') {
+            while index < characters.len() && characters[index] != '\n' {
+                index += 1;
+            }
+        } else if character == '(' && characters.get(index + 1) == Some(&';') {
+            while index + 1 < characters.len() && !(characters[index] == ';' && characters[index + 1] == ')') {
+                index += 1;
+            }
+            index += 2;
+        } else if character == '(' {
+            stack.push(vec![]);
+            index += 1;
+        } else if character == ')' {
+            let list = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => parent.push(Node::List(list)),
+                None => return Err(String::from("Unbalanced )")),
+            }
+            index += 1;
+        } else if character == '"' {
+            let start = index + 1;
+            index += 1;
+            while index < characters.len() && characters[index] != '"' {
+                if characters[index] == '\\' {
+                    return Err(String::from("Escapes in strings are not supported"));
+                }
+                index += 1;
+            }
+            if index == characters.len() {
+                return Err(String::from("Unterminated string"));
+            }
+            stack.last_mut().unwrap().push(Node::Text(characters[start..index].iter().collect()));
+            index += 1;
+        } else {
+            let start = index;
+            while index < characters.len()
+                && !characters[index].is_whitespace()
+                && characters[index] != '('
+                && characters[index] != ')'
+            {
+                index += 1;
+            }
+            stack.last_mut().unwrap().push(Node::Atom(characters[start..index].iter().collect()));
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(String::from("Unbalanced ("));
+    }
+
+    let mut nodes = stack.pop().unwrap();
+    if nodes.len() != 1 || nodes[0].head() != Some("module") {
+        return Err(String::from("Expected a single module"));
+    }
+    Ok(nodes.remove(0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    I32,
+    F32,
+    F64,
+}
+
+fn value_type(atom: &str) -> Result<ValueType, String> {
+    match atom {
+        "i32" => Ok(ValueType::I32),
+        "f32" => Ok(ValueType::F32),
+        "f64" => Ok(ValueType::F64),
+        _ => Err(format!("Unsupported value type {}", atom)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    I32(i32),
+    F32(f32),
+    F64(f64),
+}
+
+impl Value {
+    fn default(value_type: ValueType) -> Value {
+        match value_type {
+            ValueType::I32 => Value::I32(0),
+            ValueType::F32 => Value::F32(0.0),
+            ValueType::F64 => Value::F64(0.0),
+        }
+    }
+
+    pub fn i32(&self) -> i32 {
+        match self {
+            Value::I32(value) => *value,
+            _ => panic!("Expected an i32, found {:?}", self),
+        }
+    }
+
+    pub fn f32(&self) -> f32 {
+        match self {
+            Value::F32(value) => *value,
+            _ => panic!("Expected an f32, found {:?}", self),
+        }
+    }
+
+    pub fn f64(&self) -> f64 {
+        match self {
+            Value::F64(value) => *value,
+            _ => panic!("Expected an f64, found {:?}", self),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Function {
+    params: Vec<(Option<String>, ValueType)>,
+    results: Vec<ValueType>,
+    locals: Vec<(Option<String>, ValueType)>,
+    // None for imports
+    body: Option<Vec<Node>>,
+    import: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Global {
+    mutable: bool,
+    value_type: ValueType,
+    initial: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    functions: HashMap<String, Function>,
+    globals: HashMap<String, Global>,
+    memory_pages: usize,
+    data: Vec<(usize, Vec<u8>)>,
+    // name -> (func or memory, identifier)
+    exports: HashMap<String, (String, String)>,
+}
+
+fn parse_const(instruction: &str, literal: &str) -> Result<Value, String> {
+    match instruction {
+        "i32.const" => literal.parse::<i32>().map(Value::I32).map_err(|_| format!("Invalid i32 {}", literal)),
+        "f32.const" => literal.parse::<f32>().map(Value::F32).map_err(|_| format!("Invalid f32 {}", literal)),
+        "f64.const" => literal.parse::<f64>().map(Value::F64).map_err(|_| format!("Invalid f64 {}", literal)),
+        _ => Err(format!("Expected a constant, found {}", instruction)),
+    }
+}
+
+fn identifier(node: Option<&Node>) -> Result<String, String> {
+    match node.and_then(|node| node.atom()) {
+        Some(atom) if atom.starts_with('$') => Ok(atom.to_string()),
+        _ => Err(format!("Expected an identifier, found {:?}", node)),
+    }
+}
+
+// (param $x i32) or (param i32 i32), (result i32), (local $x i32)
+fn parse_signature(items: &[Node], function: &mut Function) -> Result<usize, String> {
+    let mut index = 0;
+    while let Some(item) = items.get(index) {
+        let kind = match item.head() {
+            Some(kind @ ("param" | "result" | "local")) => kind,
+            _ => break,
+        };
+        let declaration = &item.items()[1..];
+        let named = declaration.first().and_then(|node| node.atom()).is_some_and(|atom| atom.starts_with('$'));
+
+        let mut entries = vec![];
+        if named {
+            if kind == "result" || declaration.len() != 2 {
+                return Err(format!("Malformed {}", kind));
+            }
+            entries.push((Some(identifier(declaration.first())?), value_type(declaration[1].atom().unwrap_or(""))?));
+        } else {
+            for node in declaration {
+                entries.push((None, value_type(node.atom().unwrap_or(""))?));
+            }
+        }
+
+        match kind {
+            "param" if function.results.is_empty() && function.locals.is_empty() => function.params.extend(entries),
+            "result" if function.locals.is_empty() => function.results.extend(entries.into_iter().map(|(_, value_type)| value_type)),
+            "local" => function.locals.extend(entries),
+            _ => return Err(format!("{} out of order", kind)),
+        }
+        index += 1;
+    }
+    Ok(index)
+}
+
+impl Module {
+    pub fn new(module: &Node) -> Result<Module, String> {
+        let mut result = Module {
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            memory_pages: 0,
+            data: vec![],
+            exports: HashMap::new(),
+        };
+
+        for field in &module.items()[1..] {
+            let items = field.items();
+            match field.head() {
+                Some("import") => {
+                    let (module_name, name) = match (items.get(1), items.get(2)) {
+                        (Some(Node::Text(module_name)), Some(Node::Text(name))) => (module_name, name),
+                        _ => return Err(String::from("Malformed import")),
+                    };
+                    let description = items.get(3).ok_or("Malformed import")?;
+                    if description.head() != Some("func") {
+                        return Err(String::from("Only functions can be imported"));
+                    }
+                    let id = identifier(description.items().get(1))?;
+                    let mut function = Function { params: vec![], results: vec![], locals: vec![], body: None, import: Some(format!("{}.{}", module_name, name)) };
+                    let used = parse_signature(&description.items()[2..], &mut function)?;
+                    if used != description.items().len() - 2 || !function.locals.is_empty() {
+                        return Err(String::from("Malformed import"));
+                    }
+                    result.functions.insert(id, function);
+                }
+                Some("memory") => {
+                    let pages = items.last().and_then(|node| node.atom()).ok_or("Malformed memory")?;
+                    result.memory_pages = pages.parse().map_err(|_| "Malformed memory")?;
+                }
+                Some("export") => {
+                    let name = match items.get(1) {
+                        Some(Node::Text(name)) => name.clone(),
+                        _ => return Err(String::from("Malformed export")),
+                    };
+                    let target = items.get(2).ok_or("Malformed export")?;
+                    match target.head() {
+                        Some(kind @ ("memory" | "func")) => {
+                            result.exports.insert(name, (kind.to_string(), identifier(target.items().get(1))?));
+                        }
+                        _ => return Err(String::from("Malformed export")),
+                    }
+                }
+                Some("data") => {
+                    let offset = items.get(1).ok_or("Malformed data")?;
+                    if offset.head() != Some("i32.const") {
+                        return Err(String::from("Data offsets must be constant"));
+                    }
+                    let offset = parse_const("i32.const", offset.items().get(1).and_then(|node| node.atom()).unwrap_or(""))?.i32();
+                    let mut bytes = vec![];
+                    for node in &items[2..] {
+                        match node {
+                            Node::Text(text) => bytes.extend_from_slice(text.as_bytes()),
+                            _ => return Err(String::from("Malformed data")),
+                        }
+                    }
+                    result.data.push((offset as usize, bytes));
+                }
+                Some("global") => {
+                    let id = identifier(items.get(1))?;
+                    let (mutable, value_type) = match items.get(2) {
+                        Some(Node::Atom(atom)) => (false, self::value_type(atom)?),
+                        Some(node) if node.head() == Some("mut") => (true, self::value_type(node.items().get(1).and_then(|node| node.atom()).unwrap_or(""))?),
+                        _ => return Err(String::from("Malformed global")),
+                    };
+                    let initial = items.get(3).ok_or("Malformed global")?;
+                    let initial = parse_const(initial.head().unwrap_or(""), initial.items().get(1).and_then(|node| node.atom()).unwrap_or(""))?;
+                    if value_type != initial.type_of() {
+                        return Err(format!("Global {} initialised with the wrong type", id));
+                    }
+                    if result.globals.insert(id.clone(), Global { mutable, value_type, initial }).is_some() {
+                        return Err(format!("Global {} defined twice", id));
+                    }
+                }
+                Some("func") => {
+                    let id = identifier(items.get(1))?;
+                    let mut index = 2;
+                    while let Some(node) = items.get(index) {
+                        if node.head() != Some("export") {
+                            break;
+                        }
+                        match node.items().get(1) {
+                            Some(Node::Text(name)) => result.exports.insert(name.clone(), (String::from("func"), id.clone())),
+                            _ => return Err(String::from("Malformed export")),
+                        };
+                        index += 1;
+                    }
+                    let mut function = Function { params: vec![], results: vec![], locals: vec![], body: None, import: None };
+                    index += parse_signature(&items[index..], &mut function)?;
+                    function.body = Some(items[index..].to_vec());
+                    if result.functions.insert(id.clone(), function).is_some() {
+                        return Err(format!("Function {} defined twice", id));
+                    }
+                }
+                _ => return Err(format!("Unsupported module field {:?}", field.head())),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Value {
+    fn type_of(&self) -> ValueType {
+        match self {
+            Value::I32(_) => ValueType::I32,
+            Value::F32(_) => ValueType::F32,
+            Value::F64(_) => ValueType::F64,
+        }
+    }
+}
+
+// (operands, results) of the plain numeric instructions
+fn signature(instruction: &str) -> Option<(Vec<ValueType>, Vec<ValueType>)> {
+    use ValueType::*;
+    let signature = match instruction {
+        "i32.add" | "i32.sub" | "i32.mul" | "i32.div_s" | "i32.and" | "i32.or" | "i32.eq" | "i32.ne"
+        | "i32.lt_s" | "i32.gt_s" | "i32.le_s" | "i32.gt_u" => (vec![I32, I32], vec![I32]),
+        "i32.eqz" => (vec![I32], vec![I32]),
+        "f32.add" | "f32.sub" | "f32.mul" | "f32.div" => (vec![F32, F32], vec![F32]),
+        "f32.eq" | "f32.ne" | "f32.lt" | "f32.gt" | "f32.le" => (vec![F32, F32], vec![I32]),
+        "f32.neg" => (vec![F32], vec![F32]),
+        "f32.convert_i32_s" | "f32.reinterpret_i32" => (vec![I32], vec![F32]),
+        "i32.trunc_sat_f32_s" | "i32.reinterpret_f32" => (vec![F32], vec![I32]),
+        "f64.convert_i32_s" => (vec![I32], vec![F64]),
+        "f64.promote_f32" => (vec![F32], vec![F64]),
+        "f32.demote_f64" => (vec![F64], vec![F32]),
+        "i32.load" => (vec![I32], vec![I32]),
+        "i32.store" => (vec![I32, I32], vec![]),
+        "memory.size" => (vec![], vec![I32]),
+        "memory.grow" => (vec![I32], vec![I32]),
+        _ => return None,
+    };
+    Some(signature)
+}
+
+struct Context<'a> {
+    module: &'a Module,
+    locals: HashMap<String, ValueType>,
+    // innermost last, with the types a branch to it carries
+    labels: Vec<(Option<String>, Vec<ValueType>)>,
+}
+
+impl Module {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, (kind, target)) in &self.exports {
+            if kind == "func" && !self.functions.contains_key(target) {
+                return Err(format!("Export {} refers to unknown function {}", name, target));
+            }
+            if kind == "memory" && self.memory_pages == 0 {
+                return Err(format!("Export {} refers to undefined memory", name));
+            }
+        }
+        for (offset, bytes) in &self.data {
+            if offset + bytes.len() > self.memory_pages * 65536 {
+                return Err(String::from("Data segment does not fit in memory"));
+            }
+        }
+
+        for (name, function) in &self.functions {
+            let Some(body) = &function.body else { continue };
+
+            let mut locals = HashMap::new();
+            for (local, value_type) in function.params.iter().chain(function.locals.iter()) {
+                if let Some(local) = local {
+                    if locals.insert(local.clone(), *value_type).is_some() {
+                        return Err(format!("Local {} defined twice in {}", local, name));
+                    }
+                }
+            }
+
+            let mut context = Context { module: self, locals, labels: vec![] };
+            let results = context.check_sequence(body).map_err(|error| format!("{}: {}", name, error))?;
+            if let Some(results) = results {
+                if results != function.results {
+                    return Err(format!("{}: body produces {:?}, expected {:?}", name, results, function.results));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Context<'a> {
+    // the types left on the stack by a sequence of instructions, None once the rest is unreachable
+    fn check_sequence(&mut self, instructions: &[Node]) -> Result<Option<Vec<ValueType>>, String> {
+        let mut stack = vec![];
+        for instruction in instructions {
+            match self.check_instruction(instruction)? {
+                Some(results) => stack.extend(results),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(stack))
+    }
+
+    fn block_type(items: &[Node]) -> Result<(Option<String>, Vec<ValueType>, usize), String> {
+        let mut index = 1;
+        let label = match items.get(index).and_then(|node| node.atom()) {
+            Some(atom) if atom.starts_with('$') => {
+                index += 1;
+                Some(atom.to_string())
+            }
+            _ => None,
+        };
+        let mut results = vec![];
+        while let Some(node) = items.get(index) {
+            if node.head() != Some("result") {
+                break;
+            }
+            for value_type in &node.items()[1..] {
+                results.push(self::value_type(value_type.atom().unwrap_or(""))?);
+            }
+            index += 1;
+        }
+        Ok((label, results, index))
+    }
+
+    fn label(&self, label: &str) -> Result<Vec<ValueType>, String> {
+        self.labels
+            .iter()
+            .rev()
+            .find(|(name, _)| name.as_deref() == Some(label))
+            .map(|(_, types)| types.clone())
+            .ok_or(format!("Unknown label {}", label))
+    }
+
+    // checks the folded operands of an instruction against the types it consumes
+    fn check_operands(&mut self, instruction: &str, operands: &[Node], expected: &[ValueType]) -> Result<bool, String> {
+        match self.check_sequence(operands)? {
+            Some(found) if found == expected => Ok(true),
+            Some(found) => Err(format!("{} expects {:?}, found {:?}", instruction, expected, found)),
+            None => Ok(false),
+        }
+    }
+
+    fn check_instruction(&mut self, node: &Node) -> Result<Option<Vec<ValueType>>, String> {
+        let items = match node {
+            Node::Atom(atom) => match atom.as_str() {
+                "unreachable" => return Ok(None),
+                "nop" => return Ok(Some(vec![])),
+                _ => return Err(format!("Instructions must be folded, found {}", atom)),
+            },
+            Node::Text(_) => return Err(String::from("Unexpected string")),
+            Node::List(items) => items,
+        };
+        let instruction = node.head().ok_or("Expected an instruction")?;
+
+        match instruction {
+            "block" | "loop" => {
+                let (label, results, index) = Self::block_type(items)?;
+                let branch = if instruction == "loop" { vec![] } else { results.clone() };
+                self.labels.push((label, branch));
+                let found = self.check_sequence(&items[index..])?;
+                self.labels.pop();
+                match found {
+                    Some(found) if found != results => Err(format!("{} produces {:?}, expected {:?}", instruction, found, results)),
+                    _ => Ok(Some(results)),
+                }
+            }
+            "if" => {
+                let (label, results, index) = Self::block_type(items)?;
+                let then_index = items.iter().position(|item| item.head() == Some("then")).ok_or("if without then")?;
+                if !self.check_operands("if", &items[index..then_index], &[ValueType::I32])? {
+                    return Ok(None);
+                }
+                let rest = &items[then_index..];
+                if rest.len() > 2 || (rest.len() == 2 && rest[1].head() != Some("else")) {
+                    return Err(String::from("Malformed if"));
+                }
+                for branch in rest {
+                    self.labels.push((label.clone(), results.clone()));
+                    let found = self.check_sequence(&branch.items()[1..])?;
+                    self.labels.pop();
+                    if let Some(found) = found {
+                        if found != results {
+                            return Err(format!("if branch produces {:?}, expected {:?}", found, results));
+                        }
+                    }
+                }
+                if rest.len() == 1 && !results.is_empty() {
+                    return Err(String::from("if with a result needs an else"));
+                }
+                Ok(Some(results))
+            }
+            "br" | "br_if" => {
+                let label = identifier(items.get(1))?;
+                let mut expected = self.label(&label)?;
+                if instruction == "br_if" {
+                    expected.push(ValueType::I32);
+                }
+                if !self.check_operands(instruction, &items[2..], &expected)? || instruction == "br" {
+                    return Ok(None);
+                }
+                expected.pop();
+                Ok(Some(expected))
+            }
+            "i32.const" | "f32.const" | "f64.const" => {
+                if items.len() != 2 {
+                    return Err(format!("Malformed {}", instruction));
+                }
+                let value = parse_const(instruction, items[1].atom().unwrap_or(""))?;
+                Ok(Some(vec![value.type_of()]))
+            }
+            "local.get" | "local.set" | "global.get" | "global.set" => {
+                let id = identifier(items.get(1))?;
+                let (value_type, mutable) = if instruction.starts_with("local") {
+                    (*self.locals.get(&id).ok_or(format!("Unknown local {}", id))?, true)
+                } else {
+                    let global = self.module.globals.get(&id).ok_or(format!("Unknown global {}", id))?;
+                    (global.value_type, global.mutable)
+                };
+                if instruction.ends_with("get") {
+                    if items.len() != 2 {
+                        return Err(format!("Malformed {}", instruction));
+                    }
+                    return Ok(Some(vec![value_type]));
+                }
+                if !mutable {
+                    return Err(format!("{} is immutable", id));
+                }
+                if !self.check_operands(instruction, &items[2..], &[value_type])? {
+                    return Ok(None);
+                }
+                Ok(Some(vec![]))
+            }
+            "call" => {
+                let id = identifier(items.get(1))?;
+                let function = self.module.functions.get(&id).ok_or(format!("Unknown function {}", id))?;
+                let params: Vec<ValueType> = function.params.iter().map(|(_, value_type)| *value_type).collect();
+                let results = function.results.clone();
+                if !self.check_operands(&id, &items[2..], &params)? {
+                    return Ok(None);
+                }
+                Ok(Some(results))
+            }
+            _ => {
+                let (operands, results) = signature(instruction).ok_or(format!("Unsupported instruction {}", instruction))?;
+                let mut index = 1;
+                // memory immediates
+                while let Some(atom) = items.get(index).and_then(|node| node.atom()) {
+                    let offset = atom.strip_prefix("offset=").or(atom.strip_prefix("align="));
+                    match offset {
+                        Some(offset) if instruction.contains("load") || instruction.contains("store") => {
+                            offset.parse::<u32>().map_err(|_| format!("Invalid immediate {}", atom))?;
+                        }
+                        _ => return Err(format!("Unexpected immediate {} for {}", atom, instruction)),
+                    }
+                    index += 1;
+                }
+                if !self.check_operands(instruction, &items[index..], &operands)? {
+                    return Ok(None);
+                }
+                Ok(Some(results))
+            }
+        }
+    }
+}
+
+// how control leaves an instruction, other than falling through
+enum Signal {
+    Branch(String),
+    Trap(String),
+}
+
+pub struct Instance<'a> {
+    module: &'a Module,
+    pub memory: Vec<u8>,
+    pub globals: HashMap<String, Value>,
+    // (name, kind, value) for each call to env.print
+    pub printed: Vec<(String, i32, f64)>,
+    // guards against programs that never terminate
+    fuel: usize,
+}
+
+struct Frame {
+    locals: HashMap<String, Value>,
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(module: &'a Module, fuel: usize) -> Instance<'a> {
+        let mut memory = vec![0; module.memory_pages * 65536];
+        for (offset, bytes) in &module.data {
+            memory[*offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        let globals = module.globals.iter().map(|(name, global)| (name.clone(), global.initial)).collect();
+        Instance { module, memory, globals, printed: vec![], fuel }
+    }
+
+    pub fn run(&mut self, export: &str) -> Result<Vec<Value>, String> {
+        let (_, function) = self.module.exports.get(export).ok_or(format!("Unknown export {}", export))?.clone();
+        self.call(&function, vec![])
+    }
+
+    fn call(&mut self, id: &str, arguments: Vec<Value>) -> Result<Vec<Value>, String> {
+        let function = self.module.functions.get(id).ok_or(format!("Unknown function {}", id))?;
+
+        match function.import.as_deref() {
+            Some("env.print") => {
+                let address = arguments[0].i32() as usize;
+                let length = arguments[1].i32() as usize;
+                let name = String::from_utf8(self.memory[address..address + length].to_vec()).map_err(|error| error.to_string())?;
+                self.printed.push((name, arguments[2].i32(), arguments[3].f64()));
+                return Ok(vec![]);
+            }
+            Some("env.pow") => return Ok(vec![Value::F64(arguments[0].f64().powf(arguments[1].f64()))]),
+            Some(import) => return Err(format!("Unknown import {}", import)),
+            None => {}
+        }
+
+        let mut frame = Frame { locals: HashMap::new() };
+        for ((name, _), argument) in function.params.iter().zip(arguments) {
+            if let Some(name) = name {
+                frame.locals.insert(name.clone(), argument);
+            }
+        }
+        for (name, value_type) in &function.locals {
+            if let Some(name) = name {
+                frame.locals.insert(name.clone(), Value::default(*value_type));
+            }
+        }
+
+        let body = function.body.as_ref().unwrap();
+        match self.execute_sequence(body, &mut frame) {
+            Ok(values) => Ok(values),
+            Err(Signal::Trap(message)) => Err(message),
+            Err(Signal::Branch(label)) => Err(format!("Branch to {} escaped {}", label, id)),
+        }
+    }
+
+    fn execute_sequence(&mut self, instructions: &[Node], frame: &mut Frame) -> Result<Vec<Value>, Signal> {
+        let mut stack = vec![];
+        for instruction in instructions {
+            stack.extend(self.execute(instruction, frame)?);
+        }
+        Ok(stack)
+    }
+
+    fn address(&self, base: i32, offset: usize) -> Result<usize, Signal> {
+        let address = base as u32 as usize + offset;
+        if address + 4 > self.memory.len() {
+            return Err(Signal::Trap(format!("Out of bounds memory access at {}", address)));
+        }
+        Ok(address)
+    }
+
+    fn execute(&mut self, node: &Node, frame: &mut Frame) -> Result<Vec<Value>, Signal> {
+        if self.fuel == 0 {
+            return Err(Signal::Trap(String::from("Out of fuel")));
+        }
+        self.fuel -= 1;
+
+        let items = match node {
+            Node::Atom(atom) if atom == "unreachable" => return Err(Signal::Trap(String::from("unreachable"))),
+            Node::Atom(atom) if atom == "nop" => return Ok(vec![]),
+            Node::List(items) => items,
+            _ => return Err(Signal::Trap(format!("Cannot execute {:?}", node))),
+        };
+        let instruction = node.head().unwrap();
+
+        match instruction {
+            "block" | "loop" => {
+                let (label, _, index) = Context::block_type(items).map_err(Signal::Trap)?;
+                loop {
+                    match self.execute_sequence(&items[index..], frame) {
+                        Err(Signal::Branch(target)) if Some(&target) == label.as_ref() => {
+                            if instruction == "block" {
+                                return Ok(vec![]);
+                            }
+                        }
+                        result => return result,
+                    }
+                }
+            }
+            "if" => {
+                let (label, _, index) = Context::block_type(items).map_err(Signal::Trap)?;
+                let then_index = items.iter().position(|item| item.head() == Some("then")).unwrap();
+                let condition = self.execute_sequence(&items[index..then_index], frame)?[0].i32();
+                let branch = if condition != 0 { items.get(then_index) } else { items.get(then_index + 1) };
+                match branch {
+                    Some(branch) => match self.execute_sequence(&branch.items()[1..], frame) {
+                        Err(Signal::Branch(target)) if Some(&target) == label.as_ref() => Ok(vec![]),
+                        result => result,
+                    },
+                    None => Ok(vec![]),
+                }
+            }
+            "br" => Err(Signal::Branch(items[1].atom().unwrap().to_string())),
+            "br_if" => {
+                let condition = self.execute_sequence(&items[2..], frame)?[0].i32();
+                if condition != 0 {
+                    Err(Signal::Branch(items[1].atom().unwrap().to_string()))
+                } else {
+                    Ok(vec![])
+                }
+            }
+            "i32.const" | "f32.const" | "f64.const" => {
+                Ok(vec![parse_const(instruction, items[1].atom().unwrap()).map_err(Signal::Trap)?])
+            }
+            "local.get" => Ok(vec![frame.locals[items[1].atom().unwrap()]]),
+            "global.get" => Ok(vec![self.globals[items[1].atom().unwrap()]]),
+            "local.set" => {
+                let value = self.execute_sequence(&items[2..], frame)?[0];
+                frame.locals.insert(items[1].atom().unwrap().to_string(), value);
+                Ok(vec![])
+            }
+            "global.set" => {
+                let value = self.execute_sequence(&items[2..], frame)?[0];
+                self.globals.insert(items[1].atom().unwrap().to_string(), value);
+                Ok(vec![])
+            }
+            "call" => {
+                let arguments = self.execute_sequence(&items[2..], frame)?;
+                self.call(items[1].atom().unwrap(), arguments).map_err(Signal::Trap)
+            }
+            _ => {
+                let mut index = 1;
+                let mut offset = 0;
+                while let Some(atom) = items.get(index).and_then(|node| node.atom()) {
+                    if let Some(value) = atom.strip_prefix("offset=") {
+                        offset = value.parse().unwrap();
+                    }
+                    index += 1;
+                }
+                let operands = self.execute_sequence(&items[index..], frame)?;
+                self.numeric(instruction, &operands, offset).map(|value| value.into_iter().collect())
+            }
+        }
+    }
+
+    fn numeric(&mut self, instruction: &str, operands: &[Value], offset: usize) -> Result<Option<Value>, Signal> {
+        let trap = |message: &str| Err(Signal::Trap(message.to_string()));
+        let value = match instruction {
+            "i32.add" => Value::I32(operands[0].i32().wrapping_add(operands[1].i32())),
+            "i32.sub" => Value::I32(operands[0].i32().wrapping_sub(operands[1].i32())),
+            "i32.mul" => Value::I32(operands[0].i32().wrapping_mul(operands[1].i32())),
+            "i32.div_s" => match operands[0].i32().checked_div(operands[1].i32()) {
+                Some(value) => Value::I32(value),
+                None if operands[1].i32() == 0 => return trap("integer divide by zero"),
+                None => return trap("integer overflow"),
+            },
+            "i32.and" => Value::I32(operands[0].i32() & operands[1].i32()),
+            "i32.or" => Value::I32(operands[0].i32() | operands[1].i32()),
+            "i32.eq" => Value::I32((operands[0].i32() == operands[1].i32()) as i32),
+            "i32.ne" => Value::I32((operands[0].i32() != operands[1].i32()) as i32),
+            "i32.lt_s" => Value::I32((operands[0].i32() < operands[1].i32()) as i32),
+            "i32.gt_s" => Value::I32((operands[0].i32() > operands[1].i32()) as i32),
+            "i32.le_s" => Value::I32((operands[0].i32() <= operands[1].i32()) as i32),
+            "i32.gt_u" => Value::I32(((operands[0].i32() as u32) > (operands[1].i32() as u32)) as i32),
+            "i32.eqz" => Value::I32((operands[0].i32() == 0) as i32),
+            "f32.add" => Value::F32(operands[0].f32() + operands[1].f32()),
+            "f32.sub" => Value::F32(operands[0].f32() - operands[1].f32()),
+            "f32.mul" => Value::F32(operands[0].f32() * operands[1].f32()),
+            "f32.div" => Value::F32(operands[0].f32() / operands[1].f32()),
+            "f32.eq" => Value::I32((operands[0].f32() == operands[1].f32()) as i32),
+            "f32.ne" => Value::I32((operands[0].f32() != operands[1].f32()) as i32),
+            "f32.lt" => Value::I32((operands[0].f32() < operands[1].f32()) as i32),
+            "f32.gt" => Value::I32((operands[0].f32() > operands[1].f32()) as i32),
+            "f32.le" => Value::I32((operands[0].f32() <= operands[1].f32()) as i32),
+            "f32.neg" => Value::F32(-operands[0].f32()),
+            "f32.convert_i32_s" => Value::F32(operands[0].i32() as f32),
+            "f32.reinterpret_i32" => Value::F32(f32::from_bits(operands[0].i32() as u32)),
+            "i32.trunc_sat_f32_s" => Value::I32(operands[0].f32() as i32),
+            "i32.reinterpret_f32" => Value::I32(operands[0].f32().to_bits() as i32),
+            "f64.convert_i32_s" => Value::F64(operands[0].i32() as f64),
+            "f64.promote_f32" => Value::F64(operands[0].f32() as f64),
+            "f32.demote_f64" => Value::F32(operands[0].f64() as f32),
+            "i32.load" => {
+                let address = self.address(operands[0].i32(), offset)?;
+                let bytes = [self.memory[address], self.memory[address + 1], self.memory[address + 2], self.memory[address + 3]];
+                Value::I32(i32::from_le_bytes(bytes))
+            }
+            "i32.store" => {
+                let address = self.address(operands[0].i32(), offset)?;
+                self.memory[address..address + 4].copy_from_slice(&operands[1].i32().to_le_bytes());
+                return Ok(None);
+            }
+            "memory.size" => Value::I32((self.memory.len() / 65536) as i32),
+            "memory.grow" => {
+                let pages = self.memory.len() / 65536;
+                self.memory.resize(self.memory.len() + operands[0].i32() as usize * 65536, 0);
+                Value::I32(pages as i32)
+            }
+            _ => return trap(&format!("Unsupported instruction {}", instruction)),
+        };
+        Ok(Some(value))
+    }
+}
@@ -0,0 +1,458 @@
+use std::fmt::{Display, Formatter};
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Slot, Type};
+use super::super::Backend;
+
+/*
+Compiles a RIMP program into a WebAssembly text module.
+
+Every variable is a global holding its value, plus a global pointing at the top of its history. The
+history entries live in linear memory, on one stack shared by all the variables:
+
+    entry: [delta: i32, or the bits of the old value of a float][previous entry of the same variable: i32]
+
+Floats keep their old value rather than the difference, as subtracting the difference back off can round.
+
+so each variable's history is a linked list threaded through the stack. The backward program pops in the
+reverse order that the forward program pushed, so the entry being removed is normally the top of the stack
+and its memory is reclaimed, anything else is just unlinked.
+
+The module imports env.print, which is called once per variable at the reverse point with the address and
+length of the variable's name (in the exported memory), 0 for an int or 1 for a float, and its value.
+It exports the memory and a main function that runs the whole program.
+*/
+
+const PAGE_SIZE: usize = 65536;
+
+// names are written from here, address 0 is kept as the null history entry
+const NAMES_START: usize = 8;
+
+const ENTRY_SIZE: usize = 8;
+
+// the folded s-expression form of the text format
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+// lines longer than this are broken up, one operand per line
+const LINE_WIDTH: usize = 100;
+
+impl Sexp {
+    fn atom(atom: &str) -> Sexp {
+        Sexp::Atom(atom.to_string())
+    }
+
+    // (instruction immediates... operands...)
+    fn list(head: &str, items: Vec<Sexp>) -> Sexp {
+        let mut list = vec![Sexp::atom(head)];
+        list.extend(items);
+        Sexp::List(list)
+    }
+
+    fn render(&self, indent: usize) -> String {
+        let flat = self.to_string();
+        match self {
+            Sexp::List(items) if indent + flat.len() > LINE_WIDTH => {
+                // atoms at the start (instruction, names, immediates) stay on the first line
+                let atoms = items.iter().take_while(|item| matches!(item, Sexp::Atom(_))).count();
+                let head = items[..atoms].iter().map(|item| item.to_string()).collect::<Vec<String>>().join(" ");
+
+                let mut output = format!("({}", head);
+                for item in &items[atoms..] {
+                    output.push('\n');
+                    output.push_str(&" ".repeat(indent + 2));
+                    output.push_str(&item.render(indent + 2));
+                }
+                output.push(')');
+                output
+            }
+            _ => flat,
+        }
+    }
+}
+
+impl Display for Sexp {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Sexp::Atom(atom) => write!(f, "{}", atom),
+            Sexp::List(items) => {
+                let items = items.iter().map(|item| item.to_string()).collect::<Vec<String>>();
+                write!(f, "({})", items.join(" "))
+            }
+        }
+    }
+}
+
+fn i32_const(value: i32) -> Sexp {
+    Sexp::list("i32.const", vec![Sexp::Atom(value.to_string())])
+}
+
+fn f32_const(value: f32) -> Sexp {
+    // if number has no decimal point, add .0 to make it a float
+    let value = if value.to_string().contains('.') {
+        value.to_string()
+    } else {
+        format!("{}.0", value)
+    };
+    Sexp::list("f32.const", vec![Sexp::Atom(value)])
+}
+
+fn global_get(name: &str) -> Sexp {
+    Sexp::list("global.get", vec![Sexp::Atom(format!("${}", name))])
+}
+
+fn global_set(name: &str, value: Sexp) -> Sexp {
+    Sexp::list("global.set", vec![Sexp::Atom(format!("${}", name)), value])
+}
+
+fn convert(expression: Sexp, target: &Type) -> Sexp {
+    match target {
+        Type::Float => Sexp::list("f32.convert_i32_s", vec![expression]),
+        // saturating, like java and rust
+        Type::Integer => Sexp::list("i32.trunc_sat_f32_s", vec![expression]),
+        Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+        Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+    }
+}
+
+// the helpers every module needs, in the order they are written out
+const RUNTIME: &str = r#"(func $rimp.push (param $delta i32) (param $previous i32) (result i32)
+  (local $entry i32)
+  (local.set $entry (global.get $rimp.stack))
+  (if (i32.gt_u (i32.add (local.get $entry) (i32.const 8)) (i32.mul (memory.size) (i32.const 65536)))
+    (then
+      (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+        (then unreachable))))
+  (i32.store (local.get $entry) (local.get $delta))
+  (i32.store offset=4 (local.get $entry) (local.get $previous))
+  (global.set $rimp.stack (i32.add (local.get $entry) (i32.const 8)))
+  (local.get $entry))
+(func $rimp.delta (param $entry i32) (result i32)
+  (if (i32.eqz (local.get $entry))
+    (then unreachable))
+  (i32.load (local.get $entry)))
+(func $rimp.pop (param $entry i32) (result i32)
+  (if (i32.eq (i32.add (local.get $entry) (i32.const 8)) (global.get $rimp.stack))
+    (then
+      (global.set $rimp.stack (local.get $entry))))
+  (i32.load offset=4 (local.get $entry)))
+(func $rimp.pow (param $base i32) (param $exponent i32) (result i32)
+  (local $result i32)
+  (if (i32.le_s (local.get $exponent) (i32.const 0))
+    (then unreachable))
+  (local.set $result (i32.const 1))
+  (block $done
+    (loop $next
+      (br_if $done (i32.eqz (local.get $exponent)))
+      (local.set $result (i32.mul (local.get $result) (local.get $base)))
+      (local.set $exponent (i32.sub (local.get $exponent) (i32.const 1)))
+      (br $next)))
+  (local.get $result))
+(func $rimp.div (param $lhs i32) (param $rhs i32) (result i32)
+  (if (i32.eq (local.get $rhs) (i32.const -1))
+    (then
+      (local.set $lhs (i32.sub (i32.const 0) (local.get $lhs)))
+      (local.set $rhs (i32.const 1))))
+  (i32.div_s (local.get $lhs) (local.get $rhs)))"#;
+
+// only emitted when a float is raised to a power, as there is no instruction for it
+const FLOAT_POW: &str = r#"(func $rimp.pow_f32 (param $base f32) (param $exponent f32) (result f32)
+  (if (f32.le (local.get $exponent) (f32.const 0))
+    (then unreachable))
+  (f32.demote_f64 (call $rimp.pow_f64 (f64.promote_f32 (local.get $base)) (f64.promote_f32 (local.get $exponent)))))"#;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct WATCompiler {
+    // the address of each variable's name in memory, by slot
+    names: Vec<usize>,
+    // next free address for a name
+    names_end: usize,
+
+    // index for fresh labels
+    index: usize,
+
+    uses_float_pow: bool,
+}
+
+impl WATCompiler {
+    pub fn new() -> Self {
+        WATCompiler {
+            names: Vec::new(),
+            names_end: NAMES_START,
+            index: 0,
+            uses_float_pow: false,
+        }
+    }
+
+    fn new_label(&mut self, prefix: &str) -> String {
+        let label = format!("${}{}", prefix, self.index);
+        self.index += 1;
+        label
+    }
+
+    fn history(variable: &str) -> String {
+        format!("{}.history", variable)
+    }
+
+    pub fn _compile(&mut self, program: &Program) -> String {
+        for variable in &program.variables {
+            self.names.push(self.names_end);
+            self.names_end += variable.name.len();
+        }
+
+        let mut body = self.compile_block(program, &program.forward);
+        body.extend(self.compile_reverse_point(program));
+        body.extend(self.compile_block(program, &program.backward));
+
+        let mut module = vec![
+            String::from(r#"(import "env" "print" (func $rimp.print (param i32 i32 i32 f64)))"#),
+        ];
+        if self.uses_float_pow {
+            module.push(String::from(r#"(import "env" "pow" (func $rimp.pow_f64 (param f64 f64) (result f64)))"#));
+        }
+
+        // the history stack starts after the names, aligned for its entries
+        let stack_start = self.names_end.div_ceil(ENTRY_SIZE) * ENTRY_SIZE;
+        let pages = stack_start / PAGE_SIZE + 1;
+
+        module.push(format!("(memory $memory {})", pages));
+        module.push(String::from(r#"(export "memory" (memory $memory))"#));
+
+        if !program.variables.is_empty() {
+            let names = program.variables.iter().map(|variable| format!("\"{}\"", variable.name)).collect::<Vec<String>>();
+            module.push(format!("(data (i32.const {}) {})", NAMES_START, names.join(" ")));
+        }
+
+        module.push(format!("(global $rimp.stack (mut i32) (i32.const {}))", stack_start));
+        for variable in &program.variables {
+            match variable.variable_type {
+                Type::Integer => module.push(format!("(global ${} (mut i32) (i32.const 0))", variable.name)),
+                Type::Float => module.push(format!("(global ${} (mut f32) (f32.const 0))", variable.name)),
+                Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+            }
+            module.push(format!("(global ${} (mut i32) (i32.const 0))", Self::history(&variable.name)));
+        }
+
+        module.push(String::from(RUNTIME));
+        if self.uses_float_pow {
+            module.push(String::from(FLOAT_POW));
+        }
+
+        // every history starts with a 0, as the runtime classes do
+        let mut main = vec![
+            Sexp::atom("$main"),
+            Sexp::list("export", vec![Sexp::atom("\"main\"")]),
+            Sexp::list("local", vec![Sexp::atom("$int"), Sexp::atom("i32")]),
+            Sexp::list("local", vec![Sexp::atom("$float"), Sexp::atom("f32")]),
+        ];
+        for variable in &program.variables {
+            main.push(global_set(
+                &Self::history(&variable.name),
+                Sexp::list("call", vec![Sexp::atom("$rimp.push"), i32_const(0), i32_const(0)]),
+            ));
+        }
+        main.extend(body);
+
+        module.push(Sexp::list("func", main).render(0));
+
+        let module = module
+            .iter()
+            .flat_map(|field| field.lines())
+            .map(|line| format!("  {}\n", line))
+            .collect::<String>();
+
+        format!("(module\n{})\n", module.trim_end())
+    }
+
+    fn compile_reverse_point(&self, program: &Program) -> Vec<Sexp> {
+        let mut code = vec![];
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let (kind, value) = match variable.variable_type {
+                Type::Integer => (0, Sexp::list("f64.convert_i32_s", vec![global_get(&variable.name)])),
+                Type::Float => (1, Sexp::list("f64.promote_f32", vec![global_get(&variable.name)])),
+                Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+            };
+            code.push(Sexp::list("call", vec![
+                Sexp::atom("$rimp.print"),
+                i32_const(self.names[slot] as i32),
+                i32_const(variable.name.len() as i32),
+                i32_const(kind),
+                value,
+            ]));
+        }
+        code
+    }
+
+    fn compile_block(&mut self, program: &Program, block: &Block) -> Vec<Sexp> {
+        block.iter().flat_map(|instruction| self.compile_instruction(program, &instruction.value)).collect()
+    }
+
+    fn compile_instruction(&mut self, program: &Program, instruction: &Instruction) -> Vec<Sexp> {
+        match instruction {
+            Instruction::If(condition, then_block, else_block) => {
+                let condition = self.compile_condition(program, condition);
+                let then_code = self.compile_block(program, then_block);
+                let else_code = self.compile_block(program, else_block);
+
+                let mut items = vec![condition, Sexp::list("then", then_code)];
+                if !else_code.is_empty() {
+                    items.push(Sexp::list("else", else_code));
+                }
+                vec![Sexp::list("if", items)]
+            }
+            Instruction::While(condition, block, _) => {
+                let exit_label = self.new_label("exit");
+                let loop_label = self.new_label("loop");
+
+                let condition = self.compile_condition(program, condition);
+                let mut body = vec![
+                    Sexp::Atom(loop_label.clone()),
+                    Sexp::list("br_if", vec![Sexp::Atom(exit_label.clone()), Sexp::list("i32.eqz", vec![condition])]),
+                ];
+                body.extend(self.compile_block(program, block));
+                body.push(Sexp::list("br", vec![Sexp::Atom(loop_label)]));
+
+                vec![Sexp::list("block", vec![Sexp::Atom(exit_label), Sexp::list("loop", body)])]
+            }
+            Instruction::Push(slot, expression) => self.compile_push(program, *slot, expression),
+            Instruction::Set(slot, expression) => {
+                let expression = self.compile_expression(program, expression);
+                vec![global_set(&program.variable(*slot).name, expression)]
+            }
+            Instruction::Pop(slot) => {
+                let variable = program.variable(*slot);
+                let name = &variable.name;
+                let history = Self::history(name);
+
+                let delta = Sexp::list("call", vec![Sexp::atom("$rimp.delta"), global_get(&history)]);
+                let value = match variable.variable_type {
+                    Type::Integer => Sexp::list("i32.sub", vec![global_get(name), delta]),
+                    Type::Float => Sexp::list("f32.reinterpret_i32", vec![delta]),
+                    Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                    Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+                };
+
+                vec![
+                    global_set(name, value),
+                    global_set(&history, Sexp::list("call", vec![Sexp::atom("$rimp.pop"), global_get(&history)])),
+                ]
+            }
+        }
+    }
+
+    fn compile_push(&mut self, program: &Program, slot: Slot, expression: &Expression) -> Vec<Sexp> {
+        let expression = self.compile_expression(program, expression);
+        let variable = program.variable(slot);
+        let name = &variable.name;
+        let history = Self::history(name);
+
+        // the new value is kept in a local until the history entry has been pushed
+        let (local, delta) = match variable.variable_type {
+            Type::Integer => (
+                "$int",
+                Sexp::list("i32.sub", vec![Sexp::list("local.get", vec![Sexp::atom("$int")]), global_get(name)]),
+            ),
+            Type::Float => ("$float", Sexp::list("i32.reinterpret_f32", vec![global_get(name)])),
+            Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+            Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+        };
+
+        vec![
+            Sexp::list("local.set", vec![Sexp::atom(local), expression]),
+            global_set(&history, Sexp::list("call", vec![Sexp::atom("$rimp.push"), delta, global_get(&history)])),
+            global_set(name, Sexp::list("local.get", vec![Sexp::atom(local)])),
+        ]
+    }
+
+    fn compile_expression(&mut self, program: &Program, expression: &Expression) -> Sexp {
+        match expression {
+            Expression::Variable(slot, _) => global_get(&program.variable(*slot).name),
+            Expression::Integer(value) => i32_const(*value),
+            Expression::Float(value) => f32_const(value.into_inner()),
+            Expression::Convert(target, operand) => convert(self.compile_expression(program, operand), target),
+            Expression::Negation(Type::Integer, operand) => {
+                Sexp::list("i32.sub", vec![i32_const(0), self.compile_expression(program, operand)])
+            }
+            Expression::Negation(Type::Float, operand) => Sexp::list("f32.neg", vec![self.compile_expression(program, operand)]),
+            Expression::Operation(operation_type, operator, left, right) => {
+                let lhs = self.compile_expression(program, left);
+                let rhs = self.compile_expression(program, right);
+
+                let instruction = match (operator, operation_type) {
+                    (ArithmeticOperator::Addition, Type::Integer) => "i32.add",
+                    (ArithmeticOperator::Subtraction, Type::Integer) => "i32.sub",
+                    (ArithmeticOperator::Multiplication, Type::Integer) => "i32.mul",
+                    // i32.div_s traps on the smallest int divided by -1, which wraps on the other backends
+                    (ArithmeticOperator::Division, Type::Integer) => {
+                        return Sexp::list("call", vec![Sexp::atom("$rimp.div"), lhs, rhs]);
+                    }
+                    (ArithmeticOperator::Addition, Type::Float) => "f32.add",
+                    (ArithmeticOperator::Subtraction, Type::Float) => "f32.sub",
+                    (ArithmeticOperator::Multiplication, Type::Float) => "f32.mul",
+                    (ArithmeticOperator::Division, Type::Float) => "f32.div",
+                    (ArithmeticOperator::Exponentiation, Type::Integer) => {
+                        return Sexp::list("call", vec![Sexp::atom("$rimp.pow"), lhs, rhs]);
+                    }
+                    (ArithmeticOperator::Exponentiation, Type::Float) => {
+                        self.uses_float_pow = true;
+                        return Sexp::list("call", vec![Sexp::atom("$rimp.pow_f32"), lhs, rhs]);
+                    }
+                    (_, Type::BigInteger) => unreachable!("bigints are only compiled by the jvm backend"),
+                    (_, Type::Rational) => unreachable!("rationals are only interpreted, see IR::lower"),
+                };
+
+                Sexp::list(instruction, vec![lhs, rhs])
+            }
+            _ => unreachable!("bigints are only compiled by the jvm backend"),
+        }
+    }
+
+    // conditions leave 0 or 1 on the stack, both sides of && and || are evaluated as in the interpreter
+    fn compile_condition(&mut self, program: &Program, condition: &Condition) -> Sexp {
+        match condition {
+            Condition::Not(operand) => Sexp::list("i32.eqz", vec![self.compile_condition(program, operand)]),
+            Condition::And(left, right) => {
+                let lhs = self.compile_condition(program, left);
+                let rhs = self.compile_condition(program, right);
+                Sexp::list("i32.and", vec![lhs, rhs])
+            }
+            Condition::Or(left, right) => {
+                let lhs = self.compile_condition(program, left);
+                let rhs = self.compile_condition(program, right);
+                Sexp::list("i32.or", vec![lhs, rhs])
+            }
+            Condition::Comparison(comparison_type, operator, left, right) => {
+                let lhs = self.compile_expression(program, left);
+                let rhs = self.compile_expression(program, right);
+
+                let instruction = match (operator, comparison_type) {
+                    (RelationOperator::Equal, Type::Integer) => "i32.eq",
+                    (RelationOperator::NotEqual, Type::Integer) => "i32.ne",
+                    (RelationOperator::LessThan, Type::Integer) => "i32.lt_s",
+                    (RelationOperator::GreaterThan, Type::Integer) => "i32.gt_s",
+                    (RelationOperator::Equal, Type::Float) => "f32.eq",
+                    (RelationOperator::NotEqual, Type::Float) => "f32.ne",
+                    (RelationOperator::LessThan, Type::Float) => "f32.lt",
+                    (RelationOperator::GreaterThan, Type::Float) => "f32.gt",
+                    (_, Type::BigInteger) => unreachable!("bigints are only compiled by the jvm backend"),
+                    (_, Type::Rational) => unreachable!("rationals are only interpreted, see IR::lower"),
+                };
+
+                Sexp::list(instruction, vec![lhs, rhs])
+            }
+        }
+    }
+}
+
+impl Backend for WATCompiler {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
+        let mut compiler = WATCompiler::new();
+        compiler._compile(&program)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod code_gen;
+
+#[cfg(test)]
+mod checker;
+#[cfg(test)]
+mod tests;
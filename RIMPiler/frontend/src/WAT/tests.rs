@@ -0,0 +1,109 @@
+use super::checker::{parse, Instance, Module};
+use super::code_gen::WATCompiler;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::interpreter::memory_store::Value;
+use super::super::fixtures::{self, inverted_program, EXAMPLES};
+use super::super::AST::Program;
+use super::super::Backend;
+
+fn example(name: &str) -> Program {
+    inverted_program(&fixtures::example(name))
+}
+
+#[test]
+fn well_formed() {
+    for name in EXAMPLES {
+        let wat = WATCompiler::compile(&example(name));
+        let module = Module::new(&parse(&wat).unwrap()).unwrap();
+        assert_eq!(module.validate(), Ok(()), "{}", name);
+    }
+}
+
+#[test]
+fn checker_rejects_malformed_modules() {
+    let wat = WATCompiler::compile(&inverted_program("int n = 2; n = 2; float x = n * 1.5;"));
+    assert_eq!(Module::new(&parse(&wat).unwrap()).unwrap().validate(), Ok(()));
+
+    // unbalanced
+    assert!(parse(&wat[..wat.len() - 2]).is_err());
+
+    // an f32 where an i32 is expected
+    let mismatched = wat.replace("(local.set $int (i32.const 2))", "(local.set $int (f32.const 2.0))");
+    assert_ne!(mismatched, wat);
+    assert!(Module::new(&parse(&mismatched).unwrap()).unwrap().validate().is_err());
+
+    // an undefined global
+    let undefined = wat.replace("(global.get $n)", "(global.get $m)");
+    assert_ne!(undefined, wat);
+    assert!(Module::new(&parse(&undefined).unwrap()).unwrap().validate().is_err());
+}
+
+#[test]
+fn readable_module() {
+    let wat = WATCompiler::compile(&inverted_program("int n = 2; while n > 0 do { n = n / 2; };"));
+
+    assert!(wat.starts_with("(module\n  (import \"env\" \"print\" (func $rimp.print (param i32 i32 i32 f64)))\n"));
+    assert!(wat.contains("  (export \"memory\" (memory $memory))\n"));
+    assert!(wat.contains("  (data (i32.const 8) \"n\" \"generated_name_semantic_transformer0\")\n"));
+    assert!(wat.contains("  (global $n (mut i32) (i32.const 0))\n  (global $n.history (mut i32) (i32.const 0))\n"));
+    assert!(wat.contains("(br_if $exit0 (i32.eqz (i32.gt_s (global.get $n) (i32.const 0))))"));
+    assert!(wat.contains("(call $rimp.print (i32.const 8) (i32.const 1) (i32.const 0) (f64.convert_i32_s (global.get $n)))"));
+    assert!(!wat.contains("$rimp.pow_f32"));
+}
+
+#[test]
+fn matches_interpreter() {
+    for name in EXAMPLES {
+        let inverted = example(name);
+
+        let mut interpreter = InterpreterEngine::new();
+        interpreter.interpret(&inverted).unwrap();
+
+        let wat = WATCompiler::compile(&inverted);
+        let module = Module::new(&parse(&wat).unwrap()).unwrap();
+        let mut instance = Instance::new(&module, 10_000_000);
+        instance.run("main").unwrap();
+
+        assert!(!instance.printed.is_empty(), "{}", name);
+        for (variable, kind, value) in &instance.printed {
+            match interpreter.get_result(variable).unwrap().get() {
+                Value::Integer(expected) => {
+                    assert_eq!(*kind, 0, "{}: {}", name, variable);
+                    assert_eq!(*value, expected as f64, "{}: {}", name, variable);
+                }
+                Value::Float(expected) => {
+                    assert_eq!(*kind, 1, "{}: {}", name, variable);
+                    assert_eq!(*value as f32, expected, "{}: {}", name, variable);
+                }
+                Value::BigInteger(_) => unreachable!("bigints are only compiled by the jvm backend"),
+                Value::Rational(_) => unreachable!("rationals are only interpreted, see IR::lower"),
+            }
+        }
+
+        // the backward program pops every history entry, so the stack is back where it started
+        let start = wat.split("(global $rimp.stack (mut i32) (i32.const ").nth(1).unwrap();
+        let start: i32 = start[..start.find(')').unwrap()].parse().unwrap();
+        let declarations = instance.printed.len() as i32;
+        assert_eq!(instance.globals["$rimp.stack"].i32(), start + 8 * declarations, "{}", name);
+    }
+}
+
+#[test]
+fn wraps_like_interpreter() {
+    // i32.div_s traps on the smallest int divided by -1, so division goes through $rimp.div
+    let inverted = inverted_program("int smallest = 0 - 2147483647 - 1; int quotient = smallest / -1; int power = 3 ^ 21; int sum = 2147483647 + 1; int negated = -smallest;");
+
+    let mut interpreter = InterpreterEngine::new();
+    interpreter.interpret(&inverted).unwrap();
+
+    let wat = WATCompiler::compile(&inverted);
+    let module = Module::new(&parse(&wat).unwrap()).unwrap();
+    assert_eq!(module.validate(), Ok(()));
+    let mut instance = Instance::new(&module, 10_000);
+    instance.run("main").unwrap();
+
+    for (variable, _, value) in &instance.printed {
+        assert_eq!(Value::Integer(*value as i32), interpreter.get_result(variable).unwrap().get(), "{}", variable);
+    }
+    assert_eq!(instance.printed.len(), 5);
+}
@@ -0,0 +1,297 @@
+use super::super::AST;
+use super::super::IR::lower::lower;
+use super::super::IR::{ArithmeticOperator, Block, Condition, Expression, Instruction, Program, RelationOperator, Type};
+use super::super::Backend;
+
+/*
+Compiles a RIMP program into x86-64 assembly for the GNU assembler, a static Linux executable that needs nothing
+but as and ld.
+
+Every variable is a 4 byte slot in .data, holding an int or the bits of a float. The history of every variable
+shares one stack, mmap'd when the program starts, of (variable, difference) pairs, or (variable, old value) for
+floats, as subtracting the difference back off a float can round: assignments push onto it and reverse
+assignments pop the entry back off, which is always on top because the backward program undoes the assignments
+in the opposite order. At the reverse point every variable is printed in the same format as
+RIMPInt/RIMPFloat print, through a buffer that is written to stdout with write(2).
+
+Expressions leave an int in %eax or a float in %xmm0, and the left operand of a binary operator waits on the
+machine stack while the right one is evaluated. The runtime in data/main.s handles everything longer than a few
+instructions: division, powers, converting floats to ints, the history and printing.
+*/
+
+const INDENT: &str = "        ";
+
+// the bit patterns of 10^-60 to 10^60 as doubles, which rimp_put_float scales by
+fn powers_of_ten() -> String {
+    (-60..=60)
+        .map(|exponent: i32| {
+            let power = format!("1e{}", exponent).parse::<f64>().unwrap();
+            format!("{}.quad 0x{:016x}        # 1e{}\n", INDENT, power.to_bits(), exponent)
+        })
+        .collect()
+}
+
+// pushes the value of an expression onto the machine stack
+fn push(expression_type: &Type) -> &'static str {
+    match expression_type {
+        Type::Integer => "pushq %rax\n",
+        Type::Float => "movd %xmm0, %eax\npushq %rax\n",
+        Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+        Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct X86Compiler {
+    // the bits of each float literal, float_<n> is the nth
+    floats: Vec<u32>,
+    labels: usize,
+}
+
+impl X86Compiler {
+    pub fn new() -> Self {
+        X86Compiler {
+            floats: Vec::new(),
+            labels: 0,
+        }
+    }
+
+    pub fn _compile(&mut self, program: &Program, source_file: &str) -> String {
+        // the history of every variable starts with 0, a variable's index into the history is its slot
+        let mut content = String::new();
+        for slot in 0..program.variables.len() {
+            content.push_str(&format!("movl ${}, %edi\nxorl %esi, %esi\ncall rimp_push\n", slot));
+        }
+
+        content.push_str("\n# forward program\n");
+        content.push_str(&self.compile_block(program, &program.forward));
+        content.push_str("\n# reverse point\n");
+        for (slot, variable) in program.variables.iter().enumerate() {
+            let printer = match variable.variable_type {
+                Type::Integer => "rimp_print_int",
+                Type::Float => "rimp_print_float",
+                Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+            };
+            content.push_str(&format!(
+                "leaq name_{}(%rip), %rdi\nmovl ${}, %esi\nmovl var_{}(%rip), %edx\ncall {}\n",
+                variable.name, slot, variable.name, printer
+            ));
+        }
+        content.push_str("\n# backward program\n");
+        content.push_str(&self.compile_block(program, &program.backward));
+
+        let code = content
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    String::from("\n")
+                } else if line.ends_with(':') {
+                    format!("{}\n", line)
+                } else {
+                    format!("{}{}\n", INDENT, line)
+                }
+            })
+            .collect::<String>();
+
+        let mut data = String::new();
+        for variable in &program.variables {
+            data.push_str(&format!("var_{}:\n{}.long 0\n", variable.name, INDENT));
+        }
+        for (index, bits) in self.floats.iter().enumerate() {
+            data.push_str(&format!("float_{}:\n{}.long 0x{:08x}        # {:?}\n", index, INDENT, bits, f32::from_bits(*bits)));
+        }
+        for variable in &program.variables {
+            data.push_str(&format!("name_{}:\n{}.asciz \"{}\"\n", variable.name, INDENT, variable.name));
+        }
+
+        let file = include_str!("../../../../data/main.s");
+
+        file.replace("<source>", source_file)
+            .replace("<data>\n", &data)
+            .replace("<powers>\n", &powers_of_ten())
+            .replace("<code>\n", &code)
+    }
+
+    fn label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels
+    }
+
+    fn compile_block(&mut self, program: &Program, block: &Block) -> String {
+        block.iter().map(|instruction| self.compile_instruction(program, &instruction.value)).collect()
+    }
+
+    fn compile_instruction(&mut self, program: &Program, instruction: &Instruction) -> String {
+        match instruction {
+            Instruction::If(condition, then_block, else_block) => {
+                let label = self.label();
+                let condition = self.compile_condition(program, condition);
+                let then_code = self.compile_block(program, then_block);
+                let else_code = self.compile_block(program, else_block);
+
+                format!(
+                    "{}testl %eax, %eax\njz .Lif{}_else\n{}jmp .Lif{}_end\n.Lif{}_else:\n{}.Lif{}_end:\n",
+                    condition, label, then_code, label, label, else_code, label
+                )
+            }
+            Instruction::While(condition, block, _) => {
+                let label = self.label();
+                let condition = self.compile_condition(program, condition);
+                let block_code = self.compile_block(program, block);
+
+                format!(
+                    ".Lwhile{}_test:\n{}testl %eax, %eax\njz .Lwhile{}_end\n{}jmp .Lwhile{}_test\n.Lwhile{}_end:\n",
+                    label, condition, label, block_code, label, label
+                )
+            }
+            // stores the new value and pushes the difference from the old one, or the old float
+            Instruction::Push(slot, expression) => {
+                let name = &program.variable(*slot).name;
+                let mut code = format!("# assign {}\n{}", name, self.compile_expression(program, expression));
+                match expression.expression_type() {
+                    Type::Integer => {
+                        code.push_str(&format!("movl %eax, %esi\nsubl var_{}(%rip), %esi\nmovl %eax, var_{}(%rip)\n", name, name));
+                    }
+                    Type::Float => code.push_str(&format!("movl var_{}(%rip), %esi\nmovss %xmm0, var_{}(%rip)\n", name, name)),
+                    Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                    Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+                }
+                code.push_str(&format!("movl ${}, %edi\ncall rimp_push\n", slot));
+                code
+            }
+            // stores the new value, leaving the history as it is
+            Instruction::Set(slot, expression) => {
+                let name = &program.variable(*slot).name;
+                let mut code = format!("# set {}\n{}", name, self.compile_expression(program, expression));
+                match expression.expression_type() {
+                    Type::Integer => code.push_str(&format!("movl %eax, var_{}(%rip)\n", name)),
+                    Type::Float => code.push_str(&format!("movss %xmm0, var_{}(%rip)\n", name)),
+                    Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                    Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+                }
+                code
+            }
+            Instruction::Pop(slot) => {
+                let variable = program.variable(*slot);
+                let name = &variable.name;
+                let mut code = format!("# unassign {}\nmovl ${}, %edi\ncall rimp_pop\n", name, slot);
+                match variable.variable_type {
+                    Type::Integer => code.push_str(&format!("subl %eax, var_{}(%rip)\n", name)),
+                    Type::Float => code.push_str(&format!("movl %eax, var_{}(%rip)\n", name)),
+                    Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+                    Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+                }
+                code
+            }
+        }
+    }
+
+    // evaluates both operands, which have the same type. ints end up in %edi and %esi, floats in %xmm0 and %xmm1
+    fn compile_operands(&mut self, program: &Program, left: &Expression, right: &Expression) -> String {
+        let operand_type = left.expression_type();
+        let lhs = self.compile_expression(program, left);
+        let rhs = self.compile_expression(program, right);
+        let mut code = format!("{}{}{}", lhs, push(&operand_type), rhs);
+        match operand_type {
+            Type::Integer => code.push_str("movl %eax, %esi\npopq %rdi\n"),
+            Type::Float => code.push_str("movaps %xmm0, %xmm1\npopq %rax\nmovd %eax, %xmm0\n"),
+            Type::BigInteger => unreachable!("bigints are only compiled by the jvm backend"),
+            Type::Rational => unreachable!("rationals are only interpreted, see IR::lower"),
+        }
+        code
+    }
+
+    // leaves an int in %eax or a float in %xmm0
+    fn compile_expression(&mut self, program: &Program, expression: &Expression) -> String {
+        match expression {
+            Expression::Variable(slot, Type::Integer) => format!("movl var_{}(%rip), %eax\n", program.variable(*slot).name),
+            Expression::Variable(slot, Type::Float) => format!("movss var_{}(%rip), %xmm0\n", program.variable(*slot).name),
+            Expression::Integer(value) => format!("movl ${}, %eax\n", value),
+            Expression::Float(value) => {
+                let bits = value.into_inner().to_bits();
+                let index = match self.floats.iter().position(|float| *float == bits) {
+                    Some(index) => index,
+                    None => {
+                        self.floats.push(bits);
+                        self.floats.len() - 1
+                    }
+                };
+                format!("movss float_{}(%rip), %xmm0\n", index)
+            }
+            Expression::Convert(Type::Float, operand) => format!("{}cvtsi2ssl %eax, %xmm0\n", self.compile_expression(program, operand)),
+            Expression::Convert(Type::Integer, operand) => format!("{}call rimp_to_int\n", self.compile_expression(program, operand)),
+            Expression::Negation(Type::Integer, operand) => format!("{}negl %eax\n", self.compile_expression(program, operand)),
+            Expression::Negation(Type::Float, operand) => {
+                format!("{}movd %xmm0, %eax\nxorl $0x80000000, %eax\nmovd %eax, %xmm0\n", self.compile_expression(program, operand))
+            }
+            Expression::Operation(operation_type, operator, left, right) => {
+                let code = self.compile_operands(program, left, right);
+
+                let operation = match (operation_type, operator) {
+                    (Type::Integer, ArithmeticOperator::Addition) => "movl %edi, %eax\naddl %esi, %eax\n",
+                    (Type::Integer, ArithmeticOperator::Subtraction) => "movl %edi, %eax\nsubl %esi, %eax\n",
+                    (Type::Integer, ArithmeticOperator::Multiplication) => "movl %edi, %eax\nimull %esi, %eax\n",
+                    (Type::Integer, ArithmeticOperator::Division) => "call rimp_div\n",
+                    (Type::Integer, ArithmeticOperator::Exponentiation) => "call rimp_pow\n",
+                    (Type::Float, ArithmeticOperator::Addition) => "addss %xmm1, %xmm0\n",
+                    (Type::Float, ArithmeticOperator::Subtraction) => "subss %xmm1, %xmm0\n",
+                    (Type::Float, ArithmeticOperator::Multiplication) => "mulss %xmm1, %xmm0\n",
+                    (Type::Float, ArithmeticOperator::Division) => "divss %xmm1, %xmm0\n",
+                    (Type::Float, ArithmeticOperator::Exponentiation) => "call rimp_powf\n",
+                    (Type::BigInteger, _) => unreachable!("bigints are only compiled by the jvm backend"),
+                    (Type::Rational, _) => unreachable!("rationals are only interpreted, see IR::lower"),
+                };
+
+                format!("{}{}", code, operation)
+            }
+            _ => unreachable!("bigints are only compiled by the jvm backend"),
+        }
+    }
+
+    // leaves 1 in %eax if the condition holds and 0 if not
+    fn compile_condition(&mut self, program: &Program, condition: &Condition) -> String {
+        match condition {
+            Condition::Not(operand) => format!("{}xorl $1, %eax\n", self.compile_condition(program, operand)),
+            // both sides are always evaluated, as the interpreter does
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let lhs = self.compile_condition(program, left);
+                let rhs = self.compile_condition(program, right);
+
+                let operation = match condition {
+                    Condition::And(_, _) => "andl",
+                    _ => "orl",
+                };
+
+                format!("{}pushq %rax\n{}popq %rcx\n{} %ecx, %eax\n", lhs, rhs, operation)
+            }
+            Condition::Comparison(comparison_type, operator, left, right) => {
+                let code = self.compile_operands(program, left, right);
+
+                // a comparison with NaN is only ever true for !=
+                let comparison = match (comparison_type, operator) {
+                    (Type::Integer, RelationOperator::Equal) => "cmpl %esi, %edi\nsete %al\n",
+                    (Type::Integer, RelationOperator::NotEqual) => "cmpl %esi, %edi\nsetne %al\n",
+                    (Type::Integer, RelationOperator::LessThan) => "cmpl %esi, %edi\nsetl %al\n",
+                    (Type::Integer, RelationOperator::GreaterThan) => "cmpl %esi, %edi\nsetg %al\n",
+                    (Type::Float, RelationOperator::Equal) => "ucomiss %xmm1, %xmm0\nsete %al\nsetnp %cl\nandb %cl, %al\n",
+                    (Type::Float, RelationOperator::NotEqual) => "ucomiss %xmm1, %xmm0\nsetne %al\nsetp %cl\norb %cl, %al\n",
+                    (Type::Float, RelationOperator::LessThan) => "ucomiss %xmm0, %xmm1\nseta %al\n",
+                    (Type::Float, RelationOperator::GreaterThan) => "ucomiss %xmm1, %xmm0\nseta %al\n",
+                    (Type::BigInteger, _) => unreachable!("bigints are only compiled by the jvm backend"),
+                    (Type::Rational, _) => unreachable!("rationals are only interpreted, see IR::lower"),
+                };
+
+                format!("{}{}movzbl %al, %eax\n", code, comparison)
+            }
+        }
+    }
+}
+
+impl Backend for X86Compiler {
+    fn compile(program: &AST::Program) -> String {
+        let program = lower(program).unwrap();
+        let mut compiler = X86Compiler::new();
+        compiler._compile(&program, "main.rimp")
+    }
+}
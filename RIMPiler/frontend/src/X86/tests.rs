@@ -0,0 +1,130 @@
+use std::process::Command;
+use super::code_gen::X86Compiler;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::fixtures::{build, example, folder, installed, inverted_program, matches_printed, run, EXAMPLES};
+use super::super::Backend;
+
+#[test]
+fn readable_source() {
+    let inverted = inverted_program("int n = 2; float x = 0.5; while n > 0 do { n = n - 1; x = x * 2.5; };");
+
+    let source = X86Compiler::compile(&inverted);
+
+    assert!(source.starts_with("# Generated by RIMPiler from main.rimp\n"));
+    assert!(source.contains("var_n:\n        .long 0\nvar_x:\n        .long 0\n"));
+    assert!(source.contains("float_0:\n        .long 0x3f000000        # 0.5\n"));
+    assert!(source.contains("name_x:\n        .asciz \"x\"\n"));
+    assert!(source.contains("        # forward program\n        # set n\n        movl $2, %eax\n        movl %eax, var_n(%rip)\n"));
+    assert!(source.contains(".Lwhile1_test:\n"));
+    assert!(source.contains("        mulss %xmm1, %xmm0\n"));
+    assert!(source.contains("        # reverse point\n        leaq name_n(%rip), %rdi\n        movl $0, %esi\n"));
+    assert!(source.contains("        call rimp_print_float\n"));
+    assert!(source.contains("        # backward program\n"));
+    assert!(source.contains("        # unassign x\n        movl $1, %edi\n        call rimp_pop\n"));
+    assert!(source.contains("        .quad 0x3ff0000000000000        # 1e0\n"));
+}
+
+// assembles, links and runs the program, returning the exit status, stdout and stderr
+fn run_x86(name: &str, source: &str) -> (bool, String, String) {
+    let folder = folder("x86", name, "main.s", source);
+    build(&folder, "as", &["-o", "main.o", "main.s"]);
+    build(&folder, "ld", &["-o", "main", "main.o"]);
+
+    let output = run(&folder, &mut Command::new(folder.join("main")));
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn matches_interpreter() {
+    if !installed("as", "--version") {
+        println!("as not found, skipping");
+        return;
+    }
+
+    for name in EXAMPLES {
+        let inverted = inverted_program(&example(name));
+
+        let mut interpreter = InterpreterEngine::new();
+        interpreter.interpret(&inverted).unwrap();
+
+        let (success, stdout, stderr) = run_x86(name, &X86Compiler::compile(&inverted));
+        assert!(success, "{}: {}", name, stderr);
+        matches_printed(name, &stdout, &interpreter);
+    }
+}
+
+#[test]
+fn java_float_format() {
+    if !installed("as", "--version") {
+        println!("as not found, skipping");
+        return;
+    }
+
+    // assigned inside an if, so each history keeps the 0.0 the variable held before
+    let inverted = inverted_program(&format!(r#"if 0 < 1 then {{
+    float a = 7;
+    float b = 0.1;
+    float c = 12345678;
+    float d = 0.0001;
+    float e = 0 - 2.5;
+    float f = 100;
+    float g = 0.001;
+    float h = 9999999;
+    float i = {}.0;
+    float j = {};
+    float k = 0.0 * (0 - 1);
+    float l = 1 / (a - a);
+    float m = l - l;
+}} else {{
+    skip;
+}};"#, f32::MAX as f64, format_args!("{:.60}", f32::MIN_POSITIVE as f64)));
+
+    let (success, stdout, stderr) = run_x86("float_format", &X86Compiler::compile(&inverted));
+    assert!(success, "{}", stderr);
+
+    assert!(stdout.contains("a: 7.0\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("b: 0.1\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("c: 1.2345678E7\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("d: 1.0E-4\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("e: -2.5\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("f: 100.0\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("g: 0.001\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("h: 9999999.0\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("i: 3.4028235E38\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("j: 1.1754944E-38\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("k: -0.0\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("l: Infinity\t [0.0 0.0 ]\n"));
+    assert!(stdout.contains("m: NaN\t [0.0 0.0 ]\n"));
+}
+
+#[test]
+fn runtime_errors() {
+    if !installed("as", "--version") {
+        println!("as not found, skipping");
+        return;
+    }
+
+    let programs = [
+        ("division", "int a = 1; int b = a / (a - 1);", "Error: division by zero\n"),
+        ("power", "int a = 2; int b = a ^ (a - 2);", "Error: cannot raise to a power that is not positive\n"),
+    ];
+    for (name, program, message) in programs {
+        let (success, stdout, stderr) = run_x86(name, &X86Compiler::compile(&inverted_program(program)));
+        assert!(!success, "{}", name);
+        assert!(stdout.is_empty(), "{}", name);
+        assert_eq!(stderr, message, "{}", name);
+    }
+
+    // integers wrap around rather than trapping
+    let inverted = inverted_program("int a = 2147483647; int b = a + 1; int c = (0 - 2147483647 - 1) / (0 - 1); int d = 3 ^ 40;");
+    let (success, stdout, stderr) = run_x86("wrapping", &X86Compiler::compile(&inverted));
+    assert!(success, "{}", stderr);
+    assert!(stdout.contains("b: -2147483648\t"));
+    assert!(stdout.contains("c: -2147483648\t"));
+    assert!(stdout.contains(&format!("d: {}\t", 3i32.wrapping_pow(40))));
+}
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
 use crate::abstract_machine::stack::{Stack, ResultStack, ControlStack, BackStack, Builder, C, P, Lab, R, Num, Type, Var, BinOp};
 use crate::abstract_machine::store::Store;
 use crate::AST::Program;
+use crate::overflow::Overflow;
+use crate::interpreter::error::RuntimeError;
 use crate::interpreter::memory_store::{MemoryStoreElement, MemoryStoreTrait, Value};
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -209,6 +213,29 @@ pub struct Engine {
     pub store: Store,
 
     pub while_condition: HashMap<usize, P>,
+
+    pub overflow: Overflow,
+
+    // no limit when None, see check_budget
+    pub max_steps: Option<usize>,
+    pub max_history: Option<usize>,
+    // the rules applied since the machine started or was last reversed
+    pub steps: usize,
+}
+
+// the machine has no source locations, so an error is reported with the expression it happened in
+fn arithmetic_error(error: RuntimeError, expression: P) -> String {
+    format!("{}, in {}", error.message(), expression)
+}
+
+// n as a value of the type it's assigned to, an infinite float can't be a bigint or a rational
+fn converted(n: &Num, r#type: &Type) -> Result<Value, RuntimeError> {
+    Ok(match r#type {
+        Type::Int => Value::Integer(n.clone().into_int()),
+        Type::Float => Value::Float(n.clone().into_float()),
+        Type::BigInt => Value::BigInteger(n.clone().into_bigint()?),
+        Type::Rational => Value::Rational(n.clone().into_rational()?)
+    })
 }
 
 impl Engine {
@@ -220,9 +247,28 @@ impl Engine {
             result_stack: Stack::new(),
             store: Store::new(),
             while_condition: Self::while_map(cs),
+            overflow: Overflow::default(),
+            max_steps: None,
+            max_history: None,
+            steps: 0,
         }
     }
 
+    pub fn overflow(mut self, overflow: Overflow) -> Engine {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn max_steps(mut self, max_steps: Option<usize>) -> Engine {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn max_history(mut self, max_history: Option<usize>) -> Engine {
+        self.max_history = max_history;
+        self
+    }
+
     pub fn get_control_stack(&self) -> &ControlStack {
         &self.control_stack
     }
@@ -244,8 +290,15 @@ impl Engine {
         rule
     }
 
-    pub fn step(&mut self) {
+    // an arithmetic error leaves the machine as it was before the step, see BinOp::apply
+    pub fn step(&mut self) -> Result<(), String> {
         let rule = self.check_rule();
+        self.apply(rule)?;
+        self.steps += 1;
+        Ok(())
+    }
+
+    fn apply(&mut self, rule: Rules) -> Result<(), String> {
         match rule {
             // Expressions
             Rules::Num => {
@@ -275,8 +328,8 @@ impl Engine {
                         match value {
                             Some(MemoryStoreElement::Integer(i)) => R::Value(Num::Int(i.get())),
                             None => {
-                                // assign initial value (0)
-                                self.store.assign(&name, Value::Integer(0));
+                                // assign initial value (0), which isn't undone so it isn't added to the history
+                                self.store.set(&name, Value::Integer(0)).map_err(|error| error.message())?;
                                 R::Value(Num::Int(0))
                             }
                             _ => panic!("Variable Type mismatch")
@@ -290,13 +343,37 @@ impl Engine {
                                 R::Value(Num::Float(v))
                             },
                             None => {
-                                // assign initial value (0)
-                                self.store.assign(&name, Value::Float(0.0));
+                                // assign initial value (0), which isn't undone so it isn't added to the history
+                                self.store.set(&name, Value::Float(0.0)).map_err(|error| error.message())?;
                                 R::Value(Num::Float(NotNan::new(0.0).unwrap()))
                             }
                             _ => panic!("Variable Type mismatch")
                         }
                     }
+                    Type::BigInt => {
+                        let value = self.store.get(&name);
+                        match value {
+                            Some(MemoryStoreElement::BigInteger(i)) => R::Value(Num::BigInt(i.get())),
+                            None => {
+                                // assign initial value (0), which isn't undone so it isn't added to the history
+                                self.store.set(&name, Value::BigInteger(BigInt::zero())).map_err(|error| error.message())?;
+                                R::Value(Num::BigInt(BigInt::zero()))
+                            }
+                            _ => panic!("Variable Type mismatch")
+                        }
+                    }
+                    Type::Rational => {
+                        let value = self.store.get(&name);
+                        match value {
+                            Some(MemoryStoreElement::Rational(r)) => R::Value(Num::Rational(r.get())),
+                            None => {
+                                // assign initial value (0), which isn't undone so it isn't added to the history
+                                self.store.set(&name, Value::Rational(Rational::zero())).map_err(|error| error.message())?;
+                                R::Value(Num::Rational(Rational::zero()))
+                            }
+                            _ => panic!("Variable Type mismatch")
+                        }
+                    }
                 };
 
                 self.back_stack.push(C::P(P::Rav(var.clone())));
@@ -342,13 +419,21 @@ impl Engine {
             },
             Rules::BinOp => {
                 // (op · c, n2 · n1 · r, m, E2' · E1' · exp · E1' · E2' · b) −→ (c, n · r, m, (E1 op' E2 ) · b)
-                // where n = n1 op n2
+                // where n = n1 op n2, which is worked out before anything is popped, in case it fails
+                let operands = self.result_stack.peek_n(2);
+                let op = self.control_stack.peek().unwrap().unwrap_lab().unwrap_binop();
+                let n = op.apply(operands[1].unwrap_value().clone(), operands[0].unwrap_value().clone(), self.overflow);
+                let n = n.map_err(|error| {
+                    let expressions = self.back_stack.peek_n(2);
+                    let (left, right) = (expressions[1].unwrap_p().unwrap_rexp(), expressions[0].unwrap_p().unwrap_rexp());
+                    arithmetic_error(error, P::BinOp(Box::new(left.clone()), Box::new(right.clone()), op.clone()))
+                })?;
+
                 let op = self.control_stack.pop().unwrap();
                 let op = op.unwrap_lab().unwrap_binop();
-                let n2 = self.result_stack.pop().unwrap();
-                let n2 = n2.unwrap_value();
-                let n1 = self.result_stack.pop().unwrap();
-                let n1 = n1.unwrap_value();
+                // n2 and n1, which n was worked out from
+                self.result_stack.pop();
+                self.result_stack.pop();
 
                 let E2_ = self.back_stack.pop().unwrap();
                 let E1_ = self.back_stack.pop().unwrap();
@@ -360,7 +445,6 @@ impl Engine {
                 let E2_ = self.back_stack.pop().unwrap();
                 let E2_ = E2_.unwrap_p().unwrap_rexp();
 
-                let n = op.apply(n1.clone(), n2.clone());
                 self.result_stack.push(R::Value(n));
                 self.back_stack.push(C::P(P::Rexp(Box::new(P::BinOp(Box::new(E1_.clone()), Box::new(E2_.clone()), op.clone())))));
             },
@@ -413,20 +497,24 @@ impl Engine {
             },
             Rules::UnOp => {
                 // (op · c, n_ · r, m, E' · unexp · E' · b) −→ (c, n · r, m, (op' E) · b)
-                // where n = op n_
+                // where n = op n_, which is worked out before anything is popped, in case it fails
+                let op = self.control_stack.peek().unwrap().unwrap_lab().unwrap_unop();
+                let n = op.apply(self.result_stack.peek().unwrap().unwrap_value().clone(), self.overflow).map_err(|error| {
+                    let expression = self.back_stack.peek().unwrap().unwrap_p().unwrap_rexp();
+                    arithmetic_error(error, P::UnOp(Box::new(expression.clone()), op.clone()))
+                })?;
+
                 let op = self.control_stack.pop().unwrap();
                 let op = op.unwrap_lab().unwrap_unop();
 
-                let n_ = self.result_stack.pop().unwrap();
-                let n_ = n_.unwrap_value();
+                // n_, which n was worked out from
+                self.result_stack.pop();
 
                 let E_ = self.back_stack.pop().unwrap().unwrap_p().unwrap_rexp();
                 let unexp = self.back_stack.pop().unwrap().unwrap_lab();
                 let E_ = self.back_stack.pop().unwrap();
                 let E_ = E_.unwrap_p().unwrap_rexp();
 
-                let n = op.apply(n_.clone());
-
                 self.result_stack.push(R::Value(n));
                 self.back_stack.push(C::P(P::Rexp(Box::new(P::UnOp(Box::new(E_.clone()), op.clone())))));
             },
@@ -486,16 +574,22 @@ impl Engine {
             Rules::Assign => {
                 // (:= ·c, n2 · n1 · l · r, m, !l' · E' · asgn · E · b) −→
                 // (c, r, m[l |→ (n1 , +(n, m2(l)))], (l =: E) · b)
-                // where n = n1 − n2
+                // where n = n1 − n2, n1 is converted to l's type before anything is popped, in case it fails
+                let top = self.result_stack.peek_n(3);
+                let value = converted(top[1].unwrap_value(), &top[2].unwrap_var().unwrap().0).map_err(|error| {
+                    let expression = self.back_stack.peek_n(4)[3].unwrap_p().clone();
+                    arithmetic_error(error, P::Asgn(top[2].unwrap_var().clone(), Box::new(expression)))
+                })?;
+
                 let asgn = self.control_stack.pop().unwrap();
 
                 let n2 = self.result_stack.pop().unwrap();
                 let n2 = n2.unwrap_value();
-                let n1 = self.result_stack.pop().unwrap();
-                let n1 = n1.unwrap_value();
+                // n1, which value was converted from
+                self.result_stack.pop();
                 let l = self.result_stack.pop().unwrap();
                 let l = l.unwrap_var();
-                let (r#type, name) = l.unwrap();
+                let (_, name) = l.unwrap();
 
                 let l_ = self.back_stack.pop().unwrap();
                 let E_ = self.back_stack.pop().unwrap();
@@ -503,16 +597,7 @@ impl Engine {
                 let E = self.back_stack.pop().unwrap();
                 let E = E.unwrap_p();
 
-                let value = match r#type {
-                    Type::Int => {
-                        Value::Integer(n1.clone().into_int())
-                    }
-                    Type::Float => {
-                        Value::Float(n1.clone().into_float())
-                    }
-                };
-
-                self.store.assign(&name, value);
+                self.store.assign(&name, value).map_err(|error| error.message())?;
 
                 self.back_stack.push(C::P(P::Ngsa(l.clone(), Box::new(E.clone()))));
             },
@@ -539,12 +624,20 @@ impl Engine {
                     MemoryStoreElement::Float(f) => {
                         Value::Float(f.get())
                     }
+                    MemoryStoreElement::BigInteger(i) => {
+                        Value::BigInteger(i.get())
+                    }
+                    MemoryStoreElement::Rational(r) => {
+                        Value::Rational(r.get())
+                    }
                 };
-                self.store.un_assign(&l.unwrap().1, value);
+                self.store.un_assign(&l.unwrap().1, value).map_err(|error| error.message())?;
 
                 let n = match n {
                     MemoryStoreElement::Integer(i) => Num::Int(i.get()),
-                    MemoryStoreElement::Float(f) => Num::Float(NotNan::new(f.get()).unwrap())
+                    MemoryStoreElement::Float(f) => Num::Float(NotNan::new(f.get()).unwrap()),
+                    MemoryStoreElement::BigInteger(i) => Num::BigInt(i.get()),
+                    MemoryStoreElement::Rational(r) => Num::Rational(r.get())
                 };
 
                 self.back_stack.push(C::P(E.clone()));
@@ -554,9 +647,16 @@ impl Engine {
             Rules::NgsaR => {
                 // (asgnr · n · E · b, l · r, m, E· !l · =: · c) −→
                 // (b, r, m[l |→ (m1 (l) + n, +(n, m2 (l))], (l =: E) · c)
+                // n is converted to l's type before anything is popped, in case it fails
+                let l = self.result_stack.peek().unwrap().unwrap_var().clone();
+                let value = converted(self.control_stack.peek_n(2)[1].unwrap_p().unwrap_num(), &l.unwrap().0).map_err(|error| {
+                    let expression = self.back_stack.peek().unwrap().unwrap_p().clone();
+                    arithmetic_error(error, P::Ngsa(l.clone(), Box::new(expression)))
+                })?;
+
                 let asgnr = self.control_stack.pop().unwrap();
-                let n = self.control_stack.pop().unwrap();
-                let n = n.unwrap_p().unwrap_num();
+                // n, which value was converted from
+                self.control_stack.pop();
                 let E = self.control_stack.pop().unwrap();
 
                 let l = self.result_stack.pop().unwrap();
@@ -567,18 +667,9 @@ impl Engine {
                 let l_ = self.back_stack.pop().unwrap();
                 let asgn = self.back_stack.pop().unwrap();
 
-                let (r#type, name) = l.unwrap();
-
-                let value = match r#type {
-                    Type::Int => {
-                        Value::Integer(n.clone().into_int())
-                    }
-                    Type::Float => {
-                        Value::Float(n.clone().into_float())
-                    }
-                };
+                let (_, name) = l.unwrap();
 
-                self.store.assign(&name, value);
+                self.store.assign(&name, value).map_err(|error| error.message())?;
 
                 self.back_stack.push(C::P(P::Ngsa(l.clone(), Box::new(E.clone()))));
             },
@@ -874,9 +965,9 @@ impl Engine {
 
                 // update the variable associated with the loop
                 if self.check_while(i, E) {
-                    self.increment_while_counter(i);
+                    self.increment_while_counter(i)?;
                 } else {
-                    self.decrement_while_counter(i);
+                    self.decrement_while_counter(i)?;
                 }
             },
             Rules::PoolT => {
@@ -1121,16 +1212,42 @@ impl Engine {
                 self.back_stack.push(C::P(E.clone()));
             },
         }
+
+        Ok(())
     }
 
     pub fn reverse(&mut self) {
         std::mem::swap(&mut self.control_stack, &mut self.back_stack);
+        self.steps = 0;
     }
 
     pub fn is_done(&self) -> bool {
         self.control_stack.is_empty()
     }
 
+    // whether the machine can take another step, the back stack grows with every step so max_steps bounds it too
+    pub fn check_budget(&self) -> Result<(), String> {
+        if self.max_steps.is_some_and(|limit| self.steps >= limit) {
+            return Err(format!("Stopped before rule {}, the program didn't finish within {} steps", self.get_next_rule(), self.steps));
+        }
+
+        if let Some(limit) = self.max_history.filter(|limit| self.store.history_len() > *limit) {
+            return Err(format!("Stopped before rule {}, the history grew past {} entries", self.get_next_rule(), limit));
+        }
+
+        Ok(())
+    }
+
+    // steps until the program has finished, or until it runs out of steps or history, or its arithmetic fails
+    pub fn run(&mut self) -> Result<(), String> {
+        while !self.is_done() {
+            self.check_budget()?;
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
     fn check_rule(&self) -> Rules {
         // we only ever need to look at the top 3 elements of the control stack
         let top = self.control_stack.peek_n(3);
@@ -1363,23 +1480,25 @@ impl Engine {
         P::BinOp(Box::new(P::Var(Var::Int(name.clone()))), Box::new(P::Num(Num::Int(0))), BinOp::Gt)
     }
 
-    fn increment_while_counter(&mut self, i: usize) {
+    fn increment_while_counter(&mut self, i: usize) -> Result<(), String> {
         let name = format!("while_counter_{}", i);
         let current = self.store.get(&name);
-        if let Some(MemoryStoreElement::Integer(n)) = current {
-            self.store.assign(&name, Value::Integer(n.get() + 1));
+        let result = if let Some(MemoryStoreElement::Integer(n)) = current {
+            self.store.assign(&name, Value::Integer(n.get() + 1))
         } else {
-            self.store.assign(&name, Value::Integer(1));
-        }
+            self.store.assign(&name, Value::Integer(1))
+        };
+        result.map_err(|error| error.message())
     }
 
-    fn decrement_while_counter(&mut self, i: usize) {
+    fn decrement_while_counter(&mut self, i: usize) -> Result<(), String> {
         let name = format!("while_counter_{}", i);
         let current = self.store.get(&name);
-        if let Some(MemoryStoreElement::Integer(n)) = current {
-            self.store.un_assign(&name, Value::Integer(n.get() - 1));
+        let result = if let Some(MemoryStoreElement::Integer(n)) = current {
+            self.store.un_assign(&name, Value::Integer(n.get() - 1))
         } else {
-            self.store.un_assign(&name, Value::Integer(0));
-        }
+            self.store.un_assign(&name, Value::Integer(0))
+        };
+        result.map_err(|error| error.message())
     }
 }
\ No newline at end of file
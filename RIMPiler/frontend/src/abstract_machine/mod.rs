@@ -1,3 +1,5 @@
 mod stack;
 mod store;
+#[cfg(test)]
+mod tests;
 pub mod engine;
@@ -65,40 +65,74 @@ l is a variable.
 
 use std::fmt::Display;
 use ordered_float::NotNan;
-use crate::AST::{ArithmeticExpression, ArithmeticOperator, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
+use crate::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
 use super::super::post_parse::transformer::transform_if_only;
+use super::super::interpreter::error::RuntimeError;
+use super::super::overflow::Overflow;
+use utilities::debug::Location;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Type {
     Int,
-    Float
+    Float,
+    BigInt,
+    Rational
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Num {
     Int(i32),
-    Float(NotNan<f32>)
+    Float(NotNan<f32>),
+    BigInt(BigInt),
+    Rational(Rational)
 }
 
 impl Num {
     pub fn into_inner(self) -> NotNan<f32> {
         match self {
             Num::Int(n) => NotNan::new(n as f32).unwrap(),
-            Num::Float(n) => n
+            Num::Float(n) => n,
+            Num::BigInt(n) => NotNan::new(n.to_f32()).unwrap(),
+            Num::Rational(n) => NotNan::new(n.to_f32()).unwrap()
         }
     }
 
     pub fn into_float(self) -> f32 {
         match self {
             Num::Int(n) => n as f32,
-            Num::Float(n) => n.into_inner()
+            Num::Float(n) => n.into_inner(),
+            Num::BigInt(n) => n.to_f32(),
+            Num::Rational(n) => n.to_f32()
         }
     }
 
     pub fn into_int(self) -> i32 {
         match self {
             Num::Int(n) => n,
-            Num::Float(n) => n.into_inner() as i32
+            Num::Float(n) => n.into_inner() as i32,
+            Num::BigInt(n) => n.wrapping_i32(),
+            Num::Rational(n) => n.truncate().wrapping_i32()
+        }
+    }
+
+    // an error for an infinite float, like the interpreter's Value::convert
+    pub fn into_bigint(self) -> Result<BigInt, RuntimeError> {
+        match self {
+            Num::Int(n) => Ok(BigInt::from(n)),
+            Num::Float(n) => BigInt::from_f32(n.into_inner()).ok_or_else(|| mismatch(format!("Cannot convert {} to a bigint", n))),
+            Num::BigInt(n) => Ok(n),
+            Num::Rational(n) => Ok(n.truncate())
+        }
+    }
+
+    pub fn into_rational(self) -> Result<Rational, RuntimeError> {
+        match self {
+            Num::Int(n) => Ok(Rational::from(n)),
+            Num::Float(n) => Rational::from_f32(n.into_inner()).ok_or_else(|| mismatch(format!("Cannot convert {} to a rational", n))),
+            Num::BigInt(n) => Ok(Rational::from(n)),
+            Num::Rational(n) => Ok(n)
         }
     }
 }
@@ -107,7 +141,9 @@ impl Display for Num {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Num::Int(n) => write!(f, "{}", n),
-            Num::Float(n) => write!(f, "{}", n)
+            Num::Float(n) => write!(f, "{}", n),
+            Num::BigInt(n) => write!(f, "{}", n),
+            Num::Rational(n) => write!(f, "{}", n)
         }
     }
 }
@@ -115,14 +151,18 @@ impl Display for Num {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Var {
     Int(String),
-    Float(String)
+    Float(String),
+    BigInt(String),
+    Rational(String)
 }
 
 impl Var {
     pub fn from_variable(variable: Variable) -> Var {
         match variable {
             Variable::Integer(name) => Var::Int(name),
-            Variable::Float(name) => Var::Float(name)
+            Variable::Float(name) => Var::Float(name),
+            Variable::BigInteger(name) => Var::BigInt(name),
+            Variable::Rational(name) => Var::Rational(name)
         }
     }
 
@@ -130,6 +170,8 @@ impl Var {
         match self {
             Var::Int(name) => (Type::Int, name.clone()),
             Var::Float(name) => (Type::Float, name.clone()),
+            Var::BigInt(name) => (Type::BigInt, name.clone()),
+            Var::Rational(name) => (Type::Rational, name.clone()),
         }
     }
 }
@@ -138,7 +180,9 @@ impl Display for Var {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Var::Int(v) => write!(f, "{}", v),
-            Var::Float(v) => write!(f, "{}", v)
+            Var::Float(v) => write!(f, "{}", v),
+            Var::BigInt(v) => write!(f, "{}", v),
+            Var::Rational(v) => write!(f, "{}", v)
         }
     }
 }
@@ -199,37 +243,53 @@ impl Display for BinOp {
 }
 
 impl BinOp {
-    pub fn apply(&self, n1: Num, n2: Num) -> Num {
-        match self {
+    // an error when int arithmetic overflows in checked mode, or when dividing by zero or raising to a power that
+    // isn't a positive int, see Overflow::apply
+    pub fn apply(&self, n1: Num, n2: Num, overflow: Overflow) -> Result<Num, RuntimeError> {
+        // a bigint or a rational with a float is applied to floats, a rational with anything else to rationals,
+        // and a bigint with anything else to bigints, an int with a float is applied to floats like the interpreter
+        let (n1, n2) = match (n1, n2) {
+            (Num::Rational(n1), Num::Float(n2)) => (float(n1.to_f32(), || n1.to_string())?, Num::Float(n2)),
+            (Num::Float(n1), Num::Rational(n2)) => (Num::Float(n1), float(n2.to_f32(), || n2.to_string())?),
+            (Num::Rational(n1), n2) => return self.apply_rational(n1, n2.into_rational()?),
+            (n1, Num::Rational(n2)) => return self.apply_rational(n1.into_rational()?, n2),
+            (Num::BigInt(n1), Num::Float(n2)) => (float(n1.to_f32(), || n1.to_string())?, Num::Float(n2)),
+            (Num::Float(n1), Num::BigInt(n2)) => (Num::Float(n1), float(n2.to_f32(), || n2.to_string())?),
+            (Num::BigInt(n1), n2) => return self.apply_bigint(n1, n2.into_bigint()?),
+            (n1, Num::BigInt(n2)) => return self.apply_bigint(n1.into_bigint()?, n2),
+            (Num::Int(n1), Num::Float(n2)) => (float(n1 as f32, || n1.to_string())?, Num::Float(n2)),
+            (Num::Float(n1), Num::Int(n2)) => (Num::Float(n1), float(n2 as f32, || n2.to_string())?),
+            pair => pair
+        };
+        let int = |operator: ArithmeticOperator, n1: i32, n2: i32| overflow.apply(&operator, n1, n2).map(Num::Int);
+        // inf - inf, 0 * inf, 0 / 0 and a negative number to a fractional power have no value
+        let float_operation = |n: f32, n1: NotNan<f32>, operator: &str, n2: NotNan<f32>| float(n, || format!("{} {} {}", n1, operator, n2));
+
+        let n = match self {
             BinOp::Add => match (n1, n2) {
-                (Num::Int(n1), Num::Int(n2)) => Num::Int(n1 + n2),
-                (Num::Float(n1), Num::Float(n2)) => Num::Float(n1 + n2),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(n1 + n2.into_inner() as i32),
-                (Num::Float(n1), Num::Int(n2)) => Num::Float(n1 + n2 as f32),
+                (Num::Int(n1), Num::Int(n2)) => int(ArithmeticOperator::Addition, n1, n2)?,
+                (Num::Float(n1), Num::Float(n2)) => float_operation(n1.into_inner() + n2.into_inner(), n1, "+", n2)?,
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Sub => match (n1, n2) {
-                (Num::Int(n1), Num::Int(n2)) => Num::Int(n1 - n2),
-                (Num::Float(n1), Num::Float(n2)) => Num::Float(n1 - n2),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(n1 - n2.into_inner() as i32),
-                (Num::Float(n1), Num::Int(n2)) => Num::Float(n1 - n2 as f32),
+                (Num::Int(n1), Num::Int(n2)) => int(ArithmeticOperator::Subtraction, n1, n2)?,
+                (Num::Float(n1), Num::Float(n2)) => float_operation(n1.into_inner() - n2.into_inner(), n1, "-", n2)?,
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Mul => match (n1, n2) {
-                (Num::Int(n1), Num::Int(n2)) => Num::Int(n1 * n2),
-                (Num::Float(n1), Num::Float(n2)) => Num::Float(n1 * n2),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(n1 * n2.into_inner() as i32),
-                (Num::Float(n1), Num::Int(n2)) => Num::Float(n1 * n2 as f32),
+                (Num::Int(n1), Num::Int(n2)) => int(ArithmeticOperator::Multiplication, n1, n2)?,
+                (Num::Float(n1), Num::Float(n2)) => float_operation(n1.into_inner() * n2.into_inner(), n1, "*", n2)?,
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Div => match (n1, n2) {
-                (Num::Int(n1), Num::Int(n2)) => Num::Int(n1 / n2),
-                (Num::Float(n1), Num::Float(n2)) => Num::Float(n1 / n2),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(n1 / n2.into_inner() as i32),
-                (Num::Float(n1), Num::Int(n2)) => Num::Float(n1 / n2 as f32),
+                (Num::Int(n1), Num::Int(n2)) => int(ArithmeticOperator::Division, n1, n2)?,
+                (Num::Float(n1), Num::Float(n2)) => float_operation(n1.into_inner() / n2.into_inner(), n1, "/", n2)?,
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Exp => match (n1, n2) {
-                (Num::Int(n1), Num::Int(n2)) => Num::Int(n1.pow(n2 as u32)),
-                (Num::Float(n1), Num::Float(n2)) => Num::Float(NotNan::new(n1.into_inner().powf(n2.into_inner())).unwrap()),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(n1.pow(n2.into_inner() as u32)),
-                (Num::Float(n1), Num::Int(n2)) => Num::Float(NotNan::new(n1.into_inner().powf(n2 as f32)).unwrap()),
+                (Num::Int(n1), Num::Int(n2)) => int(ArithmeticOperator::Exponentiation, n1, n2)?,
+                (Num::Float(n1), Num::Float(n2)) => float_operation(n1.into_inner().powf(n2.into_inner()), n1, "^", n2)?,
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::And => match (n1, n2) {
                 (Num::Int(n1), Num::Int(n2)) => Num::Int(n1 & n2),
@@ -242,29 +302,77 @@ impl BinOp {
             BinOp::Eq => match (n1, n2) {
                 (Num::Int(n1), Num::Int(n2)) => Num::Int(if n1 == n2 { 1 } else { 0 }),
                 (Num::Float(n1), Num::Float(n2)) => Num::Int(if n1 == n2 { 1 } else { 0 }),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(if n1 == n2.into_inner() as i32 { 1 } else { 0 }),
-                (Num::Float(n1), Num::Int(n2)) => Num::Int(if n1.into_inner() as i32 == n2 { 1 } else { 0 }),
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Neq => match (n1, n2) {
                 (Num::Int(n1), Num::Int(n2)) => Num::Int(if n1 != n2 { 1 } else { 0 }),
                 (Num::Float(n1), Num::Float(n2)) => Num::Int(if n1 != n2 { 1 } else { 0 }),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(if n1 != n2.into_inner() as i32 { 1 } else { 0 }),
-                (Num::Float(n1), Num::Int(n2)) => Num::Int(if n1.into_inner() as i32 != n2 { 1 } else { 0 }),
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Lt => match (n1, n2) {
                 (Num::Int(n1), Num::Int(n2)) => Num::Int(if n1 < n2 { 1 } else { 0 }),
                 (Num::Float(n1), Num::Float(n2)) => Num::Int(if n1 < n2 { 1 } else { 0 }),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(if n1 < n2.into_inner() as i32 { 1 } else { 0 }),
-                (Num::Float(n1), Num::Int(n2)) => Num::Int(if n1.into_inner() < n2 as f32 { 1 } else { 0 }),
+                _ => unreachable!("mixed operands are promoted above")
             },
             BinOp::Gt => match (n1, n2) {
                 (Num::Int(n1), Num::Int(n2)) => Num::Int(if n1 > n2 { 1 } else { 0 }),
                 (Num::Float(n1), Num::Float(n2)) => Num::Int(if n1 > n2 { 1 } else { 0 }),
-                (Num::Int(n1), Num::Float(n2)) => Num::Int(if n1 > n2.into_inner() as i32 { 1 } else { 0 }),
-                (Num::Float(n1), Num::Int(n2)) => Num::Int(if n1.into_inner() > n2 as f32 { 1 } else { 0 }),
+                _ => unreachable!("mixed operands are promoted above")
             },
             _ => panic!("Cannot apply operation to non-number values")
-        }
+        };
+
+        Ok(n)
+    }
+
+    fn apply_bigint(&self, n1: BigInt, n2: BigInt) -> Result<Num, RuntimeError> {
+        let n = match self {
+            BinOp::Add => Num::BigInt(n1 + n2),
+            BinOp::Sub => Num::BigInt(n1 - n2),
+            BinOp::Mul => Num::BigInt(n1 * n2),
+            BinOp::Div if n2.is_zero() => return Err(divided_by_zero(Num::BigInt(n1), Num::BigInt(n2))),
+            BinOp::Div => Num::BigInt(n1 / n2),
+            BinOp::Exp => match n2.to_i32().filter(|exponent| *exponent > 0) {
+                Some(exponent) => Num::BigInt(n1.pow(exponent as u32)),
+                None => return Err(cannot_raise(Num::BigInt(n1), Num::BigInt(n2))),
+            },
+            BinOp::And => panic!("Cannot apply AND to non-integer values"),
+            BinOp::Or => panic!("Cannot apply OR to non-integer values"),
+            BinOp::Eq => Num::Int(if n1 == n2 { 1 } else { 0 }),
+            BinOp::Neq => Num::Int(if n1 != n2 { 1 } else { 0 }),
+            BinOp::Lt => Num::Int(if n1 < n2 { 1 } else { 0 }),
+            BinOp::Gt => Num::Int(if n1 > n2 { 1 } else { 0 }),
+            _ => panic!("Cannot apply operation to non-number values")
+        };
+
+        Ok(n)
+    }
+
+    fn apply_rational(&self, n1: Rational, n2: Rational) -> Result<Num, RuntimeError> {
+        let n = match self {
+            BinOp::Add => Num::Rational(n1 + n2),
+            BinOp::Sub => Num::Rational(n1 - n2),
+            BinOp::Mul => Num::Rational(n1 * n2),
+            BinOp::Div if n2.is_zero() => return Err(divided_by_zero(Num::Rational(n1), Num::Rational(n2))),
+            BinOp::Div => Num::Rational(n1 / n2),
+            // only 0 to a negative power has no value, as it is 1 / 0
+            BinOp::Exp => match n2.is_integer().then(|| n2.truncate().to_i32()).flatten() {
+                Some(exponent) => match n1.pow(exponent) {
+                    Some(n) => Num::Rational(n),
+                    None => return Err(divided_by_zero(Num::Rational(n1), Num::Rational(n2))),
+                },
+                None => return Err(cannot_raise(Num::Rational(n1), Num::Rational(n2))),
+            },
+            BinOp::And => panic!("Cannot apply AND to non-integer values"),
+            BinOp::Or => panic!("Cannot apply OR to non-integer values"),
+            BinOp::Eq => Num::Int(if n1 == n2 { 1 } else { 0 }),
+            BinOp::Neq => Num::Int(if n1 != n2 { 1 } else { 0 }),
+            BinOp::Lt => Num::Int(if n1 < n2 { 1 } else { 0 }),
+            BinOp::Gt => Num::Int(if n1 > n2 { 1 } else { 0 }),
+            _ => panic!("Cannot apply operation to non-number values")
+        };
+
+        Ok(n)
     }
 
     pub fn is_reverse(&self) -> bool {
@@ -302,6 +410,24 @@ impl BinOp {
     }
 }
 
+// the errors have no location, see Engine::step
+fn divided_by_zero(n1: Num, n2: Num) -> RuntimeError {
+    RuntimeError::DivisionByZero { location: Location::default(), variables: Vec::new(), operation: format!("{} / {}", n1, n2) }
+}
+
+fn cannot_raise(n1: Num, n2: Num) -> RuntimeError {
+    RuntimeError::InvalidExponent { location: Location::default(), variables: Vec::new(), base: n1.to_string(), exponent: n2.to_string() }
+}
+
+fn mismatch(message: String) -> RuntimeError {
+    RuntimeError::TypeMismatch { location: Location::default(), variables: Vec::new(), message }
+}
+
+// floats are NotNan, so a NaN is an error, as it is in the interpreter
+fn float(n: f32, operation: impl FnOnce() -> String) -> Result<Num, RuntimeError> {
+    NotNan::new(n).map(Num::Float).map_err(|_| mismatch(format!("{} is not a number", operation())))
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum UnOp {
     Neg,
@@ -322,18 +448,24 @@ impl Display for UnOp {
 }
 
 impl UnOp {
-    pub fn apply(&self, n: Num) -> Num {
-        match self {
+    // an error when negating the smallest int in checked mode
+    pub fn apply(&self, n: Num, overflow: Overflow) -> Result<Num, RuntimeError> {
+        let n = match self {
             UnOp::Neg => match n {
-                Num::Int(n) => Num::Int(-n),
-                Num::Float(n) => Num::Float(NotNan::new(-n.into_inner()).unwrap())
+                Num::Int(n) => Num::Int(overflow.negate(n)?),
+                Num::Float(n) => Num::Float(-n),
+                Num::BigInt(n) => Num::BigInt(-n),
+                Num::Rational(n) => Num::Rational(-n)
             },
+            // conditions are 0 or 1, so not is logical rather than bitwise
             UnOp::Not => match n {
-                Num::Int(n) => Num::Int(!n),
+                Num::Int(n) => Num::Int(if n == 0 { 1 } else { 0 }),
                 _ => panic!("Cannot apply NOT to non-integer values")
             }
             _ => panic!("Cannot apply operation to non-number values")
-        }
+        };
+
+        Ok(n)
     }
 
     pub fn is_reverse(&self) -> bool {
@@ -757,7 +889,7 @@ impl Builder {
         match ast {
             Program::Statements(block) => {
                 for statement in block.iter().rev() {
-                    stack.push(self.c_from_statement(statement.clone()));
+                    stack.push(self.c_from_statement(statement.value.clone()));
                 }
             }
         }
@@ -830,6 +962,12 @@ impl Builder {
             ArithmeticExpression::Float(n) => {
                 P::Num(Num::Float(n))
             },
+            ArithmeticExpression::BigInteger(n) => {
+                P::Num(Num::BigInt(n))
+            },
+            ArithmeticExpression::Rational(n) => {
+                P::Num(Num::Rational(n))
+            },
             ArithmeticExpression::Unary(op, e) => {
                 P::UnOp(Box::new(self.from_arithmetic_expression(*e)),
                         match op {
@@ -850,15 +988,18 @@ impl Builder {
         }
     }
 
-    pub fn from_block(&mut self, block: Vec<Statement>) -> P {
-        // if only one statement, return that statement
-        if block.len() == 1 {
-            self.from_statement(block.first().unwrap().clone())
+    pub fn from_block(&mut self, block: Block) -> P {
+        // an empty block does nothing, like skip
+        if block.is_empty() {
+            P::Skip
+        } else if block.len() == 1 {
+            // if only one statement, return that statement
+            self.from_statement(block.first().unwrap().value.clone())
         } else {
             // if multiple statements, return a sequence of statements
-            let mut sequence = self.from_statement(block.last().unwrap().clone());
+            let mut sequence = self.from_statement(block.last().unwrap().value.clone());
             for statement in block.iter().rev().skip(1) {
-                sequence = P::Seq(Box::new(self.from_statement(statement.clone())), Box::new(sequence));
+                sequence = P::Seq(Box::new(self.from_statement(statement.value.clone())), Box::new(sequence));
             }
             sequence
         }
@@ -915,7 +1056,9 @@ impl R {
             R::Value(n) => {
                 match n {
                     Num::Int(n) => *n != 0,
-                    Num::Float(n) => n.into_inner() != 0.0
+                    Num::Float(n) => n.into_inner() != 0.0,
+                    Num::BigInt(n) => !n.is_zero(),
+                    Num::Rational(n) => !n.is_zero()
                 }
             }
             R::Bool(b) => *b,
@@ -928,7 +1071,9 @@ impl R {
             R::Value(n) => {
                 match n {
                     Num::Int(n) => *n == 0,
-                    Num::Float(n) => n.into_inner() == 0.0
+                    Num::Float(n) => n.into_inner() == 0.0,
+                    Num::BigInt(n) => n.is_zero(),
+                    Num::Rational(n) => n.is_zero()
                 }
             }
             R::Bool(b) => !*b,
@@ -0,0 +1,123 @@
+use super::engine::Engine;
+use super::super::lexer::lexer::Tokeniser;
+use super::super::parser::parser::parse_without_transform;
+use super::super::interpreter::memory_store::Value;
+use super::super::AST::Program;
+
+fn machine(program: &str) -> Engine {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast: Program = parse_without_transform(&mut tokens.into()).unwrap();
+    Engine::new(ast)
+}
+
+#[test]
+fn runs_and_reverses() {
+    let mut engine = machine("int n = 5;\nint total = 0;\nwhile n > 0 do {\n    total = total + n;\n    n = n - 1;\n};");
+
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("total")).unwrap().get(), Value::Integer(15));
+    assert_eq!(engine.get_store().get(&String::from("n")).unwrap().get(), Value::Integer(0));
+
+    engine.reverse();
+    assert_eq!(engine.steps, 0);
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("total")).unwrap().get(), Value::Integer(0));
+    assert_eq!(engine.get_store().get(&String::from("n")).unwrap().get(), Value::Integer(0));
+}
+
+#[test]
+fn budgets() {
+    let program = "int n = 1;\nint m = 0;\nwhile n > 0 do {\n    m = n;\n    n = n + 1;\n};";
+
+    let mut engine = machine(program).max_steps(Some(1000));
+    let error = engine.run().unwrap_err();
+    assert_eq!(engine.steps, 1000);
+    assert!(!engine.is_done());
+    assert!(error.starts_with(&format!("Stopped before rule {}, ", engine.get_next_rule())), "{}", error);
+    assert!(error.ends_with("the program didn't finish within 1000 steps"), "{}", error);
+
+    // the store is left where the machine stopped, so the run can be undone
+    let n = engine.get_store().get(&String::from("n")).unwrap().get();
+    assert!(n > Value::Integer(1), "{}", n);
+    engine.reverse();
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("n")).unwrap().get(), Value::Integer(0));
+    assert_eq!(engine.get_store().get(&String::from("m")).unwrap().get(), Value::Integer(0));
+
+    let mut engine = machine(program).max_history(Some(20));
+    let error = engine.run().unwrap_err();
+    assert!(error.ends_with("the history grew past 20 entries"), "{}", error);
+    assert_eq!(engine.get_store().history_len(), 21);
+}
+
+#[test]
+fn negates_conditions() {
+    let mut engine = machine("int a = 0;\nif !(1 == 1) then {\n    a = 1;\n} else {\n    a = 2;\n};\nif !(1 == 2) && 1 < 2 then {\n    a = a + 10;\n} else {\n    skip;\n};");
+
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("a")).unwrap().get(), Value::Integer(12));
+}
+
+#[test]
+fn reverses_nested_ifs() {
+    // the taken branch changes the inner condition, so reversing has to use the value it had
+    let mut engine = machine("int v = 0;\nif 1 == 2 then {\n    skip;\n} else {\n    if v == 0 then {\n        v = 1;\n    } else {\n        v = 2;\n        v = 3;\n    };\n};");
+
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("v")).unwrap().get(), Value::Integer(1));
+
+    engine.reverse();
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("v")).unwrap().get(), Value::Integer(0));
+}
+
+#[test]
+fn arithmetic_errors() {
+    use super::super::overflow::Overflow;
+
+    // the step that fails isn't taken, so the machine can still be looked at, or reversed, from before it
+    let mut engine = machine("int big = 2147483647;\nint n = big + 1;").overflow(Overflow::Checked);
+    let error = engine.run().unwrap_err();
+    assert!(error.starts_with("Integer overflow, 2147483647 + 1 doesn't fit in 32 bits, in "), "{}", error);
+    let (steps, rule) = (engine.steps, engine.get_next_rule());
+    assert_eq!(engine.step(), Err(error));
+    assert_eq!((engine.steps, engine.get_next_rule()), (steps, rule));
+    assert_eq!(engine.get_store().get(&String::from("big")).unwrap().get(), Value::Integer(i32::MAX));
+
+    let mut engine = machine("int big = 2147483647;\nint n = big + 1;");
+    assert_eq!(engine.run(), Ok(()));
+    assert_eq!(engine.get_store().get(&String::from("n")).unwrap().get(), Value::Integer(i32::MIN));
+
+    let mut engine = machine("int zero = 0;\nint n = 1 / zero;");
+    assert!(engine.run().unwrap_err().starts_with("Division by zero, 1 / 0, in "));
+
+    let mut engine = machine("int n = 0 - 2147483647 - 1;\nint m = -n;").overflow(Overflow::Checked);
+    assert!(engine.run().unwrap_err().starts_with("Integer overflow, -(-2147483648) doesn't fit in 32 bits, in "));
+}
+
+#[test]
+fn float_errors() {
+    // a NaN, or an infinity that has to be a bigint or a rational, is an error and the step isn't taken
+    let mut engine = machine("float a = 0;\nfloat l = 1 / (a - a);\nfloat m = l - l;");
+    let error = engine.run().unwrap_err();
+    assert!(error.starts_with("inf - inf is not a number, in "), "{}", error);
+    let (steps, rule) = (engine.steps, engine.get_next_rule());
+    assert_eq!(engine.step(), Err(error));
+    assert_eq!((engine.steps, engine.get_next_rule()), (steps, rule));
+
+    let mut engine = machine("float a = 0 - 1;\nfloat b = a ^ 0.5;");
+    let error = engine.run().unwrap_err();
+    assert!(error.starts_with("-1 ^ 0.5 is not a number, in "), "{}", error);
+
+    let mut engine = machine("float a = 0;\nfloat b = 1 / a;\nbigint c = b;");
+    let error = engine.run().unwrap_err();
+    assert_eq!(error, "Cannot convert inf to a bigint, in c := !b");
+    let (steps, rule) = (engine.steps, engine.get_next_rule());
+    assert_eq!(engine.step(), Err(error));
+    assert_eq!((engine.steps, engine.get_next_rule()), (steps, rule));
+
+    let mut engine = machine("float a = 0;\nfloat b = 1 / a;\nrational c = b;");
+    assert_eq!(engine.run().unwrap_err(), "Cannot convert inf to a rational, in c := !b");
+}
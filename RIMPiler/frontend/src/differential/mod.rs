@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+use super::abstract_machine::engine::Engine;
+use super::fixtures::installed;
+use super::interpreter::interpreter::InterpreterEngine;
+use super::interpreter::memory_store::{MemoryStore, Value};
+use super::lexer::lexer::Tokeniser;
+use super::parser::parser::{parse, parse_without_transform};
+use super::post_parse::inverter::invert_and_combine;
+use super::IR::lower::lower;
+use super::IR::Program;
+use super::JVM::code_gen::{ClassDetails, JVMCompiler};
+use super::JVM::evaluator::{Class, Evaluator, Number, Variable};
+use super::AST::{self, Type};
+
+mod tests;
+
+/*
+Differential testing of the engines that run a program: the interpreter, the abstract machine and the JVM backend.
+
+Each engine's store is reduced to a Dump, the value of each of the program's own variables, at the reverse point and
+at the end of the backward program. Histories aren't compared, the inverter undoes some assignments without one
+while the machine records every assignment, declarations included, so they only have to agree on the values. The
+variables the engines add themselves, like the inverter's counters and the machine's while counters, aren't
+compared either, they all have an underscore in their name, which identifiers in a program can't.
+
+The JVM leg assembles the compiled class with Krakatau and runs it when krak2 and java are installed, which only
+shows the reverse point, and otherwise evaluates the Jasmin with JVM::evaluator. Programs the JVM backend can't
+compile, like ones with rationals, skip it.
+*/
+
+// how many instructions or machine steps a program gets before it is assumed not to terminate
+const FUEL: usize = 1_000_000;
+
+pub type Dump = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub reverse_point: Dump,
+    // None when the engine only shows the reverse point
+    pub end: Option<Dump>,
+}
+
+fn is_program_variable(name: &str) -> bool {
+    !name.contains('_')
+}
+
+fn dump(store: &MemoryStore) -> Dump {
+    store
+        .iter()
+        .filter(|(name, _)| is_program_variable(name))
+        .map(|(name, element)| (name.clone(), element.get()))
+        .collect()
+}
+
+fn number(number: &Number) -> Value {
+    match number {
+        Number::Int(value) => Value::Integer(*value),
+        Number::Float(value) => Value::Float(*value),
+        Number::BigInteger(value) => Value::BigInteger(value.clone()),
+    }
+}
+
+fn evaluated(variables: &[Variable]) -> Dump {
+    variables
+        .iter()
+        .filter(|variable| is_program_variable(&variable.name))
+        .map(|variable| (variable.name.clone(), number(&variable.value)))
+        .collect()
+}
+
+fn parse_value(text: &str, variable_type: &Type) -> Result<Value, String> {
+    let value = match variable_type {
+        Type::Integer => text.parse().map(Value::Integer).ok(),
+        Type::Float => text.parse().map(Value::Float).ok(),
+        Type::BigInteger => text.parse().map(Value::BigInteger).ok(),
+        Type::Rational => None,
+    };
+    value.ok_or(format!("Can't read {} as a {:?}", text, variable_type))
+}
+
+// the values in the lines print writes, "name: value\t [h0 h1 ... ]", read with the types of the program's variables
+fn printed(output: &str, program: &Program) -> Result<Dump, String> {
+    let mut dump = Dump::new();
+
+    for line in output.lines() {
+        let (name, rest) = line.split_once(": ").ok_or(format!("Unexpected output {}", line))?;
+        let (value, _) = rest.split_once("\t [").ok_or(format!("Unexpected output {}", line))?;
+        let slot = program.slot(name).ok_or(format!("Unknown variable {}", name))?;
+
+        if is_program_variable(name) {
+            dump.insert(name.to_string(), parse_value(value, &program.variable(slot).variable_type)?);
+        }
+    }
+
+    Ok(dump)
+}
+
+// the program combined with its inverse, see post_parse::inverter::invert_and_combine
+fn combined(source: &str) -> Result<AST::Program, String> {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(source.to_string()).map_err(|error| error.to_string())?;
+    let program = parse(&mut tokens.into()).map_err(|error| error.to_string())?;
+
+    Ok(invert_and_combine(&program))
+}
+
+pub fn interpreter(source: &str) -> Result<Run, String> {
+    let mut interpreter = InterpreterEngine::new().max_steps(Some(FUEL));
+    interpreter.interpret(&combined(source)?).map_err(|error| format!("Interpreter: {}", error))?;
+
+    Ok(Run {
+        reverse_point: dump(interpreter.get_reverse_point_snapshot().as_ref().ok_or("Interpreter: no reverse point")?),
+        end: Some(dump(interpreter.get_final_memory_point_snapshot().as_ref().ok_or("Interpreter: no end")?)),
+    })
+}
+
+pub fn abstract_machine(source: &str) -> Result<Run, String> {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(source.to_string()).map_err(|error| error.to_string())?;
+    let program = parse_without_transform(&mut tokens.into()).map_err(|error| error.to_string())?;
+
+    let mut engine = Engine::new(program).max_steps(Some(FUEL));
+    engine.run().map_err(|error| format!("Abstract machine: {}", error))?;
+    let reverse_point = dump(engine.get_store());
+
+    engine.reverse();
+    engine.run().map_err(|error| format!("Abstract machine: {}", error))?;
+
+    Ok(Run { reverse_point, end: Some(dump(engine.get_store())) })
+}
+
+// assembles and runs the class, the runtime classes are the ones the backend writes next to it
+fn run_jvm(byte_code: &str, program: &Program) -> Result<Dump, String> {
+    let folder = std::env::temp_dir().join(format!("rimpiler_differential_{}_{:?}", std::process::id(), std::thread::current().id()));
+    std::fs::create_dir_all(&folder).map_err(|error| error.to_string())?;
+
+    let runtime: [(&str, &[u8]); 3] = [
+        ("RIMPInt.class", include_bytes!("../../../../data/RIMPInt.class")),
+        ("RIMPFloat.class", include_bytes!("../../../../data/RIMPFloat.class")),
+        ("RIMPBigInt.class", include_bytes!("../../../../data/RIMPBigInt.class")),
+    ];
+    for (file, bytes) in runtime {
+        std::fs::write(folder.join(file), bytes).map_err(|error| error.to_string())?;
+    }
+    std::fs::write(folder.join("Main.j"), byte_code).map_err(|error| error.to_string())?;
+
+    let assembled = Command::new("krak2").args(["asm", "--out", "Main.class", "Main.j"]).current_dir(&folder).output();
+    let output = assembled.and_then(|_| Command::new("java").arg("-cp").arg(&folder).arg("Main").output());
+
+    std::fs::remove_dir_all(&folder).map_err(|error| error.to_string())?;
+
+    let output = output.map_err(|error| error.to_string())?;
+    if !output.status.success() {
+        return Err(format!("JVM: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    printed(&String::from_utf8_lossy(&output.stdout), program)
+}
+
+// Ok(None) when the program can't be compiled for the JVM
+pub fn jvm(source: &str) -> Result<Option<Run>, String> {
+    let program = match lower(&combined(source)?) {
+        Ok(program) => program,
+        Err(_) => return Ok(None),
+    };
+
+    let byte_code = JVMCompiler::new()._compile(&program, &ClassDetails::default());
+
+    if installed("krak2", "--help") && installed("java", "-version") {
+        return Ok(Some(Run { reverse_point: run_jvm(&byte_code, &program)?, end: None }));
+    }
+
+    let class = Class::new(&byte_code)?;
+    let mut evaluator = Evaluator::new(&class, FUEL);
+    evaluator.run().map_err(|error| format!("JVM: {}", error))?;
+
+    Ok(Some(Run { reverse_point: evaluated(&evaluator.printed), end: Some(evaluated(&evaluator.variables())) }))
+}
+
+fn compare(point: &str, engine: &str, expected: &Dump, actual: &Dump) -> Vec<String> {
+    let mut names: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter(|name| expected.get(*name) != actual.get(*name))
+        .map(|name| format!("{}: {} has {:?}, the interpreter has {:?}", point, engine, actual.get(name), expected.get(name)))
+        .collect()
+}
+
+// the ways the engines disagree about the program, compared against the interpreter
+pub fn differences(source: &str) -> Result<Vec<String>, String> {
+    let expected = interpreter(source)?;
+
+    let mut runs = vec![("the abstract machine", abstract_machine(source)?)];
+    if let Some(run) = jvm(source)? {
+        runs.push(("the JVM", run));
+    }
+
+    let mut differences = Vec::new();
+    for (engine, run) in runs {
+        differences.extend(compare("at the reverse point", engine, &expected.reverse_point, &run.reverse_point));
+        if let (Some(expected), Some(actual)) = (&expected.end, &run.end) {
+            differences.extend(compare("at the end", engine, expected, actual));
+        }
+    }
+
+    Ok(differences)
+}
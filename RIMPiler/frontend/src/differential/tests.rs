@@ -0,0 +1,119 @@
+use super::{abstract_machine, compare, differences, interpreter, jvm, printed, Dump};
+use super::super::interpreter::memory_store::Value;
+use super::super::IR::lower::lower;
+use super::super::JVM::code_gen::{ClassDetails, JVMCompiler};
+use super::super::JVM::evaluator::{Class, Evaluator, Number};
+
+// programs for the corners the examples don't reach
+const EDGE_CASES: [&str; 8] = [
+    // ints wrap
+    "int x = 2147483647;\nx = x + 1;\nint y = 0 - x;\ny = y * 3;",
+    // nested loops, which the inverter and the machine count differently
+    "int i = 3;\nint total = 0;\nwhile i > 0 do {\n    int j = i;\n    while j > 0 do {\n        total = total + j;\n        j = j - 1;\n    };\n    i = i - 1;\n};",
+    // a loop that never runs
+    "int n = 0;\nwhile n > 0 do {\n    n = n - 1;\n};\nn = 4;",
+    // both branches of an if, and an if in a loop
+    "int a = 3;\nint b = 0;\nwhile a > 0 do {\n    if a == 2 then {\n        b = b + 10;\n    } else {\n        b = b - 1;\n    };\n    a = a - 1;\n};",
+    // ints and floats mixed on either side
+    "int a = 3;\nfloat b = 0.25;\nfloat c = a * b;\nfloat d = b - a;\nint e = a + b;\nint f = c / a;\nfloat g = a / 2;",
+    // comparisons of ints with floats
+    "int a = 1;\nfloat b = 1.5;\nint c = 0;\nif a < b then { c = 1; } else { c = 2; };\nif b > a then { c = c + 10; } else { c = c + 20; };\nif a == b then { c = c + 100; } else { skip; };",
+    // integer division truncates towards 0 and negation
+    "int a = 0 - 7;\nint b = a / 2;\nint c = -b;\nfloat d = -1.5;\nint e = d;",
+    // bigints meeting ints and floats
+    "bigint a = 99999999999999999999;\nint b = 7;\nbigint c = a * b;\nbigint d = c / 3;\nfloat e = 2.75;\nbigint f = a - e;\nint g = d;",
+];
+
+fn example(name: &str) -> String {
+    std::fs::read_to_string(format!("../../examples/{}.rimp", name)).unwrap()
+}
+
+fn assert_engines_agree(program: &str) {
+    let found = differences(program).unwrap_or_else(|error| panic!("{}\n{}", error, program));
+    assert!(found.is_empty(), "{}\n{}", program, found.join("\n"));
+}
+
+#[test]
+fn examples_agree() {
+    let examples = std::fs::read_dir("../../examples").unwrap();
+
+    for path in examples.map(|entry| entry.unwrap().path()) {
+        if path.extension().is_some_and(|extension| extension == "rimp") {
+            assert_engines_agree(&std::fs::read_to_string(&path).unwrap());
+        }
+    }
+}
+
+#[test]
+fn edge_cases_agree() {
+    for program in EDGE_CASES {
+        assert_engines_agree(program);
+    }
+}
+
+#[test]
+fn common_form() {
+    let fibonacci = interpreter(&example("fibonacci")).unwrap();
+    assert_eq!(fibonacci.reverse_point["minus1"], Value::Integer(8));
+    assert_eq!(fibonacci.reverse_point["n"], Value::Integer(0));
+    assert!(fibonacci.reverse_point.keys().all(|name| !name.contains('_')));
+    assert!(fibonacci.end.unwrap().values().all(|value| *value == Value::Integer(0)));
+
+    let machine = abstract_machine(&example("fibonacci")).unwrap();
+    assert!(machine.reverse_point.keys().all(|name| !name.contains('_')));
+    assert_eq!(machine.reverse_point["minus1"], Value::Integer(8));
+
+    // the JVM leg skips what it can't compile
+    assert_eq!(jvm(&example("rationals")), Ok(None));
+    assert!(jvm(&example("fibonacci")).unwrap().is_some());
+}
+
+#[test]
+fn reports_mismatches() {
+    let mut expected = Dump::new();
+    expected.insert(String::from("a"), Value::Integer(1));
+    expected.insert(String::from("b"), Value::Float(0.5));
+
+    assert!(compare("at the end", "the JVM", &expected, &expected).is_empty());
+
+    let mut actual = expected.clone();
+    actual.insert(String::from("a"), Value::Integer(2));
+    actual.remove("b");
+    let found = compare("at the end", "the JVM", &expected, &actual);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0], "at the end: the JVM has Some(Integer(2)), the interpreter has Some(Integer(1))");
+    assert!(found[1].contains("has None"), "{}", found[1]);
+}
+
+#[test]
+fn reads_printed_lines() {
+    let program = lower(&super::combined("int a = 3;\nfloat b = 1.5;\nbigint c = 12345678901234567890;").unwrap()).unwrap();
+    let output = "a: 3\t [0 3 ]\nb: 1.5\t [0.0 0.0 ]\nc: 12345678901234567890\t [0 12345678901234567890 ]\n";
+
+    let dump = printed(output, &program).unwrap();
+    assert_eq!(dump["a"], Value::Integer(3));
+    assert_eq!(dump["b"], Value::Float(1.5));
+    assert_eq!(dump["c"], Value::BigInteger("12345678901234567890".parse().unwrap()));
+    assert!(printed("a = 3", &program).is_err());
+}
+
+#[test]
+fn evaluator() {
+    let byte_code = JVMCompiler::new()._compile(&lower(&super::combined(&example("collatz")).unwrap()).unwrap(), &ClassDetails::default());
+    let class = Class::new(&byte_code).unwrap();
+
+    let mut evaluator = Evaluator::new(&class, 100_000);
+    assert!(evaluator.run().is_ok());
+    let n = evaluator.printed.iter().find(|variable| variable.name == "n").unwrap();
+    assert_eq!(n.value, Number::Int(1));
+    assert!(evaluator.variables().iter().all(|variable| variable.history.len() == 1));
+
+    // it runs out of fuel rather than looping forever
+    let mut evaluator = Evaluator::new(&class, 10);
+    assert_eq!(evaluator.run(), Err(String::from("Out of fuel")));
+
+    // what the JVM would throw is an error
+    let byte_code = JVMCompiler::new()._compile(&lower(&super::combined("bigint a = 5;\nint b = 0;\nbigint c = a / b;").unwrap()).unwrap(), &ClassDetails::default());
+    let class = Class::new(&byte_code).unwrap();
+    assert!(Evaluator::new(&class, 1000).run().unwrap_err().starts_with("ArithmeticException"));
+}
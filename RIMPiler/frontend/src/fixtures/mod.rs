@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use super::interpreter::interpreter::InterpreterEngine;
+use super::lexer::lexer::Tokeniser;
+use super::parser::parser::{parse, parse_keeping_counters};
+use super::post_parse::inverter::invert_and_combine;
+use super::AST::Program;
+
+/*
+What the backends' tests share: the inverted program each backend compiles, and for the backends whose output is run
+by a tool on the machine, building and running that output in a folder of its own.
+
+The tools are optional, the tests that need one check it's installed and skip themselves when it isn't.
+*/
+
+pub const EXAMPLES: [&str; 7] = ["basic", "collatz", "exponentials", "fibonacci", "floats", "if", "while"];
+
+pub fn inverted_program(program: &str) -> Program {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    invert_and_combine(&ast)
+}
+
+// for the backends that can't keep a history, which reverse loops with their counters instead
+pub fn inverted_program_keeping_counters(program: &str) -> Program {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse_keeping_counters(&mut tokens.into()).unwrap();
+    invert_and_combine(&ast)
+}
+
+pub fn example(name: &str) -> String {
+    std::fs::read_to_string(format!("../../examples/{}.rimp", name)).unwrap()
+}
+
+pub fn installed(program: &str, flag: &str) -> bool {
+    Command::new(program).arg(flag).output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+// a folder for one test holding the source, named after the backend and the test as the tests run at the same time
+pub fn folder(backend: &str, name: &str, file: &str, source: &str) -> PathBuf {
+    let folder = std::env::temp_dir().join(format!("rimpiler_{}_{}_{}", backend, name, std::process::id()));
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join(file), source).unwrap();
+    folder
+}
+
+// a step of the build, which fails the test with what the tool printed if it fails
+pub fn build(folder: &Path, program: &str, args: &[&str]) {
+    let output = Command::new(program).args(args).current_dir(folder).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+// runs what was built and removes the folder, the output is returned whether or not the program succeeded
+pub fn run(folder: &Path, command: &mut Command) -> Output {
+    let output = command.current_dir(folder).output().unwrap();
+    std::fs::remove_dir_all(folder).unwrap();
+    output
+}
+
+// one line per variable, name: value\t [history ]
+fn parse_line(line: &str) -> (String, f32, Vec<f32>) {
+    let (variable, rest) = line.split_once(": ").unwrap();
+    let (value, history) = rest.split_once("\t [").unwrap();
+    let history = history
+        .trim_end_matches(']')
+        .split_whitespace()
+        .map(|value| value.parse::<f32>().unwrap())
+        .collect();
+    (variable.to_string(), value.parse::<f32>().unwrap(), history)
+}
+
+// each printed line against the interpreter's value and history for that variable
+pub fn matches_printed(example: &str, stdout: &str, interpreter: &InterpreterEngine) {
+    assert!(!stdout.is_empty(), "{}", example);
+
+    for line in stdout.lines() {
+        let (variable, value, history) = parse_line(line);
+        let expected = interpreter.get_result(&variable).unwrap();
+
+        assert_eq!(value, expected.get().to_string().parse::<f32>().unwrap(), "{}: {}", example, variable);
+        let expected_history: Vec<f32> = expected
+            .get_history()
+            .iter()
+            .map(|value| value.to_string().parse::<f32>().unwrap())
+            .collect();
+        assert_eq!(history, expected_history, "{}: {}", example, variable);
+    }
+}
@@ -0,0 +1,852 @@
+use std::collections::HashSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::debug::{Location, Meta};
+use utilities::rational::Rational;
+use super::parser::precedence::{arithmetic_binding_power, boolean_operator_binding_power};
+use super::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, Type, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
+
+mod tests;
+
+/*
+Random programs for property testing.
+
+Generator makes well-typed programs that terminate and don't fail: every variable is declared before it is used, in
+a block that encloses the use, and the values can't grow without bound or divide by zero, so
+ - dividing is only by a literal that isn't 0
+ - multiplying is only by a literal, or by an int variable in an int expression, as ints wrap
+ - the base of an exponent is a literal or an int variable, and the exponent is a literal from 1 to 3
+ - a while loop counts down a counter from at most 3, the counter is declared right before the loop with a name
+   starting with c, and only the last statement of the loop assigns it
+
+source writes a program as RIMP, declaring each variable where it is first assigned, and well_formed checks that a
+program keeps to the rules above, so shrink only ever tries programs that keep to them too. The statements the
+transformer and the inverter add, reverse and direct assignments and the reverse point, have no syntax, so programs
+only get them by being transformed.
+
+check is how the tests use them, a property is checked on the programs from a fixed run of seeds and the first program
+it fails on is shrunk before it's reported.
+*/
+
+// xorshift64*, so the same seed always gives the same program
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // the state can't be 0, and close seeds shouldn't give close programs
+        Random { state: seed.wrapping_mul(0x9E3779B97F4A7C15) | 1 }
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    // a number from 0 up to but not including n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    // true one time in n
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+pub struct Generator {
+    random: Random,
+    // how deep expressions and conditions go
+    depth: usize,
+    // how many statements a block has at most
+    statements: usize,
+    // how deep ifs and whiles go
+    nesting: usize,
+
+    names: usize,
+    // the variables that can be used, a scope for each block being generated
+    scopes: Vec<Vec<Variable>>,
+    // the counters of the loops being generated, which can't be assigned
+    counters: Vec<String>,
+}
+
+const TYPES: [Type; 4] = [Type::Integer, Type::Float, Type::BigInteger, Type::Rational];
+
+fn name(variable: &Variable) -> &String {
+    match variable {
+        Variable::Integer(name) | Variable::Float(name) | Variable::BigInteger(name) | Variable::Rational(name) => name,
+    }
+}
+
+fn variable_type(variable: &Variable) -> Type {
+    match variable {
+        Variable::Integer(_) => Type::Integer,
+        Variable::Float(_) => Type::Float,
+        Variable::BigInteger(_) => Type::BigInteger,
+        Variable::Rational(_) => Type::Rational,
+    }
+}
+
+fn variable(name: String, variable_type: &Type) -> Variable {
+    match variable_type {
+        Type::Integer => Variable::Integer(name),
+        Type::Float => Variable::Float(name),
+        Type::BigInteger => Variable::BigInteger(name),
+        Type::Rational => Variable::Rational(name),
+    }
+}
+
+// the types that can appear in an expression assigned to a variable of the given type
+fn mixes_with(variable_type: &Type) -> &'static [Type] {
+    match variable_type {
+        Type::Integer => &[Type::Integer, Type::Float, Type::BigInteger],
+        Type::Float => &[Type::Float, Type::Integer],
+        Type::BigInteger => &[Type::BigInteger, Type::Integer],
+        Type::Rational => &[Type::Rational, Type::Integer, Type::BigInteger],
+    }
+}
+
+fn is_literal(expression: &ArithmeticExpression) -> bool {
+    matches!(
+        expression,
+        ArithmeticExpression::Integer(_) | ArithmeticExpression::Float(_) | ArithmeticExpression::BigInteger(_) | ArithmeticExpression::Rational(_)
+    )
+}
+
+fn is_zero(expression: &ArithmeticExpression) -> bool {
+    match expression {
+        ArithmeticExpression::Integer(integer) => *integer == 0,
+        ArithmeticExpression::Float(float) => float.into_inner() == 0.0,
+        ArithmeticExpression::BigInteger(integer) => integer.is_zero(),
+        ArithmeticExpression::Rational(rational) => rational.is_zero(),
+        _ => false,
+    }
+}
+
+fn is_counter(variable: &Variable) -> bool {
+    matches!(variable, Variable::Integer(name) if name.starts_with('c'))
+}
+
+fn statement(statement: Statement) -> Meta<Statement> {
+    Meta::new(statement, Location::default())
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator { random: Random::new(seed), depth: 3, statements: 4, nesting: 2, names: 0, scopes: Vec::new(), counters: Vec::new() }
+    }
+
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn statements(mut self, statements: usize) -> Self {
+        self.statements = statements;
+        self
+    }
+
+    pub fn nesting(mut self, nesting: usize) -> Self {
+        self.nesting = nesting;
+        self
+    }
+
+    pub fn generate(&mut self) -> Program {
+        self.names = 0;
+        self.scopes = vec![Vec::new()];
+        self.counters = Vec::new();
+
+        // a few declarations first, so there is something to use
+        let mut block = Vec::new();
+        for _ in 0..1 + self.random.below(3) {
+            block.push(self.declaration());
+        }
+        block.extend(self.block(0));
+
+        normalised(&Program::Statements(block))
+    }
+
+    fn fresh(&mut self, prefix: &str) -> String {
+        self.names += 1;
+        format!("{}{}", prefix, self.names - 1)
+    }
+
+    fn in_scope(&self, types: &[Type]) -> Vec<Variable> {
+        self.scopes.iter().flatten().filter(|variable| types.contains(&variable_type(variable))).cloned().collect()
+    }
+
+    fn block(&mut self, nesting: usize) -> Block {
+        self.scopes.push(Vec::new());
+
+        let mut block = Vec::new();
+        for _ in 0..self.random.below(self.statements + 1) {
+            match self.random.below(10) {
+                0 => block.push(statement(Statement::Skip)),
+                1 | 2 => block.push(self.declaration()),
+                3 | 4 if nesting < self.nesting => block.push(self.if_statement(nesting)),
+                5 | 6 if nesting < self.nesting => block.extend(self.while_statement(nesting)),
+                _ => match self.assignment() {
+                    Some(assignment) => block.push(assignment),
+                    None => block.push(self.declaration()),
+                },
+            }
+        }
+
+        self.scopes.pop();
+        block
+    }
+
+    fn declaration(&mut self) -> Meta<Statement> {
+        let variable_type = self.random.choose(&TYPES).clone();
+        let expression = self.expression(&variable_type, self.depth);
+
+        let declared = variable(self.fresh("v"), &variable_type);
+        self.scopes.last_mut().unwrap().push(declared.clone());
+        statement(Statement::Assignment(declared, expression))
+    }
+
+    fn assignment(&mut self) -> Option<Meta<Statement>> {
+        let assignable: Vec<Variable> = self.in_scope(&TYPES).into_iter().filter(|variable| !is_counter(variable)).collect();
+        if assignable.is_empty() {
+            return None;
+        }
+
+        let assigned = self.random.choose(&assignable).clone();
+        let expression = self.expression(&variable_type(&assigned), self.depth);
+        Some(statement(Statement::Assignment(assigned, expression)))
+    }
+
+    fn if_statement(&mut self, nesting: usize) -> Meta<Statement> {
+        let condition = self.condition(self.depth);
+        let then_block = self.block(nesting + 1);
+        let else_block = self.block(nesting + 1);
+        statement(Statement::If(Box::new(condition), Box::new(then_block), Box::new(else_block)))
+    }
+
+    // the declaration of the counter and the loop
+    fn while_statement(&mut self, nesting: usize) -> Vec<Meta<Statement>> {
+        let counter = Variable::Integer(self.fresh("c"));
+        let declaration = statement(Statement::Assignment(counter.clone(), ArithmeticExpression::Integer(1 + self.random.below(3) as i32)));
+        self.scopes.last_mut().unwrap().push(counter.clone());
+
+        let counted = BooleanExpression::Relational(
+            RelationOperator::GreaterThan,
+            Box::new(ArithmeticExpression::Variable(counter.clone())),
+            Box::new(ArithmeticExpression::Integer(0)),
+        );
+        let condition = if self.random.one_in(3) {
+            BooleanExpression::Logical(BooleanOperator::And, Box::new(counted), Box::new(self.condition(self.depth - 1)))
+        } else {
+            counted
+        };
+
+        self.counters.push(name(&counter).clone());
+        let mut body = self.block(nesting + 1);
+        self.counters.pop();
+
+        body.push(statement(Statement::Assignment(
+            counter.clone(),
+            ArithmeticExpression::Operation(
+                ArithmeticOperator::Subtraction,
+                Box::new(ArithmeticExpression::Variable(counter)),
+                Box::new(ArithmeticExpression::Integer(1)),
+            ),
+        )));
+
+        vec![declaration, statement(Statement::While(Box::new(condition), Box::new(body)))]
+    }
+
+    fn literal(&mut self, literal_type: &Type) -> ArithmeticExpression {
+        match literal_type {
+            Type::Integer if self.random.one_in(8) => ArithmeticExpression::Integer(i32::MAX - self.random.below(1000) as i32),
+            Type::Integer => ArithmeticExpression::Integer(self.random.below(10) as i32),
+            // quarters, which floats hold exactly and print without an exponent
+            Type::Float => ArithmeticExpression::Float(NotNan::new(self.random.below(20) as f32 / 4.0).unwrap()),
+            // bigint literals are the ones too large for an int
+            Type::BigInteger => ArithmeticExpression::BigInteger(BigInt::from(i32::MAX as i64 + 1 + (self.random.next() >> 24) as i64)),
+            Type::Rational => ArithmeticExpression::Rational(Rational::new(BigInt::from(self.random.below(20) as i32), BigInt::from(4)).unwrap()),
+        }
+    }
+
+    fn non_zero_literal(&mut self, literal_type: &Type) -> ArithmeticExpression {
+        loop {
+            let literal = self.literal(literal_type);
+            if !is_zero(&literal) {
+                return literal;
+            }
+        }
+    }
+
+    fn leaf(&mut self, types: &[Type]) -> ArithmeticExpression {
+        let variables = self.in_scope(types);
+        if !variables.is_empty() && !self.random.one_in(3) {
+            return ArithmeticExpression::Variable(self.random.choose(&variables).clone());
+        }
+
+        let literal_type = self.random.choose(types).clone();
+        self.literal(&literal_type)
+    }
+
+    fn expression(&mut self, variable_type: &Type, depth: usize) -> ArithmeticExpression {
+        let types = mixes_with(variable_type);
+        if depth == 0 || self.random.one_in(3) {
+            return self.leaf(types);
+        }
+
+        let operation = |operator, lhs, rhs| ArithmeticExpression::Operation(operator, Box::new(lhs), Box::new(rhs));
+        match self.random.below(6) {
+            0 => ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, Box::new(self.expression(variable_type, depth - 1))),
+            1 => operation(ArithmeticOperator::Addition, self.expression(variable_type, depth - 1), self.expression(variable_type, depth - 1)),
+            2 => operation(ArithmeticOperator::Subtraction, self.expression(variable_type, depth - 1), self.expression(variable_type, depth - 1)),
+            3 => {
+                let multiplier = if *variable_type == Type::Integer && self.random.one_in(2) {
+                    self.leaf(&[Type::Integer])
+                } else {
+                    let literal_type = self.random.choose(types).clone();
+                    self.literal(&literal_type)
+                };
+                operation(ArithmeticOperator::Multiplication, self.expression(variable_type, depth - 1), multiplier)
+            }
+            4 => {
+                let literal_type = self.random.choose(types).clone();
+                let divisor = self.non_zero_literal(&literal_type);
+                operation(ArithmeticOperator::Division, self.expression(variable_type, depth - 1), divisor)
+            }
+            _ => {
+                let base = if self.random.one_in(2) {
+                    self.leaf(&[Type::Integer])
+                } else {
+                    let literal_type = self.random.choose(types).clone();
+                    self.literal(&literal_type)
+                };
+                operation(ArithmeticOperator::Exponentiation, base, ArithmeticExpression::Integer(1 + self.random.below(3) as i32))
+            }
+        }
+    }
+
+    fn condition(&mut self, depth: usize) -> BooleanExpression {
+        if depth == 0 || self.random.one_in(2) {
+            return self.relation();
+        }
+
+        match self.random.below(3) {
+            0 => BooleanExpression::Unary(UnaryBooleanOperator::Negation, Box::new(self.condition(depth - 1))),
+            1 => BooleanExpression::Logical(BooleanOperator::And, Box::new(self.condition(depth - 1)), Box::new(self.condition(depth - 1))),
+            _ => BooleanExpression::Logical(BooleanOperator::Or, Box::new(self.condition(depth - 1)), Box::new(self.condition(depth - 1))),
+        }
+    }
+
+    fn relation(&mut self) -> BooleanExpression {
+        let compared = self.random.choose(&TYPES).clone();
+        let operator = self.random.choose(&[RelationOperator::Equal, RelationOperator::NotEqual, RelationOperator::LessThan, RelationOperator::GreaterThan]).clone();
+
+        // a condition starting with a parenthesis is read as a parenthesised condition
+        let mut lhs = self.expression(&compared, self.depth - 1);
+        if arithmetic(&lhs).starts_with('(') {
+            lhs = self.leaf(mixes_with(&compared));
+        }
+        let rhs = self.expression(&compared, self.depth - 1);
+
+        BooleanExpression::Relational(operator, Box::new(lhs), Box::new(rhs))
+    }
+}
+
+fn normalised_block(block: &Block, declared: &mut HashSet<String>) -> Block {
+    block
+        .iter()
+        .map(|statement| {
+            let value = match &statement.value {
+                Statement::Assignment(variable, expression) if declared.insert(name(variable).clone()) => {
+                    let expression = match (variable, expression) {
+                        (Variable::Float(_), ArithmeticExpression::Integer(integer)) => ArithmeticExpression::Float(NotNan::new(*integer as f32).unwrap()),
+                        (Variable::BigInteger(_), ArithmeticExpression::Integer(integer)) => ArithmeticExpression::BigInteger(BigInt::from(*integer)),
+                        (Variable::Rational(_), ArithmeticExpression::Integer(integer)) => ArithmeticExpression::Rational(Rational::from(*integer)),
+                        (Variable::Rational(_), ArithmeticExpression::BigInteger(integer)) => ArithmeticExpression::Rational(Rational::from(integer.clone())),
+                        (_, expression) => expression.clone(),
+                    };
+                    Statement::Assignment(variable.clone(), expression)
+                }
+                Statement::If(condition, then_block, else_block) => Statement::If(
+                    condition.clone(),
+                    Box::new(normalised_block(then_block, declared)),
+                    Box::new(normalised_block(else_block, declared)),
+                ),
+                Statement::While(condition, body) => Statement::While(condition.clone(), Box::new(normalised_block(body, declared))),
+                other => other.clone(),
+            };
+            Meta::new(value, statement.location)
+        })
+        .collect()
+}
+
+// converts what the parser converts when it is declared, a single int literal assigned to a float, bigint or rational
+pub fn normalised(program: &Program) -> Program {
+    let Program::Statements(statements) = program;
+    Program::Statements(normalised_block(statements, &mut HashSet::new()))
+}
+
+fn unlocated_block(block: &Block) -> Block {
+    block
+        .iter()
+        .map(|statement| {
+            let value = match &statement.value {
+                Statement::If(condition, then_block, else_block) => {
+                    Statement::If(condition.clone(), Box::new(unlocated_block(then_block)), Box::new(unlocated_block(else_block)))
+                }
+                Statement::While(condition, body) => Statement::While(condition.clone(), Box::new(unlocated_block(body))),
+                other => other.clone(),
+            };
+            Meta::new(value, Location::default())
+        })
+        .collect()
+}
+
+// the program with every location unknown, as a generated one is, so a parsed program can be compared with it
+pub fn without_locations(program: &Program) -> Program {
+    let Program::Statements(statements) = program;
+    Program::Statements(unlocated_block(statements))
+}
+
+fn type_name(variable_type: &Type) -> &'static str {
+    match variable_type {
+        Type::Integer => "int",
+        Type::Float => "float",
+        Type::BigInteger => "bigint",
+        Type::Rational => "rational",
+    }
+}
+
+// a rational literal as the lexer reads it, none when it isn't a decimal
+fn decimal(rational: &Rational) -> Option<String> {
+    if rational.is_integer() {
+        return Some(rational.numerator().to_string());
+    }
+
+    for places in 1..=20u32 {
+        let scale = BigInt::from(10).pow(places);
+        if (&scale / rational.denominator()) * rational.denominator().clone() == scale {
+            let digits = format!("{:0>width$}", (rational.numerator() * &(&scale / rational.denominator())).to_string(), width = places as usize + 1);
+            let (whole, fraction) = digits.split_at(digits.len() - places as usize);
+            return Some(format!("{}.{}", whole, fraction));
+        }
+    }
+    None
+}
+
+fn operand(expression: &ArithmeticExpression, parenthesised: bool) -> String {
+    if parenthesised {
+        format!("({})", arithmetic(expression))
+    } else {
+        arithmetic(expression)
+    }
+}
+
+// an expression as RIMP, with only the parentheses it needs, see parser::precedence
+pub fn arithmetic(expression: &ArithmeticExpression) -> String {
+    match expression {
+        ArithmeticExpression::Variable(variable) => name(variable).clone(),
+        ArithmeticExpression::Integer(integer) => integer.to_string(),
+        // debug formatting keeps the .0 of a whole float, which would otherwise be read as an int
+        ArithmeticExpression::Float(float) => format!("{:?}", float.into_inner()),
+        ArithmeticExpression::BigInteger(integer) => integer.to_string(),
+        ArithmeticExpression::Rational(rational) => format!("{}r", decimal(rational).expect("rational literals are decimals")),
+        ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, negated) => {
+            format!("-{}", operand(negated, !is_literal(negated) && !matches!(**negated, ArithmeticExpression::Variable(_))))
+        }
+        ArithmeticExpression::Operation(operator, lhs, rhs) => {
+            let (left_binding_power, right_binding_power) = arithmetic_binding_power(operator);
+            // a negation is always parenthesised, as it takes any exponent after it
+            let parenthesised = |expression: &ArithmeticExpression, binding_power: u8| match expression {
+                ArithmeticExpression::Operation(operator, _, _) => arithmetic_binding_power(operator).0 < binding_power,
+                ArithmeticExpression::Unary(_, _) => true,
+                _ => false,
+            };
+            format!(
+                "{} {} {}",
+                operand(lhs, parenthesised(lhs, left_binding_power)),
+                operator,
+                operand(rhs, parenthesised(rhs, right_binding_power))
+            )
+        }
+    }
+}
+
+pub fn boolean(expression: &BooleanExpression) -> String {
+    match expression {
+        BooleanExpression::Relational(operator, lhs, rhs) => format!("{} {} {}", arithmetic(lhs), operator, arithmetic(rhs)),
+        BooleanExpression::Unary(UnaryBooleanOperator::Negation, negated) => match **negated {
+            BooleanExpression::Relational(_, _, _) => format!("!{}", boolean(negated)),
+            _ => format!("!({})", boolean(negated)),
+        },
+        BooleanExpression::Logical(operator, lhs, rhs) => {
+            let (left_binding_power, right_binding_power) = boolean_operator_binding_power(operator);
+            let side = |expression: &BooleanExpression, binding_power: u8| match expression {
+                BooleanExpression::Logical(operator, _, _) if boolean_operator_binding_power(operator).0 < binding_power => {
+                    format!("({})", boolean(expression))
+                }
+                _ => boolean(expression),
+            };
+            let operator = match operator {
+                BooleanOperator::And => "&&",
+                BooleanOperator::Or => "||",
+            };
+            format!("{} {} {}", side(lhs, left_binding_power), operator, side(rhs, right_binding_power))
+        }
+    }
+}
+
+fn source_block(block: &Block, indent: usize, declared: &mut HashSet<String>, source: &mut String) {
+    for statement in block {
+        source.push_str(&" ".repeat(indent));
+        match &statement.value {
+            Statement::Skip => source.push_str("skip"),
+            Statement::Assignment(variable, expression) => {
+                if declared.insert(name(variable).clone()) {
+                    source.push_str(type_name(&variable_type(variable)));
+                    source.push(' ');
+                }
+                source.push_str(&format!("{} = {}", name(variable), arithmetic(expression)));
+            }
+            Statement::If(condition, then_block, else_block) => {
+                source.push_str(&format!("if {} then {{\n", boolean(condition)));
+                source_block(then_block, indent + 4, declared, source);
+                source.push_str(&format!("{}}} else {{\n", " ".repeat(indent)));
+                source_block(else_block, indent + 4, declared, source);
+                source.push_str(&format!("{}}}", " ".repeat(indent)));
+            }
+            Statement::While(condition, body) => {
+                source.push_str(&format!("while {} do {{\n", boolean(condition)));
+                source_block(body, indent + 4, declared, source);
+                source.push_str(&format!("{}}}", " ".repeat(indent)));
+            }
+            other => panic!("{:?} has no syntax", other),
+        }
+        source.push_str(";\n");
+    }
+}
+
+// the program as RIMP, declaring each variable where it is first assigned
+pub fn source(program: &Program) -> String {
+    let Program::Statements(statements) = program;
+    let mut source = String::new();
+    source_block(statements, 0, &mut HashSet::new(), &mut source);
+    source
+}
+
+struct Checker {
+    scopes: Vec<Vec<Variable>>,
+    declared: HashSet<String>,
+    // the counters of the loops being checked
+    counters: Vec<String>,
+}
+
+impl Checker {
+    fn in_scope(&self, variable: &Variable) -> bool {
+        self.scopes.iter().flatten().any(|declared| declared == variable)
+    }
+
+    fn literal(&self, expression: &ArithmeticExpression) -> bool {
+        match expression {
+            ArithmeticExpression::Integer(integer) => *integer >= 0,
+            ArithmeticExpression::Float(float) => float.into_inner() >= 0.0 && float.into_inner() < 100.0 && !format!("{:?}", float.into_inner()).contains('e'),
+            ArithmeticExpression::BigInteger(integer) => *integer > BigInt::from(i32::MAX),
+            ArithmeticExpression::Rational(rational) => *rational >= Rational::zero() && decimal(rational).is_some(),
+            _ => false,
+        }
+    }
+
+    fn expression(&self, expression: &ArithmeticExpression, variable_type: &Type) -> bool {
+        let int_leaf = |expression: &ArithmeticExpression| match expression {
+            ArithmeticExpression::Variable(variable @ Variable::Integer(_)) => self.in_scope(variable),
+            ArithmeticExpression::Integer(_) => self.literal(expression),
+            _ => false,
+        };
+
+        match expression {
+            ArithmeticExpression::Variable(variable) => self.in_scope(variable),
+            ArithmeticExpression::Unary(_, negated) => self.expression(negated, variable_type),
+            ArithmeticExpression::Operation(operator, lhs, rhs) => match operator {
+                ArithmeticOperator::Addition | ArithmeticOperator::Subtraction => {
+                    self.expression(lhs, variable_type) && self.expression(rhs, variable_type)
+                }
+                ArithmeticOperator::Multiplication => {
+                    self.expression(lhs, variable_type) && (self.literal(rhs) || (*variable_type == Type::Integer && int_leaf(rhs)))
+                }
+                ArithmeticOperator::Division => self.expression(lhs, variable_type) && self.literal(rhs) && !is_zero(rhs),
+                ArithmeticOperator::Exponentiation => {
+                    (self.literal(lhs) || int_leaf(lhs)) && matches!(**rhs, ArithmeticExpression::Integer(1..=3))
+                }
+            },
+            literal => self.literal(literal),
+        }
+    }
+
+    fn condition(&self, condition: &BooleanExpression) -> bool {
+        match condition {
+            BooleanExpression::Relational(_, lhs, rhs) => {
+                // compared values aren't kept, so they can be multiplied like ints
+                !arithmetic(lhs).starts_with('(') && self.expression(lhs, &Type::Integer) && self.expression(rhs, &Type::Integer)
+            }
+            BooleanExpression::Unary(_, negated) => self.condition(negated),
+            BooleanExpression::Logical(_, lhs, rhs) => self.condition(lhs) && self.condition(rhs),
+        }
+    }
+
+    fn block(&mut self, block: &[Meta<Statement>]) -> bool {
+        self.scopes.push(Vec::new());
+        let well_formed = block.iter().all(|statement| self.statement(&statement.value));
+        self.scopes.pop();
+        well_formed
+    }
+
+    fn statement(&mut self, statement: &Statement) -> bool {
+        match statement {
+            Statement::Skip => true,
+            Statement::Assignment(variable, expression) => {
+                // a declaration can be of any literal, as the parser converts it, see normalised
+                let declared_literal = !self.in_scope(variable) && is_literal(expression) && !format!("{:?}", expression).contains('-');
+                if !(declared_literal || self.expression(expression, &variable_type(variable))) || self.counters.contains(name(variable)) {
+                    return false;
+                }
+                if is_counter(variable) && !matches!(expression, ArithmeticExpression::Integer(0..=3)) {
+                    return false;
+                }
+                if !self.in_scope(variable) {
+                    // the first assignment declares it, which can only happen once
+                    if !self.declared.insert(name(variable).clone()) {
+                        return false;
+                    }
+                    self.scopes.last_mut().unwrap().push(variable.clone());
+                }
+                true
+            }
+            Statement::If(condition, then_block, else_block) => self.condition(condition) && self.block(then_block) && self.block(else_block),
+            Statement::While(condition, body) => {
+                let (counted, rest) = match &**condition {
+                    BooleanExpression::Logical(BooleanOperator::And, counted, rest) => (&**counted, Some(&**rest)),
+                    counted => (counted, None),
+                };
+                let counter = match counted {
+                    BooleanExpression::Relational(RelationOperator::GreaterThan, counter, zero) => match (&**counter, &**zero) {
+                        (ArithmeticExpression::Variable(counter), ArithmeticExpression::Integer(0)) if is_counter(counter) && self.in_scope(counter) => {
+                            counter.clone()
+                        }
+                        _ => return false,
+                    },
+                    _ => return false,
+                };
+                if rest.is_some_and(|rest| !self.condition(rest)) {
+                    return false;
+                }
+
+                let decrement = Statement::Assignment(
+                    counter.clone(),
+                    ArithmeticExpression::Operation(
+                        ArithmeticOperator::Subtraction,
+                        Box::new(ArithmeticExpression::Variable(counter.clone())),
+                        Box::new(ArithmeticExpression::Integer(1)),
+                    ),
+                );
+                match body.split_last() {
+                    Some((last, statements)) if last.value == decrement => {
+                        self.counters.push(name(&counter).clone());
+                        let well_formed = self.block(statements);
+                        self.counters.pop();
+                        well_formed
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+// whether the program keeps to the rules Generator follows
+pub fn well_formed(program: &Program) -> bool {
+    let Program::Statements(statements) = program;
+    Checker { scopes: Vec::new(), declared: HashSet::new(), counters: Vec::new() }.block(statements)
+}
+
+fn expression_candidates(expression: &ArithmeticExpression) -> Vec<ArithmeticExpression> {
+    match expression {
+        ArithmeticExpression::Unary(operator, negated) => {
+            let mut candidates = vec![(**negated).clone()];
+            candidates.extend(expression_candidates(negated).into_iter().map(|negated| ArithmeticExpression::Unary(operator.clone(), Box::new(negated))));
+            candidates
+        }
+        ArithmeticExpression::Operation(operator, lhs, rhs) => {
+            let mut candidates = vec![(**lhs).clone(), (**rhs).clone()];
+            candidates.extend(
+                expression_candidates(lhs)
+                    .into_iter()
+                    .map(|lhs| ArithmeticExpression::Operation(operator.clone(), Box::new(lhs), rhs.clone())),
+            );
+            candidates.extend(
+                expression_candidates(rhs)
+                    .into_iter()
+                    .map(|rhs| ArithmeticExpression::Operation(operator.clone(), lhs.clone(), Box::new(rhs))),
+            );
+            candidates
+        }
+        ArithmeticExpression::Integer(integer) if *integer > 1 => vec![ArithmeticExpression::Integer(0), ArithmeticExpression::Integer(1)],
+        ArithmeticExpression::Integer(1) => vec![ArithmeticExpression::Integer(0)],
+        ArithmeticExpression::Float(float) if float.into_inner() != 0.0 => vec![ArithmeticExpression::Float(NotNan::new(0.0).unwrap())],
+        ArithmeticExpression::Rational(rational) if !rational.is_zero() => vec![ArithmeticExpression::Rational(Rational::zero())],
+        _ => Vec::new(),
+    }
+}
+
+fn condition_candidates(condition: &BooleanExpression) -> Vec<BooleanExpression> {
+    match condition {
+        BooleanExpression::Relational(operator, lhs, rhs) => {
+            let mut candidates: Vec<BooleanExpression> = expression_candidates(lhs)
+                .into_iter()
+                .map(|lhs| BooleanExpression::Relational(operator.clone(), Box::new(lhs), rhs.clone()))
+                .collect();
+            candidates.extend(
+                expression_candidates(rhs)
+                    .into_iter()
+                    .map(|rhs| BooleanExpression::Relational(operator.clone(), lhs.clone(), Box::new(rhs))),
+            );
+            candidates
+        }
+        BooleanExpression::Unary(operator, negated) => {
+            let mut candidates = vec![(**negated).clone()];
+            candidates.extend(condition_candidates(negated).into_iter().map(|negated| BooleanExpression::Unary(operator.clone(), Box::new(negated))));
+            candidates
+        }
+        BooleanExpression::Logical(operator, lhs, rhs) => {
+            let mut candidates = vec![(**lhs).clone(), (**rhs).clone()];
+            candidates.extend(
+                condition_candidates(lhs)
+                    .into_iter()
+                    .map(|lhs| BooleanExpression::Logical(operator.clone(), Box::new(lhs), rhs.clone())),
+            );
+            candidates.extend(
+                condition_candidates(rhs)
+                    .into_iter()
+                    .map(|rhs| BooleanExpression::Logical(operator.clone(), lhs.clone(), Box::new(rhs))),
+            );
+            candidates
+        }
+    }
+}
+
+fn statement_candidates(statement: &Statement) -> Vec<Statement> {
+    match statement {
+        Statement::Assignment(variable, expression) => expression_candidates(expression)
+            .into_iter()
+            .map(|expression| Statement::Assignment(variable.clone(), expression))
+            .collect(),
+        Statement::If(condition, then_block, else_block) => {
+            let mut candidates: Vec<Statement> = condition_candidates(condition)
+                .into_iter()
+                .map(|condition| Statement::If(Box::new(condition), then_block.clone(), else_block.clone()))
+                .collect();
+            candidates.extend(
+                block_candidates(then_block)
+                    .into_iter()
+                    .map(|then_block| Statement::If(condition.clone(), Box::new(then_block), else_block.clone())),
+            );
+            candidates.extend(
+                block_candidates(else_block)
+                    .into_iter()
+                    .map(|else_block| Statement::If(condition.clone(), then_block.clone(), Box::new(else_block))),
+            );
+            candidates
+        }
+        Statement::While(condition, body) => {
+            let mut candidates: Vec<Statement> = condition_candidates(condition)
+                .into_iter()
+                .map(|condition| Statement::While(Box::new(condition), body.clone()))
+                .collect();
+            candidates.extend(block_candidates(body).into_iter().map(|body| Statement::While(condition.clone(), Box::new(body))));
+            candidates
+        }
+        _ => Vec::new(),
+    }
+}
+
+// smaller blocks first: without a statement, with an if or while replaced by its body, then with a smaller statement
+fn block_candidates(block: &Block) -> Vec<Block> {
+    let mut candidates = Vec::new();
+
+    for index in 0..block.len() {
+        let mut smaller = block.clone();
+        smaller.remove(index);
+        candidates.push(smaller);
+    }
+
+    for (index, statement) in block.iter().enumerate() {
+        let bodies = match &statement.value {
+            Statement::If(_, then_block, else_block) => vec![(**then_block).clone(), (**else_block).clone()],
+            Statement::While(_, body) => vec![body[..body.len().saturating_sub(1)].to_vec()],
+            _ => Vec::new(),
+        };
+        for body in bodies {
+            candidates.push(block[..index].iter().cloned().chain(body).chain(block[index + 1..].iter().cloned()).collect());
+        }
+    }
+
+    for (index, statement) in block.iter().enumerate() {
+        for smaller in statement_candidates(&statement.value) {
+            let mut candidate = block.clone();
+            candidate[index] = Meta::new(smaller, statement.location);
+            candidates.push(candidate);
+        }
+    }
+
+    candidates
+}
+
+// the smallest well formed program found that still fails, trying smaller programs until none of them fail
+pub fn shrink(program: &Program, fails: impl Fn(&Program) -> bool) -> Program {
+    let mut smallest = program.clone();
+
+    'shrinking: loop {
+        let Program::Statements(statements) = &smallest;
+        for candidate in block_candidates(statements) {
+            let candidate = normalised(&Program::Statements(candidate));
+            if well_formed(&candidate) && fails(&candidate) {
+                smallest = candidate;
+                continue 'shrinking;
+            }
+        }
+        return smallest;
+    }
+}
+
+// how many programs each property is checked on
+pub const PROGRAMS: u64 = 150;
+const STACK: usize = 64 << 20;
+
+// checks the property on every program, shrinking the first one it doesn't hold for, the parser and the engines
+// recurse on nesting so it runs with more stack than a test thread has
+pub fn check(property: &str, holds: impl Fn(&Program) -> bool + Sync) {
+    let holds = |program: &Program| catch_unwind(AssertUnwindSafe(|| holds(program))).unwrap_or(false);
+
+    std::thread::scope(|scope| {
+        let checker = std::thread::Builder::new().stack_size(STACK).spawn_scoped(scope, || {
+            for seed in 0..PROGRAMS {
+                let program = Generator::new(seed).generate();
+                if !holds(&program) {
+                    let smallest = shrink(&program, |program| !holds(program));
+                    return Err(format!("{} doesn't hold for seed {}, which shrinks to\n{}", property, seed, source(&smallest)));
+                }
+            }
+            Ok(())
+        });
+        if let Err(failure) = checker.unwrap().join().unwrap() {
+            panic!("{}", failure);
+        }
+    });
+}
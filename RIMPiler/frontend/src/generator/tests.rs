@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use super::{check, shrink, source, well_formed, without_locations, Generator, PROGRAMS};
+use super::super::abstract_machine::engine::Engine;
+use super::super::differential;
+use super::super::interpreter::compiled::compile;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::lexer::lexer::Tokeniser;
+use super::super::parser::parser::parse_without_transform;
+use super::super::post_parse::inverter::invert_and_combine;
+use super::super::post_parse::transformer::transform;
+use super::super::verify::{verify_abstract_machine, verify_interpreter};
+use super::super::AST::{ArithmeticExpression, Block, BooleanExpression, Program, Statement};
+
+fn parsed(source: &str) -> Program {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(source.to_string()).unwrap();
+
+    parse_without_transform(&mut tokens.into()).unwrap()
+}
+
+#[test]
+fn generates_well_formed_programs() {
+    for seed in 0..PROGRAMS {
+        let program = Generator::new(seed).generate();
+        assert!(well_formed(&program), "seed {}\n{}", seed, source(&program));
+    }
+
+    assert_eq!(Generator::new(7).generate(), Generator::new(7).generate());
+    assert_ne!(Generator::new(7).generate(), Generator::new(8).generate());
+
+    // without nesting there are only assignments
+    for seed in 0..PROGRAMS {
+        let program = Generator::new(seed).depth(1).statements(8).nesting(0).generate();
+        assert!(well_formed(&program), "seed {}\n{}", seed, source(&program));
+        assert!(!source(&program).contains('{'), "seed {}\n{}", seed, source(&program));
+    }
+}
+
+#[test]
+fn parsing_the_source_gives_the_program() {
+    check("parse(source(program)) == program", |program| without_locations(&parsed(&source(program))) == *program);
+}
+
+#[test]
+fn transforming_then_inverting_restores_the_store() {
+    check("running the combined program restores every variable", |program| {
+        let mut interpreter = InterpreterEngine::new();
+        verify_interpreter(&mut interpreter, &invert_and_combine(&transform(program))) == Ok(Vec::new())
+    });
+
+    check("reversing the abstract machine restores every variable", |program| {
+        verify_abstract_machine(&mut Engine::new(program.clone())) == Ok(Vec::new())
+    });
+}
+
+#[test]
+fn interpreter_equals_abstract_machine() {
+    check("the interpreter and the abstract machine agree", |program| {
+        let source = source(program);
+        differential::interpreter(&source) == differential::abstract_machine(&source)
+    });
+}
+
+#[test]
+fn compiled_equals_interpreted() {
+    check("the compiled program does what the interpreter does", |program| {
+        let combined = invert_and_combine(&transform(program));
+        let mut interpreted = InterpreterEngine::new();
+        let mut compiled = InterpreterEngine::new();
+
+        compiled.run(&compile(&combined)) == interpreted.interpret(&combined)
+            && compiled.get_memory_store() == interpreted.get_memory_store()
+            && compiled.get_reverse_point_snapshot() == interpreted.get_reverse_point_snapshot()
+    });
+}
+
+fn expression_constructs(expression: &ArithmeticExpression, seen: &mut HashSet<String>) {
+    let construct = format!("{:?}", expression);
+    seen.insert(construct[..construct.find('(').unwrap()].to_string());
+    match expression {
+        ArithmeticExpression::Unary(operator, negated) => {
+            seen.insert(format!("{:?}", operator));
+            expression_constructs(negated, seen);
+        }
+        ArithmeticExpression::Operation(operator, lhs, rhs) => {
+            seen.insert(format!("{:?}", operator));
+            expression_constructs(lhs, seen);
+            expression_constructs(rhs, seen);
+        }
+        _ => {}
+    }
+}
+
+fn condition_constructs(condition: &BooleanExpression, seen: &mut HashSet<String>) {
+    match condition {
+        BooleanExpression::Relational(operator, lhs, rhs) => {
+            seen.insert(format!("{:?}", operator));
+            expression_constructs(lhs, seen);
+            expression_constructs(rhs, seen);
+        }
+        BooleanExpression::Unary(operator, negated) => {
+            seen.insert(format!("Not{:?}", operator));
+            condition_constructs(negated, seen);
+        }
+        BooleanExpression::Logical(operator, lhs, rhs) => {
+            seen.insert(format!("{:?}", operator));
+            condition_constructs(lhs, seen);
+            condition_constructs(rhs, seen);
+        }
+    }
+}
+
+fn block_constructs(block: &Block, seen: &mut HashSet<String>) {
+    for statement in block {
+        let construct = format!("{:?}", statement.value);
+        seen.insert(construct[..construct.find('(').unwrap_or(construct.len())].to_string());
+        match &statement.value {
+            Statement::If(condition, then_block, else_block) => {
+                condition_constructs(condition, seen);
+                block_constructs(then_block, seen);
+                block_constructs(else_block, seen);
+            }
+            Statement::While(condition, body) => {
+                condition_constructs(condition, seen);
+                block_constructs(body, seen);
+            }
+            Statement::Assignment(_, expression) | Statement::ReverseAssignment(_, expression) | Statement::DirectAssignment(_, expression, _) => {
+                expression_constructs(expression, seen)
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn covers_every_construct() {
+    let mut seen = HashSet::new();
+    for seed in 0..PROGRAMS {
+        let Program::Statements(statements) = invert_and_combine(&transform(&Generator::new(seed).generate()));
+        block_constructs(&statements, &mut seen);
+    }
+
+    let constructs = [
+        "Skip", "If", "While", "Assignment", "ReverseAssignment", "DirectAssignment", "ReversePoint",
+        "Variable", "Integer", "Float", "BigInteger", "Rational", "Unary", "Operation",
+        "Negation", "Addition", "Subtraction", "Multiplication", "Division", "Exponentiation",
+        "And", "Or", "NotNegation", "Equal", "NotEqual", "LessThan", "GreaterThan",
+    ];
+    for construct in constructs {
+        assert!(seen.contains(construct), "{} was never generated", construct);
+    }
+}
+
+#[test]
+fn shrinks_to_a_small_program() {
+    let has_while = |program: &Program| source(program).contains("while");
+
+    let seed = (0..PROGRAMS).find(|seed| has_while(&Generator::new(*seed).generate())).unwrap();
+    let smallest = shrink(&Generator::new(seed).generate(), has_while);
+
+    let Program::Statements(statements) = &smallest;
+    assert_eq!(statements.len(), 2, "{}", source(&smallest));
+    let counter = source(&smallest).split_whitespace().nth(1).unwrap().to_string();
+    assert_eq!(source(&smallest), format!("int {0} = 0;\nwhile {0} > 0 do {{\n    {0} = {0} - 1;\n}};\n", counter));
+
+    // shrinking keeps to the rules, so a program that needs a variable keeps its declaration
+    let uses_v0 = |program: &Program| source(program).contains("= v0") || source(program).contains("v0 =");
+    let seed = (0..PROGRAMS).find(|seed| uses_v0(&Generator::new(*seed).generate())).unwrap();
+    assert!(well_formed(&shrink(&Generator::new(seed).generate(), uses_v0)));
+}
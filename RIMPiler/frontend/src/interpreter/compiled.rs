@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use super::error::{RuntimeError, Snapshot};
+use super::interpreter::{
+    check_history, check_steps, converted, name, negate, negate_integer, operate, operate_integers, relate,
+    used_variables, variable_type
+};
+use super::memory_store::{MemoryStore, MemoryStoreElement, MemoryStoreTrait, Value};
+use super::super::AST::{
+    ArithmeticExpression, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement,
+    UnaryArithmeticOperator, UnaryBooleanOperator, Variable
+};
+use super::super::overflow::Overflow;
+use utilities::debug::{Location, Meta};
+
+/*
+Programs compiled for InterpreterEngine::run, which gives the same results as InterpreterEngine::interpret, errors
+and budgets included, in less time.
+
+interpret looks each variable up by name in the MemoryStore's HashMap every time it is read or written, and walks
+the AST, matching on every node each time it runs. Here a resolution pass gives each variable a slot first, its
+index in a Vec, and the program is compiled to a tree of closures that only read and write slots, so a loop does
+little more than its arithmetic. Expressions with only ints in them, which the types in the AST show, are compiled
+to closures that give an i32 rather than a Value, so they skip promoting their operands. The reverse point copies the Vec instead of the HashMap, and the slots are turned
+back into a MemoryStore when the program stops, so the results look the same as interpret's.
+*/
+
+// what the variables are kept in while a compiled program runs, None until a variable is first assigned or set
+pub(super) type Slots = Vec<Option<MemoryStoreElement>>;
+
+type Run = Box<dyn Fn(&mut Machine) -> Result<(), RuntimeError>>;
+type Evaluate = Box<dyn Fn(&mut Machine) -> Result<Value, RuntimeError>>;
+type EvaluateInteger = Box<dyn Fn(&mut Machine) -> Result<i32, RuntimeError>>;
+type Test = Box<dyn Fn(&mut Machine) -> Result<bool, RuntimeError>>;
+
+// the variables of a program, numbered in the order they first appear
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolution {
+    names: Vec<String>,
+    slots: HashMap<String, usize>,
+}
+
+impl Resolution {
+    pub fn new(program: &Program) -> Self {
+        let mut resolution = Resolution::default();
+        match program {
+            Program::Statements(statements) => resolution.block(statements),
+        }
+        resolution
+    }
+
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.slots.get(name).copied()
+    }
+
+    pub fn name(&self, slot: usize) -> &String {
+        &self.names[slot]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    fn resolve(&mut self, name: &String) -> usize {
+        match self.slots.get(name) {
+            Some(slot) => *slot,
+            None => {
+                self.slots.insert(name.clone(), self.names.len());
+                self.names.push(name.clone());
+                self.names.len() - 1
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for statement in block {
+            for name in used_variables(&statement.value) {
+                self.resolve(&name);
+            }
+
+            match &statement.value {
+                Statement::If(_, if_block, else_block) => {
+                    self.block(if_block);
+                    self.block(else_block);
+                }
+                Statement::While(_, block) => self.block(block),
+                _ => {}
+            }
+        }
+    }
+}
+
+// the state of a compiled program as it runs, see InterpreterEngine::run
+pub(super) struct Machine {
+    pub(super) slots: Slots,
+    pub(super) history_len: usize,
+    pub(super) reverse_point: Option<(Slots, usize)>,
+    pub(super) overflow: Overflow,
+    pub(super) max_steps: Option<usize>,
+    pub(super) max_history: Option<usize>,
+    pub(super) steps: usize,
+    // see MemoryStore::checkpoints
+    pub(super) checkpoints: Option<usize>,
+}
+
+impl Machine {
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        check_steps(self.steps, self.max_steps)
+    }
+
+    fn read(&self, slot: usize, name: &str) -> Result<Value, RuntimeError> {
+        match &self.slots[slot] {
+            Some(element) => Ok(element.get()),
+            None => Err(undefined(name)),
+        }
+    }
+
+    // an int variable only ever holds ints, as MemoryStoreElement::assign won't give it anything else, unless the
+    // name was declared as another type first
+    fn read_integer(&self, slot: usize, name: &String) -> Result<i32, RuntimeError> {
+        match &self.slots[slot] {
+            Some(MemoryStoreElement::Integer(integer)) => Ok(integer.get()),
+            Some(element) => Err(RuntimeError::TypeMismatch {
+                location: Location::default(),
+                variables: Vec::new(),
+                message: format!("{} is used as an int, but holds {}", name, element.get()),
+            }),
+            None => Err(undefined(name)),
+        }
+    }
+
+    // like MemoryStore::assign
+    fn assign(&mut self, slot: usize, value: Value) -> Result<(), RuntimeError> {
+        match self.slots[slot].as_mut() {
+            Some(element) => element.assign(value)?,
+            None => self.slots[slot] = Some(MemoryStoreElement::first_assigned(value, self.checkpoints)),
+        }
+
+        self.history_len += 1;
+        check_history(self.history_len, self.max_history)
+    }
+
+    fn assign_integer(&mut self, slot: usize, value: i32) -> Result<(), RuntimeError> {
+        match self.slots[slot].as_mut() {
+            Some(MemoryStoreElement::Integer(integer)) => {
+                self.history_len += 1;
+                integer.assign(value);
+                check_history(self.history_len, self.max_history)
+            }
+            _ => self.assign(slot, Value::Integer(value)),
+        }
+    }
+
+    // like MemoryStore::un_assign
+    fn un_assign(&mut self, slot: usize, name: &String, value: Value) -> Result<(), RuntimeError> {
+        match self.slots[slot].as_mut() {
+            Some(element) => {
+                element.un_assign(name, value)?;
+                self.history_len = self.history_len.saturating_sub(1);
+                Ok(())
+            }
+            None => Err(undefined(name)),
+        }
+    }
+
+    // like MemoryStore::set
+    fn set(&mut self, slot: usize, value: Value) -> Result<(), RuntimeError> {
+        match self.slots[slot].as_mut() {
+            Some(element) => element.set(value)?,
+            None => self.slots[slot] = Some(MemoryStoreElement::first_set(value, self.checkpoints)),
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self, used: &[(String, usize)]) -> Snapshot {
+        used.iter()
+            .filter_map(|(name, slot)| self.slots[*slot].as_ref().map(|element| (name.clone(), element.get())))
+            .collect()
+    }
+}
+
+pub struct CompiledProgram {
+    resolution: Resolution,
+    statements: Vec<Run>,
+}
+
+impl CompiledProgram {
+    pub fn resolution(&self) -> &Resolution {
+        &self.resolution
+    }
+
+    pub(super) fn run(&self, machine: &mut Machine) -> Result<(), RuntimeError> {
+        for statement in &self.statements {
+            statement(machine)?;
+        }
+        Ok(())
+    }
+
+    // the program's variables as they are in the store, which is where a run starts from
+    pub(super) fn slots(&self, store: &MemoryStore) -> Slots {
+        self.resolution.names.iter().map(|name| store.get(name).cloned()).collect()
+    }
+
+    // the store with the variables from the slots
+    pub(super) fn store(&self, store: &MemoryStore, slots: &Slots, history_len: usize) -> MemoryStore {
+        let elements = slots.iter().enumerate().filter_map(|(slot, element)| {
+            element.as_ref().map(|element| (self.resolution.name(slot).clone(), element.clone()))
+        });
+        store.replaced(elements, history_len)
+    }
+}
+
+pub fn compile(program: &Program) -> CompiledProgram {
+    let mut resolution = Resolution::new(program);
+
+    let statements = match program {
+        Program::Statements(statements) => statements.iter().map(|statement| located_statement(&mut resolution, statement)).collect(),
+    };
+
+    CompiledProgram { resolution, statements }
+}
+
+fn undefined(name: &str) -> RuntimeError {
+    RuntimeError::UndefinedVariable { location: Location::default(), variables: Vec::new(), variable: name.to_string() }
+}
+
+// the variable's slot, which the resolution pass has given every variable in the program already, so this only
+// gives a variable a slot of its own if the two ever disagree
+fn slot(resolution: &mut Resolution, name: &String) -> usize {
+    resolution.resolve(name)
+}
+
+// errors are placed at the statement like InterpreterEngine::interpret_located_statement does
+fn located_statement(resolution: &mut Resolution, statement: &Meta<Statement>) -> Run {
+    let location = statement.location;
+    let used: Vec<(String, usize)> = used_variables(&statement.value)
+        .into_iter()
+        .map(|name| {
+            let slot = slot(resolution, &name);
+            (name, slot)
+        })
+        .collect();
+    let run = compile_statement(resolution, &statement.value);
+
+    Box::new(move |machine| {
+        machine.step()
+            .and_then(|_| run(machine))
+            .map_err(|error| error.at(location, machine.snapshot(&used)))
+    })
+}
+
+fn compile_block(resolution: &mut Resolution, block: &Block) -> Run {
+    let statements: Vec<Run> = block.iter().map(|statement| located_statement(resolution, statement)).collect();
+
+    Box::new(move |machine| {
+        for statement in &statements {
+            statement(machine)?;
+        }
+        Ok(())
+    })
+}
+
+fn compile_statement(resolution: &mut Resolution, statement: &Statement) -> Run {
+    match statement {
+        Statement::Skip => Box::new(|_| Ok(())),
+        Statement::Assignment(variable @ Variable::Integer(_), expression) if is_integer(expression) => {
+            let slot = slot(resolution, name(variable));
+            let value = compile_integer(resolution, expression);
+
+            Box::new(move |machine| {
+                let value = value(machine)?;
+                machine.assign_integer(slot, value)
+            })
+        }
+        Statement::Assignment(variable, expression) => {
+            let slot = slot(resolution, name(variable));
+            let value = converted_expression(resolution, variable, expression);
+
+            Box::new(move |machine| {
+                let value = value(machine)?;
+                machine.assign(slot, value)
+            })
+        }
+        Statement::ReverseAssignment(variable, expression) => {
+            let name = name(variable).clone();
+            let slot = slot(resolution, &name);
+            let value = converted_expression(resolution, variable, expression);
+
+            Box::new(move |machine| {
+                let value = value(machine)?;
+                machine.un_assign(slot, &name, value)
+            })
+        }
+        Statement::DirectAssignment(variable, expression, _) => {
+            let slot = slot(resolution, name(variable));
+            let value = converted_expression(resolution, variable, expression);
+
+            Box::new(move |machine| {
+                let value = value(machine)?;
+                machine.set(slot, value)
+            })
+        }
+        Statement::ReversePoint => Box::new(|machine| {
+            machine.reverse_point = Some((machine.slots.clone(), machine.history_len));
+            Ok(())
+        }),
+        Statement::If(condition, if_block, else_block) => {
+            let condition = compile_boolean(resolution, condition);
+            let if_block = compile_block(resolution, if_block);
+            let else_block = compile_block(resolution, else_block);
+
+            Box::new(move |machine| {
+                if condition(machine)? {
+                    if_block(machine)
+                } else {
+                    else_block(machine)
+                }
+            })
+        }
+        Statement::While(condition, block) => {
+            let condition = compile_boolean(resolution, condition);
+            let block = compile_block(resolution, block);
+
+            Box::new(move |machine| {
+                while condition(machine)? {
+                    machine.step()?;
+                    block(machine)?;
+                }
+                Ok(())
+            })
+        }
+    }
+}
+
+fn converted_expression(resolution: &mut Resolution, variable: &Variable, expression: &ArithmeticExpression) -> Evaluate {
+    let variable_type = variable_type(variable);
+    let value = compile_arithmetic(resolution, expression);
+    // only needed for the error when the value isn't a number
+    let expression = expression.clone();
+
+    Box::new(move |machine| converted(value(machine)?, &variable_type, &expression))
+}
+
+// both sides of && and || are evaluated, as interpret does, so the same errors stop the program
+fn compile_boolean(resolution: &mut Resolution, expression: &BooleanExpression) -> Test {
+    match expression {
+        BooleanExpression::Relational(operator, left_hand_side, right_hand_side)
+            if is_integer(left_hand_side) && is_integer(right_hand_side) => {
+            let operator = operator.clone();
+            let left_hand_side = compile_integer(resolution, left_hand_side);
+            let right_hand_side = compile_integer(resolution, right_hand_side);
+
+            Box::new(move |machine| {
+                let left_hand_side = left_hand_side(machine)?;
+                let right_hand_side = right_hand_side(machine)?;
+                Ok(match operator {
+                    RelationOperator::GreaterThan => left_hand_side > right_hand_side,
+                    RelationOperator::LessThan => left_hand_side < right_hand_side,
+                    RelationOperator::Equal => left_hand_side == right_hand_side,
+                    RelationOperator::NotEqual => left_hand_side != right_hand_side,
+                })
+            })
+        }
+        BooleanExpression::Relational(operator, left_hand_side, right_hand_side) => {
+            let operator = operator.clone();
+            let left_hand_side = compile_arithmetic(resolution, left_hand_side);
+            let right_hand_side = compile_arithmetic(resolution, right_hand_side);
+
+            Box::new(move |machine| {
+                let left_hand_side = left_hand_side(machine)?;
+                let right_hand_side = right_hand_side(machine)?;
+                Ok(relate(&operator, &left_hand_side, &right_hand_side))
+            })
+        }
+        BooleanExpression::Logical(operator, left_hand_side, right_hand_side) => {
+            let left_hand_side = compile_boolean(resolution, left_hand_side);
+            let right_hand_side = compile_boolean(resolution, right_hand_side);
+
+            match operator {
+                BooleanOperator::And => Box::new(move |machine| {
+                    let left_hand_side = left_hand_side(machine)?;
+                    let right_hand_side = right_hand_side(machine)?;
+                    Ok(left_hand_side && right_hand_side)
+                }),
+                BooleanOperator::Or => Box::new(move |machine| {
+                    let left_hand_side = left_hand_side(machine)?;
+                    let right_hand_side = right_hand_side(machine)?;
+                    Ok(left_hand_side || right_hand_side)
+                }),
+            }
+        }
+        BooleanExpression::Unary(UnaryBooleanOperator::Negation, operand) => {
+            let operand = compile_boolean(resolution, operand);
+            Box::new(move |machine| Ok(!operand(machine)?))
+        }
+    }
+}
+
+// whether the expression only has ints in it, so it is an int too
+fn is_integer(expression: &ArithmeticExpression) -> bool {
+    match expression {
+        ArithmeticExpression::Variable(variable) => matches!(variable, Variable::Integer(_)),
+        ArithmeticExpression::Integer(_) => true,
+        ArithmeticExpression::Float(_) | ArithmeticExpression::BigInteger(_) | ArithmeticExpression::Rational(_) => false,
+        ArithmeticExpression::Unary(_, operand) => is_integer(operand),
+        ArithmeticExpression::Operation(_, left_hand_side, right_hand_side) => {
+            is_integer(left_hand_side) && is_integer(right_hand_side)
+        }
+    }
+}
+
+// an expression is_integer says is an int
+fn compile_integer(resolution: &mut Resolution, expression: &ArithmeticExpression) -> EvaluateInteger {
+    match expression {
+        ArithmeticExpression::Variable(variable) => {
+            let name = name(variable).clone();
+            let slot = slot(resolution, &name);
+            Box::new(move |machine| machine.read_integer(slot, &name))
+        }
+        ArithmeticExpression::Integer(integer) => {
+            let integer = *integer;
+            Box::new(move |_| Ok(integer))
+        }
+        ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, operand) => {
+            let operand = compile_integer(resolution, operand);
+            Box::new(move |machine| {
+                let operand = operand(machine)?;
+                negate_integer(machine.overflow, operand)
+            })
+        }
+        ArithmeticExpression::Operation(operator, left_hand_side, right_hand_side) => {
+            let operator = operator.clone();
+            let left_hand_side = compile_integer(resolution, left_hand_side);
+            let right_hand_side = compile_integer(resolution, right_hand_side);
+
+            Box::new(move |machine| {
+                let left_hand_side = left_hand_side(machine)?;
+                let right_hand_side = right_hand_side(machine)?;
+                operate_integers(machine.overflow, &operator, left_hand_side, right_hand_side)
+            })
+        }
+        _ => unreachable!("{} isn't an int", expression),
+    }
+}
+
+fn compile_arithmetic(resolution: &mut Resolution, expression: &ArithmeticExpression) -> Evaluate {
+    if is_integer(expression) {
+        let integer = compile_integer(resolution, expression);
+        return Box::new(move |machine| integer(machine).map(Value::Integer));
+    }
+
+    match expression {
+        ArithmeticExpression::Variable(variable) => {
+            let name = name(variable).clone();
+            let slot = slot(resolution, &name);
+            Box::new(move |machine| machine.read(slot, &name))
+        }
+        ArithmeticExpression::Integer(integer) => {
+            let integer = *integer;
+            Box::new(move |_| Ok(Value::Integer(integer)))
+        }
+        ArithmeticExpression::Float(float) => {
+            let float = float.into_inner();
+            Box::new(move |_| Ok(Value::Float(float)))
+        }
+        ArithmeticExpression::BigInteger(integer) => {
+            let integer = integer.clone();
+            Box::new(move |_| Ok(Value::BigInteger(integer.clone())))
+        }
+        ArithmeticExpression::Rational(rational) => {
+            let rational = rational.clone();
+            Box::new(move |_| Ok(Value::Rational(rational.clone())))
+        }
+        ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, operand) => {
+            let operand = compile_arithmetic(resolution, operand);
+            Box::new(move |machine| {
+                let operand = operand(machine)?;
+                negate(machine.overflow, operand)
+            })
+        }
+        ArithmeticExpression::Operation(operator, left_hand_side, right_hand_side) => {
+            let operator = operator.clone();
+            let left_hand_side = compile_arithmetic(resolution, left_hand_side);
+            let right_hand_side = compile_arithmetic(resolution, right_hand_side);
+
+            Box::new(move |machine| {
+                let left_hand_side = left_hand_side(machine)?;
+                let right_hand_side = right_hand_side(machine)?;
+                operate(machine.overflow, &operator, left_hand_side, right_hand_side)
+            })
+        }
+    }
+}
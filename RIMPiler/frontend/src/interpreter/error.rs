@@ -0,0 +1,114 @@
+use std::fmt::{Display, Formatter};
+use utilities::debug::Location;
+use super::memory_store::Value;
+
+/*
+The errors that stop the interpreter.
+
+Each one has the statement it happened in and the values of the variables that statement uses. Errors found by
+the memory store or a Value don't know either yet, so they start with an unknown location and no variables, and
+InterpreterEngine fills them in as the error leaves the statement. Statements the inverter added have no
+location, so an error in one of them is reported at the statement around it.
+
+There is no AssertionFailed, as RIMP has no assert statement for one to come from. It belongs with that statement
+if the language gets one.
+*/
+
+// the variables an error is about, with their values when it happened
+pub type Snapshot = Vec<(String, Value)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    // an int, bigint or rational divided by zero, floats give an infinity instead
+    DivisionByZero { location: Location, variables: Snapshot, operation: String },
+    // int arithmetic that doesn't fit in 32 bits in checked mode, see overflow
+    Overflow { location: Location, variables: Snapshot, operation: String },
+    UndefinedVariable { location: Location, variables: Snapshot, variable: String },
+    // a value that can't be held by the type it is used as, like an infinite float assigned to a bigint
+    TypeMismatch { location: Location, variables: Snapshot, message: String },
+    // a power that has no value the base's type can hold, like an int raised to a power that isn't positive
+    InvalidExponent { location: Location, variables: Snapshot, base: String, exponent: String },
+    // undoing an assignment of a variable that has nothing left in its history
+    HistoryUnderflow { location: Location, variables: Snapshot, variable: String },
+    // the program ran for longer than it was allowed to
+    StepLimitExceeded { location: Location, variables: Snapshot, limit: usize },
+    // the histories held more entries than they were allowed to, see MemoryStore::history_len
+    HistoryLimitExceeded { location: Location, variables: Snapshot, limit: usize },
+    // a direct assignment whose inverse doesn't give back the value it replaced, see
+    // InterpreterEngine::interpret_checking_inverses
+    BrokenInverse { location: Location, variables: Snapshot, message: String },
+}
+
+impl RuntimeError {
+    pub fn location(&self) -> Location {
+        *self.parts().0
+    }
+
+    pub fn variables(&self) -> &Snapshot {
+        self.parts().1
+    }
+
+    // places the error at a statement and the variables it uses, unless it is already in one inside it
+    pub fn at(mut self, location: Location, variables: Snapshot) -> RuntimeError {
+        let (current_location, current_variables) = self.parts_mut();
+        if current_location.is_unknown() {
+            *current_location = location;
+            *current_variables = variables;
+        }
+        self
+    }
+
+    // what went wrong, without where or the variables, for the abstract machine and the JVM, which have neither
+    pub fn message(&self) -> String {
+        match self {
+            RuntimeError::DivisionByZero { operation, .. } => format!("Division by zero, {}", operation),
+            RuntimeError::Overflow { operation, .. } => format!("Integer overflow, {} doesn't fit in 32 bits", operation),
+            RuntimeError::UndefinedVariable { variable, .. } => format!("Variable {} is not defined", variable),
+            RuntimeError::TypeMismatch { message, .. } | RuntimeError::BrokenInverse { message, .. } => message.clone(),
+            RuntimeError::InvalidExponent { base, exponent, .. } => format!("Cannot raise {} to the power of {}", base, exponent),
+            RuntimeError::HistoryUnderflow { variable, .. } => format!("The history of {} is empty, so it can't be undone", variable),
+            RuntimeError::StepLimitExceeded { limit, .. } => format!("The program didn't finish within {} steps", limit),
+            RuntimeError::HistoryLimitExceeded { limit, .. } => format!("The history grew past {} entries before the program finished", limit),
+        }
+    }
+
+    fn parts(&self) -> (&Location, &Snapshot) {
+        match self {
+            RuntimeError::DivisionByZero { location, variables, .. }
+            | RuntimeError::Overflow { location, variables, .. }
+            | RuntimeError::UndefinedVariable { location, variables, .. }
+            | RuntimeError::TypeMismatch { location, variables, .. }
+            | RuntimeError::InvalidExponent { location, variables, .. }
+            | RuntimeError::HistoryUnderflow { location, variables, .. }
+            | RuntimeError::StepLimitExceeded { location, variables, .. }
+            | RuntimeError::HistoryLimitExceeded { location, variables, .. }
+            | RuntimeError::BrokenInverse { location, variables, .. } => (location, variables),
+        }
+    }
+
+    fn parts_mut(&mut self) -> (&mut Location, &mut Snapshot) {
+        match self {
+            RuntimeError::DivisionByZero { location, variables, .. }
+            | RuntimeError::Overflow { location, variables, .. }
+            | RuntimeError::UndefinedVariable { location, variables, .. }
+            | RuntimeError::TypeMismatch { location, variables, .. }
+            | RuntimeError::InvalidExponent { location, variables, .. }
+            | RuntimeError::HistoryUnderflow { location, variables, .. }
+            | RuntimeError::StepLimitExceeded { location, variables, .. }
+            | RuntimeError::HistoryLimitExceeded { location, variables, .. }
+            | RuntimeError::BrokenInverse { location, variables, .. } => (location, variables),
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.location(), self.message())?;
+
+        for (index, (variable, value)) in self.variables().iter().enumerate() {
+            let separator = if index == 0 { "\n\twhere " } else { ", " };
+            write!(f, "{}{} = {}", separator, variable, value)?;
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,30 @@
+use super::compiled::{CompiledProgram, Machine};
+use super::error::{RuntimeError, Snapshot};
 use super::memory_store::{MemoryStore, MemoryStoreElement, Value};
-use super::super::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
+use super::profile::Profile;
+use super::super::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, Type, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
+use super::super::overflow::Overflow;
+use utilities::debug::{Location, Meta};
+
+pub(super) fn name(variable: &Variable) -> &String {
+    match variable {
+        Variable::Integer(name) | Variable::Float(name) | Variable::BigInteger(name) | Variable::Rational(name) => name,
+    }
+}
 
 pub struct InterpreterEngine {
     memory_store: MemoryStore,
     reverse_point_snapshot: Option<MemoryStore>,
     final_memory_point_snapshot: Option<MemoryStore>,
+    overflow: Overflow,
+    // no limit when None, see step
+    max_steps: Option<usize>,
+    max_history: Option<usize>,
+    steps: usize,
+    // None unless profiling, see profile
+    profile: Option<Profile>,
+    // see interpret_checking_inverses
+    check_inverses: bool,
 }
 
 impl InterpreterEngine {
@@ -13,9 +33,51 @@ impl InterpreterEngine {
             memory_store: MemoryStore::new(),
             reverse_point_snapshot: None,
             final_memory_point_snapshot: None,
+            overflow: Overflow::default(),
+            max_steps: None,
+            max_history: None,
+            steps: 0,
+            profile: None,
+            check_inverses: false,
         }
     }
 
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn max_steps(mut self, max_steps: Option<usize>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn max_history(mut self, max_history: Option<usize>) -> Self {
+        self.max_history = max_history;
+        self
+    }
+
+    // see MemoryStore::checkpoints
+    pub fn checkpoints(mut self, every: Option<usize>) -> Self {
+        self.memory_store = self.memory_store.checkpoints(every);
+        self
+    }
+
+    // counts what interpret does, run doesn't profile
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile.then(Profile::new);
+        self
+    }
+
+    pub fn get_profile(&self) -> &Option<Profile> {
+        &self.profile
+    }
+
+    // the store as it is now, which is where the program got to if it stopped with an error
+    pub fn get_memory_store(&self) -> &MemoryStore {
+        &self.memory_store
+    }
+
     pub fn get_final_memory_point_snapshot(&self) -> &Option<MemoryStore> {
         &self.final_memory_point_snapshot
     }
@@ -39,11 +101,11 @@ impl InterpreterEngine {
         }
     }
 
-    pub fn interpret(&mut self, program: &Program) -> Result<(), String> {
+    pub fn interpret(&mut self, program: &Program) -> Result<(), RuntimeError> {
         match program {
             Program::Statements(statement) => {
                 for statement in statement {
-                    let res = self.interpret_statement(statement);
+                    let res = self.interpret_located_statement(statement);
 
                     if res.is_err() {
                         return Err(res.unwrap_err());
@@ -57,7 +119,68 @@ impl InterpreterEngine {
         Ok(())
     }
 
-    fn interpret_statement(&mut self, statement: &Statement) -> Result<(), String> {
+    // interpret, but after each direct assignment its inverse has to give back the value the assignment replaced,
+    // which catches a broken inverse before a later one hides it, like the first assignment of a variable setting
+    // it back to 0 whatever it holds
+    pub fn interpret_checking_inverses(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        self.check_inverses = true;
+        let result = self.interpret(program);
+        self.check_inverses = false;
+        result
+    }
+
+    // runs a program compiled with compiled::compile, which does what interpret does in less time, the snapshots
+    // and the store are MemoryStores again once it stops
+    pub fn run(&mut self, program: &CompiledProgram) -> Result<(), RuntimeError> {
+        let mut machine = Machine {
+            slots: program.slots(&self.memory_store),
+            history_len: self.memory_store.history_len(),
+            reverse_point: None,
+            overflow: self.overflow,
+            max_steps: self.max_steps,
+            max_history: self.max_history,
+            steps: self.steps,
+            checkpoints: self.memory_store.get_checkpoints(),
+        };
+
+        let result = program.run(&mut machine);
+
+        if let Some((slots, history_len)) = &machine.reverse_point {
+            self.reverse_point_snapshot = Some(program.store(&self.memory_store, slots, *history_len));
+        }
+        self.memory_store = program.store(&self.memory_store, &machine.slots, machine.history_len);
+        self.steps = machine.steps;
+
+        result?;
+        self.final_memory_point_snapshot = Some(self.memory_store.clone());
+
+        Ok(())
+    }
+
+    // errors are placed at the statement, unless they happened in one inside it, see RuntimeError::at
+    fn interpret_located_statement(&mut self, statement: &Meta<Statement>) -> Result<(), RuntimeError> {
+        if let Some(profile) = &mut self.profile {
+            profile.enter(statement);
+        }
+
+        let result = self.step()
+            .and_then(|_| self.interpret_statement(&statement.value))
+            .map_err(|error| error.at(statement.location, self.snapshot(&statement.value)));
+
+        if let Some(profile) = &mut self.profile {
+            profile.leave();
+        }
+        result
+    }
+
+    // every statement and every iteration of a loop is a step, so a loop that never ends runs out of them even if
+    // its body is empty
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        check_steps(self.steps, self.max_steps)
+    }
+
+    fn interpret_statement(&mut self, statement: &Statement) -> Result<(), RuntimeError> {
         match statement {
             Statement::Skip => {}
             Statement::Assignment(variable, expression) => {
@@ -74,8 +197,19 @@ impl InterpreterEngine {
                     return Err(result.unwrap_err());
                 }
             }
+            Statement::DirectAssignment(variable, expression, inverse) => {
+                let result = self.interpret_direct_assignment(variable, expression, inverse);
+
+                if result.is_err() {
+                    return Err(result.unwrap_err());
+                }
+            }
             Statement::ReversePoint => {
                 self.reverse_point_snapshot = Some(self.memory_store.clone());
+
+                if let Some(profile) = &mut self.profile {
+                    profile.reverse_point();
+                }
             }
             Statement::If(boolean_expression, if_block, else_block) => {
                 let result = self.interpret_if(boolean_expression, if_block, else_block);
@@ -96,62 +230,102 @@ impl InterpreterEngine {
         Ok(())
     }
 
-    fn interpret_assignment(&mut self, variable: &Variable, expression: &ArithmeticExpression) -> Result<(), String> {
-        match variable {
-            Variable::Integer(variable) => {
-                let result = self.interpret_arithmetic_expression(expression);
+    fn interpret_assignment(&mut self, variable: &Variable, expression: &ArithmeticExpression) -> Result<(), RuntimeError> {
+        let value = self.interpret_converted_expression(variable, expression);
 
-                if result.is_err() {
-                    return Err(result.unwrap_err());
-                }
-
-                let value = result.unwrap();
+        if value.is_err() {
+            return Err(value.unwrap_err());
+        }
 
-                self.memory_store.assign(variable, value);
-            }
-            Variable::Float(variable) => {
-                let result = self.interpret_arithmetic_expression(expression);
+        let before = self.history_bytes(variable);
+        self.memory_store.assign(name(variable), value.unwrap())?;
+        self.profile_history(variable, before);
 
-                if result.is_err() {
-                    return Err(result.unwrap_err());
-                }
+        // only assign adds to the history, so this is where it outgrows its budget
+        check_history(self.memory_store.history_len(), self.max_history)
+    }
 
-                let value = result.unwrap();
+    fn interpret_reverse_assignment(&mut self, variable: &Variable, expression: &ArithmeticExpression) -> Result<(), RuntimeError> {
+        let value = self.interpret_converted_expression(variable, expression);
 
-                self.memory_store.assign(variable, value);
-            }
+        if value.is_err() {
+            return Err(value.unwrap_err());
         }
 
+        let before = self.history_bytes(variable);
+        self.memory_store.un_assign(name(variable), value.unwrap())?;
+        self.profile_history(variable, before);
         Ok(())
     }
 
-    fn interpret_reverse_assignment(&mut self, variable: &Variable, expression: &ArithmeticExpression) -> Result<(), String> {
-        match variable {
-            Variable::Integer(variable) => {
-                let result = self.interpret_arithmetic_expression(expression);
+    // only worked out when profiling
+    fn history_bytes(&self, variable: &Variable) -> Option<usize> {
+        self.profile.as_ref()?;
+        Some(self.memory_store.get(name(variable)).map_or(0, MemoryStoreElement::history_bytes))
+    }
 
-                if result.is_err() {
-                    return Err(result.unwrap_err());
-                }
+    fn profile_history(&mut self, variable: &Variable, before: Option<usize>) {
+        let after = self.history_bytes(variable);
+        if let (Some(profile), Some(before), Some(after)) = (&mut self.profile, before, after) {
+            profile.history(name(variable), before, after);
+        }
+    }
 
-                let value = result.unwrap();
+    fn interpret_direct_assignment(
+        &mut self,
+        variable: &Variable,
+        expression: &ArithmeticExpression,
+        inverse: &ArithmeticExpression,
+    ) -> Result<(), RuntimeError> {
+        // a variable that hasn't been assigned yet holds 0
+        let replaced = match self.memory_store.get(name(variable)) {
+            Some(element) if self.check_inverses => Some(element.get()),
+            None if self.check_inverses => Some(Value::Integer(0).convert(&variable_type(variable))?),
+            _ => None,
+        };
+
+        let value = self.interpret_converted_expression(variable, expression);
+
+        if value.is_err() {
+            return Err(value.unwrap_err());
+        }
 
-                self.memory_store.un_assign(variable, value);
+        self.memory_store.set(name(variable), value.unwrap())?;
+
+        if let Some(expected) = replaced {
+            let found = self.interpret_converted_expression(variable, inverse)?;
+
+            // floats by their bits, so a NaN is the same as itself and -0 isn't 0
+            let same = match (&found, &expected) {
+                (Value::Float(found), Value::Float(expected)) => found.to_bits() == expected.to_bits(),
+                _ => found == expected,
+            };
+            if !same {
+                let message = format!(
+                    "{} = {} isn't undone by {} = {}, which gives {} rather than {}",
+                    name(variable),
+                    expression,
+                    name(variable),
+                    inverse,
+                    found,
+                    expected
+                );
+                return Err(RuntimeError::BrokenInverse { location: Location::default(), variables: Vec::new(), message });
             }
-            Variable::Float(variable) => {
-                let result = self.interpret_arithmetic_expression(expression);
+        }
 
-                if result.is_err() {
-                    return Err(result.unwrap_err());
-                }
+        Ok(())
+    }
 
-                let value = result.unwrap();
+    // the value of the expression, as the type of the variable it is assigned to
+    fn interpret_converted_expression(&mut self, variable: &Variable, expression: &ArithmeticExpression) -> Result<Value, RuntimeError> {
+        let result = self.interpret_arithmetic_expression(expression);
 
-                self.memory_store.un_assign(variable, value);
-            }
+        if result.is_err() {
+            return Err(result.unwrap_err());
         }
 
-        Ok(())
+        converted(result.unwrap(), &variable_type(variable), expression)
     }
 
     fn interpret_if(
@@ -159,7 +333,7 @@ impl InterpreterEngine {
         boolean_expression: &BooleanExpression,
         if_block: &Block,
         else_block: &Block,
-    ) -> Result<(), String> {
+    ) -> Result<(), RuntimeError> {
         let result = self.interpret_boolean_expression(boolean_expression);
 
         if result.is_err() {
@@ -189,7 +363,7 @@ impl InterpreterEngine {
         &mut self,
         boolean_expression: &BooleanExpression,
         block: &Block,
-    ) -> Result<(), String> {
+    ) -> Result<(), RuntimeError> {
         let result = self.interpret_boolean_expression(boolean_expression);
 
         if result.is_err() {
@@ -199,7 +373,11 @@ impl InterpreterEngine {
         let mut value = result.unwrap();
 
         while value {
-            let result = self.interpret_block(block);
+            if let Some(profile) = &mut self.profile {
+                profile.iteration();
+            }
+
+            let result = self.step().and_then(|_| self.interpret_block(block));
 
             if result.is_err() {
                 return Err(result.unwrap_err());
@@ -217,9 +395,9 @@ impl InterpreterEngine {
         Ok(())
     }
 
-    fn interpret_block(&mut self, block: &Block) -> Result<(), String> {
+    fn interpret_block(&mut self, block: &Block) -> Result<(), RuntimeError> {
         for statement in block {
-            let result = self.interpret_statement(statement);
+            let result = self.interpret_located_statement(statement);
 
             if result.is_err() {
                 return Err(result.unwrap_err());
@@ -232,7 +410,7 @@ impl InterpreterEngine {
     fn interpret_boolean_expression(
         &mut self,
         boolean_expression: &BooleanExpression,
-    ) -> Result<bool, String> {
+    ) -> Result<bool, RuntimeError> {
         match boolean_expression {
             BooleanExpression::Relational(operator, left_hand_side, right_hand_side) => {
                 let left_hand_side = self.interpret_arithmetic_expression(left_hand_side);
@@ -247,20 +425,7 @@ impl InterpreterEngine {
                     return Err(right_hand_side.unwrap_err());
                 }
 
-                match operator {
-                    RelationOperator::GreaterThan => {
-                        Ok(left_hand_side.unwrap() > right_hand_side.unwrap())
-                    }
-                    RelationOperator::LessThan => {
-                        Ok(left_hand_side.unwrap() < right_hand_side.unwrap())
-                    }
-                    RelationOperator::Equal => {
-                        Ok(left_hand_side.unwrap() == right_hand_side.unwrap())
-                    }
-                    RelationOperator::NotEqual => {
-                        Ok(left_hand_side.unwrap() != right_hand_side.unwrap())
-                    }
-                }
+                Ok(relate(operator, &left_hand_side.unwrap(), &right_hand_side.unwrap()))
             }
             BooleanExpression::Logical(operator, left_hand_side, right_hand_side) => {
                 let left_hand_side = self.interpret_boolean_expression(left_hand_side);
@@ -297,22 +462,21 @@ impl InterpreterEngine {
     fn interpret_arithmetic_expression(
         &mut self,
         arithmetic_expression: &ArithmeticExpression,
-    ) -> Result<Value, String> {
+    ) -> Result<Value, RuntimeError> {
         match arithmetic_expression {
             ArithmeticExpression::Variable(variable) => {
-                let value = match variable {
-                    Variable::Integer(variable) => self.memory_store.get(variable),
-                    Variable::Float(variable) => self.memory_store.get(variable),
-                };
+                let value = self.memory_store.get(name(variable));
 
                 if value.is_none() {
-                    return Err(format!("Variable {} is not defined", variable));
+                    return Err(RuntimeError::UndefinedVariable { location: Location::default(), variables: Vec::new(), variable: name(variable).clone() });
                 }
 
                 Ok(value.unwrap().get())
             }
             ArithmeticExpression::Integer(i) => Ok(Value::Integer(*i)),
             ArithmeticExpression::Float(f) => Ok(Value::Float(f.into_inner() as f32)),
+            ArithmeticExpression::BigInteger(i) => Ok(Value::BigInteger(i.clone())),
+            ArithmeticExpression::Rational(r) => Ok(Value::Rational(r.clone())),
             ArithmeticExpression::Unary(operator, operand) => {
                 let operand = self.interpret_arithmetic_expression(operand);
 
@@ -321,7 +485,7 @@ impl InterpreterEngine {
                 }
 
                 match operator {
-                    UnaryArithmeticOperator::Negation => Ok(-operand.unwrap()),
+                    UnaryArithmeticOperator::Negation => negate(self.overflow, operand.unwrap()),
                 }
             }
             ArithmeticExpression::Operation(operator, left_hand_side, right_hand_side) => {
@@ -337,53 +501,165 @@ impl InterpreterEngine {
                     return Err(right_hand_side.unwrap_err());
                 }
 
-                match operator {
-                    ArithmeticOperator::Addition => {
-                        Ok(left_hand_side.unwrap() + right_hand_side.unwrap())
-                    }
-                    ArithmeticOperator::Subtraction => {
-                        Ok(left_hand_side.unwrap() - right_hand_side.unwrap())
-                    }
-                    ArithmeticOperator::Multiplication => {
-                        Ok(left_hand_side.unwrap() * right_hand_side.unwrap())
-                    }
-                    ArithmeticOperator::Division => {
-                        Ok(left_hand_side.unwrap() / right_hand_side.unwrap())
-                    }
-                    ArithmeticOperator::Exponentiation => {
-                        if right_hand_side.clone().unwrap() > Value::Integer(0) {
-                            match left_hand_side.unwrap() {
-                                Value::Integer(lhs) => {
-                                    match right_hand_side.unwrap() {
-                                        Value::Integer(rhs) => {
-                                            Ok(Value::Integer(lhs.pow(rhs as u32)))
-                                        }
-                                        Value::Float(rhs) => {
-                                            Ok(Value::Float((lhs as f32).powf(rhs)))
-                                        }
-                                    }
-                                }
-                                Value::Float(lhs) => {
-                                    match right_hand_side.unwrap() {
-                                        Value::Integer(rhs) => {
-                                            Ok(Value::Float(lhs.powf(rhs as f32)))
-                                        }
-                                        Value::Float(rhs) => {
-                                            Ok(Value::Float(lhs.powf(rhs)))
-                                        }
-                                    }
-                                }
-                            }
-                        } else {
-                            Err(format!(
-                                "Cannot raise {} to the power of {}",
-                                left_hand_side.unwrap(),
-                                right_hand_side.unwrap()
-                            ))
-                        }
-                    }
-                }
+                operate(self.overflow, operator, left_hand_side.unwrap(), right_hand_side.unwrap())
+            }
+        }
+    }
+
+    // the variables the statement uses, that have a value
+    fn snapshot(&self, statement: &Statement) -> Snapshot {
+        let mut snapshot: Snapshot = Vec::new();
+        for name in used_variables(statement) {
+            if let Some(element) = self.memory_store.get(&name) {
+                snapshot.push((name, element.get()));
             }
         }
+        snapshot
     }
 }
+
+/*
+What a statement or an expression does, apart from where the variables are kept, so InterpreterEngine::interpret
+and the compiled programs InterpreterEngine::run runs give the same results, see compiled.
+*/
+
+// the variables the statement uses, each once, in the order they appear
+pub(super) fn used_variables(statement: &Statement) -> Vec<String> {
+    let mut names = Vec::new();
+    match statement {
+        Statement::Assignment(variable, expression)
+        | Statement::ReverseAssignment(variable, expression)
+        | Statement::DirectAssignment(variable, expression, _) => {
+            names.push(name(variable).clone());
+            arithmetic_variables(expression, &mut names);
+        }
+        Statement::If(condition, _, _) | Statement::While(condition, _) => boolean_variables(condition, &mut names),
+        Statement::Skip | Statement::ReversePoint => {}
+    }
+
+    let mut used: Vec<String> = Vec::new();
+    for name in names {
+        if !used.contains(&name) {
+            used.push(name);
+        }
+    }
+    used
+}
+
+fn arithmetic_variables(expression: &ArithmeticExpression, names: &mut Vec<String>) {
+    match expression {
+        ArithmeticExpression::Variable(variable) => names.push(name(variable).clone()),
+        ArithmeticExpression::Integer(_)
+        | ArithmeticExpression::Float(_)
+        | ArithmeticExpression::BigInteger(_)
+        | ArithmeticExpression::Rational(_) => {}
+        ArithmeticExpression::Unary(_, operand) => arithmetic_variables(operand, names),
+        ArithmeticExpression::Operation(_, left, right) => {
+            arithmetic_variables(left, names);
+            arithmetic_variables(right, names);
+        }
+    }
+}
+
+fn boolean_variables(expression: &BooleanExpression, names: &mut Vec<String>) {
+    match expression {
+        BooleanExpression::Relational(_, left, right) => {
+            arithmetic_variables(left, names);
+            arithmetic_variables(right, names);
+        }
+        BooleanExpression::Logical(_, left, right) => {
+            boolean_variables(left, names);
+            boolean_variables(right, names);
+        }
+        BooleanExpression::Unary(_, operand) => boolean_variables(operand, names),
+    }
+}
+
+// every statement and every iteration of a loop is a step
+pub(super) fn check_steps(steps: usize, max_steps: Option<usize>) -> Result<(), RuntimeError> {
+    match max_steps {
+        Some(limit) if steps > limit => {
+            Err(RuntimeError::StepLimitExceeded { location: Location::default(), variables: Vec::new(), limit })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn check_history(history_len: usize, max_history: Option<usize>) -> Result<(), RuntimeError> {
+    match max_history {
+        Some(limit) if history_len > limit => {
+            Err(RuntimeError::HistoryLimitExceeded { location: Location::default(), variables: Vec::new(), limit })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn variable_type(variable: &Variable) -> Type {
+    match variable {
+        Variable::Integer(_) => Type::Integer,
+        Variable::Float(_) => Type::Float,
+        Variable::BigInteger(_) => Type::BigInteger,
+        Variable::Rational(_) => Type::Rational,
+    }
+}
+
+// the value of the expression, as the type of the variable it is assigned to
+pub(super) fn converted(value: Value, variable_type: &Type, expression: &ArithmeticExpression) -> Result<Value, RuntimeError> {
+    // floats are stored as NotNan, so NaN can't be assigned
+    match value.convert(variable_type) {
+        Ok(Value::Float(float)) if float.is_nan() => Err(RuntimeError::TypeMismatch {
+            location: Location::default(),
+            variables: Vec::new(),
+            message: format!("{} is not a number", expression),
+        }),
+        value => value,
+    }
+}
+
+pub(super) fn relate(operator: &RelationOperator, left_hand_side: &Value, right_hand_side: &Value) -> bool {
+    match operator {
+        RelationOperator::GreaterThan => left_hand_side > right_hand_side,
+        RelationOperator::LessThan => left_hand_side < right_hand_side,
+        RelationOperator::Equal => left_hand_side == right_hand_side,
+        RelationOperator::NotEqual => left_hand_side != right_hand_side,
+    }
+}
+
+pub(super) fn negate(overflow: Overflow, value: Value) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Integer(value) => negate_integer(overflow, value).map(Value::Integer),
+        value => Ok(-value),
+    }
+}
+
+pub(super) fn negate_integer(overflow: Overflow, value: i32) -> Result<i32, RuntimeError> {
+    overflow.negate(value)
+}
+
+pub(super) fn operate(overflow: Overflow, operator: &ArithmeticOperator, left_hand_side: Value, right_hand_side: Value) -> Result<Value, RuntimeError> {
+    if let (Value::Integer(lhs), Value::Integer(rhs)) = (&left_hand_side, &right_hand_side) {
+        return operate_integers(overflow, operator, *lhs, *rhs).map(Value::Integer);
+    }
+
+    // dividing floats gives an infinity instead
+    let float = matches!(left_hand_side, Value::Float(_)) || matches!(right_hand_side, Value::Float(_));
+    if *operator == ArithmeticOperator::Division && !float && right_hand_side.is_exact_zero() {
+        return Err(RuntimeError::DivisionByZero {
+            location: Location::default(),
+            variables: Vec::new(),
+            operation: format!("{} / {}", left_hand_side, right_hand_side),
+        });
+    }
+
+    match operator {
+        ArithmeticOperator::Addition => Ok(left_hand_side + right_hand_side),
+        ArithmeticOperator::Subtraction => Ok(left_hand_side - right_hand_side),
+        ArithmeticOperator::Multiplication => Ok(left_hand_side * right_hand_side),
+        ArithmeticOperator::Division => Ok(left_hand_side / right_hand_side),
+        ArithmeticOperator::Exponentiation => left_hand_side.pow(right_hand_side),
+    }
+}
+
+pub(super) fn operate_integers(overflow: Overflow, operator: &ArithmeticOperator, left_hand_side: i32, right_hand_side: i32) -> Result<i32, RuntimeError> {
+    overflow.apply(operator, left_hand_side, right_hand_side)
+}
@@ -1,40 +1,263 @@
 use std::collections::HashMap;
 use std::fmt::Display;
 use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
+use super::super::post_parse::name_generator::readable_name;
+use super::super::AST::Type;
+use super::error::RuntimeError;
+use utilities::debug::Location;
 
 pub trait MemoryStoreTrait<T> {
     fn get(&self) -> T;
     fn get_history(&self) -> Vec<T>;
     fn assign(&mut self, value: T);
-    fn un_assign(&mut self, value: T);
+    // false when there is nothing left in the history to undo
+    fn un_assign(&mut self, value: T) -> bool;
+    // changes the value without recording it, for assignments that are undone by another one
+    fn set(&mut self, value: T);
+}
+
+/*
+A variable's history, the entries assign adds and un_assign takes off again, which are the differences the assignments
+made, or for floats the values they had before. Entries that follow on from each other by the same step are kept as
+one run, so a loop counter incremented a million times, or a variable assigned it each time round, holds one run
+rather than a million entries. Every so often, when checkpoints are on, an entry records the whole value the variable
+had before the assignment as well, see MemoryStore::checkpoints, and un_assign goes straight back to it.
+ */
+trait Step: Clone + PartialEq + Display {
+    fn zero() -> Self;
+    // the step from one entry to the next, when there is one that gives the entry back exactly
+    fn step(last: &Self, next: &Self) -> Option<Self>;
+    // the entry before one in a run
+    fn back(next: &Self, step: &Self) -> Self;
+    fn follows(last: &Self, step: &Self, next: &Self) -> bool {
+        Self::back(next, step) == *last
+    }
+}
+
+impl Step for i32 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn step(last: &Self, next: &Self) -> Option<Self> {
+        Some(next.wrapping_sub(*last))
+    }
+
+    fn back(next: &Self, step: &Self) -> Self {
+        next.wrapping_sub(*step)
+    }
+}
+
+// only steps that subtract back off bit for bit, so -0.0 and 0.0 are told apart
+impl Step for NotNan<f32> {
+    fn zero() -> Self {
+        NotNan::new(0.0).unwrap()
+    }
+
+    fn step(last: &Self, next: &Self) -> Option<Self> {
+        let step = NotNan::new(next.into_inner() - last.into_inner()).ok()?;
+        Some(step).filter(|step| Self::follows(last, step, next))
+    }
+
+    fn back(next: &Self, step: &Self) -> Self {
+        NotNan::new(next.into_inner() - step.into_inner()).unwrap_or(*next)
+    }
+
+    fn follows(last: &Self, step: &Self, next: &Self) -> bool {
+        (next.into_inner() - step.into_inner()).to_bits() == last.to_bits()
+    }
+}
+
+impl Step for BigInt {
+    fn zero() -> Self {
+        BigInt::zero()
+    }
+
+    fn step(last: &Self, next: &Self) -> Option<Self> {
+        Some(next - last)
+    }
+
+    fn back(next: &Self, step: &Self) -> Self {
+        next - step
+    }
+}
+
+impl Step for Rational {
+    fn zero() -> Self {
+        Rational::zero()
+    }
+
+    fn step(last: &Self, next: &Self) -> Option<Self> {
+        Some(next - last)
+    }
+
+    fn back(next: &Self, step: &Self) -> Self {
+        next - step
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Entry<T> {
+    // count entries, each the one before it plus step, up to last, a single entry's step is 0
+    Run { last: T, step: T, count: u32 },
+    Checkpoint { entry: T, before: T },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct History<T> {
+    entries: Vec<Entry<T>>,
+    // the entries with every run counted out, which is what get_history gives
+    len: usize,
+    // no checkpoints when None
+    checkpoints: Option<usize>,
+}
+
+impl<T: Step> History<T> {
+    fn new(first: T, checkpoints: Option<usize>) -> Self {
+        Self { entries: Vec::from([Entry::Run { last: first, step: T::zero(), count: 1 }]), len: 1, checkpoints }
+    }
+
+    // how many runs and checkpoints are held, which is what the history costs
+    fn runs(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn bytes(&self) -> usize {
+        self.entries.len() * std::mem::size_of::<Entry<T>>()
+    }
+
+    // whether the history only has the entry it starts with
+    fn is_only(&self, first: &T) -> bool {
+        matches!(self.entries.as_slice(), [Entry::Run { last, count: 1, .. }] if last == first)
+    }
+
+    fn push(&mut self, entry: T, before: impl FnOnce() -> T) {
+        let checkpoint = self.checkpoints.is_some_and(|every| self.len.is_multiple_of(every));
+        self.len += 1;
+        if checkpoint {
+            self.entries.push(Entry::Checkpoint { entry, before: before() });
+            return;
+        }
+
+        let mut repeat = None;
+        if let Some(Entry::Run { last, step, count }) = self.entries.last_mut() {
+            if *count == 1 {
+                if let Some(next) = T::step(last, &entry) {
+                    *step = next;
+                    *last = entry;
+                    *count = 2;
+                    return;
+                }
+            } else if *count < u32::MAX && T::follows(last, step, &entry) {
+                *last = entry;
+                *count += 1;
+                return;
+            } else if *count == 2 && *last == entry {
+                // a repeat takes the last entry of a run of 2 over, so the 0 before a loop's 1s doesn't split them
+                repeat = T::step(last, &entry);
+                if repeat.is_some() {
+                    *last = T::back(last, step);
+                    *step = T::zero();
+                    *count = 1;
+                }
+            }
+        }
+
+        match repeat {
+            Some(step) => self.entries.push(Entry::Run { last: entry, step, count: 2 }),
+            None => self.entries.push(Entry::Run { last: entry, step: T::zero(), count: 1 }),
+        }
+    }
+
+    // the last entry, and the whole value before it if it was a checkpoint, in O(1)
+    fn pop(&mut self) -> Option<(T, Option<T>)> {
+        let popped = match self.entries.last_mut()? {
+            Entry::Run { last, step, count } if *count > 1 => {
+                *count -= 1;
+                let previous = T::back(last, step);
+                if *count == 1 {
+                    *step = T::zero();
+                }
+                (std::mem::replace(last, previous), None)
+            }
+            _ => match self.entries.pop()? {
+                Entry::Run { last, .. } => (last, None),
+                Entry::Checkpoint { entry, before } => (entry, Some(before)),
+            },
+        };
+        self.len -= 1;
+        Some(popped)
+    }
+
+    fn first(last: &T, step: &T, count: u32) -> T {
+        let mut first = last.clone();
+        for _ in 1..count {
+            first = T::back(&first, step);
+        }
+        first
+    }
+
+    // the entries of a run, oldest first
+    fn run(last: &T, step: &T, count: u32) -> Vec<T> {
+        let mut entries = Vec::from([last.clone()]);
+        for _ in 1..count {
+            entries.push(T::back(entries.last().unwrap(), step));
+        }
+        entries.reverse();
+        entries
+    }
+
+    fn entries(&self) -> Vec<T> {
+        let mut entries = Vec::with_capacity(self.len);
+        for entry in &self.entries {
+            match entry {
+                Entry::Run { last, step, count } => entries.extend(History::run(last, step, *count)),
+                Entry::Checkpoint { entry, .. } => entries.push(entry.clone()),
+            }
+        }
+        entries
+    }
+
+    // runs of the same entry are written once with their count, other runs by their ends and step, and checkpoints
+    // with the value they hold
+    fn summary(&self, separator: &str) -> String {
+        let mut summary = Vec::new();
+        for entry in &self.entries {
+            summary.push(match entry {
+                Entry::Run { last, count: 1, .. } => format!("{}", last),
+                Entry::Run { last, step, count } if *step == T::zero() => format!("{} ({} times)", last, count),
+                Entry::Run { last, step, count: 2 } => format!("{}{}{}", T::back(last, step), separator, last),
+                Entry::Run { last, step, count } => format!(
+                    "{}{}...{}{} ({} in steps of {})",
+                    History::first(last, step, *count), separator, separator, last, count, step
+                ),
+                Entry::Checkpoint { entry, before } => format!("{} (from {})", entry, before),
+            });
+        }
+        summary.join(separator)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Integer {
     value: i32,
-    history: Vec<i32>,
+    history: History<i32>,
 }
 
 impl Default for Integer {
     fn default() -> Self {
         Self {
             value: 0,
-            history: Vec::from([0]),
+            history: History::new(0, None),
         }
     }
 }
 
 impl Display for Integer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut history = String::new();
-        for (index, value) in self.history.iter().enumerate() {
-            if index == 0 {
-                history.push_str(&format!("{}", value));
-            } else {
-                history.push_str(&format!(" + {}", value));
-            }
-        }
-        write!(f, "{}:    {}", self.value, history)
+        write!(f, "{}:    {}", self.value, self.history.summary(" + "))
     }
 }
 
@@ -44,46 +267,50 @@ impl MemoryStoreTrait<i32> for Integer {
     }
 
     fn get_history(&self) -> Vec<i32> {
-        self.history.clone()
+        self.history.entries()
     }
 
     fn assign(&mut self, value: i32) {
-        self.history.push(value - self.value);
+        let previous = self.value;
+        self.history.push(value.wrapping_sub(previous), || previous);
         self.value = value;
     }
 
-    fn un_assign(&mut self, _: i32) {
-        self.value = self.value - self.history.last().unwrap();
-        self.history.pop();
+    fn un_assign(&mut self, _: i32) -> bool {
+        match self.history.pop() {
+            Some((_, Some(before))) => self.value = before,
+            Some((difference, None)) => self.value = self.value.wrapping_sub(difference),
+            None => return false,
+        }
+        true
+    }
+
+    fn set(&mut self, value: i32) {
+        self.value = value;
     }
 }
 
+// the history holds the value before each assignment rather than the difference, as subtracting the difference
+// back off a float can round, so floats are restored bit for bit, and there is no need for checkpoints
 #[derive(Debug, Clone, PartialEq)]
 pub struct Float {
     value: NotNan<f32>,
-    history: Vec<NotNan<f32>>,
+    history: History<NotNan<f32>>,
 }
 
 impl Default for Float {
     fn default() -> Self {
         Self {
             value: NotNan::new(0.0).unwrap(),
-            history: Vec::from([NotNan::new(0.0).unwrap()]),
+            history: History::new(NotNan::new(0.0).unwrap(), None),
         }
     }
 }
 
 impl Display for Float {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut history = String::new();
-        for (index, value) in self.history.iter().enumerate() {
-            if index == 0 {
-                history.push_str(&format!("{}", value));
-            } else {
-                history.push_str(&format!(" + {}", value));
-            }
-        }
-        write!(f, "{}:    {}", self.value, history)
+        // each value the variable had, up to the current one
+        write!(f, "{}:    {} -> {}", self.value, self.history.summary(" -> "), self.value)
     }
 }
 
@@ -93,17 +320,127 @@ impl MemoryStoreTrait<f32> for Float {
     }
 
     fn get_history(&self) -> Vec<f32> {
-        self.history.clone().iter().map(|value| value.into_inner()).collect()
+        self.history.entries().iter().map(|value| value.into_inner()).collect()
     }
 
     fn assign(&mut self, value: f32) {
-        self.history.push(NotNan::new(value - self.value.into_inner()).unwrap());
+        let previous = self.value;
+        self.history.push(previous, || previous);
+        self.value = NotNan::new(value).unwrap();
+    }
+
+    fn un_assign(&mut self, _: f32) -> bool {
+        match self.history.pop() {
+            Some((value, _)) => self.value = value,
+            None => return false,
+        }
+        true
+    }
+
+    fn set(&mut self, value: f32) {
         self.value = NotNan::new(value).unwrap();
     }
+}
+
+// like Integer, the history holds differences, which are exact
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigInteger {
+    value: BigInt,
+    history: History<BigInt>,
+}
+
+impl Default for BigInteger {
+    fn default() -> Self {
+        Self {
+            value: BigInt::zero(),
+            history: History::new(BigInt::zero(), None),
+        }
+    }
+}
+
+impl Display for BigInteger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:    {}", self.value, self.history.summary(" + "))
+    }
+}
+
+impl MemoryStoreTrait<BigInt> for BigInteger {
+    fn get(&self) -> BigInt {
+        self.value.clone()
+    }
+
+    fn get_history(&self) -> Vec<BigInt> {
+        self.history.entries()
+    }
+
+    fn assign(&mut self, value: BigInt) {
+        let previous = &self.value;
+        self.history.push(&value - previous, || previous.clone());
+        self.value = value;
+    }
+
+    fn un_assign(&mut self, _: BigInt) -> bool {
+        match self.history.pop() {
+            Some((_, Some(before))) => self.value = before,
+            Some((difference, None)) => self.value = &self.value - &difference,
+            None => return false,
+        }
+        true
+    }
+
+    fn set(&mut self, value: BigInt) {
+        self.value = value;
+    }
+}
+
+// like BigInteger, the history holds differences, which are exact for rationals too
+#[derive(Debug, Clone, PartialEq)]
+pub struct RationalNumber {
+    value: Rational,
+    history: History<Rational>,
+}
+
+impl Default for RationalNumber {
+    fn default() -> Self {
+        Self {
+            value: Rational::zero(),
+            history: History::new(Rational::zero(), None),
+        }
+    }
+}
+
+impl Display for RationalNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:    {}", self.value, self.history.summary(" + "))
+    }
+}
+
+impl MemoryStoreTrait<Rational> for RationalNumber {
+    fn get(&self) -> Rational {
+        self.value.clone()
+    }
 
-    fn un_assign(&mut self, _: f32) {
-        self.value = self.value - self.history.last().unwrap();
-        self.history.pop();
+    fn get_history(&self) -> Vec<Rational> {
+        self.history.entries()
+    }
+
+    fn assign(&mut self, value: Rational) {
+        let previous = &self.value;
+        self.history.push(&value - previous, || previous.clone());
+        self.value = value;
+    }
+
+    fn un_assign(&mut self, _: Rational) -> bool {
+        match self.history.pop() {
+            Some((_, Some(before))) => self.value = before,
+            Some((difference, None)) => self.value = &self.value - &difference,
+            None => return false,
+        }
+        true
+    }
+
+    fn set(&mut self, value: Rational) {
+        self.value = value;
     }
 }
 
@@ -111,18 +448,25 @@ impl MemoryStoreTrait<f32> for Float {
 pub enum MemoryStoreElement {
     Integer(Integer),
     Float(Float),
+    BigInteger(BigInteger),
+    Rational(RationalNumber),
 }
 
 impl From<Value> for MemoryStoreElement {
     fn from(value: Value) -> Self {
         match value {
-            Value::Integer(value) => MemoryStoreElement::Integer(Integer {
-                value,
-                history: Vec::from([value]),
-            }),
             Value::Float(value) => MemoryStoreElement::Float(Float {
                 value: NotNan::new(value).unwrap(),
-                history: Vec::from([NotNan::new(value).unwrap()]),
+                history: History::new(NotNan::new(value).unwrap(), None),
+            }),
+            Value::Integer(value) => MemoryStoreElement::Integer(Integer { value, history: History::new(value, None) }),
+            Value::BigInteger(value) => MemoryStoreElement::BigInteger(BigInteger {
+                value: value.clone(),
+                history: History::new(value, None),
+            }),
+            Value::Rational(value) => MemoryStoreElement::Rational(RationalNumber {
+                value: value.clone(),
+                history: History::new(value, None),
             }),
         }
     }
@@ -133,15 +477,79 @@ impl Display for MemoryStoreElement {
         match self {
             MemoryStoreElement::Integer(integer) => write!(f, "{}", integer),
             MemoryStoreElement::Float(float) => write!(f, "{}", float),
+            MemoryStoreElement::BigInteger(integer) => write!(f, "{}", integer),
+            MemoryStoreElement::Rational(rational) => write!(f, "{}", rational),
         }
     }
 }
 
 impl MemoryStoreElement {
+    // a variable's first assignment, which is recorded after the 0 the history starts with
+    pub fn first_assigned(value: Value, checkpoints: Option<usize>) -> Self {
+        let mut element = MemoryStoreElement::initial(&value, checkpoints);
+        // the element has the value's type, so this can't fail
+        let _ = element.assign(value);
+        element
+    }
+
+    // a variable that is set before it is assigned, see MemoryStore::set
+    pub fn first_set(value: Value, checkpoints: Option<usize>) -> Self {
+        let mut element = MemoryStoreElement::initial(&value, checkpoints);
+        let _ = element.set(value);
+        element
+    }
+
+    fn initial(value: &Value, checkpoints: Option<usize>) -> Self {
+        match value {
+            Value::Integer(_) => MemoryStoreElement::Integer(Integer { value: 0, history: History::new(0, checkpoints) }),
+            Value::Float(_) => MemoryStoreElement::Float(Float::default()),
+            Value::BigInteger(_) => MemoryStoreElement::BigInteger(BigInteger {
+                value: BigInt::zero(),
+                history: History::new(BigInt::zero(), checkpoints),
+            }),
+            Value::Rational(_) => MemoryStoreElement::Rational(RationalNumber {
+                value: Rational::zero(),
+                history: History::new(Rational::zero(), checkpoints),
+            }),
+        }
+    }
+
+    // whether the variable is as it starts, 0 with nothing in its history but the entry the history starts with
+    pub fn is_initial(&self) -> bool {
+        match self {
+            MemoryStoreElement::Integer(integer) => integer.value == 0 && integer.history.is_only(&0),
+            MemoryStoreElement::Float(float) => float.value == 0.0 && float.history.is_only(&NotNan::new(0.0).unwrap()),
+            MemoryStoreElement::BigInteger(integer) => integer.value.is_zero() && integer.history.is_only(&BigInt::zero()),
+            MemoryStoreElement::Rational(rational) => rational.value.is_zero() && rational.history.is_only(&Rational::zero()),
+        }
+    }
+
+    // how many entries the history holds with each run counted once, which grows far slower than get_history
+    pub fn history_runs(&self) -> usize {
+        match self {
+            MemoryStoreElement::Integer(integer) => integer.history.runs(),
+            MemoryStoreElement::Float(float) => float.history.runs(),
+            MemoryStoreElement::BigInteger(integer) => integer.history.runs(),
+            MemoryStoreElement::Rational(rational) => rational.history.runs(),
+        }
+    }
+
+    // the bytes the runs and checkpoints of the history take up, not counting what bigints and rationals hold
+    pub fn history_bytes(&self) -> usize {
+        match self {
+            MemoryStoreElement::Integer(integer) => integer.history.bytes(),
+            MemoryStoreElement::Float(float) => float.history.bytes(),
+            MemoryStoreElement::BigInteger(integer) => integer.history.bytes(),
+            MemoryStoreElement::Rational(rational) => rational.history.bytes(),
+        }
+    }
+
     pub fn get(&self) -> Value {
         match self {
             MemoryStoreElement::Integer(integer) => Value::Integer(integer.get()),
             MemoryStoreElement::Float(float) => Value::Float(float.get()),
+            MemoryStoreElement::BigInteger(integer) => Value::BigInteger(integer.get()),
+            MemoryStoreElement::Rational(rational) => Value::Rational(rational.get()),
         }
     }
 
@@ -153,44 +561,74 @@ impl MemoryStoreElement {
             MemoryStoreElement::Float(float) => {
                 float.get_history().iter().map(|value| Value::Float(*value)).collect()
             }
+            MemoryStoreElement::BigInteger(integer) => {
+                integer.get_history().into_iter().map(Value::BigInteger).collect()
+            }
+            MemoryStoreElement::Rational(rational) => {
+                rational.get_history().into_iter().map(Value::Rational).collect()
+            }
         }
     }
 
-    pub fn assign(&mut self, value: Value) {
-        match self {
-            MemoryStoreElement::Integer(integer) => {
-                if let Value::Integer(value) = value {
-                    integer.assign(value);
-                } else {
-                    panic!("Value is not an integer");
-                }
-            }
-            MemoryStoreElement::Float(float) => {
-                if let Value::Float(value) = value {
-                    float.assign(value);
-                } else {
-                    panic!("Value is not a float");
-                }
+    // an error when the value isn't the type the variable was declared as
+    pub fn assign(&mut self, value: Value) -> Result<(), RuntimeError> {
+        match (self, value) {
+            (MemoryStoreElement::Integer(integer), Value::Integer(value)) => integer.assign(value),
+            (MemoryStoreElement::Float(float), Value::Float(value)) => float.assign(value),
+            (MemoryStoreElement::BigInteger(integer), Value::BigInteger(value)) => integer.assign(value),
+            (MemoryStoreElement::Rational(rational), Value::Rational(value)) => rational.assign(value),
+            (element, value) => return Err(element.mismatched(&value)),
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, value: Value) -> Result<(), RuntimeError> {
+        match (self, value) {
+            (MemoryStoreElement::Integer(integer), Value::Integer(value)) => integer.set(value),
+            (MemoryStoreElement::Float(float), Value::Float(value)) => float.set(value),
+            (MemoryStoreElement::BigInteger(integer), Value::BigInteger(value)) => integer.set(value),
+            (MemoryStoreElement::Rational(rational), Value::Rational(value)) => rational.set(value),
+            (element, value) => return Err(element.mismatched(&value)),
+        }
+        Ok(())
+    }
+
+    fn mismatched(&self, value: &Value) -> RuntimeError {
+        RuntimeError::TypeMismatch {
+            location: Location::default(),
+            variables: Vec::new(),
+            message: format!("Cannot assign {} to a variable that holds {}", value, self.kind()),
+        }
+    }
+
+    pub fn un_assign(&mut self, variable: &String, value: Value) -> Result<(), RuntimeError> {
+        let undone = match (self, value) {
+            (MemoryStoreElement::Integer(integer), Value::Integer(value)) => integer.un_assign(value),
+            (MemoryStoreElement::Float(float), Value::Float(value)) => float.un_assign(value),
+            (MemoryStoreElement::BigInteger(integer), Value::BigInteger(value)) => integer.un_assign(value),
+            (MemoryStoreElement::Rational(rational), Value::Rational(value)) => rational.un_assign(value),
+            (element, value) => {
+                return Err(RuntimeError::TypeMismatch {
+                    location: Location::default(),
+                    variables: Vec::new(),
+                    message: format!("Cannot undo {} = {}, as {} is {}", variable, value, variable, element.kind()),
+                });
             }
+        };
+
+        if !undone {
+            return Err(RuntimeError::HistoryUnderflow { location: Location::default(), variables: Vec::new(), variable: variable.clone() });
         }
+        Ok(())
     }
 
-    pub fn un_assign(&mut self, value: Value) {
+    // what the variable was declared as, for errors
+    fn kind(&self) -> &'static str {
         match self {
-            MemoryStoreElement::Integer(integer) => {
-                if let Value::Integer(value) = value {
-                    integer.un_assign(value);
-                } else {
-                    panic!("Value is not an integer");
-                }
-            }
-            MemoryStoreElement::Float(float) => {
-                if let Value::Float(value) = value {
-                    float.un_assign(value);
-                } else {
-                    panic!("Value is not a float");
-                }
-            }
+            MemoryStoreElement::Integer(_) => "an int",
+            MemoryStoreElement::Float(_) => "a float",
+            MemoryStoreElement::BigInteger(_) => "a bigint",
+            MemoryStoreElement::Rational(_) => "a rational",
         }
     }
 }
@@ -199,21 +637,120 @@ impl MemoryStoreElement {
 pub enum Value {
     Integer(i32),
     Float(f32),
+    BigInteger(BigInt),
+    Rational(Rational),
+}
+
+impl Value {
+    fn as_float(&self) -> f32 {
+        match self {
+            Value::Integer(value) => *value as f32,
+            Value::Float(value) => *value,
+            Value::BigInteger(value) => value.to_f32(),
+            Value::Rational(value) => value.to_f32(),
+        }
+    }
+
+    // the value as the type of the variable it is assigned to, floats become ints by truncating and saturating,
+    // bigints become ints by keeping their lowest 32 bits, and rationals are truncated first
+    pub fn convert(self, to: &Type) -> Result<Value, RuntimeError> {
+        match (self, to) {
+            (Value::Float(value), Type::Integer) => Ok(Value::Integer(value as i32)),
+            (Value::BigInteger(value), Type::Integer) => Ok(Value::Integer(value.wrapping_i32())),
+            (Value::Rational(value), Type::Integer) => Ok(Value::Integer(value.truncate().wrapping_i32())),
+            (value, Type::Float) => Ok(Value::Float(value.as_float())),
+            (Value::Integer(value), Type::BigInteger) => Ok(Value::BigInteger(BigInt::from(value))),
+            (Value::Float(value), Type::BigInteger) => match BigInt::from_f32(value) {
+                Some(value) => Ok(Value::BigInteger(value)),
+                None => Err(mismatch(format!("Cannot convert {} to a bigint", value))),
+            },
+            (Value::Rational(value), Type::BigInteger) => Ok(Value::BigInteger(value.truncate())),
+            (Value::Integer(value), Type::Rational) => Ok(Value::Rational(Rational::from(value))),
+            (Value::BigInteger(value), Type::Rational) => Ok(Value::Rational(Rational::from(value))),
+            (Value::Float(value), Type::Rational) => match Rational::from_f32(value) {
+                Some(value) => Ok(Value::Rational(value)),
+                None => Err(mismatch(format!("Cannot convert {} to a rational", value))),
+            },
+            (value, _) => Ok(value),
+        }
+    }
+
+    // the exponent has to be positive, and fit in 32 bits for bigints, while rationals can be raised to any whole
+    // power that fits in 32 bits, other than 0 to a negative one
+    pub fn pow(self, rhs: Value) -> Result<Value, RuntimeError> {
+        let operation = format!("{} ^ {}", self, rhs);
+        let error = RuntimeError::InvalidExponent {
+            location: Location::default(),
+            variables: Vec::new(),
+            base: self.to_string(),
+            exponent: rhs.to_string(),
+        };
+        let rational = matches!(self, Value::Rational(_)) || matches!(rhs, Value::Rational(_));
+        if !rational && rhs <= Value::Integer(0) {
+            return Err(error);
+        }
+
+        match promote(self, rhs) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Ok(Value::Integer(lhs.wrapping_pow(rhs as u32))),
+            (Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs.powf(rhs))),
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => match rhs.to_i32() {
+                Some(rhs) => Ok(Value::BigInteger(lhs.pow(rhs as u32))),
+                None => Err(error),
+            },
+            (Value::Rational(lhs), Value::Rational(rhs)) if rhs.is_integer() => match rhs.truncate().to_i32() {
+                // only 0 to a negative power has no value, as it is 1 / 0
+                Some(rhs) => match lhs.pow(rhs) {
+                    Some(value) => Ok(Value::Rational(value)),
+                    None => Err(RuntimeError::DivisionByZero { location: Location::default(), variables: Vec::new(), operation }),
+                },
+                None => Err(error),
+            },
+            (Value::Rational(_), Value::Rational(_)) => Err(error),
+            _ => unreachable!("promote gives both operands the same type"),
+        }
+    }
+
+    // ints, bigints and rationals can't be divided by it
+    pub fn is_exact_zero(&self) -> bool {
+        match self {
+            Value::Integer(value) => *value == 0,
+            Value::Float(_) => false,
+            Value::BigInteger(value) => value.is_zero(),
+            Value::Rational(value) => value.is_zero(),
+        }
+    }
+}
+
+fn mismatch(message: String) -> RuntimeError {
+    RuntimeError::TypeMismatch { location: Location::default(), variables: Vec::new(), message }
+}
+
+// both operands with the same type: floats if either is a float, otherwise rationals if either is a rational,
+// otherwise bigints if either is a bigint
+fn promote(lhs: Value, rhs: Value) -> (Value, Value) {
+    match (&lhs, &rhs) {
+        (Value::Float(_), _) | (_, Value::Float(_)) => (Value::Float(lhs.as_float()), Value::Float(rhs.as_float())),
+        (Value::Rational(_), _) | (_, Value::Rational(_)) => {
+            (lhs.convert(&Type::Rational).unwrap(), rhs.convert(&Type::Rational).unwrap())
+        }
+        (Value::BigInteger(_), _) | (_, Value::BigInteger(_)) => {
+            (lhs.convert(&Type::BigInteger).unwrap(), rhs.convert(&Type::BigInteger).unwrap())
+        }
+        _ => (lhs, rhs),
+    }
 }
 
+// ints wrap, the interpreter applies the other overflow modes itself, see overflow
 impl std::ops::Add for Value {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
-        match self {
-            Value::Integer(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Integer(lhs + rhs),
-                Value::Float(rhs) => Value::Float(lhs as f32 + rhs),
-            },
-            Value::Float(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Float(lhs + rhs as f32),
-                Value::Float(rhs) => Value::Float(lhs + rhs),
-            },
+        match promote(self, rhs) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(lhs.wrapping_add(rhs)),
+            (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs + rhs),
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => Value::BigInteger(lhs + rhs),
+            (Value::Rational(lhs), Value::Rational(rhs)) => Value::Rational(lhs + rhs),
+            _ => unreachable!("promote gives both operands the same type"),
         }
     }
 }
@@ -222,15 +759,12 @@ impl std::ops::Sub for Value {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self {
-        match self {
-            Value::Integer(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Integer(lhs - rhs),
-                Value::Float(rhs) => Value::Float(lhs as f32 - rhs),
-            },
-            Value::Float(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Float(lhs - rhs as f32),
-                Value::Float(rhs) => Value::Float(lhs - rhs),
-            },
+        match promote(self, rhs) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(lhs.wrapping_sub(rhs)),
+            (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs - rhs),
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => Value::BigInteger(lhs - rhs),
+            (Value::Rational(lhs), Value::Rational(rhs)) => Value::Rational(lhs - rhs),
+            _ => unreachable!("promote gives both operands the same type"),
         }
     }
 }
@@ -239,15 +773,12 @@ impl std::ops::Mul for Value {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self {
-        match self {
-            Value::Integer(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Integer(lhs * rhs),
-                Value::Float(rhs) => Value::Float(lhs as f32 * rhs),
-            },
-            Value::Float(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Float(lhs * rhs as f32),
-                Value::Float(rhs) => Value::Float(lhs * rhs),
-            },
+        match promote(self, rhs) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(lhs.wrapping_mul(rhs)),
+            (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs * rhs),
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => Value::BigInteger(lhs * rhs),
+            (Value::Rational(lhs), Value::Rational(rhs)) => Value::Rational(lhs * rhs),
+            _ => unreachable!("promote gives both operands the same type"),
         }
     }
 }
@@ -256,15 +787,12 @@ impl std::ops::Div for Value {
     type Output = Self;
 
     fn div(self, rhs: Self) -> Self {
-        match self {
-            Value::Integer(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Integer(lhs / rhs),
-                Value::Float(rhs) => Value::Float(lhs as f32 / rhs),
-            },
-            Value::Float(lhs) => match rhs {
-                Value::Integer(rhs) => Value::Float(lhs / rhs as f32),
-                Value::Float(rhs) => Value::Float(lhs / rhs),
-            },
+        match promote(self, rhs) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => Value::Integer(lhs.wrapping_div(rhs)),
+            (Value::Float(lhs), Value::Float(rhs)) => Value::Float(lhs / rhs),
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => Value::BigInteger(lhs / rhs),
+            (Value::Rational(lhs), Value::Rational(rhs)) => Value::Rational(lhs / rhs),
+            _ => unreachable!("promote gives both operands the same type"),
         }
     }
 }
@@ -274,38 +802,34 @@ impl std::ops::Neg for Value {
 
     fn neg(self) -> Self {
         match self {
-            Value::Integer(value) => Value::Integer(-value),
+            Value::Integer(value) => Value::Integer(value.wrapping_neg()),
             Value::Float(value) => Value::Float(-value),
+            Value::BigInteger(value) => Value::BigInteger(-value),
+            Value::Rational(value) => Value::Rational(-value),
         }
     }
 }
 
 impl std::cmp::PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
-        match self {
-            Value::Integer(lhs) => match other {
-                Value::Integer(rhs) => lhs == rhs,
-                Value::Float(rhs) => *lhs as f32 == *rhs,
-            },
-            Value::Float(lhs) => match other {
-                Value::Integer(rhs) => *lhs == *rhs as f32,
-                Value::Float(rhs) => *lhs == *rhs,
-            },
+        match promote(self.clone(), other.clone()) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => lhs == rhs,
+            (Value::Float(lhs), Value::Float(rhs)) => lhs == rhs,
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => lhs == rhs,
+            (Value::Rational(lhs), Value::Rational(rhs)) => lhs == rhs,
+            _ => unreachable!("promote gives both operands the same type"),
         }
     }
 }
 
 impl std::cmp::PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self {
-            Value::Integer(lhs) => match other {
-                Value::Integer(rhs) => lhs.partial_cmp(rhs),
-                Value::Float(rhs) => (*lhs as f32).partial_cmp(rhs),
-            },
-            Value::Float(lhs) => match other {
-                Value::Integer(rhs) => lhs.partial_cmp(&(*rhs as f32)),
-                Value::Float(rhs) => lhs.partial_cmp(rhs),
-            },
+        match promote(self.clone(), other.clone()) {
+            (Value::Integer(lhs), Value::Integer(rhs)) => lhs.partial_cmp(&rhs),
+            (Value::Float(lhs), Value::Float(rhs)) => lhs.partial_cmp(&rhs),
+            (Value::BigInteger(lhs), Value::BigInteger(rhs)) => lhs.partial_cmp(&rhs),
+            (Value::Rational(lhs), Value::Rational(rhs)) => lhs.partial_cmp(&rhs),
+            _ => unreachable!("promote gives both operands the same type"),
         }
     }
 }
@@ -315,6 +839,8 @@ impl Display for Value {
         match self {
             Value::Integer(value) => write!(f, "{}", value),
             Value::Float(value) => write!(f, "{}", value),
+            Value::BigInteger(value) => write!(f, "{}", value),
+            Value::Rational(value) => write!(f, "{}", value),
         }
     }
 }
@@ -322,19 +848,15 @@ impl Display for Value {
 #[derive(Debug, Clone, PartialEq)]
 pub struct MemoryStore {
     memory: HashMap<String, MemoryStoreElement>,
+    // the entries assign has added to the histories and un_assign hasn't removed yet
+    history_len: usize,
+    // how often the histories of the variables assigned record a checkpoint, never when None
+    checkpoints: Option<usize>,
 }
 
 impl Display for MemoryStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut memory = String::new();
-        for (index, (variable, value)) in self.memory.iter().enumerate() {
-            if index == 0 {
-                memory.push_str(&format!("{}: {}", variable, value));
-            } else {
-                memory.push_str(&format!("\n\t{}: {}", variable, value));
-            }
-        }
-        write!(f, "{}", memory)
+        write!(f, "{}", self.labelled(&HashMap::new()))
     }
 }
 
@@ -342,37 +864,87 @@ impl MemoryStore {
     pub fn new() -> Self {
         Self {
             memory: HashMap::new(),
+            history_len: 0,
+            checkpoints: None,
         }
     }
 
+    // every so many entries an int, bigint or rational's history records the whole value it had as well as the
+    // difference, and undoing the assignment goes straight back to it, floats always record the whole value
+    pub fn checkpoints(mut self, every: Option<usize>) -> Self {
+        self.checkpoints = every;
+        self
+    }
+
+    pub fn get_checkpoints(&self) -> Option<usize> {
+        self.checkpoints
+    }
+
     pub fn get(&self, variable: &String) -> Option<&MemoryStoreElement> {
         self.memory.get(variable)
     }
 
-    pub fn assign(&mut self, variable: &String, value: Value) {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &MemoryStoreElement)> {
+        self.memory.iter()
+    }
+
+    // the store as Display writes it, with the generated variables described by their labels, see
+    // post_parse::name_generator::labels
+    pub fn labelled(&self, labels: &HashMap<String, String>) -> String {
+        let mut memory = String::new();
+        for (index, (variable, value)) in self.memory.iter().enumerate() {
+            if index == 0 {
+                memory.push_str(&format!("{}: {}", readable_name(variable, labels), value));
+            } else {
+                memory.push_str(&format!("\n\t{}: {}", readable_name(variable, labels), value));
+            }
+        }
+        memory
+    }
+
+    // the store with the variables given replaced, for programs that keep them elsewhere while they run, see
+    // compiled
+    pub fn replaced(&self, elements: impl IntoIterator<Item = (String, MemoryStoreElement)>, history_len: usize) -> Self {
+        let mut store = self.clone();
+        store.memory.extend(elements);
+        store.history_len = history_len;
+        store
+    }
+
+    // how much history the store is holding, which grows with every assignment until it is undone
+    pub fn history_len(&self) -> usize {
+        self.history_len
+    }
+
+    pub fn assign(&mut self, variable: &String, value: Value) -> Result<(), RuntimeError> {
         if let Some(element) = self.memory.get_mut(variable) {
-            element.assign(value.into());
+            element.assign(value)?;
         } else {
-            match value {
-                Value::Integer(integer) => {
-                    let mut element = Integer::default();
-                    element.assign(integer);
-                    self.memory.insert(variable.clone(), MemoryStoreElement::Integer(element));
-                }
-                Value::Float(float) => {
-                    let mut element = Float::default();
-                    element.assign(float);
-                    self.memory.insert(variable.clone(), MemoryStoreElement::Float(element));
-                }
-            }
+            self.memory.insert(variable.clone(), MemoryStoreElement::first_assigned(value, self.checkpoints));
         }
+        self.history_len += 1;
+        Ok(())
     }
 
-    pub fn un_assign(&mut self, variable: &String, value: Value) {
+    // like assign, without adding to the history
+    pub fn set(&mut self, variable: &String, value: Value) -> Result<(), RuntimeError> {
         if let Some(element) = self.memory.get_mut(variable) {
-            element.un_assign(value);
+            element.set(value)?;
         } else {
-            panic!("Variable {} not found in memory", variable);
+            self.memory.insert(variable.clone(), MemoryStoreElement::first_set(value, self.checkpoints));
+        }
+        Ok(())
+    }
+
+    pub fn un_assign(&mut self, variable: &String, value: Value) -> Result<(), RuntimeError> {
+        match self.memory.get_mut(variable) {
+            Some(element) => {
+                element.un_assign(variable, value)?;
+                // a variable only ever set still has the entry its history starts with, which isn't counted
+                self.history_len = self.history_len.saturating_sub(1);
+                Ok(())
+            }
+            None => Err(RuntimeError::UndefinedVariable { location: Location::default(), variables: Vec::new(), variable: variable.clone() }),
         }
     }
 }
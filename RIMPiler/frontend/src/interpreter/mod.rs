@@ -2,3 +2,6 @@ pub mod memory_store;
 #[cfg(test)]
 mod tests;
 pub mod interpreter;
+pub mod compiled;
+pub mod profile;
+pub mod error;
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use super::super::post_parse::name_generator::readable_name;
+use super::super::AST::Statement;
+use utilities::debug::{Location, Meta};
+
+/*
+What a program did while InterpreterEngine::interpret ran it with profiling on, counted separately for the forward
+half, up to the reverse point, and the backward half after it.
+
+Every statement is counted by where it is and what it is, as the statements the transformer and the inverter add
+share the location of the one they came from. A loop also counts its iterations, and every variable counts the
+assignments that added to its history, or took from it going backward, and how many bytes that added or freed,
+which is 0 for most of them once the history is run-length encoded, see memory_store::History.
+
+The folded stacks have a line for every statement with the statements it is inside before it, and how many steps it
+took itself, the statement and each iteration of a loop, which is the format flamegraph tools read.
+ */
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, PartialOrd, Ord, Default)]
+pub enum Half {
+    #[default]
+    Forward,
+    Backward,
+}
+
+impl Display for Half {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Half::Forward => f.pad("forward"),
+            Half::Backward => f.pad("backward"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct ProfiledStatement {
+    pub half: Half,
+    pub location: Location,
+    // the first line of the statement, so an if or a while without its blocks
+    pub statement: String,
+}
+
+impl ProfiledStatement {
+    // a frame of the folded stacks, which are separated by semicolons
+    fn frame(&self) -> String {
+        format!("{} {}", self.statement, self.location).replace(';', ",")
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct StatementCounts {
+    pub executions: usize,
+    // only loops have any
+    pub iterations: usize,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct HistoryCounts {
+    pub assignments: usize,
+    pub bytes_added: usize,
+    pub bytes_freed: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    half: Half,
+    statements: HashMap<ProfiledStatement, StatementCounts>,
+    variables: HashMap<(Half, String), HistoryCounts>,
+    // the statements being run, outermost first, with their frames joined up to them
+    stack: Vec<(ProfiledStatement, String)>,
+    folded: HashMap<String, usize>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn enter(&mut self, statement: &Meta<Statement>) {
+        let profiled = ProfiledStatement {
+            half: self.half,
+            location: statement.location,
+            statement: statement.value.to_string().lines().next().unwrap_or_default().trim().to_string(),
+        };
+        let stack = match self.stack.last() {
+            Some((_, stack)) => format!("{};{}", stack, profiled.frame()),
+            None => format!("{};{}", self.half, profiled.frame()),
+        };
+
+        self.statements.entry(profiled.clone()).or_default().executions += 1;
+        *self.folded.entry(stack.clone()).or_default() += 1;
+        self.stack.push((profiled, stack));
+    }
+
+    pub(super) fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    // another iteration of the loop that is running
+    pub(super) fn iteration(&mut self) {
+        if let Some((profiled, stack)) = self.stack.last() {
+            self.statements.entry(profiled.clone()).or_default().iterations += 1;
+            *self.folded.entry(stack.clone()).or_default() += 1;
+        }
+    }
+
+    pub(super) fn reverse_point(&mut self) {
+        self.half = Half::Backward;
+    }
+
+    // an assignment or its undoing, with the bytes the variable's history took up before and after it
+    pub(super) fn history(&mut self, variable: &str, before: usize, after: usize) {
+        let counts = self.variables.entry((self.half, variable.to_string())).or_default();
+        counts.assignments += 1;
+        counts.bytes_added += after.saturating_sub(before);
+        counts.bytes_freed += before.saturating_sub(after);
+    }
+
+    // the statements run most often first
+    pub fn statements(&self) -> Vec<(&ProfiledStatement, &StatementCounts)> {
+        let mut statements: Vec<_> = self.statements.iter().collect();
+        statements.sort_by(|(a, a_counts), (b, b_counts)| {
+            (b_counts.executions + b_counts.iterations)
+                .cmp(&(a_counts.executions + a_counts.iterations))
+                .then(a.half.cmp(&b.half))
+                .then((a.location.line, a.location.column).cmp(&(b.location.line, b.location.column)))
+                .then(a.statement.cmp(&b.statement))
+        });
+        statements
+    }
+
+    // the variables whose histories grew the most first
+    pub fn variables(&self) -> Vec<(Half, &String, &HistoryCounts)> {
+        let mut variables: Vec<_> = self.variables.iter().map(|((half, variable), counts)| (*half, variable, counts)).collect();
+        variables.sort_by(|(a_half, a, a_counts), (b_half, b, b_counts)| {
+            b_counts.bytes_added
+                .cmp(&a_counts.bytes_added)
+                .then(b_counts.assignments.cmp(&a_counts.assignments))
+                .then(a_half.cmp(b_half))
+                .then(a.cmp(b))
+        });
+        variables
+    }
+
+    // one line per stack, with the steps taken at the top of it
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<_> = self.folded.iter().map(|(stack, steps)| format!("{} {}\n", stack, steps)).collect();
+        lines.sort();
+        lines.concat()
+    }
+
+    // the profile as Display writes it, with the generated variables described by their labels, see
+    // post_parse::name_generator::labels
+    pub fn labelled(&self, labels: &HashMap<String, String>) -> String {
+        let mut table = format!(
+            "{:<10}{:<14}{:>12}{:>12}  {}\n",
+            "half", "location", "executions", "iterations", "statement"
+        );
+        for (statement, counts) in self.statements() {
+            table.push_str(&format!(
+                "{:<10}{:<14}{:>12}{:>12}  {}\n",
+                statement.half,
+                statement.location.to_string(),
+                counts.executions,
+                counts.iterations,
+                statement.statement
+            ));
+        }
+
+        table.push_str(&format!(
+            "\n{:<10}{:>12}{:>14}{:>14}  {}\n",
+            "half", "assignments", "bytes added", "bytes freed", "variable"
+        ));
+        for (half, variable, counts) in self.variables() {
+            table.push_str(&format!(
+                "{:<10}{:>12}{:>14}{:>14}  {}\n",
+                half,
+                counts.assignments,
+                counts.bytes_added,
+                counts.bytes_freed,
+                readable_name(variable, labels)
+            ));
+        }
+        table
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.labelled(&HashMap::new()))
+    }
+}
@@ -1,8 +1,20 @@
-use crate::interpreter::memory_store::Value;
+use crate::interpreter::memory_store::{MemoryStore, Value};
+use super::error::RuntimeError;
+use std::time::Instant;
+use ordered_float::NotNan;
+use utilities::debug::{Location, Meta};
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
 use super::interpreter::InterpreterEngine;
+use super::compiled::{compile, Resolution};
+use super::profile::{Half, StatementCounts};
 use super::super::lexer::lexer::Tokeniser;
 use super::super::parser::parser::parse;
-use super::super::post_parse::inverter::invert_and_combine;
+use super::super::post_parse::inverter::{invert_and_combine, invert_and_combine_with};
+use super::super::post_parse::transformer::transform;
+use super::super::overflow::Overflow;
+use super::super::generator::check;
+use super::super::AST::{ArithmeticExpression, BooleanExpression, Program, RelationOperator, Statement, Variable};
 
 #[test]
 fn basic_interpreted() {
@@ -93,3 +105,484 @@ fn result() {
         Value::Integer(55)
     );
 }
+
+// everything is inside an if, so every assignment is undone from the history rather than by being reset
+fn inside_an_if(program: &Program) -> Program {
+    let Program::Statements(statements) = program;
+    let condition = BooleanExpression::Relational(
+        RelationOperator::LessThan,
+        Box::new(ArithmeticExpression::Integer(0)),
+        Box::new(ArithmeticExpression::Integer(1)),
+    );
+    let skip = vec![Meta::new(Statement::Skip, Location::default())];
+    Program::Statements(vec![Meta::new(
+        Statement::If(Box::new(condition), Box::new(statements.clone()), Box::new(skip)),
+        Location::default(),
+    )])
+}
+
+#[test]
+fn floats_are_restored_exactly() {
+    check("every variable is restored, floats to exactly 0.0", |program| {
+        let inverted = invert_and_combine(&transform(&inside_an_if(program)));
+
+        [None, Some(3)].into_iter().all(|checkpoints| {
+            let mut interpreter = InterpreterEngine::new().checkpoints(checkpoints);
+            interpreter.interpret(&inverted) == Ok(())
+                && interpreter.get_final_memory_point_snapshot().as_ref().is_some_and(|store| {
+                    store.iter().all(|(_, element)| match element.get() {
+                        Value::Float(value) => value.to_bits() == 0 && element.is_initial(),
+                        _ => element.is_initial(),
+                    })
+                })
+        })
+    });
+}
+
+#[test]
+fn bigints() {
+    let program = std::fs::read_to_string("../../examples/bigfibonacci.rimp").unwrap();
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = invert_and_combine(&ast);
+
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(interpreter.interpret(&inverted), Ok(()));
+    assert_eq!(
+        interpreter.get_result(&String::from("minus2")).unwrap().get(),
+        Value::BigInteger("280571172992510140037611932413038677189525".parse().unwrap())
+    );
+
+    // and the whole of it is undone
+    let store = interpreter.get_final_memory_point_snapshot().clone().unwrap();
+    for variable in ["minus1", "minus2", "temp"] {
+        assert_eq!(store.get(&variable.to_string()).unwrap().get(), Value::BigInteger(BigInt::zero()), "{}", variable);
+    }
+
+    // ints are converted to bigints when they're mixed, and bigints to ints by keeping the lowest 32 bits
+    let program = r#"
+        bigint factorial = 1;
+        int n = 30;
+        while n > 0 do {
+            factorial = factorial * n;
+            n = n - 1;
+        };
+        int low = factorial;
+        bigint power = 2;
+        power = power ^ 100;
+    "#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = invert_and_combine(&ast);
+
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(interpreter.interpret(&inverted), Ok(()));
+
+    let result = |name: &str| interpreter.get_result(&name.to_string()).unwrap().get();
+    assert_eq!(result("factorial"), Value::BigInteger("265252859812191058636308480000000".parse().unwrap()));
+    assert_eq!(result("low"), Value::Integer(1409286144));
+    assert_eq!(result("power"), Value::BigInteger("1267650600228229401496703205376".parse().unwrap()));
+}
+
+#[test]
+fn rationals() {
+    let program = std::fs::read_to_string("../../examples/rationals.rimp").unwrap();
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = invert_and_combine(&ast);
+
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(interpreter.interpret(&inverted), Ok(()));
+
+    let result = |name: &str| interpreter.get_result(&name.to_string()).unwrap().get();
+    assert_eq!(result("harmonic"), Value::Rational("7381/2520".parse().unwrap()));
+    assert_eq!(result("growth"), Value::Rational("59049/1024".parse().unwrap()));
+
+    // multiplying by 1.5r is undone by dividing by it, so growth has no history beyond its first value
+    assert_eq!(interpreter.get_result(&String::from("growth")).unwrap().get_history().len(), 1);
+
+    let store = interpreter.get_final_memory_point_snapshot().clone().unwrap();
+    for variable in ["harmonic", "growth"] {
+        assert_eq!(store.get(&variable.to_string()).unwrap().get(), Value::Rational(Rational::zero()), "{}", variable);
+    }
+
+    // rationals are converted to ints and bigints by truncating, and to floats by rounding
+    let program = r#"
+        rational third = 1r / 3;
+        int truncated = 0 - 7r / 2;
+        float rounded = third;
+        rational cube = third ^ 3;
+        rational inverse = third ^ -2;
+    "#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = invert_and_combine(&ast);
+
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(interpreter.interpret(&inverted), Ok(()));
+
+    let result = |name: &str| interpreter.get_result(&name.to_string()).unwrap().get();
+    assert_eq!(result("truncated"), Value::Integer(-3));
+    assert_eq!(result("rounded"), Value::Float(1.0 / 3.0));
+    assert_eq!(result("cube"), Value::Rational("1/27".parse().unwrap()));
+    assert_eq!(result("inverse"), Value::Rational(Rational::from(9)));
+}
+
+#[test]
+fn overflow_modes() {
+    let program = r#"
+        int big = 2147483647;
+        int smallest = 0 - big - 1;
+        int sum = big + 1;
+        int quotient = smallest / -1;
+        int power = 3 ^ 21;
+        int negated = -smallest;
+        int total = 0;
+        int counter = 0;
+        while counter < 3 do {
+            total = total + big;
+            counter = counter + 1;
+        };
+    "#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+    let ast = parse(&mut tokens.into()).unwrap();
+
+    let expected = [
+        (Overflow::Wrapping, [i32::MIN, i32::MIN, 1870418611, i32::MIN, 2147483645]),
+        (Overflow::Saturating, [i32::MAX, i32::MAX, i32::MAX, i32::MAX, i32::MAX]),
+    ];
+    for (overflow, values) in expected {
+        let inverted = invert_and_combine_with(&ast, overflow);
+
+        let mut interpreter = InterpreterEngine::new().overflow(overflow);
+        assert_eq!(interpreter.interpret(&inverted), Ok(()));
+
+        for (name, value) in ["sum", "quotient", "power", "negated", "total"].iter().zip(values) {
+            assert_eq!(interpreter.get_result(&name.to_string()).unwrap().get(), Value::Integer(value), "{}", name);
+        }
+
+        // a saturated total can't be undone by subtracting, so it is restored from the history instead
+        let store = interpreter.get_final_memory_point_snapshot().clone().unwrap();
+        assert_eq!(store.get(&String::from("total")).unwrap().get(), Value::Integer(0), "{:?}", overflow);
+    }
+
+    // checked mode stops at the first statement that overflows
+    let inverted = invert_and_combine_with(&ast, Overflow::Checked);
+    let mut interpreter = InterpreterEngine::new().overflow(Overflow::Checked);
+    let error = interpreter.interpret(&inverted).unwrap_err();
+    assert_eq!(error, RuntimeError::Overflow {
+        location: Location::new(4, 8),
+        variables: vec![(String::from("big"), Value::Integer(i32::MAX))],
+        operation: String::from("2147483647 + 1"),
+    });
+    assert_eq!(error.to_string(), "[4, 8] Integer overflow, 2147483647 + 1 doesn't fit in 32 bits\n\twhere big = 2147483647");
+}
+
+#[test]
+fn runtime_errors() {
+    let error = |program: &str| {
+        let mut tokeniser = Tokeniser::new().initialise();
+        let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+        let ast = parse(&mut tokens.into()).unwrap();
+        let inverted = invert_and_combine(&ast);
+
+        InterpreterEngine::new().interpret(&inverted).unwrap_err()
+    };
+
+    let division = error("int x = 4;\nint y = 0;\nwhile x > 0 do {\n    x = x / y;\n};");
+    assert_eq!(division, RuntimeError::DivisionByZero {
+        location: Location::new(4, 4),
+        variables: vec![(String::from("x"), Value::Integer(4)), (String::from("y"), Value::Integer(0))],
+        operation: String::from("4 / 0"),
+    });
+    assert_eq!(division.to_string(), "[4, 4] Division by zero, 4 / 0\n\twhere x = 4, y = 0");
+
+    // floats divide by zero to an infinity, but can't be NaN
+    assert!(matches!(error("float f = 1.0 / 0;\nfloat g = f - f;"), RuntimeError::TypeMismatch { location, .. } if location == Location::new(2, 0)));
+    assert!(matches!(error("rational q = 0r ^ -1;"), RuntimeError::DivisionByZero { .. }));
+    assert_eq!(
+        error("int n = 2;\nn = n ^ 0;").to_string(),
+        "[2, 0] Cannot raise 2 to the power of 0\n\twhere n = 2"
+    );
+    assert!(matches!(error("int n = 2;\nn = n ^ 0;"), RuntimeError::InvalidExponent { .. }));
+
+    // the memory store reports errors without a location, which the interpreter fills in
+    let mut store = MemoryStore::new();
+    let x = String::from("x");
+    assert!(matches!(store.un_assign(&x, Value::Integer(1)), Err(RuntimeError::UndefinedVariable { variable, .. }) if variable == "x"));
+    assert_eq!(store.assign(&x, Value::Integer(1)), Ok(()));
+    assert!(matches!(store.assign(&x, Value::Float(1.0)), Err(RuntimeError::TypeMismatch { .. })));
+    assert!(matches!(store.set(&x, Value::Float(1.0)), Err(RuntimeError::TypeMismatch { .. })));
+    assert_eq!(store.history_len(), 1);
+    assert!(matches!(store.un_assign(&x, Value::Float(1.0)), Err(RuntimeError::TypeMismatch { .. })));
+    assert_eq!(store.un_assign(&x, Value::Integer(1)), Ok(()));
+    assert_eq!(store.un_assign(&x, Value::Integer(0)), Ok(()));
+    let underflow = store.un_assign(&x, Value::Integer(0)).unwrap_err();
+    assert_eq!(underflow.location(), Location::default());
+    assert!(matches!(underflow, RuntimeError::HistoryUnderflow { .. }));
+}
+
+#[test]
+fn budgets() {
+    let program = "int n = 1;\nint m = 0;\nwhile n < 1000000 do {\n    m = n;\n    n = n + 1;\n};";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let inverted = invert_and_combine(&ast);
+
+    // long before n gets to a million the program runs out of steps
+    let mut interpreter = InterpreterEngine::new().max_steps(Some(100));
+    let error = interpreter.interpret(&inverted).unwrap_err();
+    assert!(matches!(error, RuntimeError::StepLimitExceeded { limit: 100, .. }));
+    assert!(!error.location().is_unknown());
+    let n = interpreter.get_memory_store().get(&String::from("n")).unwrap().get();
+    assert!(n > Value::Integer(10), "{}", n);
+    assert_eq!(interpreter.get_final_memory_point_snapshot(), &None);
+
+    // m = n needs history, n = n + 1 doesn't, so the eleventh m = n is one entry too many
+    let mut interpreter = InterpreterEngine::new().max_history(Some(10));
+    let error = interpreter.interpret(&inverted).unwrap_err();
+    assert_eq!(error, RuntimeError::HistoryLimitExceeded {
+        location: Location::new(4, 4),
+        variables: vec![(String::from("m"), Value::Integer(11)), (String::from("n"), Value::Integer(11))],
+        limit: 10,
+    });
+    assert_eq!(interpreter.get_memory_store().history_len(), 11);
+
+    // budgets the program stays within don't change anything
+    let inverted = invert_and_combine(&parse(&mut tokeniser.tokenise("int n = 5;\nwhile n > 0 do {\n    n = n - 1;\n};".to_string()).unwrap().into()).unwrap());
+    let mut interpreter = InterpreterEngine::new().max_steps(Some(100)).max_history(Some(0));
+    assert_eq!(interpreter.interpret(&inverted), Ok(()));
+    assert_eq!(interpreter.get_result(&String::from("n")).unwrap().get(), Value::Integer(0));
+}
+
+#[test]
+fn compressed_histories() {
+    let program = combined("int n = 0;\nint m = 0;\nfloat f = 0.0;\nfloat g = 0.0;\nwhile n < 100000 do {\n    n = n + 1;\n    m = n;\n    f = f + 1.0;\n    g = f;\n};");
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(interpreter.run(&compile(&program)), Ok(()));
+
+    let m = interpreter.get_result(&String::from("m")).unwrap();
+    let g = interpreter.get_result(&String::from("g")).unwrap();
+    // every iteration adds the same difference after the 0 the history starts with
+    assert_eq!(m.history_runs(), 2);
+    assert_eq!(m.get_history().len(), 100001);
+    assert_eq!(m.to_string(), "100000:    0 + 1 (100000 times)");
+    // the history of a float is the value it had before each assignment, which goes up in steps of 1
+    assert_eq!(g.history_runs(), 2);
+    assert_eq!(g.to_string(), "100000:    0 (2 times) -> 1 -> ... -> 99999 (99999 in steps of 1) -> 100000");
+    assert_eq!(g.get_history()[100000], Value::Float(99999.0));
+
+    // undoing the runs leaves the histories as they started
+    for variable in ["m", "g"] {
+        let element = interpreter.get_final_memory_point_snapshot().as_ref().unwrap().get(&variable.to_string()).unwrap().clone();
+        assert!(element.is_initial(), "{}: {}", variable, element);
+    }
+
+    // checkpoints give the same values back as the differences do, and floats are restored bit for bit
+    let assignments = [
+        Value::Integer(1), Value::Integer(2), Value::Integer(3), Value::Integer(i32::MIN), Value::Integer(3),
+        Value::Float(0.1), Value::Float(0.2), Value::Float(0.3), Value::Float(-0.0), Value::Float(0.0), Value::Float(1e30),
+        Value::BigInteger(BigInt::from(7)), Value::BigInteger(BigInt::from(14)), Value::BigInteger(BigInt::from(21)),
+        Value::Rational(Rational::from(1)), Value::Rational(Rational::from(2)),
+    ];
+    let mut plain = MemoryStore::new();
+    let mut checkpointed = MemoryStore::new().checkpoints(Some(2));
+    let mut assigned = Vec::new();
+    for value in assignments {
+        let variable = match value {
+            Value::Integer(_) => String::from("i"),
+            Value::Float(_) => String::from("f"),
+            Value::BigInteger(_) => String::from("b"),
+            Value::Rational(_) => String::from("r"),
+        };
+        plain.assign(&variable, value.clone()).unwrap();
+        checkpointed.assign(&variable, value.clone()).unwrap();
+        assigned.push((variable, value));
+    }
+    for (variable, element) in plain.iter() {
+        let other = checkpointed.get(variable).unwrap();
+        assert_eq!(element.get_history(), other.get_history(), "{}", variable);
+        assert_eq!(other.to_string().contains("(from "), variable != "f", "{}", other);
+    }
+
+    for (variable, value) in assigned.into_iter().rev() {
+        for store in [&mut plain, &mut checkpointed] {
+            assert_eq!(format!("{:?}", store.get(&variable).unwrap().get()), format!("{:?}", value), "{}", variable);
+            assert_eq!(store.un_assign(&variable, value.clone()), Ok(()));
+        }
+    }
+    for store in [&plain, &checkpointed] {
+        for (variable, element) in store.iter() {
+            assert!(element.is_initial(), "{}: {}", variable, element);
+        }
+    }
+}
+
+#[test]
+fn profile() {
+    let program = combined("int n = 3;\nint m = 0;\nwhile n > 0 do {\n    m = n;\n    n = n - 1;\n};");
+    let mut interpreter = InterpreterEngine::new().profile(true);
+    assert_eq!(interpreter.interpret(&program), Ok(()));
+    let profile = interpreter.get_profile().as_ref().unwrap();
+
+    // the loop and its inverse ran once each, and went round 3 times
+    let statements = profile.statements();
+    for (index, half) in [Half::Forward, Half::Backward].into_iter().enumerate() {
+        let (statement, counts) = statements[index];
+        assert_eq!((statement.half, statement.location), (half, Location::new(3, 0)), "{}", profile);
+        assert!(statement.statement.starts_with("while "), "{}", profile);
+        assert_eq!(*counts, StatementCounts { executions: 1, iterations: 3 });
+    }
+    let (_, counts) = statements.iter().find(|(statement, _)| statement.statement == "m = n").unwrap();
+    assert_eq!(counts.executions, 3);
+
+    // only m = n needs history, which grows going forward and shrinks by as much going backward
+    let variables = profile.variables();
+    assert_eq!(variables.len(), 2, "{}", profile);
+    let (forward, backward) = (variables[0].2, variables[1].2);
+    assert_eq!((variables[0].0, variables[0].1.as_str(), variables[1].0), (Half::Forward, "m", Half::Backward));
+    assert_eq!((forward.assignments, backward.assignments), (3, 3));
+    assert!(forward.bytes_added > 0 && forward.bytes_freed == 0, "{}", profile);
+    assert_eq!((backward.bytes_added, backward.bytes_freed), (0, forward.bytes_added));
+
+    // each statement counts its own steps, and a loop each of its iterations as well
+    let folded = profile.folded();
+    assert!(folded.contains("forward;while (> n 0) [3, 0] 4\n"), "{}", folded);
+    assert!(folded.contains("forward;while (> n 0) [3, 0];m = n [4, 4] 3\n"), "{}", folded);
+    assert!(profile.to_string().starts_with("half      location        executions  iterations  statement\nforward   [3, 0]"), "{}", profile);
+
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(interpreter.interpret(&program), Ok(()));
+    assert!(interpreter.get_profile().is_none());
+}
+
+fn combined(program: &str) -> Program {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    invert_and_combine(&parse(&mut tokens.into()).unwrap())
+}
+
+// runs the program both ways, checking they end the same, errors and budgets included
+fn assert_compiled_matches(program: &Program, engine: impl Fn() -> InterpreterEngine) {
+    let mut interpreted = engine();
+    let mut compiled = engine();
+
+    assert_eq!(compiled.run(&compile(program)), interpreted.interpret(program), "{}", program);
+    assert_eq!(compiled.get_memory_store(), interpreted.get_memory_store(), "{}", program);
+    assert_eq!(compiled.get_reverse_point_snapshot(), interpreted.get_reverse_point_snapshot(), "{}", program);
+    assert_eq!(compiled.get_final_memory_point_snapshot(), interpreted.get_final_memory_point_snapshot(), "{}", program);
+}
+
+#[test]
+fn compiled_programs_match() {
+    let examples = std::fs::read_dir("../../examples").unwrap();
+    for path in examples.map(|entry| entry.unwrap().path()) {
+        if path.extension().is_some_and(|extension| extension == "rimp") {
+            assert_compiled_matches(&combined(&std::fs::read_to_string(&path).unwrap()), InterpreterEngine::new);
+        }
+    }
+
+    let errors = [
+        "int x = 4;\nint y = 0;\nwhile x > 0 do {\n    x = x / y;\n};",
+        "float f = 1.0 / 0;\nfloat g = f - f;",
+        "rational q = 0r ^ -1;",
+        "int n = 2;\nn = n ^ 0;",
+        "int big = 2147483647;\nif 0 < 1 then {\n    big = big + 1;\n} else {\n    skip;\n};",
+    ];
+    for program in errors {
+        assert_compiled_matches(&combined(program), || InterpreterEngine::new().overflow(Overflow::Checked));
+    }
+
+    let endless = combined("int n = 1;\nint m = 0;\nwhile n > 0 do {\n    m = n;\n    n = n + 1;\n};");
+    assert_compiled_matches(&endless, || InterpreterEngine::new().max_steps(Some(100)));
+    assert_compiled_matches(&endless, || InterpreterEngine::new().max_history(Some(10)));
+
+    // a program that never declares what it reads stops at the read
+    let undefined = Program::Statements(vec![Meta::new(
+        Statement::Assignment(Variable::Integer(String::from("x")), ArithmeticExpression::Variable(Variable::Integer(String::from("y")))),
+        Location::new(1, 0),
+    )]);
+    assert_compiled_matches(&undefined, InterpreterEngine::new);
+
+    // the parser won't declare a name twice, but an AST built without it can assign a float to an int
+    let mismatched = Program::Statements(vec![
+        Meta::new(Statement::Assignment(Variable::Integer(String::from("x")), ArithmeticExpression::Integer(1)), Location::new(1, 0)),
+        Meta::new(
+            Statement::Assignment(Variable::Float(String::from("x")), ArithmeticExpression::Float(NotNan::new(2.5).unwrap())),
+            Location::new(2, 0),
+        ),
+    ]);
+    assert_compiled_matches(&mismatched, InterpreterEngine::new);
+    assert_eq!(
+        InterpreterEngine::new().interpret(&mismatched).unwrap_err().to_string(),
+        "[2, 0] Cannot assign 2.5 to a variable that holds an int\n\twhere x = 1"
+    );
+}
+
+#[test]
+fn resolution() {
+    let resolution = Resolution::new(&combined("int a = 1;\nint b = a;\nwhile b > 0 do {\n    int c = a + b;\n    b = b - 1;\n};"));
+
+    assert_eq!(resolution.slot("a"), Some(0));
+    assert_eq!(resolution.slot("b"), Some(1));
+    // the inverter's counter for the loop is assigned before it
+    assert_eq!(resolution.name(2), "generated_name_semantic_transformer0");
+    assert_eq!(resolution.slot("c"), Some(3));
+    assert_eq!(resolution.slot("d"), None);
+    assert_eq!(resolution.len(), 4);
+}
+
+// a long running loop, run with cargo test --release -- --ignored --nocapture
+#[ignore = "this benchmark takes a long time"]
+#[test]
+fn compiled_speedup() {
+    let program = combined(r#"
+        int n = 1000000;
+        int total = 0;
+        float mean = 0.0;
+        while n > 0 do {
+            int square = n * n;
+            total = total + square / 1000;
+            mean = mean + (total - mean) / 2;
+            n = n - 1;
+        };
+    "#);
+
+    let now = Instant::now();
+    let mut interpreted = InterpreterEngine::new();
+    interpreted.interpret(&program).unwrap();
+    let interpreting = now.elapsed();
+
+    let now = Instant::now();
+    let compiled = compile(&program);
+    let mut running = InterpreterEngine::new();
+    running.run(&compiled).unwrap();
+    let compiling_and_running = now.elapsed();
+
+    assert_eq!(running.get_reverse_point_snapshot(), interpreted.get_reverse_point_snapshot());
+    println!(
+        "interpret {}ms, compile and run {}ms, {:.1} times faster",
+        interpreting.as_millis(),
+        compiling_and_running.as_millis(),
+        interpreting.as_secs_f64() / compiling_and_running.as_secs_f64()
+    );
+}
@@ -23,7 +23,7 @@ We need a lexer for the following grammar:
     | <ArithmeticFactor> '/' <ArithmeticTerm> | <ArithmeticFactor> '^' <ArithmeticTerm>
     | <ArithmeticFactor>
 
-    <ArithmeticFactor> ::= '('<ArithmeticExpression>')' | number | identifier
+    <ArithmeticFactor> ::= '('<ArithmeticExpression>')' | number | rational | identifier
 
     <BooleanExpression> ::= <ArithmeticExpression>'=='<ArithmeticExpression>
     | <ArithmeticExpression>'"<"'<ArithmeticExpression>
@@ -35,7 +35,7 @@ We need a lexer for the following grammar:
 
     <BooleanFactor> ::= '('<BooleanExpression>')'
 
-    <type> ::= 'int' | 'float'
+    <type> ::= 'int' | 'float' | 'bigint' | 'rational'
 */
 
 pub struct InitialisationRequired;
@@ -51,6 +51,7 @@ pub struct Tokeniser<T> {
     /*
     Numbers can be:
         0 | [1-9][0-9]*
+    and are bigints when they don't fit in 32 bits
      */
     integer: Re,
     /*
@@ -59,8 +60,14 @@ pub struct Tokeniser<T> {
      */
     float: Re,
     /*
+    Rationals can be:
+        (0 | [1-9][0-9]* | ([0-9]*\.[0-9]+))r
+    and are exact, so 0.1r is one tenth
+     */
+    rational: Re,
+    /*
     Keywords can be:
-        skip | if | then | else | while | do | int
+        skip | if | then | else | while | do | int | float | bigint | rational
      */
     keyword: Re,
     /*
@@ -123,6 +130,12 @@ impl Tokeniser<InitialisationRequired> {
                 & Re::Star(Box::new(Re::Range(vec![Range::Range('0'..='9')])))))
                 | ((Re::Plus(Box::new(Re::Range(vec![Range::Range('0'..='9')]))) & Re::Char('.'))
                 & Re::Plus(Box::new(Re::Range(vec![Range::Range('0'..='9')])))),
+            rational: ((Re::Char('0')
+                | (Re::Range(vec![Range::Range('1'..='9')])
+                & Re::Star(Box::new(Re::Range(vec![Range::Range('0'..='9')])))))
+                | ((Re::Plus(Box::new(Re::Range(vec![Range::Range('0'..='9')]))) & Re::Char('.'))
+                & Re::Plus(Box::new(Re::Range(vec![Range::Range('0'..='9')])))))
+                & Re::Char('r'),
             keyword: Re::seq_from("skip".to_string())
                 | Re::seq_from("if".to_string())
                 | Re::seq_from("then".to_string())
@@ -130,7 +143,9 @@ impl Tokeniser<InitialisationRequired> {
                 | Re::seq_from("while".to_string())
                 | Re::seq_from("do".to_string())
                 | Re::seq_from("int".to_string())
-                | Re::seq_from("float".to_string()),
+                | Re::seq_from("float".to_string())
+                | Re::seq_from("bigint".to_string())
+                | Re::seq_from("rational".to_string()),
             identifier: (Re::Range(vec![Range::Range('a'..='z'), Range::Range('A'..='Z')])
                 & Re::Star(Box::new(Re::Range(vec![
                 Range::Range('a'..='z'),
@@ -181,6 +196,7 @@ impl Tokeniser<InitialisationRequired> {
             )
                 | Re::Record(String::from("integer"), Box::new(self.integer.clone()))
                 | Re::Record(String::from("float"), Box::new(self.float.clone()))
+                | Re::Record(String::from("rational"), Box::new(self.rational.clone()))
                 | Re::Record(String::from("comment"), Box::new(self.comment.clone()))
                 | Re::Record(
                 String::from("identifier"),
@@ -192,6 +208,7 @@ impl Tokeniser<InitialisationRequired> {
             comment: self.comment.clone(),
             integer: self.integer.clone(),
             float: self.float.clone(),
+            rational: self.rational.clone(),
             keyword: self.keyword.clone(),
             identifier: self.identifier.clone(),
             operator: self.operator.clone(),
@@ -232,3 +232,51 @@ fn collatz_lex() {
         ]
     )
 }
+
+#[test]
+fn bigint_lex() {
+    let result = Tokeniser::new()
+        .initialise()
+        .tokenise("bigint n = 2147483647 + 2147483648".to_owned())
+        .unwrap();
+
+    let tokens: Vec<RIMPToken> = result.into_iter().map(|token| token.value).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            RIMPToken::Keyword(Keyword::BigInt),
+            RIMPToken::Identifier("n".to_string()),
+            RIMPToken::Operator(Operator::Assign),
+            RIMPToken::Integer(2147483647),
+            RIMPToken::Operator(Operator::Add),
+            RIMPToken::BigInteger("2147483648".parse().unwrap()),
+        ]
+    );
+
+    // only the whole word is a keyword
+    let result = Tokeniser::new().initialise().tokenise("bigints".to_owned()).unwrap();
+    assert_eq!(result[0].value, RIMPToken::Identifier("bigints".to_string()));
+}
+
+#[test]
+fn rational_lex() {
+    let result = Tokeniser::new()
+        .initialise()
+        .tokenise("rational q = 0.1r * 3r / r".to_owned())
+        .unwrap();
+
+    let tokens: Vec<RIMPToken> = result.into_iter().map(|token| token.value).collect();
+    assert_eq!(
+        tokens,
+        vec![
+            RIMPToken::Keyword(Keyword::Rational),
+            RIMPToken::Identifier("q".to_string()),
+            RIMPToken::Operator(Operator::Assign),
+            RIMPToken::Rational("1/10".parse().unwrap()),
+            RIMPToken::Operator(Operator::Multiply),
+            RIMPToken::Rational("3".parse().unwrap()),
+            RIMPToken::Operator(Operator::Divide),
+            RIMPToken::Identifier("r".to_string()),
+        ]
+    );
+}
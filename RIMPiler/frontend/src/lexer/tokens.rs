@@ -2,7 +2,9 @@ use std::fmt::Display;
 use ordered_float::NotNan;
 use regex::lexer::Token;
 
+use utilities::bigint::BigInt;
 use utilities::debug::Meta;
+use utilities::rational::Rational;
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Keyword {
@@ -14,6 +16,8 @@ pub enum Keyword {
     Do,
     Int,
     Float,
+    BigInt,
+    Rational,
 }
 
 impl Keyword {
@@ -27,6 +31,8 @@ impl Keyword {
             "do" => Keyword::Do,
             "int" => Keyword::Int,
             "float" => Keyword::Float,
+            "bigint" => Keyword::BigInt,
+            "rational" => Keyword::Rational,
             _ => unreachable!(
                 "Should only be called by the Lexer, invalid keyword, {}",
                 string
@@ -126,8 +132,12 @@ pub enum RIMPToken {
     Keyword(Keyword),
     Identifier(String),
     Operator(Operator),
-    Integer(i32), // I believe PISA only supports 32-bit integers, if so, no need to lex larger
+    Integer(i32),
+    // an integer literal too large for an int, which makes the expression it is in a bigint
+    BigInteger(BigInt),
     Float(NotNan<f32>),
+    // an exact literal ending in r, which makes the expression it is in a rational
+    Rational(Rational),
     Bracket(Bracket),
     Semicolon,
     Whitespace,
@@ -135,11 +145,15 @@ pub enum RIMPToken {
 }
 
 impl RIMPToken {
-    fn parse_integer(string: String) -> Result<i32, String> {
-        let parsed = string.parse::<i32>();
-        match parsed {
-            Ok(number) => Ok(number),
-            Err(_) => return Err(format!("Invalid 32 bit number, {}", string)),
+    // literals that fit in 32 bits are ints, larger ones are bigints
+    fn parse_integer(string: String) -> Result<RIMPToken, String> {
+        if let Ok(number) = string.parse::<i32>() {
+            return Ok(RIMPToken::Integer(number));
+        }
+
+        match string.parse::<BigInt>() {
+            Ok(number) => Ok(RIMPToken::BigInteger(number)),
+            Err(_) => Err(format!("Invalid integer, {}", string)),
         }
     }
 
@@ -151,15 +165,25 @@ impl RIMPToken {
         }
     }
 
+    // the digits before the r, as a decimal
+    fn parse_rational(string: String) -> Result<RIMPToken, String> {
+        match string.trim_end_matches('r').parse::<Rational>() {
+            Ok(number) => Ok(RIMPToken::Rational(number)),
+            Err(_) => Err(format!("Invalid rational, {}", string)),
+        }
+    }
+
     // a clone function which uses copy as much as possible, unless
-    // it is an identifier, in which case it clones the string
+    // it is an identifier, a bigint or a rational, in which case it clones them
     fn copy_clone(&self) -> Self {
         match self {
             RIMPToken::Keyword(keyword) => RIMPToken::Keyword(*keyword),
             RIMPToken::Identifier(identifier) => RIMPToken::Identifier(identifier.clone()),
             RIMPToken::Operator(binary_operator) => RIMPToken::Operator(*binary_operator),
             RIMPToken::Integer(number) => RIMPToken::Integer(*number),
+            RIMPToken::BigInteger(number) => RIMPToken::BigInteger(number.clone()),
             RIMPToken::Float(number) => RIMPToken::Float(*number),
+            RIMPToken::Rational(number) => RIMPToken::Rational(number.clone()),
             RIMPToken::Bracket(bracket) => RIMPToken::Bracket(*bracket),
             RIMPToken::Semicolon => RIMPToken::Semicolon,
             RIMPToken::Whitespace => RIMPToken::Whitespace,
@@ -208,13 +232,7 @@ impl Token for RIMPToken {
             "keyword" => Ok(RIMPToken::Keyword(Keyword::new(string))),
             "identifier" => Ok(RIMPToken::Identifier(string)),
             "operator" => Ok(RIMPToken::Operator(Operator::new(string))),
-            "integer" => {
-                let number = RIMPToken::parse_integer(string);
-                match number {
-                    Ok(number) => Ok(RIMPToken::Integer(number)),
-                    Err(error) => Err(error),
-                }
-            }
+            "integer" => RIMPToken::parse_integer(string),
             "float" => {
                 let number = RIMPToken::parse_float(string);
                 match number {
@@ -222,6 +240,7 @@ impl Token for RIMPToken {
                     Err(error) => Err(error),
                 }
             }
+            "rational" => RIMPToken::parse_rational(string),
             "bracket" => Ok(RIMPToken::Bracket(Bracket::new(string))),
             "semicolon" => Ok(RIMPToken::Semicolon),
             "whitespace" => Ok(RIMPToken::Whitespace),
@@ -5,8 +5,24 @@ pub mod interpreter;
 pub mod lexer;
 pub mod parser;
 pub mod post_parse;
+pub mod optimiser;
 pub mod JVM;
+pub mod Java;
+pub mod C;
+pub mod WAT;
+pub mod PISA;
+pub mod Circuit;
+pub mod X86;
+pub mod IR;
 pub mod abstract_machine;
+pub mod overflow;
+pub mod verify;
+#[cfg(test)]
+mod differential;
+#[cfg(test)]
+mod generator;
+#[cfg(test)]
+mod fixtures;
 
 pub trait Backend {
     fn compile(program: &Program) -> String;
@@ -0,0 +1,110 @@
+use ordered_float::NotNan;
+use utilities::debug::Meta;
+use super::super::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, Program, Statement, UnaryArithmeticOperator};
+use super::{is_generated_variable, Pass};
+
+// evaluates operations on literals, the same way the interpreter would. Anything that would be an error at
+// runtime (dividing an int by 0, raising to a power that isn't positive, overflowing) or isn't a finite float
+// is left for the program to do
+pub struct ConstantFolding;
+
+impl Pass for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "constant folding"
+    }
+
+    fn run(&self, program: &Program) -> Program {
+        let Program::Statements(statements) = program;
+        Program::Statements(fold_block(statements))
+    }
+}
+
+fn fold_block(block: &Block) -> Block {
+    block.iter().map(|statement| Meta::new(fold_statement(&statement.value), statement.location)).collect()
+}
+
+fn fold_statement(statement: &Statement) -> Statement {
+    match statement {
+        Statement::Assignment(variable, expression) if !is_generated_variable(variable) => {
+            Statement::Assignment(variable.clone(), fold(expression))
+        }
+        Statement::If(condition, if_block, else_block) => Statement::If(
+            Box::new(fold_condition(condition)),
+            Box::new(fold_block(if_block)),
+            Box::new(fold_block(else_block)),
+        ),
+        Statement::While(condition, block) => Statement::While(Box::new(fold_condition(condition)), Box::new(fold_block(block))),
+        statement => statement.clone(),
+    }
+}
+
+fn fold_condition(condition: &BooleanExpression) -> BooleanExpression {
+    match condition {
+        BooleanExpression::Relational(operator, left, right) => {
+            BooleanExpression::Relational(operator.clone(), Box::new(fold(left)), Box::new(fold(right)))
+        }
+        BooleanExpression::Logical(operator, left, right) => {
+            BooleanExpression::Logical(operator.clone(), Box::new(fold_condition(left)), Box::new(fold_condition(right)))
+        }
+        BooleanExpression::Unary(operator, operand) => BooleanExpression::Unary(operator.clone(), Box::new(fold_condition(operand))),
+    }
+}
+
+fn fold(expression: &ArithmeticExpression) -> ArithmeticExpression {
+    match expression {
+        ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, operand) => {
+            let operand = fold(operand);
+            let folded = match &operand {
+                ArithmeticExpression::Integer(value) => value.checked_neg().map(ArithmeticExpression::Integer),
+                ArithmeticExpression::Float(value) => float(-value.into_inner()),
+                _ => None,
+            };
+            folded.unwrap_or(ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, Box::new(operand)))
+        }
+        ArithmeticExpression::Operation(operator, left, right) => {
+            let left = fold(left);
+            let right = fold(right);
+            evaluate(operator, &left, &right)
+                .unwrap_or(ArithmeticExpression::Operation(operator.clone(), Box::new(left), Box::new(right)))
+        }
+        expression => expression.clone(),
+    }
+}
+
+fn float(value: f32) -> Option<ArithmeticExpression> {
+    if value.is_finite() {
+        NotNan::new(value).ok().map(ArithmeticExpression::Float)
+    } else {
+        None
+    }
+}
+
+// an int operation stays an int, otherwise both sides are floats
+fn evaluate(operator: &ArithmeticOperator, left: &ArithmeticExpression, right: &ArithmeticExpression) -> Option<ArithmeticExpression> {
+    let (left, right) = match (left, right) {
+        (ArithmeticExpression::Integer(left), ArithmeticExpression::Integer(right)) => {
+            let value = match operator {
+                ArithmeticOperator::Addition => left.checked_add(*right),
+                ArithmeticOperator::Subtraction => left.checked_sub(*right),
+                ArithmeticOperator::Multiplication => left.checked_mul(*right),
+                ArithmeticOperator::Division => left.checked_div(*right),
+                ArithmeticOperator::Exponentiation if *right > 0 => left.checked_pow(*right as u32),
+                ArithmeticOperator::Exponentiation => None,
+            };
+            return value.map(ArithmeticExpression::Integer);
+        }
+        (ArithmeticExpression::Integer(left), ArithmeticExpression::Float(right)) => (*left as f32, right.into_inner()),
+        (ArithmeticExpression::Float(left), ArithmeticExpression::Integer(right)) => (left.into_inner(), *right as f32),
+        (ArithmeticExpression::Float(left), ArithmeticExpression::Float(right)) => (left.into_inner(), right.into_inner()),
+        _ => return None,
+    };
+
+    match operator {
+        ArithmeticOperator::Addition => float(left + right),
+        ArithmeticOperator::Subtraction => float(left - right),
+        ArithmeticOperator::Multiplication => float(left * right),
+        ArithmeticOperator::Division => float(left / right),
+        ArithmeticOperator::Exponentiation if right > 0.0 => float(left.powf(right)),
+        ArithmeticOperator::Exponentiation => None,
+    }
+}
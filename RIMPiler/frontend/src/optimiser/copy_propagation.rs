@@ -0,0 +1,107 @@
+use std::collections::{HashMap, HashSet};
+use utilities::debug::Meta;
+use super::super::AST::{ArithmeticExpression, Block, Program, Statement, Variable};
+use super::{assigned, is_generated_variable, variable_name, Pass};
+
+// replaces the uses of a variable that was last assigned a literal or another variable with that literal or
+// variable, until either of them is assigned again. Only the right hand sides of assignments are rewritten,
+// and only with values of the variable's own type, as the interpreter doesn't convert what it stores
+pub struct CopyPropagation;
+
+impl Pass for CopyPropagation {
+    fn name(&self) -> &'static str {
+        "copy propagation"
+    }
+
+    fn run(&self, program: &Program) -> Program {
+        let Program::Statements(statements) = program;
+        Program::Statements(propagate_block(statements, &mut HashMap::new()))
+    }
+}
+
+// what each variable can be replaced with
+type Copies = HashMap<String, ArithmeticExpression>;
+
+// forgets everything that depends on the value of a variable
+fn kill(copies: &mut Copies, name: &str) {
+    copies.remove(name);
+    copies.retain(|_, copy| !matches!(copy, ArithmeticExpression::Variable(variable) if variable_name(variable) == name));
+}
+
+fn kill_all(copies: &mut Copies, names: &HashSet<String>) {
+    for name in names {
+        kill(copies, name);
+    }
+}
+
+fn is_copy(variable: &Variable, expression: &ArithmeticExpression) -> bool {
+    match (variable, expression) {
+        (Variable::Integer(_), ArithmeticExpression::Integer(_)) => true,
+        (Variable::Float(_), ArithmeticExpression::Float(_)) => true,
+        (Variable::Integer(name), ArithmeticExpression::Variable(Variable::Integer(source)))
+        | (Variable::Float(name), ArithmeticExpression::Variable(Variable::Float(source))) => name != source,
+        _ => false,
+    }
+}
+
+fn propagate_block(block: &Block, copies: &mut Copies) -> Block {
+    let mut new_block = Vec::with_capacity(block.len());
+
+    for statement in block {
+        let new_statement = match &statement.value {
+            Statement::Assignment(variable, expression) if !is_generated_variable(variable) => {
+                let expression = substitute(expression, copies);
+                kill(copies, variable_name(variable));
+
+                if is_copy(variable, &expression) {
+                    copies.insert(variable_name(variable).clone(), expression.clone());
+                }
+
+                Statement::Assignment(variable.clone(), expression)
+            }
+            Statement::Assignment(variable, _) | Statement::ReverseAssignment(variable, _) | Statement::DirectAssignment(variable, _, _) => {
+                kill(copies, variable_name(variable));
+                statement.value.clone()
+            }
+            Statement::If(condition, if_block, else_block) => {
+                let new_if_block = propagate_block(if_block, &mut copies.clone());
+                let new_else_block = propagate_block(else_block, &mut copies.clone());
+
+                let mut changed = HashSet::new();
+                assigned(if_block, &mut changed);
+                assigned(else_block, &mut changed);
+                kill_all(copies, &changed);
+
+                Statement::If(condition.clone(), Box::new(new_if_block), Box::new(new_else_block))
+            }
+            Statement::While(condition, body) => {
+                // the body can run any number of times, so nothing it assigns is known inside or after it
+                let mut changed = HashSet::new();
+                assigned(body, &mut changed);
+                kill_all(copies, &changed);
+
+                let new_body = propagate_block(body, &mut copies.clone());
+
+                Statement::While(condition.clone(), Box::new(new_body))
+            }
+            Statement::Skip | Statement::ReversePoint => statement.value.clone(),
+        };
+
+        new_block.push(Meta::new(new_statement, statement.location));
+    }
+
+    new_block
+}
+
+fn substitute(expression: &ArithmeticExpression, copies: &Copies) -> ArithmeticExpression {
+    match expression {
+        ArithmeticExpression::Variable(variable) => copies.get(variable_name(variable)).cloned().unwrap_or(expression.clone()),
+        ArithmeticExpression::Integer(_) | ArithmeticExpression::Float(_) | ArithmeticExpression::BigInteger(_) | ArithmeticExpression::Rational(_) => expression.clone(),
+        ArithmeticExpression::Unary(operator, operand) => ArithmeticExpression::Unary(operator.clone(), Box::new(substitute(operand, copies))),
+        ArithmeticExpression::Operation(operator, left, right) => ArithmeticExpression::Operation(
+            operator.clone(),
+            Box::new(substitute(left, copies)),
+            Box::new(substitute(right, copies)),
+        ),
+    }
+}
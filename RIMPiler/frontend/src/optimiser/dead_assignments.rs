@@ -0,0 +1,108 @@
+use utilities::debug::Meta;
+use super::super::overflow::Overflow;
+use super::super::post_parse::invertibility::expression_type;
+use super::super::AST::{ArithmeticExpression, ArithmeticOperator, Block, Program, Statement, Type, Variable};
+use super::{expression_reads, is_generated_variable, mentions, variable_name, Pass};
+
+// removes assignments that are overwritten later in the same block before anything reads them. The last
+// assignment of every variable is kept, as the whole store is observed at the reverse point, and so are those
+// that could fail, so that optimising doesn't hide errors: dividing or raising to a power, int arithmetic that
+// can overflow in checked mode, float arithmetic that can give NaN, and floats made into bigints or rationals,
+// which fails for infinities
+pub struct DeadAssignments {
+    overflow: Overflow,
+}
+
+impl Pass for DeadAssignments {
+    fn name(&self) -> &'static str {
+        "dead assignment elimination"
+    }
+
+    fn run(&self, program: &Program) -> Program {
+        let Program::Statements(statements) = program;
+        Program::Statements(self.eliminate_block(statements))
+    }
+}
+
+impl DeadAssignments {
+    pub fn new(overflow: Overflow) -> Self {
+        DeadAssignments { overflow }
+    }
+
+    fn can_fail(&self, variable: &Variable, expression: &ArithmeticExpression) -> bool {
+        let converts_float = matches!(variable, Variable::BigInteger(_) | Variable::Rational(_)) && expression_type(expression) == Type::Float;
+        converts_float || self.can_fail_expression(expression)
+    }
+
+    fn can_fail_expression(&self, expression: &ArithmeticExpression) -> bool {
+        let arithmetic_fails = |expression: &ArithmeticExpression| match expression_type(expression) {
+            Type::Integer => self.overflow == Overflow::Checked,
+            Type::Float => true,
+            Type::BigInteger | Type::Rational => false,
+        };
+
+        match expression {
+            ArithmeticExpression::Variable(_)
+            | ArithmeticExpression::Integer(_)
+            | ArithmeticExpression::Float(_)
+            | ArithmeticExpression::BigInteger(_)
+            | ArithmeticExpression::Rational(_) => false,
+            // negating a float can't make a NaN
+            ArithmeticExpression::Unary(_, operand) => {
+                (expression_type(operand) == Type::Integer && arithmetic_fails(operand)) || self.can_fail_expression(operand)
+            }
+            ArithmeticExpression::Operation(ArithmeticOperator::Division | ArithmeticOperator::Exponentiation, _, _) => true,
+            ArithmeticExpression::Operation(_, left, right) => {
+                arithmetic_fails(expression) || self.can_fail_expression(left) || self.can_fail_expression(right)
+            }
+        }
+    }
+
+    fn is_dead(&self, index: usize, block: &Block) -> bool {
+        let Statement::Assignment(variable, expression) = &block[index].value else {
+            return false;
+        };
+
+        if is_generated_variable(variable) || self.can_fail(variable, expression) {
+            return false;
+        }
+
+        let name = variable_name(variable);
+
+        for statement in &block[index + 1..] {
+            match &statement.value {
+                Statement::Assignment(other, expression) if variable_name(other) == name => {
+                    return !expression_reads(expression, name);
+                }
+                statement if mentions(statement, name) => return false,
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    fn eliminate_block(&self, block: &Block) -> Block {
+        let mut new_block = Vec::with_capacity(block.len());
+
+        for (index, statement) in block.iter().enumerate() {
+            if self.is_dead(index, block) {
+                continue;
+            }
+
+            let new_statement = match &statement.value {
+                Statement::If(condition, if_block, else_block) => Statement::If(
+                    condition.clone(),
+                    Box::new(self.eliminate_block(if_block)),
+                    Box::new(self.eliminate_block(else_block)),
+                ),
+                Statement::While(condition, body) => Statement::While(condition.clone(), Box::new(self.eliminate_block(body))),
+                statement => statement.clone(),
+            };
+
+            new_block.push(Meta::new(new_statement, statement.location));
+        }
+
+        new_block
+    }
+}
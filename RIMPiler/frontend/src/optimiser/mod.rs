@@ -0,0 +1,124 @@
+/*
+Optimisations of the transformed program, run before it is inverted by invert_and_combine.
+
+Each pass only rewrites the forward program, so anything it removes has no inverse generated for it,
+and the values of every variable at the reverse point are the same as without optimisation. Only the
+histories change, as fewer assignments are made.
+
+The statements the semantic transformer added (loop counters and the copies if conditions are tested on)
+are left as they are, and conditions are only ever folded, never rewritten to use other variables, as the
+inverted program tests them at the end of the if instead of the start.
+*/
+use std::collections::HashSet;
+use super::overflow::Overflow;
+use super::post_parse::name_generator::is_generated;
+use super::AST::{ArithmeticExpression, Block, BooleanExpression, Program, Statement, Variable};
+
+mod constant_folding;
+mod copy_propagation;
+mod dead_assignments;
+#[cfg(test)]
+mod tests;
+
+pub use constant_folding::ConstantFolding;
+pub use copy_propagation::CopyPropagation;
+pub use dead_assignments::DeadAssignments;
+
+pub trait Pass {
+    fn name(&self) -> &'static str;
+    fn run(&self, program: &Program) -> Program;
+}
+
+// the passes run by -O, in order, for the overflow mode the program runs with, see DeadAssignments
+pub fn passes(overflow: Overflow) -> Vec<Box<dyn Pass>> {
+    vec![Box::new(CopyPropagation), Box::new(ConstantFolding), Box::new(DeadAssignments::new(overflow))]
+}
+
+pub fn optimise(program: &Program, overflow: Overflow) -> Program {
+    optimise_with(program, &passes(overflow))
+}
+
+// the passes make more work for each other (a folded constant can be propagated, which can make an assignment
+// dead), so they are repeated until the program stops changing
+pub fn optimise_with(program: &Program, passes: &[Box<dyn Pass>]) -> Program {
+    let mut program = program.clone();
+
+    loop {
+        let optimised = passes.iter().fold(program.clone(), |program, pass| pass.run(&program));
+
+        if optimised == program {
+            return program;
+        }
+
+        program = optimised;
+    }
+}
+
+fn variable_name(variable: &Variable) -> &String {
+    match variable {
+        Variable::Integer(name) => name,
+        Variable::Float(name) => name,
+        Variable::BigInteger(name) => name,
+        Variable::Rational(name) => name,
+    }
+}
+
+// assignments to the transformer's variables are never changed
+fn is_generated_variable(variable: &Variable) -> bool {
+    is_generated(variable_name(variable))
+}
+
+// every variable assigned anywhere in a block, including nested blocks
+fn assigned(block: &Block, variables: &mut HashSet<String>) {
+    for statement in block {
+        match &statement.value {
+            Statement::Assignment(variable, _) | Statement::ReverseAssignment(variable, _) | Statement::DirectAssignment(variable, _, _) => {
+                variables.insert(variable_name(variable).clone());
+            }
+            Statement::If(_, if_block, else_block) => {
+                assigned(if_block, variables);
+                assigned(else_block, variables);
+            }
+            Statement::While(_, block) => assigned(block, variables),
+            Statement::Skip | Statement::ReversePoint => {}
+        }
+    }
+}
+
+fn expression_reads(expression: &ArithmeticExpression, name: &str) -> bool {
+    match expression {
+        ArithmeticExpression::Variable(variable) => variable_name(variable) == name,
+        ArithmeticExpression::Integer(_) | ArithmeticExpression::Float(_) | ArithmeticExpression::BigInteger(_) | ArithmeticExpression::Rational(_) => false,
+        ArithmeticExpression::Unary(_, operand) => expression_reads(operand, name),
+        ArithmeticExpression::Operation(_, left, right) => expression_reads(left, name) || expression_reads(right, name),
+    }
+}
+
+fn condition_reads(condition: &BooleanExpression, name: &str) -> bool {
+    match condition {
+        BooleanExpression::Relational(_, left, right) => expression_reads(left, name) || expression_reads(right, name),
+        BooleanExpression::Logical(_, left, right) => condition_reads(left, name) || condition_reads(right, name),
+        BooleanExpression::Unary(_, operand) => condition_reads(operand, name),
+    }
+}
+
+// whether a statement uses or changes a variable in any way
+fn mentions(statement: &Statement, name: &str) -> bool {
+    match statement {
+        Statement::Assignment(variable, expression) | Statement::ReverseAssignment(variable, expression) => {
+            variable_name(variable) == name || expression_reads(expression, name)
+        }
+        Statement::DirectAssignment(variable, expression, inverse) => {
+            variable_name(variable) == name || expression_reads(expression, name) || expression_reads(inverse, name)
+        }
+        Statement::If(condition, if_block, else_block) => {
+            condition_reads(condition, name)
+                || if_block.iter().any(|statement| mentions(&statement.value, name))
+                || else_block.iter().any(|statement| mentions(&statement.value, name))
+        }
+        Statement::While(condition, block) => {
+            condition_reads(condition, name) || block.iter().any(|statement| mentions(&statement.value, name))
+        }
+        Statement::Skip | Statement::ReversePoint => false,
+    }
+}
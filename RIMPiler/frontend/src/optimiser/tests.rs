@@ -0,0 +1,208 @@
+use ordered_float::NotNan;
+use super::{optimise, optimise_with, ConstantFolding, CopyPropagation, DeadAssignments, Pass};
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::interpreter::memory_store::Value;
+use super::super::lexer::lexer::Tokeniser;
+use super::super::parser::parser::parse;
+use super::super::overflow::Overflow;
+use super::super::post_parse::inverter::{invert_and_combine, invert_and_combine_with};
+use super::super::AST::{ArithmeticExpression, BooleanExpression, Program, RelationOperator, Statement, Variable};
+use super::super::IR::lower::lower;
+
+fn program(program: &str) -> Program {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    parse(&mut tokens.into()).unwrap()
+}
+
+fn example(name: &str) -> Program {
+    program(&std::fs::read_to_string(format!("../../examples/{}.rimp", name)).unwrap())
+}
+
+fn statements(program: &Program) -> Vec<Statement> {
+    let Program::Statements(statements) = program;
+    statements.iter().map(|statement| statement.value.clone()).collect()
+}
+
+// the value of every variable at the reverse point, and whether the backward program put them all back to 0
+fn store(program: &Program) -> (Vec<(String, Value)>, bool) {
+    let inverted = invert_and_combine(program);
+
+    let mut interpreter = InterpreterEngine::new();
+    interpreter.interpret(&inverted).unwrap();
+
+    // in name order, as removing an assignment can change which variable is assigned first
+    let mut values = lower(&inverted)
+        .unwrap()
+        .variables
+        .iter()
+        .map(|variable| (variable.name.clone(), interpreter.get_result(&variable.name).unwrap().get()))
+        .collect::<Vec<(String, Value)>>();
+    values.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    let final_store = interpreter.get_final_memory_point_snapshot().as_ref().unwrap();
+    let restored = lower(&inverted)
+        .unwrap()
+        .variables
+        .iter()
+        .all(|variable| final_store.get(&variable.name).unwrap().get() == Value::Integer(0));
+
+    (values, restored)
+}
+
+fn assert_same_store(source: &str, optimised: &Program, unoptimised: &Program) {
+    let (optimised_values, optimised_restored) = store(optimised);
+    let (unoptimised_values, unoptimised_restored) = store(unoptimised);
+
+    assert_eq!(optimised_values, unoptimised_values, "{}\n{}", source, optimised);
+    assert!(optimised_restored && unoptimised_restored, "{}", source);
+}
+
+#[test]
+fn examples_keep_their_store() {
+    for name in ["basic", "collatz", "exponentials", "fibonacci", "floats", "if", "while"] {
+        let unoptimised = example(name);
+        let optimised = optimise(&unoptimised, Overflow::default());
+
+        assert_same_store(name, &optimised, &unoptimised);
+    }
+}
+
+#[test]
+fn programs_keep_their_store() {
+    let programs = [
+        "int x = 1; x = 2; x = x + 3; int y = x * 2; x = 7;",
+        "int a = 3; int b = a; a = 4; int c = b + a; b = c;",
+        "float f = 1.5; float g = f * 2; f = g; g = f / 4;",
+        "int n = 10; int s = 0; while n > 0 do { s = s + n; n = n - 1; s = s + 0; };",
+        "int n = 4; int m = n; if n > 2 then { n = m + 1; m = 0; } else { m = n; }; n = m;",
+        "int t = 1; int u = t; while t < 5 do { u = t; t = t + u; };",
+        "int p = 2; float q = 0.5; q = q * p; int r = p ^ 3 - 2 * p;",
+        "int i = 5; int j = i; i = 1; j = 2; i = j; j = i;",
+    ];
+
+    for source in programs {
+        let unoptimised = program(source);
+        let optimised = optimise(&unoptimised, Overflow::default());
+
+        assert_same_store(source, &optimised, &unoptimised);
+    }
+}
+
+#[test]
+fn exponentials_are_folded() {
+    let optimised = optimise(&example("exponentials"), Overflow::default());
+
+    // every variable ends up assigned a literal, and nothing else is left to assign
+    for statement in statements(&optimised) {
+        let Statement::Assignment(_, expression) = statement else {
+            panic!("{}", optimised);
+        };
+        assert!(matches!(expression, ArithmeticExpression::Integer(_) | ArithmeticExpression::Float(_)), "{}", optimised);
+    }
+
+    assert!(statements(&optimised).contains(&Statement::Assignment(Variable::Integer(String::from("d")), ArithmeticExpression::Integer(4))));
+    assert!(statements(&optimised).contains(&Statement::Assignment(
+        Variable::Float(String::from("e")),
+        ArithmeticExpression::Float(NotNan::new(2.25).unwrap())
+    )));
+}
+
+#[test]
+fn dead_assignments_lose_their_inverse() {
+    let unoptimised = program("int x = 1; x = 2; int y = x;");
+    let optimised = optimise_with(&unoptimised, &[Box::new(DeadAssignments::new(Overflow::default())) as Box<dyn Pass>]);
+
+    assert_eq!(statements(&optimised).len(), 2);
+
+    // an inverse is either a pop of the history or a direct assignment undoing the forward one
+    let inverses = |program: &Program| {
+        statements(&invert_and_combine(program))
+            .iter()
+            .skip_while(|statement| **statement != Statement::ReversePoint)
+            .filter(|statement| matches!(statement, Statement::ReverseAssignment(_, _) | Statement::DirectAssignment(_, _, _)))
+            .count()
+    };
+    assert_eq!(inverses(&unoptimised), 3);
+    assert_eq!(inverses(&optimised), 2);
+
+    // fewer pushes means less history, with the same values
+    let pushes = |program: &Program| lower(&invert_and_combine(program)).unwrap().forward.len();
+    assert!(pushes(&optimised) < pushes(&unoptimised));
+    assert_same_store("dead", &optimised, &unoptimised);
+}
+
+#[test]
+fn errors_are_not_folded_away() {
+    let unoptimised = program("int x = 1 / 0; x = 2; int y = 2 ^ 0; int z = 2147483647 + 1;");
+    let optimised = optimise(&unoptimised, Overflow::default());
+
+    // the division is kept even though x is overwritten, so the program still fails
+    assert_eq!(statements(&optimised).len(), 4);
+    for statement in statements(&optimised).iter().filter(|statement| !matches!(statement, Statement::Assignment(_, ArithmeticExpression::Integer(2)))) {
+        assert!(matches!(statement, Statement::Assignment(_, ArithmeticExpression::Operation(_, _, _))), "{}", optimised);
+    }
+}
+
+#[test]
+fn dead_assignments_keep_their_errors() {
+    // each second assignment is dead, but computing it fails in some overflow mode, which -O has to keep
+    let programs = [
+        "int x = 2147483647; int y = x + 1; y = 0;",
+        "int x = 0 - 2147483647 - 1; int y = -x; y = 0;",
+        "int x = 65536; int y = x * x; y = 0;",
+        "float a = 0; float l = 1 / a; float m = l - l; m = 0;",
+        "float a = 0; float b = 1 / a; bigint c = b; c = 0;",
+    ];
+
+    // where the run stopped and why, the variables can differ as copy propagation replaces them
+    let run = |program: &Program, overflow: Overflow| {
+        let mut interpreter = InterpreterEngine::new().overflow(overflow);
+        interpreter.interpret(&invert_and_combine_with(program, overflow)).map_err(|error| (error.location(), error.message()))
+    };
+
+    for source in programs {
+        let unoptimised = program(source);
+        for overflow in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+            let optimised = optimise(&unoptimised, overflow);
+            assert_eq!(run(&optimised, overflow), run(&unoptimised, overflow), "{} {}\n{}", overflow.name(), source, optimised);
+        }
+    }
+
+    assert_eq!(
+        run(&optimise(&program(programs[0]), Overflow::Checked), Overflow::Checked).unwrap_err().1,
+        "Integer overflow, 2147483647 + 1 doesn't fit in 32 bits"
+    );
+
+    // ints can't fail when they wrap, so there the dead assignment still goes
+    assert_eq!(statements(&optimise(&program(programs[0]), Overflow::Wrapping)).len(), 2);
+}
+
+#[test]
+fn conditions_keep_their_variables() {
+    // the copy the transformer tests the if on is still assigned and tested, as the inverted if tests it after
+    // n has been changed
+    let unoptimised = program("int n = 3; if n > 2 then { n = n - 1; } else { skip; };");
+    let optimised = optimise_with(&unoptimised, &[Box::new(CopyPropagation) as Box<dyn Pass>, Box::new(ConstantFolding)]);
+
+    let Statement::If(condition, _, _) = &statements(&optimised)[2] else {
+        panic!("{}", optimised);
+    };
+    assert_eq!(**condition, BooleanExpression::Relational(
+        RelationOperator::GreaterThan,
+        Box::new(ArithmeticExpression::Variable(Variable::Integer(String::from("generated_name_semantic_transformer0")))),
+        Box::new(ArithmeticExpression::Integer(2)),
+    ));
+    assert_same_store("if", &optimised, &unoptimised);
+}
+
+#[test]
+fn loops_are_not_propagated_into() {
+    let unoptimised = program("int n = 3; int m = n; while n > 0 do { m = n; n = n - 1; };");
+    let optimised = optimise(&unoptimised, Overflow::default());
+
+    // n changes in the loop, so m = n can't become m = 3
+    assert!(optimised.to_string().contains("m = n"), "{}", optimised);
+    assert_same_store("loop", &optimised, &unoptimised);
+}
@@ -0,0 +1,104 @@
+/*
+What int arithmetic does when the result doesn't fit in 32 bits, selected with --overflow:
+    - wrapping keeps the lowest 32 bits, like two's complement hardware, and is what every backend does
+    - checked stops the program with an error
+    - saturating clamps the result to the smallest or largest int
+
+Saturating arithmetic can't be undone from the result, as x + 1 and x + 2 are both the largest int when x is
+close to it, so invertibility treats int addition, subtraction and negation as destructive in that mode.
+Dividing by zero isn't an overflow and fails in every mode.
+*/
+
+use super::interpreter::error::RuntimeError;
+use super::AST::ArithmeticOperator;
+use utilities::debug::Location;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum Overflow {
+    Checked,
+    #[default]
+    Wrapping,
+    Saturating,
+}
+
+impl Overflow {
+    pub fn from_name(name: &str) -> Result<Overflow, String> {
+        match name {
+            "checked" => Ok(Overflow::Checked),
+            "wrapping" => Ok(Overflow::Wrapping),
+            "saturating" => Ok(Overflow::Saturating),
+            _ => Err(format!("Unknown overflow mode {}, expected one of: checked, wrapping, saturating", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Overflow::Checked => "checked",
+            Overflow::Wrapping => "wrapping",
+            Overflow::Saturating => "saturating",
+        }
+    }
+
+    // an error when the result overflows in checked mode, the exponent of a power has to be positive, the errors
+    // have no location yet, see RuntimeError::at
+    pub fn apply(&self, operator: &ArithmeticOperator, lhs: i32, rhs: i32) -> Result<i32, RuntimeError> {
+        let operation = || format!("{} {} {}", lhs, operator, rhs);
+        match operator {
+            ArithmeticOperator::Division if rhs == 0 => {
+                return Err(RuntimeError::DivisionByZero { location: Location::default(), variables: Vec::new(), operation: operation() })
+            }
+            ArithmeticOperator::Exponentiation if rhs <= 0 => {
+                return Err(RuntimeError::InvalidExponent {
+                    location: Location::default(),
+                    variables: Vec::new(),
+                    base: lhs.to_string(),
+                    exponent: rhs.to_string(),
+                })
+            }
+            _ => {}
+        }
+
+        let result = match (self, operator) {
+            (Overflow::Checked, ArithmeticOperator::Addition) => lhs.checked_add(rhs),
+            (Overflow::Checked, ArithmeticOperator::Subtraction) => lhs.checked_sub(rhs),
+            (Overflow::Checked, ArithmeticOperator::Multiplication) => lhs.checked_mul(rhs),
+            (Overflow::Checked, ArithmeticOperator::Division) => lhs.checked_div(rhs),
+            (Overflow::Checked, ArithmeticOperator::Exponentiation) => lhs.checked_pow(rhs as u32),
+            (Overflow::Wrapping, ArithmeticOperator::Addition) => Some(lhs.wrapping_add(rhs)),
+            (Overflow::Wrapping, ArithmeticOperator::Subtraction) => Some(lhs.wrapping_sub(rhs)),
+            (Overflow::Wrapping, ArithmeticOperator::Multiplication) => Some(lhs.wrapping_mul(rhs)),
+            (Overflow::Wrapping, ArithmeticOperator::Division) => Some(lhs.wrapping_div(rhs)),
+            (Overflow::Wrapping, ArithmeticOperator::Exponentiation) => Some(lhs.wrapping_pow(rhs as u32)),
+            (Overflow::Saturating, ArithmeticOperator::Addition) => Some(lhs.saturating_add(rhs)),
+            (Overflow::Saturating, ArithmeticOperator::Subtraction) => Some(lhs.saturating_sub(rhs)),
+            (Overflow::Saturating, ArithmeticOperator::Multiplication) => Some(lhs.saturating_mul(rhs)),
+            (Overflow::Saturating, ArithmeticOperator::Division) => Some(lhs.saturating_div(rhs)),
+            (Overflow::Saturating, ArithmeticOperator::Exponentiation) => Some(lhs.saturating_pow(rhs as u32)),
+        };
+
+        result.ok_or_else(|| RuntimeError::Overflow { location: Location::default(), variables: Vec::new(), operation: operation() })
+    }
+
+    // an error when negating the smallest int in checked mode
+    pub fn negate(&self, value: i32) -> Result<i32, RuntimeError> {
+        let result = match self {
+            Overflow::Checked => value.checked_neg(),
+            Overflow::Wrapping => Some(value.wrapping_neg()),
+            Overflow::Saturating => Some(value.saturating_neg()),
+        };
+
+        result.ok_or_else(|| RuntimeError::Overflow {
+            location: Location::default(),
+            variables: Vec::new(),
+            operation: format!("-({})", value),
+        })
+    }
+
+    // whether int addition, subtraction and negation can be undone from their result
+    pub fn is_reversible(&self) -> bool {
+        *self != Overflow::Saturating
+    }
+}
@@ -0,0 +1,57 @@
+use super::Overflow;
+use super::super::interpreter::error::RuntimeError;
+use super::super::AST::ArithmeticOperator;
+
+#[test]
+fn modes() {
+    for mode in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+        assert_eq!(Overflow::from_name(mode.name()), Ok(mode));
+    }
+    assert!(Overflow::from_name("trapping").is_err());
+    assert_eq!(Overflow::default(), Overflow::Wrapping);
+}
+
+#[test]
+fn overflowing() {
+    let cases = [
+        (ArithmeticOperator::Addition, i32::MAX, 1, i32::MIN, i32::MAX),
+        (ArithmeticOperator::Subtraction, i32::MIN, 1, i32::MAX, i32::MIN),
+        (ArithmeticOperator::Multiplication, 65536, -65536, 0, i32::MIN),
+        (ArithmeticOperator::Division, i32::MIN, -1, i32::MIN, i32::MAX),
+        (ArithmeticOperator::Exponentiation, -3, 21, -1870418611, i32::MIN),
+    ];
+
+    for (operator, lhs, rhs, wrapped, saturated) in cases {
+        let error = Overflow::Checked.apply(&operator, lhs, rhs).unwrap_err();
+        assert_eq!(error.message(), format!("Integer overflow, {} {} {} doesn't fit in 32 bits", lhs, operator, rhs));
+        assert_eq!(Overflow::Wrapping.apply(&operator, lhs, rhs), Ok(wrapped));
+        assert_eq!(Overflow::Saturating.apply(&operator, lhs, rhs), Ok(saturated));
+    }
+
+    assert!(matches!(Overflow::Checked.negate(i32::MIN), Err(RuntimeError::Overflow { .. })));
+    assert_eq!(Overflow::Wrapping.negate(i32::MIN), Ok(i32::MIN));
+    assert_eq!(Overflow::Saturating.negate(i32::MIN), Ok(i32::MAX));
+}
+
+#[test]
+fn in_range() {
+    for mode in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+        assert_eq!(mode.apply(&ArithmeticOperator::Addition, 2, 3), Ok(5));
+        assert_eq!(mode.apply(&ArithmeticOperator::Division, -7, 2), Ok(-3));
+        assert_eq!(mode.apply(&ArithmeticOperator::Exponentiation, -2, 31), Ok(i32::MIN));
+        assert_eq!(mode.negate(i32::MAX), Ok(-i32::MAX));
+    }
+}
+
+#[test]
+fn undefined() {
+    // these fail in every mode
+    for mode in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+        let error = mode.apply(&ArithmeticOperator::Division, 7, 0).unwrap_err();
+        assert_eq!(error.message(), "Division by zero, 7 / 0");
+        assert!(matches!(error, RuntimeError::DivisionByZero { .. }));
+
+        let error = mode.apply(&ArithmeticOperator::Exponentiation, 2, -1).unwrap_err();
+        assert_eq!(error.message(), "Cannot raise 2 to the power of -1");
+    }
+}
@@ -1,4 +1,4 @@
-mod precedence;
+pub(crate) mod precedence;
 pub mod parser;
 
 #[cfg(test)]
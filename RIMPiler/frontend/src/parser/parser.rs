@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 use ordered_float::NotNan;
-use utilities::debug::{Error, Location};
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
+use utilities::debug::{Error, Location, Meta};
 use crate::AST::{ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, BooleanOperator, Program, RelationOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator, Variable};
 use crate::lexer::tokens::{Bracket, Keyword, Operator, RIMPToken, Tokens};
 use crate::parser::precedence;
-use crate::post_parse::transformer::transform;
+use crate::post_parse::transformer::{transform, transform_keeping_counters};
 
 fn expect_operator(operator: Operator, tokens: &mut Tokens) -> std::result::Result<(), Location> {
     let next_token = tokens.next();
@@ -87,6 +89,17 @@ pub fn parse(tokens: &mut Tokens) -> utilities::debug::Result<Program> {
     Ok(transform(&result.unwrap()))
 }
 
+// for the backends that need a counter in every loop, see transformer::transform_keeping_counters
+pub fn parse_keeping_counters(tokens: &mut Tokens) -> utilities::debug::Result<Program> {
+    let mut parser = Parser::new();
+    let result = parser.parse_program(tokens);
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    Ok(transform_keeping_counters(&result.unwrap()))
+}
+
 pub fn parse_without_transform(tokens: &mut Tokens) -> utilities::debug::Result<Program> {
     let mut parser = Parser::new();
     let result = parser.parse_program(tokens);
@@ -140,10 +153,10 @@ impl Parser {
 
         loop {
             let next_token = tokens.peek();
-            match next_token {
-                Some(_) => {}
+            let location = match next_token {
+                Some(token) => token.location,
                 None => return Ok(Program::Statements(statements)),
-            }
+            };
 
             let statement = self.parse_statement(tokens);
 
@@ -161,7 +174,7 @@ impl Parser {
                 ));
             }
 
-            statements.push(statement.unwrap());
+            statements.push(Meta::new(statement.unwrap(), location));
         }
     }
 
@@ -207,6 +220,8 @@ impl Parser {
                     match type_.unwrap().as_str() {
                         "int" => Ok(Statement::Assignment(Variable::Integer(identifier), expression.unwrap())),
                         "float" => Ok(Statement::Assignment(Variable::Float(identifier), expression.unwrap())),
+                        "bigint" => Ok(Statement::Assignment(Variable::BigInteger(identifier), expression.unwrap())),
+                        "rational" => Ok(Statement::Assignment(Variable::Rational(identifier), expression.unwrap())),
                         type_ => {
                             return Err(Error::new(
                                 token.location,
@@ -380,6 +395,106 @@ impl Parser {
                             exp => Ok(Statement::Assignment(Variable::Float(identifier.unwrap()), exp))
                         }
                     }
+                    Keyword::BigInt => {
+                        let identifier = expect_identifier(tokens);
+
+                        if identifier.is_err() {
+                            return Err(Error::new(
+                                identifier.unwrap_err(),
+                                "Expected identifier".to_string(),
+                                "Parser".to_string(),
+                            ));
+                        }
+
+                        let result = expect_operator(Operator::Assign, tokens);
+
+                        if result.is_err() {
+                            return Err(Error::new(
+                                result.unwrap_err(),
+                                "Expected assignment operator".to_string(),
+                                "Parser".to_string(),
+                            ));
+                        }
+
+                        let expression = self.parse_arithmetic_expression(tokens, 0);
+
+                        if expression.is_err() {
+                            return Err(expression.unwrap_err());
+                        }
+
+                        let map_result = self.map_type(identifier.clone().unwrap(), "bigint".to_string());
+                        if map_result.is_err() {
+                            let location = token.location;
+                            return Err(
+                                Error::new(
+                                    location,
+                                    "Variable already declared".to_string(),
+                                    "Parser".to_string(),
+                                )
+                            )
+                        }
+
+                        // like floats, a single integer is converted now
+                        match expression.unwrap() {
+                            ArithmeticExpression::Integer(integer) => Ok(Statement::Assignment(
+                                Variable::BigInteger(identifier.unwrap()),
+                                ArithmeticExpression::BigInteger(BigInt::from(integer)))
+                            ),
+                            exp => Ok(Statement::Assignment(Variable::BigInteger(identifier.unwrap()), exp))
+                        }
+                    }
+                    Keyword::Rational => {
+                        let identifier = expect_identifier(tokens);
+
+                        if identifier.is_err() {
+                            return Err(Error::new(
+                                identifier.unwrap_err(),
+                                "Expected identifier".to_string(),
+                                "Parser".to_string(),
+                            ));
+                        }
+
+                        let result = expect_operator(Operator::Assign, tokens);
+
+                        if result.is_err() {
+                            return Err(Error::new(
+                                result.unwrap_err(),
+                                "Expected assignment operator".to_string(),
+                                "Parser".to_string(),
+                            ));
+                        }
+
+                        let expression = self.parse_arithmetic_expression(tokens, 0);
+
+                        if expression.is_err() {
+                            return Err(expression.unwrap_err());
+                        }
+
+                        let map_result = self.map_type(identifier.clone().unwrap(), "rational".to_string());
+                        if map_result.is_err() {
+                            let location = token.location;
+                            return Err(
+                                Error::new(
+                                    location,
+                                    "Variable already declared".to_string(),
+                                    "Parser".to_string(),
+                                )
+                            )
+                        }
+
+                        // like floats, a single integer is converted now
+                        match expression.unwrap() {
+                            ArithmeticExpression::Integer(integer) => Ok(Statement::Assignment(
+                                Variable::Rational(identifier.unwrap()),
+                                ArithmeticExpression::Rational(Rational::from(integer)))
+                            ),
+                            ArithmeticExpression::BigInteger(integer) => Ok(Statement::Assignment(
+                                Variable::Rational(identifier.unwrap()),
+                                ArithmeticExpression::Rational(Rational::from(integer)))
+                            ),
+                            exp => Ok(Statement::Assignment(Variable::Rational(identifier.unwrap()), exp))
+                        }
+                    }
                     _ => {
                         return Err(Error::new(
                             token.location,
@@ -421,12 +536,13 @@ impl Parser {
 
         loop {
             let next_token = tokens.peek();
-            match next_token {
+            let location = match next_token {
                 Some(token) => {
                     if token.value == RIMPToken::Bracket(Bracket::RightBrace) {
                         tokens.next();
                         return Ok(statements);
                     }
+                    token.location
                 }
                 None => {
                     return Err(Error::new(
@@ -435,7 +551,7 @@ impl Parser {
                         "Parser".to_string(),
                     ));
                 }
-            }
+            };
 
             let statement = self.parse_statement(tokens);
 
@@ -453,7 +569,7 @@ impl Parser {
                 ));
             }
 
-            statements.push(statement.unwrap());
+            statements.push(Meta::new(statement.unwrap(), location));
         }
     }
 
@@ -466,6 +582,8 @@ impl Parser {
             Some(token) => match token.value {
                 RIMPToken::Integer(number) => ArithmeticExpression::Integer(number),
                 RIMPToken::Float(number) => ArithmeticExpression::Float(number),
+                RIMPToken::BigInteger(number) => ArithmeticExpression::BigInteger(number),
+                RIMPToken::Rational(number) => ArithmeticExpression::Rational(number),
                 RIMPToken::Identifier(identifier) => {
                     let type_ = self.get_type(&identifier);
                     if type_.is_err() {
@@ -482,6 +600,8 @@ impl Parser {
                     match type_.as_str() {
                         "int" => ArithmeticExpression::Variable(Variable::Integer(identifier)),
                         "float" => ArithmeticExpression::Variable(Variable::Float(identifier)),
+                        "bigint" => ArithmeticExpression::Variable(Variable::BigInteger(identifier)),
+                        "rational" => ArithmeticExpression::Variable(Variable::Rational(identifier)),
                         _ => {
                             return Err(Error::new(
                                 token.location,
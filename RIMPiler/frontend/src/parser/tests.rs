@@ -5,6 +5,9 @@ use super::super::AST::{
     Program, RelationOperator, Statement, UnaryArithmeticOperator, UnaryBooleanOperator,
     Variable
 };
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
+use utilities::debug::{Location, Meta};
 
 #[test]
 fn basic_parse() {
@@ -228,56 +231,120 @@ fn parse_programs() {
     assert_eq!(
         result.unwrap(),
         Program::Statements(vec![
-            Statement::Assignment(Variable::Integer("x".to_string()),
+            Meta::new(Statement::Assignment(Variable::Integer("x".to_string()),
                 ArithmeticExpression::Integer(5)
-            ),
-            Statement::While(
+            ), Location::new(1, 0)),
+            Meta::new(Statement::While(
                 Box::new(BooleanExpression::Relational(
                     RelationOperator::GreaterThan,
                     Box::new(ArithmeticExpression::Variable(Variable::Integer("x".to_string()))),
                     Box::new(ArithmeticExpression::Integer(0))
                 )),
                 Box::new(vec![
-                    Statement::Assignment(Variable::Integer("y".to_string()),
+                    Meta::new(Statement::Assignment(Variable::Integer("y".to_string()),
                                           ArithmeticExpression::Integer(5)
-                    ),
-                    Statement::While(
+                    ), Location::new(3, 8)),
+                    Meta::new(Statement::While(
                         Box::new(BooleanExpression::Relational(
                             RelationOperator::GreaterThan,
                             Box::new(ArithmeticExpression::Variable(Variable::Integer("y".to_string()))),
                             Box::new(ArithmeticExpression::Integer(0))
                         )),
-                        Box::new(vec![Statement::If(
+                        Box::new(vec![Meta::new(Statement::If(
                             Box::new(BooleanExpression::Relational(
                                 RelationOperator::Equal,
                                 Box::new(ArithmeticExpression::Integer(4)),
                                 Box::new(ArithmeticExpression::Integer(5))
                             )),
-                            Box::new(vec![Statement::Assignment(Variable::Integer("y".to_string()),
+                            Box::new(vec![Meta::new(Statement::Assignment(Variable::Integer("y".to_string()),
                                 ArithmeticExpression::Operation(
                                     ArithmeticOperator::Subtraction,
                                     Box::new(ArithmeticExpression::Variable(Variable::Integer("y".to_string()))),
                                     Box::new(ArithmeticExpression::Integer(1))
                                 )
-                            )]),
-                            Box::new(vec![Statement::Assignment(Variable::Integer("y".to_string()),
+                            ), Location::new(6, 16))]),
+                            Box::new(vec![Meta::new(Statement::Assignment(Variable::Integer("y".to_string()),
                                 ArithmeticExpression::Operation(
                                     ArithmeticOperator::Subtraction,
                                     Box::new(ArithmeticExpression::Variable(Variable::Integer("y".to_string()))),
                                     Box::new(ArithmeticExpression::Integer(1))
                                 )
-                            )])
-                        )])
-                    ),
-                    Statement::Assignment(Variable::Integer("x".to_string()),
+                            ), Location::new(8, 16))])
+                        ), Location::new(5, 12))])
+                    ), Location::new(4, 8)),
+                    Meta::new(Statement::Assignment(Variable::Integer("x".to_string()),
                         ArithmeticExpression::Operation(
                             ArithmeticOperator::Subtraction,
                             Box::new(ArithmeticExpression::Variable(Variable::Integer("x".to_string()))),
                             Box::new(ArithmeticExpression::Integer(1))
                         )
-                    )
+                    ), Location::new(11, 8))
                 ])
-            )
+            ), Location::new(2, 4))
+        ])
+    );
+}
+
+#[test]
+fn parse_bigints() {
+    let program = "bigint x = 12345678901234567890;
+    bigint y = 5;
+    x = x * y + 1;";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let result = Parser::new().parse_program(&mut tokens.into());
+
+    let x = || Variable::BigInteger("x".to_string());
+    let y = || Variable::BigInteger("y".to_string());
+    assert_eq!(
+        result.unwrap(),
+        Program::Statements(vec![
+            Meta::new(Statement::Assignment(x(),
+                ArithmeticExpression::BigInteger("12345678901234567890".parse().unwrap())
+            ), Location::new(1, 0)),
+            // like floats, a single int literal becomes a literal of the declared type
+            Meta::new(Statement::Assignment(y(),
+                ArithmeticExpression::BigInteger(BigInt::from(5))
+            ), Location::new(2, 4)),
+            Meta::new(Statement::Assignment(x(),
+                ArithmeticExpression::Operation(
+                    ArithmeticOperator::Addition,
+                    Box::new(ArithmeticExpression::Operation(
+                        ArithmeticOperator::Multiplication,
+                        Box::new(ArithmeticExpression::Variable(x())),
+                        Box::new(ArithmeticExpression::Variable(y())),
+                    )),
+                    Box::new(ArithmeticExpression::Integer(1)),
+                )
+            ), Location::new(3, 4)),
+        ])
+    );
+}
+
+#[test]
+fn parse_rationals() {
+    let program = "rational q = 3;
+    q = q / 0.5r;";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let result = Parser::new().parse_program(&mut tokens.into());
+
+    let q = || Variable::Rational("q".to_string());
+    assert_eq!(
+        result.unwrap(),
+        Program::Statements(vec![
+            Meta::new(Statement::Assignment(q(), ArithmeticExpression::Rational(Rational::from(3))), Location::new(1, 0)),
+            Meta::new(Statement::Assignment(q(),
+                ArithmeticExpression::Operation(
+                    ArithmeticOperator::Division,
+                    Box::new(ArithmeticExpression::Variable(q())),
+                    Box::new(ArithmeticExpression::Rational("1/2".parse().unwrap())),
+                )
+            ), Location::new(2, 4)),
         ])
     );
 }
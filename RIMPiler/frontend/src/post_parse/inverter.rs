@@ -2,10 +2,21 @@ use super::super::AST::{
     ArithmeticExpression, Block, BooleanExpression, Program, RelationOperator,
     Statement, Variable
 };
+use super::invertibility::mark_with;
+use super::super::overflow::Overflow;
+use super::loop_analysis::{counter, reverse_condition};
+use utilities::debug::{Location, Meta};
 
+// assignments that can be undone without the history become direct assignments first, see invertibility
 pub fn invert_and_combine(program: &Program) -> Program {
-    let inverted = invert(program);
-    combine(program, &inverted)
+    invert_and_combine_with(program, Overflow::default())
+}
+
+// how ints overflow decides which assignments can be undone without the history
+pub fn invert_and_combine_with(program: &Program, overflow: Overflow) -> Program {
+    let program = mark_with(program, overflow);
+    let inverted = invert(&program);
+    combine(&program, &inverted)
 }
 
 pub fn combine(forward: &Program, backward: &Program) -> Program {
@@ -14,7 +25,7 @@ pub fn combine(forward: &Program, backward: &Program) -> Program {
 
     let mut statements = Vec::new();
     statements.extend(forward_statements.clone());
-    statements.push(Statement::ReversePoint);
+    statements.push(Meta::new(Statement::ReversePoint, Location::default()));
     statements.extend(backward_statements.clone());
 
     Program::Statements(statements)
@@ -44,11 +55,11 @@ impl Inverter {
                 let mut new_statements = Vec::with_capacity(statements.len());
 
                 for _ in 0..statements.len() {
-                    new_statements.push(Statement::Skip);
+                    new_statements.push(Meta::new(Statement::Skip, Location::default()));
                 }
 
                 for (index, statement) in statements.iter().enumerate() {
-                    if let Statement::Assignment(variable, _) = statement {
+                    if let Statement::Assignment(variable, _) = &statement.value {
                         match variable {
                             Variable::Integer(variable) => {
                                 self.last_variable_name = Some(variable.clone());
@@ -58,9 +69,18 @@ impl Inverter {
                                 self.last_variable_name = Some(variable.clone());
                                 self.last_variable_type = Some("float".to_string());
                             }
+                            Variable::BigInteger(variable) => {
+                                self.last_variable_name = Some(variable.clone());
+                                self.last_variable_type = Some("bigint".to_string());
+                            }
+                            Variable::Rational(variable) => {
+                                self.last_variable_name = Some(variable.clone());
+                                self.last_variable_type = Some("rational".to_string());
+                            }
                         }
                     }
-                    new_statements[statements.len() - index - 1] = self.invert_statement(statement);
+                    new_statements[statements.len() - index - 1] =
+                        Meta::new(self.invert_statement(&statement.value, &statements[..index]), statement.location);
                 }
 
                 Program::Statements(new_statements)
@@ -68,17 +88,21 @@ impl Inverter {
         }
     }
 
-    fn invert_statement(&mut self, statement: &Statement) -> Statement {
+    // before is the rest of the statement's block up to it, which a loop without a counter is reversed from
+    fn invert_statement(&mut self, statement: &Statement, before: &[Meta<Statement>]) -> Statement {
         match statement {
             Statement::If(boolean_expression, if_block, else_block) => {
                 self.invert_if_statement(boolean_expression, if_block, else_block)
             }
             Statement::While(boolean_expression, block) => {
-                self.invert_while_statement(boolean_expression, block)
+                self.invert_while_statement(boolean_expression, block, before)
             }
             Statement::Assignment(variable, expression) => self.invert_assignment(variable, expression),
             Statement::Skip => Statement::Skip,
             Statement::ReverseAssignment(variable, expression) => Statement::Assignment(variable.clone(), expression.clone()),
+            Statement::DirectAssignment(variable, expression, inverse) => {
+                Statement::DirectAssignment(variable.clone(), inverse.clone(), expression.clone())
+            }
             Statement::ReversePoint => Statement::ReversePoint,
         }
     }
@@ -98,9 +122,17 @@ impl Inverter {
 
     fn invert_while_statement(
         &mut self,
-        _: &BooleanExpression,
+        boolean_expression: &BooleanExpression,
         block: &Block,
+        before: &[Meta<Statement>],
     ) -> Statement {
+        // the transformer left the counter out, as the loop's own variables say when to stop
+        if counter(block).is_none() {
+            let condition = reverse_condition(boolean_expression, before, block)
+                .expect("A loop without a counter has to be decidable, see loop_analysis");
+            return Statement::While(Box::new(condition), Box::new(self.invert_block(block)));
+        }
+
         if self.last_variable_name.is_none() {
             unreachable!(
                 "This should only be called on an AST that has gone through semantic transformations"
@@ -130,11 +162,11 @@ impl Inverter {
     fn invert_block(&mut self, block: &Block) -> Block {
         let mut new_block = Vec::with_capacity(block.len());
         for _ in 0..block.len() {
-            new_block.push(Statement::Skip);
+            new_block.push(Meta::new(Statement::Skip, Location::default()));
         }
 
         for (index, statement) in block.iter().enumerate() {
-            if let Statement::Assignment(variable, _) = statement {
+            if let Statement::Assignment(variable, _) = &statement.value {
                 match variable {
                     Variable::Integer(variable) => {
                         self.last_variable_name = Some(variable.clone());
@@ -144,9 +176,18 @@ impl Inverter {
                         self.last_variable_name = Some(variable.clone());
                         self.last_variable_type = Some("float".to_string());
                     }
+                    Variable::BigInteger(variable) => {
+                        self.last_variable_name = Some(variable.clone());
+                        self.last_variable_type = Some("bigint".to_string());
+                    }
+                    Variable::Rational(variable) => {
+                        self.last_variable_name = Some(variable.clone());
+                        self.last_variable_type = Some("rational".to_string());
+                    }
                 }
             }
-            new_block[block.len() - index - 1] = self.invert_statement(statement);
+            new_block[block.len() - index - 1] =
+                Meta::new(self.invert_statement(&statement.value, &block[..index]), statement.location);
         }
 
         new_block
@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use ordered_float::NotNan;
+use utilities::bigint::BigInt;
+use utilities::rational::Rational;
+use super::super::post_parse::name_generator::is_generated;
+use super::super::overflow::Overflow;
+use super::super::AST::{
+    ArithmeticExpression, ArithmeticOperator, Block, Program, Statement, Type, UnaryArithmeticOperator, Variable
+};
+use utilities::debug::{Location, Meta};
+
+/*
+Works out which assignments can be undone without the history.
+
+An assignment is injective when the old value of the variable can be computed from the new one and the other
+variables, which it doesn't change:
+    - x = x + e, x = e + x and x = x - e for ints and bigints, where e doesn't use x, are undone by adding or
+      subtracting e
+    - x = e - x for ints and bigints is its own inverse
+    - x = x + e, x = e + x, x = x - e and x = e - x for rationals, in the same way, as they're exact too
+    - x = x * c, x = c * x and x = x / c for rationals, where c is a non-zero literal, are undone by dividing
+      or multiplying by c, which is why rationals are exact rather than rounding
+    - x = -x and x = x are their own inverse, for floats as well
+    - the first assignment of a variable, at the top level of the program, is undone by setting it back to 0, as
+      long as the value has the variable's type
+Float addition and subtraction round, so they are destructive, like every other assignment, and need the
+history to be undone. So are int addition, subtraction and negation when ints saturate, see overflow, which
+leaves x = x as the only int assignment undone without the history, apart from first assignments.
+
+mark replaces injective assignments with direct assignments, which the inverter turns into the inverse
+instead of popping the history, so neither half records anything. The transformer's counters and copies are
+left as they are.
+*/
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Invertibility {
+    // undone by assigning this to the variable
+    Injective(ArithmeticExpression),
+    // the old value is lost, so it is kept in the history
+    Destructive,
+}
+
+// an assignment of the program, with whether it needs history
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Classification {
+    pub location: Location,
+    pub variable: Variable,
+    pub expression: ArithmeticExpression,
+    pub invertibility: Invertibility,
+}
+
+impl Display for Classification {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {} = {} ", self.location.line, self.variable, self.expression)?;
+        match &self.invertibility {
+            Invertibility::Injective(inverse) => write!(f, "is undone by {} = {}", self.variable, inverse),
+            Invertibility::Destructive => write!(f, "needs history"),
+        }
+    }
+}
+
+// every assignment the user wrote, in the order they appear
+pub fn analyse(program: &Program) -> Vec<Classification> {
+    analyse_with(program, Overflow::default())
+}
+
+pub fn analyse_with(program: &Program, overflow: Overflow) -> Vec<Classification> {
+    let Program::Statements(statements) = mark_with(program, overflow);
+
+    let mut classifications = Vec::new();
+    collect(&statements, &mut classifications);
+    classifications
+}
+
+fn collect(block: &Block, classifications: &mut Vec<Classification>) {
+    for statement in block {
+        let (variable, expression, invertibility) = match &statement.value {
+            Statement::Assignment(variable, expression) if !is_generated(variable_name(variable)) => {
+                (variable, expression, Invertibility::Destructive)
+            }
+            Statement::DirectAssignment(variable, expression, inverse) => {
+                (variable, expression, Invertibility::Injective(inverse.clone()))
+            }
+            Statement::If(_, if_block, else_block) => {
+                collect(if_block, classifications);
+                collect(else_block, classifications);
+                continue;
+            }
+            Statement::While(_, block) => {
+                collect(block, classifications);
+                continue;
+            }
+            _ => continue,
+        };
+
+        classifications.push(Classification {
+            location: statement.location,
+            variable: variable.clone(),
+            expression: expression.clone(),
+            invertibility,
+        });
+    }
+}
+
+// replaces every injective assignment with a direct assignment
+pub fn mark(program: &Program) -> Program {
+    mark_with(program, Overflow::default())
+}
+
+pub fn mark_with(program: &Program, overflow: Overflow) -> Program {
+    let Program::Statements(statements) = program;
+
+    let mut analyser = Analyser::new(overflow);
+    Program::Statements(analyser.mark_block(statements, true))
+}
+
+fn variable_name(variable: &Variable) -> &String {
+    match variable {
+        Variable::Integer(name) => name,
+        Variable::Float(name) => name,
+        Variable::BigInteger(name) => name,
+        Variable::Rational(name) => name,
+    }
+}
+
+fn variable_type(variable: &Variable) -> Type {
+    match variable {
+        Variable::Integer(_) => Type::Integer,
+        Variable::Float(_) => Type::Float,
+        Variable::BigInteger(_) => Type::BigInteger,
+        Variable::Rational(_) => Type::Rational,
+    }
+}
+
+// the type of the value, which only stays an int if everything in it is, and otherwise is the widest of float,
+// rational and bigint in it
+pub fn expression_type(expression: &ArithmeticExpression) -> Type {
+    match expression {
+        ArithmeticExpression::Variable(variable) => variable_type(variable),
+        ArithmeticExpression::Integer(_) => Type::Integer,
+        ArithmeticExpression::Float(_) => Type::Float,
+        ArithmeticExpression::BigInteger(_) => Type::BigInteger,
+        ArithmeticExpression::Rational(_) => Type::Rational,
+        ArithmeticExpression::Unary(_, operand) => expression_type(operand),
+        ArithmeticExpression::Operation(_, left, right) => match (expression_type(left), expression_type(right)) {
+            (Type::Float, _) | (_, Type::Float) => Type::Float,
+            (Type::Rational, _) | (_, Type::Rational) => Type::Rational,
+            (Type::BigInteger, _) | (_, Type::BigInteger) => Type::BigInteger,
+            _ => Type::Integer,
+        },
+    }
+}
+
+fn reads(expression: &ArithmeticExpression, name: &str) -> bool {
+    match expression {
+        ArithmeticExpression::Variable(variable) => variable_name(variable) == name,
+        ArithmeticExpression::Integer(_)
+        | ArithmeticExpression::Float(_)
+        | ArithmeticExpression::BigInteger(_)
+        | ArithmeticExpression::Rational(_) => false,
+        ArithmeticExpression::Unary(_, operand) => reads(operand, name),
+        ArithmeticExpression::Operation(_, left, right) => reads(left, name) || reads(right, name),
+    }
+}
+
+fn zero(variable: &Variable) -> ArithmeticExpression {
+    match variable {
+        Variable::Integer(_) => ArithmeticExpression::Integer(0),
+        Variable::Float(_) => ArithmeticExpression::Float(NotNan::new(0.0).unwrap()),
+        Variable::BigInteger(_) => ArithmeticExpression::BigInteger(BigInt::zero()),
+        Variable::Rational(_) => ArithmeticExpression::Rational(Rational::zero()),
+    }
+}
+
+// a literal, possibly negated, that isn't zero, so multiplying by it can be undone
+fn non_zero_literal(expression: &ArithmeticExpression) -> bool {
+    match expression {
+        ArithmeticExpression::Integer(value) => *value != 0,
+        ArithmeticExpression::Float(value) => value.into_inner() != 0.0,
+        ArithmeticExpression::BigInteger(value) => !value.is_zero(),
+        ArithmeticExpression::Rational(value) => !value.is_zero(),
+        ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, operand) => non_zero_literal(operand),
+        _ => false,
+    }
+}
+
+// the inverse of an assignment, if it can be undone from the new value
+pub fn inverse(variable: &Variable, expression: &ArithmeticExpression) -> Option<ArithmeticExpression> {
+    let name = variable_name(variable);
+    let is_variable = |expression: &ArithmeticExpression| matches!(expression, ArithmeticExpression::Variable(other) if other == variable);
+    let operation = |operator: ArithmeticOperator, left: &ArithmeticExpression, right: &ArithmeticExpression| {
+        ArithmeticExpression::Operation(operator, Box::new(left.clone()), Box::new(right.clone()))
+    };
+
+    match expression {
+        expression if is_variable(expression) => Some(expression.clone()),
+        ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, operand) if is_variable(operand) => Some(expression.clone()),
+        ArithmeticExpression::Operation(operator, left, right)
+            if variable_type(variable) != Type::Float && expression_type(expression) == variable_type(variable) =>
+        {
+            let variable_expression = ArithmeticExpression::Variable(variable.clone());
+            let rational = variable_type(variable) == Type::Rational;
+            match operator {
+                ArithmeticOperator::Addition if is_variable(left) && !reads(right, name) => {
+                    Some(operation(ArithmeticOperator::Subtraction, &variable_expression, right))
+                }
+                ArithmeticOperator::Addition if is_variable(right) && !reads(left, name) => {
+                    Some(operation(ArithmeticOperator::Subtraction, &variable_expression, left))
+                }
+                ArithmeticOperator::Subtraction if is_variable(left) && !reads(right, name) => {
+                    Some(operation(ArithmeticOperator::Addition, &variable_expression, right))
+                }
+                ArithmeticOperator::Subtraction if is_variable(right) && !reads(left, name) => Some(expression.clone()),
+                ArithmeticOperator::Multiplication if rational && is_variable(left) && non_zero_literal(right) => {
+                    Some(operation(ArithmeticOperator::Division, &variable_expression, right))
+                }
+                ArithmeticOperator::Multiplication if rational && is_variable(right) && non_zero_literal(left) => {
+                    Some(operation(ArithmeticOperator::Division, &variable_expression, left))
+                }
+                ArithmeticOperator::Division if rational && is_variable(left) && non_zero_literal(right) => {
+                    Some(operation(ArithmeticOperator::Multiplication, &variable_expression, right))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+struct Analyser {
+    // the variables that have been assigned so far
+    assigned: HashSet<String>,
+    overflow: Overflow,
+}
+
+impl Analyser {
+    fn new(overflow: Overflow) -> Analyser {
+        Analyser { assigned: HashSet::new(), overflow }
+    }
+
+    fn classify(&self, variable: &Variable, expression: &ArithmeticExpression, top_level: bool) -> Invertibility {
+        let saturating = !self.overflow.is_reversible() && variable_type(variable) == Type::Integer;
+        if let Some(inverse) = inverse(variable, expression) {
+            if !saturating || matches!(expression, ArithmeticExpression::Variable(_)) {
+                return Invertibility::Injective(inverse);
+            }
+        }
+
+        let name = variable_name(variable);
+        if top_level && !self.assigned.contains(name) && !reads(expression, name) && expression_type(expression) == variable_type(variable) {
+            return Invertibility::Injective(zero(variable));
+        }
+
+        Invertibility::Destructive
+    }
+
+    fn mark_block(&mut self, block: &Block, top_level: bool) -> Block {
+        let mut new_block = Vec::with_capacity(block.len());
+
+        for statement in block {
+            let new_statement = match &statement.value {
+                Statement::Assignment(variable, expression) if !is_generated(variable_name(variable)) => {
+                    let invertibility = self.classify(variable, expression, top_level);
+                    self.assigned.insert(variable_name(variable).clone());
+
+                    match invertibility {
+                        Invertibility::Injective(inverse) => Statement::DirectAssignment(variable.clone(), expression.clone(), inverse),
+                        Invertibility::Destructive => statement.value.clone(),
+                    }
+                }
+                Statement::Assignment(variable, _) | Statement::ReverseAssignment(variable, _) | Statement::DirectAssignment(variable, _, _) => {
+                    self.assigned.insert(variable_name(variable).clone());
+                    statement.value.clone()
+                }
+                Statement::If(condition, if_block, else_block) => Statement::If(
+                    condition.clone(),
+                    Box::new(self.mark_block(if_block, false)),
+                    Box::new(self.mark_block(else_block, false)),
+                ),
+                Statement::While(condition, block) => Statement::While(condition.clone(), Box::new(self.mark_block(block, false))),
+                Statement::Skip | Statement::ReversePoint => statement.value.clone(),
+            };
+
+            new_block.push(Meta::new(new_statement, statement.location));
+        }
+
+        new_block
+    }
+}
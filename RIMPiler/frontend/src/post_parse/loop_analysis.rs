@@ -0,0 +1,219 @@
+use std::fmt::{Display, Formatter};
+use super::super::post_parse::name_generator::is_generated;
+use super::super::AST::{
+    ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, Program, RelationOperator, Statement, Variable
+};
+use utilities::debug::{Location, Meta};
+
+/*
+Works out when a while loop can be run backwards without the counter the semantic transformer adds to it.
+
+The backward loop has to run its body once for every time the forward loop did, so it needs a condition that is
+false when the forward loop was entered and true after each of its iterations. The counter always gives one
+(counter > 0), but the loop's own variables can as well: if the body steps an int by a constant, exactly once and
+not inside an if or another loop, and the int was assigned a constant just before the loop, then
+    n = 10; while n > 0 do { ...; n = n - 1; };
+is undone by
+    while n != 10 do { n = n + 1; ... };
+as n is only 10 again once every iteration has been undone.
+
+That only holds if the int can't wrap around to where it started, which it can when ints wrap, e.g. adding 2^30
+four times gets back to 0. So the loop's condition has to keep the step from overflowing as well: it compares the
+int with a constant, or an int the body doesn't assign, and the int is stepped towards it, so the int only ever
+moves one way. n > 0 keeps n - 1 from overflowing, but a variable bound only does that for steps of 1, as
+m < k keeps m + 2 below the largest int only if k isn't the largest int.
+*/
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Reversal {
+    // the backward loop runs while the transformer's counter is above 0
+    Counted(Variable),
+    // the backward loop runs while this holds, without a counter
+    Decided(BooleanExpression),
+}
+
+// how a loop of the program is run backwards
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LoopDecision {
+    pub location: Location,
+    pub reversal: Reversal,
+}
+
+impl Display for LoopDecision {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match &self.reversal {
+            Reversal::Counted(counter) => write!(f, "line {}: loop counted by {}", self.location.line, counter),
+            Reversal::Decided(condition) => {
+                write!(f, "line {}: loop needs no counter, it is run backwards while {}", self.location.line, condition)
+            }
+        }
+    }
+}
+
+// the decision made for every loop of a transformed program, in the order they appear
+pub fn decisions(program: &Program) -> Vec<LoopDecision> {
+    let Program::Statements(statements) = program;
+
+    let mut decisions = Vec::new();
+    collect(statements, &mut decisions);
+    decisions
+}
+
+fn collect(block: &Block, decisions: &mut Vec<LoopDecision>) {
+    for (index, statement) in block.iter().enumerate() {
+        match &statement.value {
+            Statement::If(_, if_block, else_block) => {
+                collect(if_block, decisions);
+                collect(else_block, decisions);
+            }
+            Statement::While(condition, body) => {
+                let reversal = match counter(body) {
+                    Some(counter) => Some(Reversal::Counted(counter.clone())),
+                    None => reverse_condition(condition, &block[..index], body).map(Reversal::Decided),
+                };
+
+                if let Some(reversal) = reversal {
+                    decisions.push(LoopDecision { location: statement.location, reversal });
+                }
+
+                collect(body, decisions);
+            }
+            _ => {}
+        }
+    }
+}
+
+// the transformer's counter, if the body ends by incrementing one
+pub fn counter(body: &Block) -> Option<&Variable> {
+    match body.last().map(|statement| &statement.value) {
+        Some(Statement::Assignment(
+            variable @ Variable::Integer(name),
+            ArithmeticExpression::Operation(ArithmeticOperator::Addition, left, right),
+        )) if is_generated(name)
+            && **left == ArithmeticExpression::Variable(variable.clone())
+            && **right == ArithmeticExpression::Integer(1) => Some(variable),
+        _ => None,
+    }
+}
+
+// the condition the backward loop can run on instead of a counter, given the loop's condition, the statements
+// before the loop in its block and its body
+pub fn reverse_condition(
+    condition: &BooleanExpression,
+    before: &[Meta<Statement>],
+    body: &Block,
+) -> Option<BooleanExpression> {
+    let (variable, step) = stepped_variable(body)?;
+    if !bounds(condition, variable, step, body) {
+        return None;
+    }
+    let entry = entry_value(before, variable)?;
+
+    Some(BooleanExpression::Relational(
+        RelationOperator::NotEqual,
+        Box::new(ArithmeticExpression::Variable(variable.clone())),
+        Box::new(ArithmeticExpression::Integer(entry)),
+    ))
+}
+
+// the variable and value of an assignment, whether or not it records history
+fn assignment(statement: &Statement) -> Option<(&Variable, &ArithmeticExpression)> {
+    match statement {
+        Statement::Assignment(variable, expression) | Statement::DirectAssignment(variable, expression, _) => {
+            Some((variable, expression))
+        }
+        _ => None,
+    }
+}
+
+fn assignments(block: &Block, variable: &Variable) -> usize {
+    block
+        .iter()
+        .map(|statement| match &statement.value {
+            Statement::If(_, if_block, else_block) => assignments(if_block, variable) + assignments(else_block, variable),
+            Statement::While(_, body) => assignments(body, variable),
+            statement => assignment(statement).filter(|(assigned, _)| *assigned == variable).map_or(0, |_| 1),
+        })
+        .sum()
+}
+
+// an int the body adds a constant other than 0 to, with no other assignment to it anywhere in the body, and the
+// constant, negated if it is subtracted
+fn stepped_variable(body: &Block) -> Option<(&Variable, i64)> {
+    body.iter().filter_map(|statement| assignment(&statement.value)).find_map(|(variable, expression)| {
+        let Variable::Integer(name) = variable else {
+            return None;
+        };
+
+        let step = match expression {
+            ArithmeticExpression::Operation(operator, left, right)
+                if **left == ArithmeticExpression::Variable(variable.clone()) => match (operator, &**right) {
+                (ArithmeticOperator::Addition, ArithmeticExpression::Integer(step)) => i64::from(*step),
+                (ArithmeticOperator::Subtraction, ArithmeticExpression::Integer(step)) => -i64::from(*step),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        (step != 0 && !is_generated(name) && assignments(body, variable) == 1).then_some((variable, step))
+    })
+}
+
+// whether the condition keeps the variable's step from overflowing, as the variable is on the side of the bound it
+// is stepped towards whenever the body runs, and it isn't stepped before the condition is checked again
+fn bounds(condition: &BooleanExpression, variable: &Variable, step: i64, body: &Block) -> bool {
+    let BooleanExpression::Relational(operator, left, right) = condition else {
+        return false;
+    };
+
+    let stepped = ArithmeticExpression::Variable(variable.clone());
+    // whether the variable has to be below the bound, and the bound
+    let (below, bound) = match operator {
+        RelationOperator::LessThan if **left == stepped => (true, &**right),
+        RelationOperator::LessThan if **right == stepped => (false, &**left),
+        RelationOperator::GreaterThan if **left == stepped => (false, &**right),
+        RelationOperator::GreaterThan if **right == stepped => (true, &**left),
+        _ => return false,
+    };
+
+    // the variable is at most one step past the bound, so one less than it or one more than it
+    let furthest = match bound {
+        ArithmeticExpression::Integer(bound) if below => i64::from(*bound) - 1 + step,
+        ArithmeticExpression::Integer(bound) => i64::from(*bound) + 1 + step,
+        ArithmeticExpression::Variable(bound @ Variable::Integer(_)) if bound != variable && assignments(body, bound) == 0 => {
+            return if below { step == 1 } else { step == -1 };
+        }
+        _ => return false,
+    };
+
+    if below {
+        step > 0 && furthest <= i64::from(i32::MAX)
+    } else {
+        step < 0 && furthest >= i64::from(i32::MIN)
+    }
+}
+
+// the constant the variable holds when the loop is entered, from the last assignment to it before the loop
+fn entry_value(before: &[Meta<Statement>], variable: &Variable) -> Option<i32> {
+    for statement in before.iter().rev() {
+        match &statement.value {
+            Statement::If(_, if_block, else_block) => {
+                if assignments(if_block, variable) + assignments(else_block, variable) > 0 {
+                    return None;
+                }
+            }
+            Statement::While(_, body) => {
+                if assignments(body, variable) > 0 {
+                    return None;
+                }
+            }
+            statement => match assignment(statement) {
+                Some((assigned, ArithmeticExpression::Integer(value))) if assigned == variable => return Some(*value),
+                Some((assigned, _)) if assigned == variable => return None,
+                _ => {}
+            },
+        }
+    }
+
+    None
+}
@@ -1,5 +1,7 @@
 pub mod inverter;
-mod name_generator;
+pub mod invertibility;
+pub mod loop_analysis;
+pub mod name_generator;
 pub mod transformer;
 
 #[cfg(test)]
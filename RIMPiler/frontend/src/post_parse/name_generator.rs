@@ -1,3 +1,64 @@
+use std::collections::HashMap;
+use super::super::post_parse::loop_analysis::counter;
+use super::super::AST::{ArithmeticExpression, Block, Program, Statement, Variable};
+
+/*
+Generated names can never be the same as a user's. Identifiers are lexed as [a-zA-Z][a-zA-Z0-9]*, so no name with
+an underscore gets past the lexer, and every generated name starts with PREFIX, which has one.
+
+The names say nothing about why a variable was added, so labels maps them back to the statement they were added
+for, which is what stores and diagnostics show instead of the bare name.
+*/
+const PREFIX: &str = "generated_name_";
+
+pub fn is_generated(name: &str) -> bool {
+    name.starts_with(PREFIX)
+}
+
+// a readable description of every generated variable of a transformed program, by name
+pub fn labels(program: &Program) -> HashMap<String, String> {
+    let Program::Statements(statements) = program;
+
+    let mut labels = HashMap::new();
+    collect_labels(statements, &mut labels);
+    labels
+}
+
+fn collect_labels(block: &Block, labels: &mut HashMap<String, String>) {
+    for statement in block {
+        match &statement.value {
+            // the copies of the variables an if tests, assigned just before it
+            Statement::Assignment(Variable::Integer(name) | Variable::Float(name) | Variable::BigInteger(name) | Variable::Rational(name), ArithmeticExpression::Variable(copied))
+                if is_generated(name) =>
+            {
+                let copied = match copied {
+                    Variable::Integer(copied) | Variable::Float(copied) | Variable::BigInteger(copied) | Variable::Rational(copied) => copied,
+                };
+                labels.insert(name.clone(), format!("copy of {} for if at line {}", copied, statement.location.line));
+            }
+            Statement::If(_, if_block, else_block) => {
+                collect_labels(if_block, labels);
+                collect_labels(else_block, labels);
+            }
+            Statement::While(_, body) => {
+                if let Some(Variable::Integer(name) | Variable::Float(name) | Variable::BigInteger(name) | Variable::Rational(name)) = counter(body) {
+                    labels.insert(name.clone(), format!("loop counter for while at line {}", statement.location.line));
+                }
+                collect_labels(body, labels);
+            }
+            _ => {}
+        }
+    }
+}
+
+// the label of a generated variable, or the name of any other
+pub fn readable_name(name: &str, labels: &HashMap<String, String>) -> String {
+    match labels.get(name) {
+        Some(label) => format!("{} ({})", name, label),
+        None => name.to_string(),
+    }
+}
+
 pub struct NameGenerator {
     name: String,
     counter: usize,
@@ -5,7 +66,7 @@ pub struct NameGenerator {
 
 impl NameGenerator {
     pub fn new(base: String) -> Self {
-        let mut name = String::from("generated_name_");
+        let mut name = String::from(PREFIX);
         name.push_str(&base);
         Self { name, counter: 0 }
     }
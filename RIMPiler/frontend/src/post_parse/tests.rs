@@ -3,8 +3,10 @@ use super::super::parser::parser::parse;
 use super::super::post_parse::inverter::invert;
 use super::super::AST::{
     ArithmeticExpression, ArithmeticOperator, BooleanExpression, Program,
-    RelationOperator, Statement, Variable
+    RelationOperator, Statement, UnaryArithmeticOperator, Variable
 };
+use ordered_float::NotNan;
+use utilities::debug::{Location, Meta};
 
 #[test]
 fn name_generator() {
@@ -33,18 +35,18 @@ fn transformer() {
     assert_eq!(
         ast,
         Program::Statements(vec![
-            Statement::Assignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
+            Meta::new(Statement::Assignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
                 ArithmeticExpression::Integer(0)
-            ),
-            Statement::While(
+            ), Location::new(2, 8)),
+            Meta::new(Statement::While(
                 Box::new(BooleanExpression::Relational(
                     RelationOperator::LessThan,
                     Box::new(ArithmeticExpression::Integer(1)),
                     Box::new(ArithmeticExpression::Integer(2))
                 )),
                 Box::new(vec![
-                    Statement::Skip,
-                    Statement::Assignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
+                    Meta::new(Statement::Skip, Location::new(3, 12)),
+                    Meta::new(Statement::Assignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
                         ArithmeticExpression::Operation(
                             ArithmeticOperator::Addition,
                             Box::new(ArithmeticExpression::Variable(Variable::Integer(String::from(
@@ -52,9 +54,9 @@ fn transformer() {
                             )))),
                             Box::new(ArithmeticExpression::Integer(1))
                         )
-                    )
+                    ), Location::new(2, 8))
                 ])
-            )
+            ), Location::new(2, 8))
         ])
     )
 }
@@ -81,7 +83,7 @@ fn inverter() {
     assert_eq!(
         reversed_ast,
         Program::Statements(vec![
-            Statement::While(
+            Meta::new(Statement::While(
                 Box::new(BooleanExpression::Relational(
                     RelationOperator::GreaterThan,
                     Box::new(ArithmeticExpression::Variable(Variable::Integer(
@@ -92,7 +94,7 @@ fn inverter() {
                     Box::new(ArithmeticExpression::Integer(0))
                 )),
                 Box::new(vec![
-                    Statement::ReverseAssignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
+                    Meta::new(Statement::ReverseAssignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
                         ArithmeticExpression::Operation(
                             ArithmeticOperator::Addition,
                             Box::new(ArithmeticExpression::Variable(Variable::Integer(
@@ -102,21 +104,374 @@ fn inverter() {
                             ))),
                             Box::new(ArithmeticExpression::Integer(1))
                         )
-                    ),
-                    Statement::If(
+                    ), Location::new(2, 8)),
+                    Meta::new(Statement::If(
                         Box::new(BooleanExpression::Relational(
                             RelationOperator::LessThan,
                             Box::new(ArithmeticExpression::Integer(1)),
                             Box::new(ArithmeticExpression::Integer(2))
                         )),
-                        Box::new(vec![Statement::Skip]),
-                        Box::new(vec![Statement::Skip])
-                    )
+                        Box::new(vec![Meta::new(Statement::Skip, Location::new(4, 16))]),
+                        Box::new(vec![Meta::new(Statement::Skip, Location::new(6, 16))])
+                    ), Location::new(3, 12))
                 ])
-            ),
-            Statement::ReverseAssignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
+            ), Location::new(2, 8)),
+            Meta::new(Statement::ReverseAssignment(Variable::Integer(String::from("generated_name_semantic_transformer0")),
                 ArithmeticExpression::Integer(0)
-            )
+            ), Location::new(2, 8))
         ])
     )
 }
+
+#[test]
+fn invertibility() {
+    use super::invertibility::{analyse, Invertibility};
+
+    let program = r#"int n = 10;
+float x = 0.5;
+while n > 0 do {
+    n = n - 1;
+    x = x * 2;
+    x = -x;
+};
+int m = 3 - n;
+n = n * 2;
+m = m + n;"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+
+    let lines = analyse(&ast)
+        .iter()
+        .map(|classification| (classification.location.line, classification.invertibility.clone()))
+        .collect::<Vec<(usize, Invertibility)>>();
+
+    let variable = |name: &str| Box::new(ArithmeticExpression::Variable(Variable::Integer(String::from(name))));
+    assert_eq!(lines, vec![
+        (1, Invertibility::Injective(ArithmeticExpression::Integer(0))),
+        (2, Invertibility::Injective(ArithmeticExpression::Float(NotNan::new(0.0).unwrap()))),
+        (4, Invertibility::Injective(ArithmeticExpression::Operation(ArithmeticOperator::Addition, variable("n"), Box::new(ArithmeticExpression::Integer(1))))),
+        // float multiplication rounds
+        (5, Invertibility::Destructive),
+        (6, Invertibility::Injective(ArithmeticExpression::Unary(
+            UnaryArithmeticOperator::Negation,
+            Box::new(ArithmeticExpression::Variable(Variable::Float(String::from("x")))),
+        ))),
+        (8, Invertibility::Injective(ArithmeticExpression::Integer(0))),
+        (9, Invertibility::Destructive),
+        (10, Invertibility::Injective(ArithmeticExpression::Operation(ArithmeticOperator::Subtraction, variable("m"), variable("n")))),
+    ]);
+}
+
+#[test]
+fn rational_invertibility() {
+    use super::invertibility::{analyse, Invertibility};
+
+    let program = r#"rational q = 1;
+rational p = 2;
+q = q * 3r;
+q = 0.5r * q;
+q = q / -2;
+q = q * 0r;
+q = q * p;
+q = q / 0.5;"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+
+    let lines = analyse(&ast)
+        .iter()
+        .map(|classification| (classification.location.line, classification.invertibility.clone()))
+        .collect::<Vec<(usize, Invertibility)>>();
+
+    let q = || Box::new(ArithmeticExpression::Variable(Variable::Rational(String::from("q"))));
+    let rational = |string: &str| Box::new(ArithmeticExpression::Rational(string.parse().unwrap()));
+    assert_eq!(lines, vec![
+        (1, Invertibility::Injective(*rational("0"))),
+        (2, Invertibility::Injective(*rational("0"))),
+        (3, Invertibility::Injective(ArithmeticExpression::Operation(ArithmeticOperator::Division, q(), rational("3")))),
+        (4, Invertibility::Injective(ArithmeticExpression::Operation(ArithmeticOperator::Division, q(), rational("1/2")))),
+        (5, Invertibility::Injective(ArithmeticExpression::Operation(
+            ArithmeticOperator::Multiplication,
+            q(),
+            Box::new(ArithmeticExpression::Unary(UnaryArithmeticOperator::Negation, Box::new(ArithmeticExpression::Integer(2)))),
+        ))),
+        // multiplying by zero, or by something that could be, loses the old value
+        (6, Invertibility::Destructive),
+        (7, Invertibility::Destructive),
+        // and so does anything with a float in it, which rounds
+        (8, Invertibility::Destructive),
+    ]);
+}
+
+#[test]
+fn direct_assignments_keep_the_store() {
+    use super::super::interpreter::interpreter::InterpreterEngine;
+    use super::super::interpreter::memory_store::Value;
+    use super::inverter::{combine, invert_and_combine};
+    use super::super::IR::lower::lower;
+
+    // the values at the reverse point, and whether the backward program put everything back to 0
+    let run = |program: &Program| {
+        let mut interpreter = InterpreterEngine::new();
+        interpreter.interpret(program).unwrap();
+
+        let variables = lower(program).unwrap().variables;
+        let values = variables
+            .iter()
+            .map(|variable| (variable.name.clone(), interpreter.get_result(&variable.name).unwrap().get()))
+            .collect::<Vec<(String, Value)>>();
+
+        let final_store = interpreter.get_final_memory_point_snapshot().as_ref().unwrap();
+        let restored = variables.iter().all(|variable| final_store.get(&variable.name).unwrap().get() == Value::Integer(0));
+
+        (values, restored)
+    };
+
+    for name in ["basic", "collatz", "exponentials", "fibonacci", "floats", "if", "while"] {
+        let program = std::fs::read_to_string(format!("../../examples/{}.rimp", name)).unwrap();
+
+        let mut tokeniser = Tokeniser::new().initialise();
+        let tokens = tokeniser.tokenise(program).unwrap();
+
+        let ast = parse(&mut tokens.into()).unwrap();
+
+        let (values, restored) = run(&invert_and_combine(&ast));
+        let (history_values, history_restored) = run(&combine(&ast, &invert(&ast)));
+
+        assert_eq!(values, history_values, "{}", name);
+        assert!(restored && history_restored, "{}", name);
+    }
+}
+
+#[test]
+fn loop_decisions() {
+    use super::loop_analysis::{decisions, Reversal};
+
+    let program = r#"int n = 10;
+int m = 0;
+while n > 0 do {
+    m = 3;
+    while m > 0 do {
+        m = m - 1;
+    };
+    n = n - 2;
+};
+while m < n do {
+    m = m + 1;
+};
+int i = 0;
+while i < 5 do {
+    if i > 2 then { i = i + 1; } else { skip; };
+    i = i + 1;
+};
+int j = 0;
+while i > 0 do {
+    j = j + 1;
+    i = i - 1;
+};
+int k = 2147483640;
+while k < 2147483647 do {
+    k = k + 8;
+};
+int p = 3;
+while 0 < p do {
+    p = p - 1;
+};"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+
+    let decided = |variable: &str, entry: i32| Reversal::Decided(BooleanExpression::Relational(
+        RelationOperator::NotEqual,
+        Box::new(ArithmeticExpression::Variable(Variable::Integer(String::from(variable)))),
+        Box::new(ArithmeticExpression::Integer(entry)),
+    ));
+    let lines = decisions(&ast)
+        .into_iter()
+        .map(|decision| (decision.location.line, decision.reversal))
+        .collect::<Vec<(usize, Reversal)>>();
+
+    assert_eq!(lines, vec![
+        (3, decided("n", 10)),
+        (5, decided("m", 3)),
+        // m was changed by the loop before, so its value isn't known
+        (10, Reversal::Counted(Variable::Integer(String::from("generated_name_semantic_transformer0")))),
+        // i is assigned twice in the body
+        (14, Reversal::Counted(Variable::Integer(String::from("generated_name_semantic_transformer2")))),
+        // j is stepped, but the condition doesn't stop it wrapping
+        (19, Reversal::Counted(Variable::Integer(String::from("generated_name_semantic_transformer3")))),
+        // the last step goes past the largest int
+        (24, Reversal::Counted(Variable::Integer(String::from("generated_name_semantic_transformer4")))),
+        (28, decided("p", 3)),
+    ]);
+}
+
+#[test]
+fn loops_without_counters_keep_the_store() {
+    use super::super::interpreter::interpreter::InterpreterEngine;
+    use super::super::interpreter::memory_store::Value;
+    use super::super::parser::parser::parse_keeping_counters;
+    use super::inverter::invert_and_combine;
+    use super::super::IR::lower::lower;
+
+    // the user's variables at the reverse point, whether everything was put back to 0, and how many variables
+    // there were
+    let run = |program: &Program| {
+        let inverted = invert_and_combine(program);
+
+        let mut interpreter = InterpreterEngine::new();
+        interpreter.interpret(&inverted).unwrap();
+
+        let variables = lower(&inverted).unwrap().variables;
+        let values = variables
+            .iter()
+            .filter(|variable| !variable.generated)
+            .map(|variable| (variable.name.clone(), interpreter.get_result(&variable.name).unwrap().get()))
+            .collect::<Vec<(String, Value)>>();
+
+        let final_store = interpreter.get_final_memory_point_snapshot().as_ref().unwrap();
+        let restored = variables.iter().all(|variable| final_store.get(&variable.name).unwrap().get() == Value::Integer(0));
+
+        (values, restored, variables.len())
+    };
+
+    let programs = [
+        String::from("int n = 10; int s = 0; while n > 0 do { s = s + n * n; n = n - 1; };"),
+        String::from("int i = 0; float x = 1.0; while i < 30 do { x = x * 1.5; i = i + 3; };"),
+        String::from("int n = 4; int t = 0; while n > 0 do { int m = 0; while m < n do { t = t + m; m = m + 1; }; n = n - 1; };"),
+        String::from("int n = 6; int p = 1; while n > 1 do { if p > 10 then { p = p - 10; } else { p = p * n; }; n = n - 1; };"),
+        std::fs::read_to_string("../../examples/while.rimp").unwrap(),
+        std::fs::read_to_string("../../examples/fibonacci.rimp").unwrap(),
+    ];
+
+    for source in programs {
+        let mut tokeniser = Tokeniser::new().initialise();
+        let without_counters = parse(&mut tokeniser.tokenise(source.clone()).unwrap().into()).unwrap();
+        let mut tokeniser = Tokeniser::new().initialise();
+        let with_counters = parse_keeping_counters(&mut tokeniser.tokenise(source.clone()).unwrap().into()).unwrap();
+
+        let (values, restored, variables) = run(&without_counters);
+        let (counted_values, counted_restored, counted_variables) = run(&with_counters);
+
+        assert_eq!(values, counted_values, "{}", source);
+        assert!(restored && counted_restored, "{}", source);
+        assert!(variables < counted_variables, "{}", source);
+    }
+}
+
+#[test]
+fn generated_names_are_not_identifiers() {
+    use super::name_generator::{is_generated, NameGenerator};
+
+    let mut name_generator = NameGenerator::new(String::from("semantic_transformer"));
+
+    for _ in 0..3 {
+        let name = name_generator.generate();
+        assert!(is_generated(&name));
+
+        // a program can't declare or use the name, so it can't collide with one the transformer adds
+        for program in [format!("int {} = 1;", name), format!("int n = {};", name)] {
+            let mut tokeniser = Tokeniser::new().initialise();
+            assert!(tokeniser.tokenise(program.clone()).is_err(), "{}", program);
+        }
+    }
+}
+
+#[test]
+fn generated_names_have_labels() {
+    use super::name_generator::{labels, readable_name};
+    use super::inverter::invert_and_combine;
+    use super::super::IR::lower::lower;
+
+    let program = r#"int n = 3;
+int m = 0;
+while n > 0 do {
+    if n > 1 then {
+        n = n - 1;
+    } else {
+        n = n - 1;
+    };
+    m = m * 2;
+};"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let labels = labels(&ast);
+
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels["generated_name_semantic_transformer0"], "copy of n for if at line 4");
+    assert_eq!(labels["generated_name_semantic_transformer1"], "loop counter for while at line 3");
+    assert_eq!(readable_name("generated_name_semantic_transformer1", &labels), "generated_name_semantic_transformer1 (loop counter for while at line 3)");
+    assert_eq!(readable_name("n", &labels), "n");
+
+    // the combined program has the same labels, which the IR shows
+    let text = lower(&invert_and_combine(&ast)).unwrap().to_string();
+    assert!(text.contains("2: int generated_name_semantic_transformer1 (loop counter for while at line 3)\n"), "{}", text);
+    assert!(text.contains("3: int generated_name_semantic_transformer0 (copy of n for if at line 4)\n"), "{}", text);
+}
+
+#[test]
+fn ifs_inside_untouched_ifs_are_transformed() {
+    use super::name_generator::labels;
+
+    // the outer condition shares no variable with its branches, but the inner one does
+    let program = r#"int n = 2;
+if 0 < 1 then {
+    if n > 1 then {
+        n = n - 1;
+    } else {
+        skip;
+    };
+} else {
+    skip;
+};"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+    let labels = labels(&ast);
+
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels["generated_name_semantic_transformer0"], "copy of n for if at line 3");
+}
+
+#[test]
+fn saturating_invertibility() {
+    use super::invertibility::{analyse, analyse_with, Invertibility};
+    use super::super::overflow::Overflow;
+
+    let program = r#"int x = 1;
+int y = 2;
+x = x + y;
+x = y - x;
+x = -x;
+x = x;"#;
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    let ast = parse(&mut tokens.into()).unwrap();
+
+    let injective = |overflow: Overflow| {
+        analyse_with(&ast, overflow)
+            .iter()
+            .map(|classification| classification.invertibility != Invertibility::Destructive)
+            .collect::<Vec<bool>>()
+    };
+
+    assert_eq!(analyse(&ast), analyse_with(&ast, Overflow::Wrapping));
+    assert_eq!(injective(Overflow::Wrapping), vec![true; 6]);
+    assert_eq!(injective(Overflow::Checked), vec![true; 6]);
+    // a saturated int can't be undone from its new value, but first assignments and x = x still can
+    assert_eq!(injective(Overflow::Saturating), vec![true, true, false, false, false, true]);
+}
@@ -1,27 +1,36 @@
+use super::super::post_parse::loop_analysis::reverse_condition;
 use super::super::post_parse::name_generator::NameGenerator;
 use super::super::AST::{
     ArithmeticExpression, ArithmeticOperator, Block, BooleanExpression, Program,
     Statement, Variable
 };
 use std::collections::{HashMap, HashSet};
+use utilities::debug::{Location, Meta};
 
+// loops that can be run backwards without a counter are left without one, see loop_analysis
 pub fn transform(program: &Program) -> Program {
     let mut name_generator = NameGenerator::new(String::from("semantic_transformer"));
-    transform_program(program, &mut name_generator)
+    transform_program(program, &mut name_generator, false)
 }
 
-fn transform_program(program: &Program, name_generator: &mut NameGenerator) -> Program {
+// every loop gets a counter, for the backends that find where a loop was entered from it
+pub fn transform_keeping_counters(program: &Program) -> Program {
+    let mut name_generator = NameGenerator::new(String::from("semantic_transformer"));
+    transform_program(program, &mut name_generator, true)
+}
+
+fn transform_program(program: &Program, name_generator: &mut NameGenerator, keep_counters: bool) -> Program {
     match program {
         Program::Statements(statements) => {
-            Program::Statements(transform_block(statements, name_generator))
+            Program::Statements(transform_block(statements, name_generator, keep_counters))
         }
     }
 }
 
-fn transform_block(block: &Block, name_generator: &mut NameGenerator) -> Block {
+fn transform_block(block: &Block, name_generator: &mut NameGenerator, keep_counters: bool) -> Block {
     let mut new_block = Vec::new();
     for statement in block {
-        if let Some(new_statement) = transform_statement(statement, name_generator) {
+        if let Some(new_statement) = transform_statement(statement, &new_block, name_generator, keep_counters) {
             new_block.extend(new_statement);
         } else {
             new_block.push(statement.clone());
@@ -31,17 +40,33 @@ fn transform_block(block: &Block, name_generator: &mut NameGenerator) -> Block {
     new_block
 }
 
-fn transform_statement(statement: &Statement, name_generator: &mut NameGenerator) -> Option<Block> {
-    match statement {
+fn transform_statement(
+    statement: &Meta<Statement>,
+    before: &[Meta<Statement>],
+    name_generator: &mut NameGenerator,
+    keep_counters: bool,
+) -> Option<Block> {
+    match &statement.value {
         Statement::If(boolean_expression, if_block, else_block) => {
             transform_if_statement(boolean_expression,
-                                   &transform_block(if_block, name_generator),
-                                   &transform_block(else_block, name_generator),
+                                   &transform_block(if_block, name_generator, keep_counters),
+                                   &transform_block(else_block, name_generator, keep_counters),
+                                   statement.location,
                                    name_generator)
         }
         Statement::While(boolean_expression, block) => {
+            let block = transform_block(block, name_generator, keep_counters);
+
+            if !keep_counters && reverse_condition(boolean_expression, before, &block).is_some() {
+                return Some(vec![Meta::new(
+                    Statement::While(boolean_expression.clone(), Box::new(block)),
+                    statement.location,
+                )]);
+            }
+
             transform_while_statement(boolean_expression,
-                                      &transform_block(block, name_generator),
+                                      &block,
+                                      statement.location,
                                       name_generator)
         }
         _ => None,
@@ -56,24 +81,37 @@ pub fn transform_if_only(program: &Program) -> Program {
 fn transform_program_if_only(program: &Program, name_generator: &mut NameGenerator) -> Program {
     match program {
         Program::Statements(statements) => {
-            let mut new_statements = Vec::new();
-            for statement in statements {
-                if let Some(statements) = transform_statement_if_only(statement, name_generator) {
-                    new_statements.extend(statements);
-                } else {
-                    new_statements.push(statement.clone());
-                }
-            }
-            Program::Statements(new_statements)
+            Program::Statements(transform_block_if_only(statements, name_generator))
         }
     }
 }
 
-fn transform_statement_if_only(statement: &Statement, name_generator: &mut NameGenerator) -> Option<Block> {
-    match statement {
+// ifs nested in the blocks of other statements are transformed too
+fn transform_block_if_only(block: &Block, name_generator: &mut NameGenerator) -> Block {
+    let mut new_block = Vec::new();
+    for statement in block {
+        if let Some(statements) = transform_statement_if_only(statement, name_generator) {
+            new_block.extend(statements);
+        } else {
+            new_block.push(statement.clone());
+        }
+    }
+    new_block
+}
+
+fn transform_statement_if_only(statement: &Meta<Statement>, name_generator: &mut NameGenerator) -> Option<Block> {
+    match &statement.value {
         Statement::If(boolean_expression, if_block, else_block) => {
-            transform_if_statement(boolean_expression, if_block, else_block, name_generator)
+            transform_if_statement(boolean_expression,
+                                   &transform_block_if_only(if_block, name_generator),
+                                   &transform_block_if_only(else_block, name_generator),
+                                   statement.location,
+                                   name_generator)
         }
+        Statement::While(boolean_expression, block) => Some(vec![Meta::new(
+            Statement::While(boolean_expression.clone(), Box::new(transform_block_if_only(block, name_generator))),
+            statement.location,
+        )]),
         _ => None,
     }
 }
@@ -82,6 +120,7 @@ fn transform_if_statement(
     boolean_expression: &BooleanExpression,
     if_block: &Block,
     else_block: &Block,
+    location: Location,
     name_generator: &mut NameGenerator,
 ) -> Option<Block> {
     // get all variables assigned to in the if an else block
@@ -99,8 +138,16 @@ fn transform_if_statement(
     variables_in_both
         .extend(variables_in_else_block.intersection(&variables_in_boolean_expression));
 
+    // the blocks are still returned, as they may have been transformed themselves
     if variables_in_both.is_empty() {
-        return None;
+        return Some(vec![Meta::new(
+            Statement::If(
+                Box::new(boolean_expression.clone()),
+                Box::new(if_block.clone()),
+                Box::new(else_block.clone()),
+            ),
+            location,
+        )]);
     }
 
     let mut variables = HashMap::new();
@@ -115,16 +162,19 @@ fn transform_if_statement(
 
     let mut new_block = Vec::new();
     for (variable, new_variable) in &variables {
-        new_block.push(create_assignment_statement(
-            new_variable.clone(),
-            variable.clone(),
+        new_block.push(Meta::new(
+            create_assignment_statement(new_variable.clone(), variable.clone()),
+            location,
         ));
     }
 
-    new_block.push(Statement::If(
-        Box::new(new_boolean_expression),
-        Box::new(if_block.clone()),
-        Box::new(else_block.clone()),
+    new_block.push(Meta::new(
+        Statement::If(
+            Box::new(new_boolean_expression),
+            Box::new(if_block.clone()),
+            Box::new(else_block.clone()),
+        ),
+        location,
     ));
 
     Some(new_block)
@@ -133,6 +183,7 @@ fn transform_if_statement(
 fn transform_while_statement(
     boolean_expression: &BooleanExpression,
     block: &Block,
+    location: Location,
     name_generator: &mut NameGenerator,
 ) -> Option<Block> {
     // here we need to add a counter assignment at the start of the block
@@ -154,14 +205,17 @@ fn transform_while_statement(
     );
 
     let mut new_block = Vec::new();
-    new_block.push(counter);
+    new_block.push(Meta::new(counter, location));
 
     let mut while_block = block.clone();
-    while_block.push(increment);
+    while_block.push(Meta::new(increment, location));
 
-    new_block.push(Statement::While(
-        Box::new(boolean_expression.clone()),
-        Box::new(while_block),
+    new_block.push(Meta::new(
+        Statement::While(
+            Box::new(boolean_expression.clone()),
+            Box::new(while_block),
+        ),
+        location,
     ));
 
     Some(new_block)
@@ -172,7 +226,7 @@ fn transform_while_statement(
 fn get_variables_in_block(block: &Block) -> HashSet<(String, String)> {
     let mut variables = HashSet::new();
     for statement in block {
-        variables.extend(get_variables_in_statement(statement));
+        variables.extend(get_variables_in_statement(&statement.value));
     }
     variables
 }
@@ -202,8 +256,18 @@ fn get_variables_in_statement(statement: &Statement) -> HashSet<(String, String)
                 variables.insert((varriable.clone(), "float".to_string()));
                 variables
             }
+            Variable::BigInteger(varriable) => {
+                let mut variables = HashSet::new();
+                variables.insert((varriable.clone(), "bigint".to_string()));
+                variables
+            }
+            Variable::Rational(varriable) => {
+                let mut variables = HashSet::new();
+                variables.insert((varriable.clone(), "rational".to_string()));
+                variables
+            }
         },
-        Statement::ReverseAssignment(_, _) | Statement::ReversePoint => {
+        Statement::ReverseAssignment(_, _) | Statement::DirectAssignment(_, _, _) | Statement::ReversePoint => {
             unreachable!(
                 "You should never call this function after the reversal function has been called!"
             )
@@ -246,10 +310,18 @@ fn get_variables_in_arithmetic_expression(
                 Variable::Float(varriable) => {
                     variables.insert((varriable.clone(), "float".to_string()));
                 }
+                Variable::BigInteger(varriable) => {
+                    variables.insert((varriable.clone(), "bigint".to_string()));
+                }
+                Variable::Rational(varriable) => {
+                    variables.insert((varriable.clone(), "rational".to_string()));
+                }
             }
         }
         ArithmeticExpression::Integer(_) => {}
         ArithmeticExpression::Float(_) => {}
+        ArithmeticExpression::BigInteger(_) => {}
+        ArithmeticExpression::Rational(_) => {}
         ArithmeticExpression::Unary(_, arithmetic_expression) => {
             variables.extend(get_variables_in_arithmetic_expression(
                 arithmetic_expression,
@@ -317,12 +389,16 @@ fn remap_variables_in_arithmetic_expression(
             let (type_of_variable, name) = match variable {
                 Variable::Integer(name) => ("int".to_string(), name.clone()),
                 Variable::Float(name) => ("float".to_string(), name.clone()),
+                Variable::BigInteger(name) => ("bigint".to_string(), name.clone()),
+                Variable::Rational(name) => ("rational".to_string(), name.clone()),
             };
 
             if let Some(new_variable) = variables.get(&(name, type_of_variable.clone())) {
                 match type_of_variable.as_str() {
                     "int" => ArithmeticExpression::Variable(Variable::Integer(new_variable.clone())),
                     "float" => ArithmeticExpression::Variable(Variable::Float(new_variable.clone())),
+                    "bigint" => ArithmeticExpression::Variable(Variable::BigInteger(new_variable.clone())),
+                    "rational" => ArithmeticExpression::Variable(Variable::Rational(new_variable.clone())),
                     _ => unreachable!(),
                 }
             } else {
@@ -331,6 +407,8 @@ fn remap_variables_in_arithmetic_expression(
         }
         ArithmeticExpression::Integer(i) => ArithmeticExpression::Integer(i.clone()),
         ArithmeticExpression::Float(f) => ArithmeticExpression::Float(f.clone()),
+        ArithmeticExpression::BigInteger(integer) => ArithmeticExpression::BigInteger(integer.clone()),
+        ArithmeticExpression::Rational(rational) => ArithmeticExpression::Rational(rational.clone()),
         ArithmeticExpression::Unary(op, arithmetic_expression) => ArithmeticExpression::Unary(
             op.clone(),
             Box::new(remap_variables_in_arithmetic_expression(
@@ -366,6 +444,14 @@ fn create_assignment_statement(
             Variable::Float(new_variable_name),
             ArithmeticExpression::Variable(Variable::Float(right_hand_variable_name.0)),
         ),
+        "bigint" => Statement::Assignment(
+            Variable::BigInteger(new_variable_name),
+            ArithmeticExpression::Variable(Variable::BigInteger(right_hand_variable_name.0)),
+        ),
+        "rational" => Statement::Assignment(
+            Variable::Rational(new_variable_name),
+            ArithmeticExpression::Variable(Variable::Rational(right_hand_variable_name.0)),
+        ),
         _ => unreachable!(),
     }
 }
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use super::abstract_machine::engine::Engine;
+use super::interpreter::error::RuntimeError;
+use super::interpreter::interpreter::InterpreterEngine;
+use super::interpreter::memory_store::{MemoryStore, MemoryStoreElement};
+use super::post_parse::name_generator::readable_name;
+use super::AST::Program;
+
+#[cfg(test)]
+mod tests;
+
+/*
+Checks that a program really is reversible: running it forward and then backward has to leave every variable as it
+started, 0 with nothing in its history but the entry every history starts with.
+
+A variable that isn't is reported along with what it was left as, whether that is a different value, like a float
+that drifted, or entries left in its history, like an assignment the inverter didn't undo.
+
+Every variable starts at 0, so the end of the run can't tell a broken inverse from a working one when a later inverse
+sets the variable back to 0 anyway, which is how the first assignment of a variable is undone, see
+post_parse::invertibility. So the interpreter also checks each direct assignment as it runs, see
+InterpreterEngine::interpret_checking_inverses, and a broken inverse stops the verification with an error.
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unrestored {
+    pub variable: String,
+    pub element: MemoryStoreElement,
+}
+
+impl Unrestored {
+    // the variable as MemoryStore::labelled writes it, see post_parse::name_generator::labels
+    pub fn labelled(&self, labels: &HashMap<String, String>) -> String {
+        format!("{} was left as {}", readable_name(&self.variable, labels), self.element)
+    }
+}
+
+impl Display for Unrestored {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.labelled(&HashMap::new()))
+    }
+}
+
+// the variables of the store that aren't back to how they started, by name
+pub fn unrestored(store: &MemoryStore) -> Vec<Unrestored> {
+    let mut unrestored: Vec<Unrestored> = store
+        .iter()
+        .filter(|(_, element)| !element.is_initial())
+        .map(|(variable, element)| Unrestored { variable: variable.clone(), element: element.clone() })
+        .collect();
+    unrestored.sort_by(|a, b| a.variable.cmp(&b.variable));
+    unrestored
+}
+
+// runs the program, which has to be combined with its inverse, see post_parse::inverter::invert_and_combine
+pub fn verify_interpreter(interpreter: &mut InterpreterEngine, combined: &Program) -> Result<Vec<Unrestored>, RuntimeError> {
+    interpreter.interpret_checking_inverses(combined)?;
+
+    Ok(unrestored(interpreter.get_final_memory_point_snapshot().as_ref().unwrap()))
+}
+
+// runs the machine to the end of the program and back again
+pub fn verify_abstract_machine(engine: &mut Engine) -> Result<Vec<Unrestored>, String> {
+    engine.run()?;
+    engine.reverse();
+    engine.run()?;
+
+    Ok(unrestored(engine.get_store()))
+}
@@ -0,0 +1,110 @@
+use super::{unrestored, verify_abstract_machine, verify_interpreter};
+use super::super::abstract_machine::engine::Engine;
+use super::super::interpreter::error::RuntimeError;
+use super::super::interpreter::interpreter::InterpreterEngine;
+use super::super::interpreter::memory_store::{MemoryStore, Value};
+use super::super::lexer::lexer::Tokeniser;
+use super::super::parser::parser::{parse, parse_without_transform};
+use super::super::post_parse::inverter::invert_and_combine;
+use super::super::AST::{ArithmeticExpression, ArithmeticOperator, Program, Statement, Variable};
+
+const EXAMPLES: [&str; 9] = ["basic", "bigfibonacci", "collatz", "exponentials", "fibonacci", "floats", "if", "rationals", "while"];
+
+fn example(name: &str) -> String {
+    std::fs::read_to_string(format!("../../examples/{}.rimp", name)).unwrap()
+}
+
+fn combined(program: &str) -> Program {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    invert_and_combine(&parse(&mut tokens.into()).unwrap())
+}
+
+fn machine(program: &str) -> Engine {
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+
+    Engine::new(parse_without_transform(&mut tokens.into()).unwrap())
+}
+
+#[test]
+fn examples_are_restored() {
+    for name in EXAMPLES {
+        let mut interpreter = InterpreterEngine::new();
+        assert_eq!(verify_interpreter(&mut interpreter, &combined(&example(name))), Ok(Vec::new()), "{}", name);
+
+        assert_eq!(verify_abstract_machine(&mut machine(&example(name))), Ok(Vec::new()), "{}", name);
+    }
+}
+
+#[test]
+fn reports_unrestored_variables() {
+    // leaving out the last statement of the backward program leaves x where the forward program put it
+    let Program::Statements(mut statements) = combined("int x = 3;\nint y = x * 2;");
+    statements.pop();
+    let broken = Program::Statements(statements);
+
+    let mut interpreter = InterpreterEngine::new();
+    let left = verify_interpreter(&mut interpreter, &broken).unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].variable, "x");
+    assert_eq!(left[0].element.get(), Value::Integer(3));
+    assert!(left[0].to_string().starts_with("x was left as 3"), "{}", left[0]);
+
+    // a value back at 0 still has to have emptied its history
+    let mut store = MemoryStore::new();
+    store.assign(&String::from("a"), Value::Integer(5)).unwrap();
+    store.assign(&String::from("a"), Value::Integer(0)).unwrap();
+    store.set(&String::from("b"), Value::Float(0.0)).unwrap();
+    let left = unrestored(&store);
+    assert_eq!(left.iter().map(|variable| variable.variable.as_str()).collect::<Vec<_>>(), vec!["a"]);
+
+    // errors stop the verification
+    let mut interpreter = InterpreterEngine::new().max_steps(Some(10));
+    assert!(verify_interpreter(&mut interpreter, &combined("int n = 1;\nwhile n > 0 do {\n    n = n + 1;\n};")).is_err());
+    let mut engine = machine("int n = 1;\nwhile n > 0 do {\n    n = n + 1;\n};").max_steps(Some(100));
+    assert!(verify_abstract_machine(&mut engine).is_err());
+}
+
+#[test]
+fn wrapping_steps_are_restored() {
+    use super::super::post_parse::loop_analysis::{decisions, Reversal};
+
+    // n is back to 0 after the fourth iteration, so running the loop backwards until n is 0 again would stop at once
+    let program = "int n = 0;\nif 1 > 0 then {\n    int c = 0;\n} else {\n    skip;\n};\nwhile c < 4 do {\n    n = n + 1073741824;\n    c = c + 1;\n};";
+
+    let mut tokeniser = Tokeniser::new().initialise();
+    let tokens = tokeniser.tokenise(program.to_string()).unwrap();
+    let decided = decisions(&parse(&mut tokens.into()).unwrap());
+    assert!(matches!(decided[..], [ref decision] if matches!(decision.reversal, Reversal::Counted(_))), "{:?}", decided);
+
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(verify_interpreter(&mut interpreter, &combined(program)), Ok(Vec::new()));
+}
+
+#[test]
+fn broken_inverses_are_caught() {
+    // undoing x = x + 2 with x = x - 5 leaves x at 0 rather than 3, which undoing int x = 3 sets it to anyway
+    let Program::Statements(mut statements) = combined("int x = 3;\nx = x + 2;");
+    let x = Variable::Integer(String::from("x"));
+    let plus = |value| ArithmeticExpression::Operation(ArithmeticOperator::Addition, Box::new(ArithmeticExpression::Variable(x.clone())), Box::new(ArithmeticExpression::Integer(value)));
+    let minus = |value| ArithmeticExpression::Operation(ArithmeticOperator::Subtraction, Box::new(ArithmeticExpression::Variable(x.clone())), Box::new(ArithmeticExpression::Integer(value)));
+    assert_eq!(statements[3].value, Statement::DirectAssignment(x.clone(), minus(2), plus(2)));
+    statements[3].value = Statement::DirectAssignment(x.clone(), minus(5), plus(2));
+    let broken = Program::Statements(statements);
+
+    // so the store the program ends with looks restored
+    let mut interpreter = InterpreterEngine::new();
+    interpreter.interpret(&broken).unwrap();
+    assert!(unrestored(interpreter.get_final_memory_point_snapshot().as_ref().unwrap()).is_empty());
+
+    let mut interpreter = InterpreterEngine::new();
+    let error = verify_interpreter(&mut interpreter, &broken).unwrap_err();
+    assert!(matches!(error, RuntimeError::BrokenInverse { .. }), "{:?}", error);
+    assert_eq!(error.message(), "x = (- x 5) isn't undone by x = (+ x 2), which gives 2 rather than 5");
+
+    // and checking doesn't change what a working program does
+    let mut interpreter = InterpreterEngine::new();
+    assert_eq!(verify_interpreter(&mut interpreter, &combined("int x = 3;\nx = x + 2;\nfloat y = 0.5;\ny = -y;")), Ok(Vec::new()));
+}
@@ -0,0 +1,152 @@
+<package>import java.util.Stack;
+
+// Generated by RIMPiler from <source>
+public class <class> {
+    public static void main(String[] args) {
+<declarations>
+<code>
+    }
+
+    static int pow(int base, int exponent) {
+        if (exponent <= 0) {
+            throw new ArithmeticException("Cannot raise " + base + " to the power of " + exponent);
+        }
+        int result = 1;
+        for (int i = 0; i < exponent; i++) {
+            result *= base;
+        }
+        return result;
+    }
+
+    static float pow(float base, float exponent) {
+        if (exponent <= 0) {
+            throw new ArithmeticException("Cannot raise " + base + " to the power of " + exponent);
+        }
+        return (float) Math.pow(base, exponent);
+    }
+
+    static boolean debug() {
+        String debugEnv = System.getenv("RIMP_DEBUG");
+        return debugEnv != null && debugEnv.equals("1");
+    }
+
+    static final class RIMPInt {
+        String name;
+        int value;
+        Stack<Integer> history;
+
+        boolean debug = debug();
+
+        RIMPInt(String name) {
+            value = 0;
+            history = new Stack<>();
+            history.push(0);
+            this.name = name;
+            if (debug) {
+                System.out.println("Creating new RIMPInt: " + this.name);
+            }
+        }
+
+        void assign(int value) {
+            if (debug) {
+                System.out.println("Assigning " + this.name + " to " + value + " new size: " + (this.history.size() + 1));
+            }
+            this.history.push(value - this.value);
+            this.value = value;
+        }
+
+        void set(int value) {
+            if (debug) {
+                System.out.println("Setting " + this.name + " to " + value + " without history");
+            }
+            this.value = value;
+        }
+
+        void unAssign() {
+            if (debug) {
+                if (this.history.isEmpty()) {
+                    System.out.println("Unassigning " + this.name + " failed: history is empty");
+                } else {
+                    System.out.println("Unassigning " + this.name + " to " + (this.value - this.history.peek()) + " remaining assignments: " + (this.history.size() - 1));
+                }
+            }
+            this.value = this.value - this.history.peek();
+            this.history.pop();
+        }
+
+        int get() {
+            if (debug) {
+                System.out.println("Getting " + this.name + " value: " + this.value);
+            }
+            return this.value;
+        }
+
+        void print() {
+            System.out.print(this.name + ": " + this.value + "\t [");
+            for (Integer integer : this.history) {
+                System.out.print(integer + " ");
+            }
+            System.out.println("]");
+        }
+    }
+
+    static final class RIMPFloat {
+        String name;
+        float value;
+        // the value before each assignment, as subtracting the difference back off a float can round
+        Stack<Float> history;
+
+        boolean debug = debug();
+
+        RIMPFloat(String name) {
+            value = 0;
+            history = new Stack<>();
+            history.push(0.0f);
+            this.name = name;
+            if (debug) {
+                System.out.println("Creating new RIMPFloat: " + this.name);
+            }
+        }
+
+        void assign(float value) {
+            if (debug) {
+                System.out.println("Assigning " + this.name + " to " + value + " new size: " + (this.history.size() + 1));
+            }
+            this.history.push(this.value);
+            this.value = value;
+        }
+
+        void set(float value) {
+            if (debug) {
+                System.out.println("Setting " + this.name + " to " + value + " without history");
+            }
+            this.value = value;
+        }
+
+        void unAssign() {
+            if (debug) {
+                if (this.history.isEmpty()) {
+                    System.out.println("Unassigning " + this.name + " failed: history is empty");
+                } else {
+                    System.out.println("Unassigning " + this.name + " to " + this.history.peek() + " remaining assignments: " + (this.history.size() - 1));
+                }
+            }
+            this.value = this.history.pop();
+        }
+
+        float get() {
+            if (debug) {
+                System.out.println("Getting " + this.name + " value: " + this.value);
+            }
+            return this.value;
+        }
+
+        void print() {
+            System.out.print(this.name + ": " + this.value + "\t [");
+            for (Float float_ : this.history) {
+                System.out.print(float_ + " ");
+            }
+            System.out.println("]");
+        }
+    }
+}
@@ -0,0 +1,73 @@
+import java.math.BigDecimal;
+import java.math.BigInteger;
+import java.util.Stack;
+
+public class RIMPBigInt {
+    String name;
+    BigInteger value;
+    Stack<BigInteger> history;
+
+    boolean debug = false;
+
+    public RIMPBigInt(String name) {
+        value = BigInteger.ZERO;
+        history = new Stack<>();
+        history.push(BigInteger.ZERO);
+        this.name = name;
+        // check if debug environment variable is set
+        String debugEnv = System.getenv("RIMP_DEBUG");
+        if (debugEnv != null && debugEnv.equals("1")) {
+            debug = true;
+        }
+        if (debug) {
+            System.out.println("Creating new RIMPBigInt: " + this.name);
+        }
+    }
+
+    public void assign(BigInteger value) {
+        if (debug) {
+            System.out.println("Assigning " + this.name + " to " + value + " new size: " + (this.history.size() + 1));
+        }
+        this.history.push(value.subtract(this.value));
+        this.value = value;
+    }
+
+    public void set(BigInteger value) {
+        if (debug) {
+            System.out.println("Setting " + this.name + " to " + value + " without history");
+        }
+        this.value = value;
+    }
+
+    public void unAssign() {
+        if (debug) {
+            if (this.history.isEmpty()) {
+                System.out.println("Unassigning " + this.name + " failed: history is empty");
+            } else {
+                System.out.println("Unassigning " + this.name + " to " + this.value.subtract(this.history.peek()) + " remaining assignments: " + (this.history.size() - 1));
+            }
+        }
+        this.value = this.value.subtract(this.history.peek());
+        this.history.pop();
+    }
+
+    public BigInteger get() {
+        if (debug) {
+            System.out.println("Getting " + this.name + " value: " + this.value);
+        }
+        return this.value;
+    }
+
+    public void print() {
+        System.out.print(this.name + ": " + this.value + "\t [");
+        for (BigInteger integer : this.history) {
+            System.out.print(integer + " ");
+        }
+        System.out.println("]");
+    }
+
+    // floats become bigints by truncating, like (int) does for ints
+    public static BigInteger fromFloat(float value) {
+        return new BigDecimal(value).toBigInteger();
+    }
+}
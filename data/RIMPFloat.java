@@ -3,6 +3,7 @@ import java.util.Stack;
 public class RIMPFloat {
     String name;
     float value;
+    // the value before each assignment, as subtracting the difference back off a float can round
     Stack<Float> history;
 
     boolean debug = false;
@@ -26,7 +27,14 @@ public class RIMPFloat {
         if (debug) {
             System.out.println("Assigning " + this.name + " to " + value + " new size: " + (this.history.size() + 1));
         }
-        this.history.push(value - this.value);
+        this.history.push(this.value);
+        this.value = value;
+    }
+
+    public void set(float value) {
+        if (debug) {
+            System.out.println("Setting " + this.name + " to " + value + " without history");
+        }
         this.value = value;
     }
 
@@ -35,11 +43,10 @@ public class RIMPFloat {
             if (this.history.isEmpty()) {
                 System.out.println("Unassigning " + this.name + " failed: history is empty");
             } else {
-                System.out.println("Unassigning " + this.name + " to " + (this.value - this.history.peek()) + " remaining assignments: " + (this.history.size() - 1));
+                System.out.println("Unassigning " + this.name + " to " + this.history.peek() + " remaining assignments: " + (this.history.size() - 1));
             }
         }
-        this.value = this.value - this.history.peek();
-        this.history.pop();
+        this.value = this.history.pop();
     }
 
     public float get() {
@@ -30,6 +30,13 @@ public class RIMPInt {
         this.value = value;
     }
 
+    public void set(int value) {
+        if (debug) {
+            System.out.println("Setting " + this.name + " to " + value + " without history");
+        }
+        this.value = value;
+    }
+
     public void unAssign() {
         if (debug) {
             if (this.history.isEmpty()) {
@@ -49,6 +56,73 @@ public class RIMPInt {
         return this.value;
     }
 
+    // int arithmetic for the overflow modes that the jvm doesn't have instructions for, see Overflow in the compiler
+
+    public static int pow(int base, int exponent) {
+        if (exponent <= 0) {
+            throw new ArithmeticException("Cannot raise " + base + " to the power of " + exponent);
+        }
+        int result = 1;
+        for (int i = 0; i < exponent; i++) {
+            result *= base;
+        }
+        return result;
+    }
+
+    public static int divideExact(int lhs, int rhs) {
+        if (lhs == Integer.MIN_VALUE && rhs == -1) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return lhs / rhs;
+    }
+
+    public static int powExact(int base, int exponent) {
+        if (exponent <= 0) {
+            throw new ArithmeticException("Cannot raise " + base + " to the power of " + exponent);
+        }
+        int result = 1;
+        for (int i = 0; i < exponent; i++) {
+            result = Math.multiplyExact(result, base);
+        }
+        return result;
+    }
+
+    static int saturate(long value) {
+        return (int) Math.max(Integer.MIN_VALUE, Math.min(Integer.MAX_VALUE, value));
+    }
+
+    public static int addSaturating(int lhs, int rhs) {
+        return saturate((long) lhs + rhs);
+    }
+
+    public static int subtractSaturating(int lhs, int rhs) {
+        return saturate((long) lhs - rhs);
+    }
+
+    public static int multiplySaturating(int lhs, int rhs) {
+        return saturate((long) lhs * rhs);
+    }
+
+    public static int divideSaturating(int lhs, int rhs) {
+        return saturate((long) lhs / rhs);
+    }
+
+    public static int negateSaturating(int value) {
+        return saturate(-(long) value);
+    }
+
+    // once the result saturates it stays saturated, with the sign flipping for negative bases like the exact power
+    public static int powSaturating(int base, int exponent) {
+        if (exponent <= 0) {
+            throw new ArithmeticException("Cannot raise " + base + " to the power of " + exponent);
+        }
+        int result = 1;
+        for (int i = 0; i < exponent; i++) {
+            result = multiplySaturating(result, base);
+        }
+        return result;
+    }
+
     public void print() {
         System.out.print(this.name + ": " + this.value + "\t [");
         for (Integer integer : this.history) {
@@ -0,0 +1,258 @@
+/* Generated by RIMPiler from <source> */
+#include <inttypes.h>
+#include <math.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    const char *name;
+    int32_t value;
+    int32_t *history;
+    size_t size;
+    size_t capacity;
+} rimp_int;
+
+typedef struct {
+    const char *name;
+    float value;
+    float *history;
+    size_t size;
+    size_t capacity;
+} rimp_float;
+
+static inline void rimp_error(const char *message) {
+    fprintf(stderr, "Error: %s\n", message);
+    exit(1);
+}
+
+static inline void *rimp_grow(void *history, size_t *capacity, size_t element_size) {
+    *capacity = *capacity == 0 ? 8 : *capacity * 2;
+    history = realloc(history, *capacity * element_size);
+    if (history == NULL) {
+        rimp_error("out of memory");
+    }
+    return history;
+}
+
+static inline void rimp_int_init(rimp_int *variable, const char *name) {
+    variable->name = name;
+    variable->value = 0;
+    variable->history = NULL;
+    variable->size = 0;
+    variable->capacity = 0;
+    variable->history = rimp_grow(variable->history, &variable->capacity, sizeof(int32_t));
+    variable->history[variable->size++] = 0;
+}
+
+/* the history holds the difference made by each assignment, using wrapping arithmetic like the JVM */
+static inline void rimp_int_assign(rimp_int *variable, int32_t value) {
+    if (variable->size == variable->capacity) {
+        variable->history = rimp_grow(variable->history, &variable->capacity, sizeof(int32_t));
+    }
+    variable->history[variable->size++] = (int32_t) ((uint32_t) value - (uint32_t) variable->value);
+    variable->value = value;
+}
+
+static inline void rimp_int_unassign(rimp_int *variable) {
+    if (variable->size == 0) {
+        rimp_error("history is empty");
+    }
+    variable->size--;
+    variable->value = (int32_t) ((uint32_t) variable->value - (uint32_t) variable->history[variable->size]);
+}
+
+static inline void rimp_int_print(const rimp_int *variable) {
+    size_t index;
+    printf("%s: %" PRId32 "\t [", variable->name, variable->value);
+    for (index = 0; index < variable->size; index++) {
+        printf("%" PRId32 " ", variable->history[index]);
+    }
+    printf("]\n");
+}
+
+static inline void rimp_float_init(rimp_float *variable, const char *name) {
+    variable->name = name;
+    variable->value = 0.0f;
+    variable->history = NULL;
+    variable->size = 0;
+    variable->capacity = 0;
+    variable->history = rimp_grow(variable->history, &variable->capacity, sizeof(float));
+    variable->history[variable->size++] = 0.0f;
+}
+
+/* the history holds the value before each assignment, as subtracting the difference back off a float can round */
+static inline void rimp_float_assign(rimp_float *variable, float value) {
+    if (variable->size == variable->capacity) {
+        variable->history = rimp_grow(variable->history, &variable->capacity, sizeof(float));
+    }
+    variable->history[variable->size++] = variable->value;
+    variable->value = value;
+}
+
+static inline void rimp_float_unassign(rimp_float *variable) {
+    if (variable->size == 0) {
+        rimp_error("history is empty");
+    }
+    variable->size--;
+    variable->value = variable->history[variable->size];
+}
+
+/* prints a float the way java's Float.toString does, the shortest digits that read back as the same float */
+static inline void rimp_float_format(float value, char *buffer) {
+    char digits[32];
+    char mantissa[16];
+    int precision, exponent, length, index, point;
+    float magnitude = value < 0 ? -value : value;
+
+    if (isnan(value)) {
+        strcpy(buffer, "NaN");
+        return;
+    }
+    if (isinf(value)) {
+        strcpy(buffer, value < 0 ? "-Infinity" : "Infinity");
+        return;
+    }
+    if (value == 0.0f) {
+        strcpy(buffer, signbit(value) ? "-0.0" : "0.0");
+        return;
+    }
+
+    for (precision = 1; precision <= 9; precision++) {
+        sprintf(digits, "%.*e", precision - 1, magnitude);
+        if (strtof(digits, NULL) == magnitude) {
+            break;
+        }
+    }
+
+    /* digits is d.ddde[+-]xx, split it into the significant digits and the exponent */
+    length = 0;
+    for (index = 0; digits[index] != 'e'; index++) {
+        if (digits[index] != '.') {
+            mantissa[length++] = digits[index];
+        }
+    }
+    mantissa[length] = '\0';
+    exponent = atoi(&digits[index + 1]);
+    while (length > 1 && mantissa[length - 1] == '0') {
+        mantissa[--length] = '\0';
+    }
+
+    if (value < 0) {
+        *buffer++ = '-';
+    }
+
+    if (magnitude >= 1e-3f && magnitude < 1e7f) {
+        if (exponent < 0) {
+            *buffer++ = '0';
+            *buffer++ = '.';
+            for (index = -1; index > exponent; index--) {
+                *buffer++ = '0';
+            }
+            strcpy(buffer, mantissa);
+        } else {
+            point = exponent + 1;
+            for (index = 0; index < point; index++) {
+                *buffer++ = index < length ? mantissa[index] : '0';
+            }
+            *buffer++ = '.';
+            if (point >= length) {
+                *buffer++ = '0';
+            }
+            for (index = point; index < length; index++) {
+                *buffer++ = mantissa[index];
+            }
+            *buffer = '\0';
+        }
+    } else {
+        *buffer++ = mantissa[0];
+        *buffer++ = '.';
+        if (length == 1) {
+            *buffer++ = '0';
+        }
+        for (index = 1; index < length; index++) {
+            *buffer++ = mantissa[index];
+        }
+        sprintf(buffer, "E%d", exponent);
+    }
+}
+
+static inline void rimp_float_print(const rimp_float *variable) {
+    char buffer[32];
+    size_t index;
+    rimp_float_format(variable->value, buffer);
+    printf("%s: %s\t [", variable->name, buffer);
+    for (index = 0; index < variable->size; index++) {
+        rimp_float_format(variable->history[index], buffer);
+        printf("%s ", buffer);
+    }
+    printf("]\n");
+}
+
+/* integer arithmetic wraps on overflow as it does on the JVM, rather than being undefined */
+static inline int32_t rimp_add(int32_t lhs, int32_t rhs) {
+    return (int32_t) ((uint32_t) lhs + (uint32_t) rhs);
+}
+
+static inline int32_t rimp_sub(int32_t lhs, int32_t rhs) {
+    return (int32_t) ((uint32_t) lhs - (uint32_t) rhs);
+}
+
+static inline int32_t rimp_mul(int32_t lhs, int32_t rhs) {
+    return (int32_t) ((uint32_t) lhs * (uint32_t) rhs);
+}
+
+static inline int32_t rimp_neg(int32_t value) {
+    return (int32_t) (0u - (uint32_t) value);
+}
+
+static inline int32_t rimp_div(int32_t lhs, int32_t rhs) {
+    if (rhs == 0) {
+        rimp_error("division by zero");
+    }
+    if (lhs == INT32_MIN && rhs == -1) {
+        return INT32_MIN;
+    }
+    return lhs / rhs;
+}
+
+static inline int32_t rimp_pow(int32_t base, int32_t exponent) {
+    int32_t result = 1;
+    int32_t index;
+    if (exponent <= 0) {
+        rimp_error("cannot raise to a power that is not positive");
+    }
+    for (index = 0; index < exponent; index++) {
+        result = rimp_mul(result, base);
+    }
+    return result;
+}
+
+static inline float rimp_powf(float base, float exponent) {
+    if (exponent <= 0) {
+        rimp_error("cannot raise to a power that is not positive");
+    }
+    return powf(base, exponent);
+}
+
+/* converting an out of range float to an int saturates, like java and rust */
+static inline int32_t rimp_to_int(float value) {
+    if (isnan(value)) {
+        return 0;
+    }
+    if (value >= 2147483648.0f) {
+        return INT32_MAX;
+    }
+    if (value < -2147483648.0f) {
+        return INT32_MIN;
+    }
+    return (int32_t) value;
+}
+
+int main(void) {
+<declarations>
+<code>
+<cleanup>
+    return 0;
+}
@@ -0,0 +1,554 @@
+# Generated by RIMPiler from <source>
+# Build with: as -o main.o main.s && ld -o main main.o
+
+        .section .note.GNU-stack, "", @progbits
+
+        .equ SYS_WRITE, 1
+        .equ SYS_MMAP, 9
+        .equ SYS_EXIT_GROUP, 231
+
+        # the history is reserved up front, the kernel only backs the pages that are used
+        .equ HISTORY_SIZE, 1 << 32
+        .equ PROT_READ_WRITE, 3
+        .equ MAP_PRIVATE_ANONYMOUS_NORESERVE, 0x4022
+
+        .equ OUTPUT_SIZE, 4096
+
+        .bss
+        .align 8
+rimp_history_base:
+        .zero 8
+rimp_output_size:
+        .zero 8
+rimp_output:
+        .zero OUTPUT_SIZE
+# decimal digits are written backwards from the end, which is always 0
+rimp_digits:
+        .zero 40
+
+        .data
+        .align 4
+<data>
+        .section .rodata
+rimp_error_prefix:
+        .asciz "Error: "
+rimp_newline:
+        .asciz "\n"
+rimp_message_division:
+        .asciz "division by zero"
+rimp_message_power:
+        .asciz "cannot raise to a power that is not positive"
+rimp_message_empty:
+        .asciz "history is empty"
+rimp_message_full:
+        .asciz "history is full"
+rimp_message_memory:
+        .asciz "out of memory"
+rimp_separator:
+        .asciz ": "
+rimp_history_open:
+        .asciz "\t ["
+rimp_history_close:
+        .asciz "]\n"
+rimp_nan:
+        .asciz "NaN"
+rimp_infinity:
+        .asciz "Infinity"
+rimp_zero:
+        .asciz "0.0"
+
+        .align 4
+# floats between these are printed without an exponent, like java's Float.toString
+rimp_small:
+        .long 0x3a83126f        # 1.0E-3
+rimp_large:
+        .long 0x4b189680        # 1.0E7
+rimp_exact:
+        .long 0x4b800000        # 2^24, every float at least this big is an even integer
+
+        .align 8
+rimp_tens:
+        .quad 1, 10, 100, 1000, 10000, 100000, 1000000, 10000000, 100000000, 1000000000, 10000000000
+# 10^-60 to 10^60 as doubles
+rimp_powers:
+<powers>
+
+        .text
+        .globl _start
+_start:
+        movl $SYS_MMAP, %eax
+        xorl %edi, %edi
+        movabsq $HISTORY_SIZE, %rsi
+        movl $PROT_READ_WRITE, %edx
+        movl $MAP_PRIVATE_ANONYMOUS_NORESERVE, %r10d
+        movq $-1, %r8
+        xorl %r9d, %r9d
+        syscall
+        cmpq $-4095, %rax
+        jae .Lout_of_memory
+
+        # %r15 is the top of the history and %r14 its end, for the whole program
+        movq %rax, rimp_history_base(%rip)
+        movq %rax, %r15
+        movabsq $HISTORY_SIZE, %r14
+        addq %rax, %r14
+
+<code>
+        call rimp_flush
+        movl $SYS_EXIT_GROUP, %eax
+        xorl %edi, %edi
+        syscall
+
+.Lout_of_memory:
+        leaq rimp_message_memory(%rip), %rdi
+        jmp rimp_error
+
+# The runtime. Arguments are passed in %edi, %esi and %edx (or %xmm0 and %xmm1 for floats) and results are
+# returned in %eax or %xmm0. Everything but %rbx, %rbp and %r12 to %r15 may be overwritten.
+
+# write(fd: %edi, buffer: %rsi, length: %rdx), until everything is written or it fails
+rimp_write:
+1:      testq %rdx, %rdx
+        jz 2f
+        movl $SYS_WRITE, %eax
+        syscall
+        testq %rax, %rax
+        jle 2f
+        addq %rax, %rsi
+        subq %rax, %rdx
+        jmp 1b
+2:      ret
+
+rimp_flush:
+        movl $1, %edi
+        leaq rimp_output(%rip), %rsi
+        movq rimp_output_size(%rip), %rdx
+        call rimp_write
+        movq $0, rimp_output_size(%rip)
+        ret
+
+# prints "Error: <message: %rdi>" to stderr and exits with 1, after everything printed so far
+rimp_error:
+        pushq %rdi
+        call rimp_flush
+        movl $2, %edi
+        leaq rimp_error_prefix(%rip), %rsi
+        movl $7, %edx
+        call rimp_write
+        popq %rsi
+        movq %rsi, %rdx
+1:      cmpb $0, (%rdx)
+        je 2f
+        incq %rdx
+        jmp 1b
+2:      subq %rsi, %rdx
+        movl $2, %edi
+        call rimp_write
+        movl $2, %edi
+        leaq rimp_newline(%rip), %rsi
+        movl $1, %edx
+        call rimp_write
+        movl $SYS_EXIT_GROUP, %eax
+        movl $1, %edi
+        syscall
+
+# pushes (variable: %edi, difference or old float: %esi) onto the history
+rimp_push:
+        cmpq %r14, %r15
+        jae 1f
+        movl %edi, (%r15)
+        movl %esi, 4(%r15)
+        addq $8, %r15
+        ret
+1:      leaq rimp_message_full(%rip), %rdi
+        jmp rimp_error
+
+# pops the difference (or old float) last pushed for variable %edi, which has to be on top of the history
+rimp_pop:
+        cmpq rimp_history_base(%rip), %r15
+        jbe 1f
+        cmpl %edi, -8(%r15)
+        jne 1f
+        subq $8, %r15
+        movl 4(%r15), %eax
+        ret
+1:      leaq rimp_message_empty(%rip), %rdi
+        jmp rimp_error
+
+# integer arithmetic wraps on overflow as it does on the JVM
+rimp_div:
+        testl %esi, %esi
+        jz 2f
+        movl %edi, %eax
+        cmpl $-1, %esi
+        jne 1f
+        negl %eax
+        ret
+1:      cltd
+        idivl %esi
+        ret
+2:      leaq rimp_message_division(%rip), %rdi
+        jmp rimp_error
+
+rimp_pow:
+        testl %esi, %esi
+        jle 3f
+        movl $1, %eax
+1:      testl $1, %esi
+        jz 2f
+        imull %edi, %eax
+2:      imull %edi, %edi
+        shrl %esi
+        jnz 1b
+        ret
+3:      leaq rimp_message_power(%rip), %rdi
+        jmp rimp_error
+
+# %xmm0 ^ %xmm1 = 2 ^ (%xmm1 * log2 |%xmm0|), with the x87 instructions for logarithms and powers of 2
+rimp_powf:
+        xorps %xmm2, %xmm2
+        ucomiss %xmm2, %xmm1
+        jbe 6f
+        xorl %ecx, %ecx
+        ucomiss %xmm2, %xmm0
+        jp 2f
+        je 4f
+
+        # the sign of the result, a negative number can only be raised to an integer power
+        jae 2f
+        ucomiss rimp_exact(%rip), %xmm1
+        jae 1f
+        cvttss2si %xmm1, %eax
+        cvtsi2ss %eax, %xmm2
+        ucomiss %xmm1, %xmm2
+        jne 3f
+        jp 3f
+        andl $1, %eax
+        shll $31, %eax
+        movl %eax, %ecx
+1:      movd %xmm0, %eax
+        andl $0x7fffffff, %eax
+        movd %eax, %xmm0
+
+        # logarithms of infinity don't scale back
+2:      movd %xmm0, %eax
+        cmpl $0x7f800000, %eax
+        je 5f
+
+        subq $8, %rsp
+        movss %xmm1, (%rsp)
+        movss %xmm0, 4(%rsp)
+        flds (%rsp)
+        flds 4(%rsp)
+        fyl2x
+        fld %st(0)
+        frndint
+        fxch %st(1)
+        fsub %st(1), %st
+        f2xm1
+        fld1
+        faddp
+        fscale
+        fstp %st(1)
+        fstps (%rsp)
+        movl (%rsp), %eax
+        addq $8, %rsp
+        xorl %ecx, %eax
+        movd %eax, %xmm0
+        ret
+3:      movl $0x7fc00000, %eax  # NaN
+        movd %eax, %xmm0
+        ret
+4:      xorps %xmm0, %xmm0
+        ret
+5:      orl %ecx, %eax
+        movd %eax, %xmm0
+        ret
+6:      leaq rimp_message_power(%rip), %rdi
+        jmp rimp_error
+
+# converting an out of range float to an int saturates, like java and rust
+rimp_to_int:
+        ucomiss %xmm0, %xmm0
+        jp 1f
+        cvttss2si %xmm0, %eax
+        cmpl $-2147483648, %eax
+        jne 2f
+        xorps %xmm1, %xmm1
+        ucomiss %xmm1, %xmm0
+        jbe 2f
+        movl $2147483647, %eax
+        ret
+1:      xorl %eax, %eax
+2:      ret
+
+# appends %dil to the output buffer
+rimp_put_char:
+        movq rimp_output_size(%rip), %rax
+        cmpq $OUTPUT_SIZE, %rax
+        jb 1f
+        pushq %rdi
+        call rimp_flush
+        popq %rdi
+        xorl %eax, %eax
+1:      leaq rimp_output(%rip), %rcx
+        movb %dil, (%rcx,%rax)
+        incq %rax
+        movq %rax, rimp_output_size(%rip)
+        ret
+
+# appends the string ending in 0 at %rdi
+rimp_put_string:
+        pushq %rbx
+        movq %rdi, %rbx
+1:      movzbl (%rbx), %edi
+        testl %edi, %edi
+        jz 2f
+        call rimp_put_char
+        incq %rbx
+        jmp 1b
+2:      popq %rbx
+        ret
+
+# writes the digits of %rdi backwards into rimp_digits, leaving the first one in %rax
+rimp_format_unsigned:
+        leaq rimp_digits+32(%rip), %rsi
+        movq %rdi, %rax
+        movl $10, %ecx
+1:      xorl %edx, %edx
+        divq %rcx
+        addb $'0', %dl
+        decq %rsi
+        movb %dl, (%rsi)
+        testq %rax, %rax
+        jnz 1b
+        movq %rsi, %rax
+        ret
+
+rimp_put_int:
+        movslq %edi, %rdi
+        testq %rdi, %rdi
+        jns 1f
+        pushq %rdi
+        movl $'-', %edi
+        call rimp_put_char
+        popq %rdi
+        negq %rdi
+1:      call rimp_format_unsigned
+        movq %rax, %rdi
+        jmp rimp_put_string
+
+# prints the float with bits %edi the way java's Float.toString does, the shortest digits that read back as
+# the same float. The digits are found with doubles, which hold every float exactly and have enough precision
+# to round 9 digits
+rimp_put_float:
+        pushq %rbx
+        pushq %rbp
+        pushq %r12
+        pushq %r13
+        pushq %r14
+        movl %edi, %ebx
+        andl $0x7fffffff, %ebx
+        cmpl $0x7f800000, %ebx
+        ja .Lfloat_nan
+        testl %edi, %edi
+        jns 1f
+        movl $'-', %edi
+        call rimp_put_char
+1:      cmpl $0x7f800000, %ebx
+        je .Lfloat_infinity
+        testl %ebx, %ebx
+        jz .Lfloat_zero
+
+        movd %ebx, %xmm0
+        cvtss2sd %xmm0, %xmm6
+        leaq rimp_powers+60*8(%rip), %rsi
+
+        # the exponent %r13d, the largest with 10 ^ exponent <= the float
+        movl $38, %r13d
+2:      movslq %r13d, %rax
+        ucomisd (%rsi,%rax,8), %xmm6
+        jae 3f
+        decl %r13d
+        jmp 2b
+
+        # %r12d digits, rounded to the nearest %rbp, until they read back as the float
+3:      movl $1, %r12d
+4:      movl %r12d, %eax
+        subl $1, %eax
+        subl %r13d, %eax
+        cltq
+        movapd %xmm6, %xmm0
+        mulsd (%rsi,%rax,8), %xmm0
+        cvtsd2si %xmm0, %rbp
+        cvtsi2sd %rbp, %xmm0
+        divsd (%rsi,%rax,8), %xmm0
+        cvtsd2ss %xmm0, %xmm0
+        movd %xmm0, %eax
+        cmpl %eax, %ebx
+        je 5f
+        incl %r12d
+        cmpl $9, %r12d
+        jbe 4b
+        decl %r12d
+
+        # rounding up can give one digit more, 10 ^ digits
+5:      leaq rimp_tens(%rip), %rax
+        cmpq (%rax,%r12,8), %rbp
+        jne 6f
+        incl %r13d
+        decl %r12d
+        movq (%rax,%r12,8), %rbp
+
+        # the digits without trailing zeros, from %rbx with length %r12
+6:      movd %ebx, %xmm7
+        movq %rbp, %rdi
+        call rimp_format_unsigned
+        movq %rax, %rbx
+        leaq rimp_digits+32(%rip), %r12
+        subq %rbx, %r12
+7:      cmpq $1, %r12
+        jbe 8f
+        cmpb $'0', -1(%rbx,%r12)
+        jne 8f
+        decq %r12
+        jmp 7b
+
+8:      ucomiss rimp_small(%rip), %xmm7
+        jb .Lfloat_scientific
+        ucomiss rimp_large(%rip), %xmm7
+        jae .Lfloat_scientific
+
+        testl %r13d, %r13d
+        jns .Lfloat_whole
+
+        # 0.000ddd
+        movl $'0', %edi
+        call rimp_put_char
+        movl $'.', %edi
+        call rimp_put_char
+        movl $-1, %r14d
+1:      cmpl %r13d, %r14d
+        jle 2f
+        movl $'0', %edi
+        call rimp_put_char
+        decl %r14d
+        jmp 1b
+2:      xorl %r14d, %r14d
+        jmp .Lfloat_rest
+
+        # ddd.ddd, with at least one digit after the point
+.Lfloat_whole:
+        incl %r13d
+        xorl %r14d, %r14d
+1:      cmpl %r13d, %r14d
+        jge 3f
+        movl $'0', %edi
+        cmpq %r12, %r14
+        jae 2f
+        movzbl (%rbx,%r14), %edi
+2:      call rimp_put_char
+        incl %r14d
+        jmp 1b
+3:      movl $'.', %edi
+        call rimp_put_char
+        cmpq %r12, %r14
+        jb .Lfloat_rest
+        movl $'0', %edi
+        call rimp_put_char
+        jmp .Lfloat_done
+
+        # d.dddEx
+.Lfloat_scientific:
+        movzbl (%rbx), %edi
+        call rimp_put_char
+        movl $'.', %edi
+        call rimp_put_char
+        movl $1, %r14d
+        cmpq $1, %r12
+        jne 1f
+        movl $'0', %edi
+        call rimp_put_char
+1:      call .Lfloat_rest_digits
+        movl $'E', %edi
+        call rimp_put_char
+        movl %r13d, %edi
+        call rimp_put_int
+        jmp .Lfloat_done
+
+        # the digits from %r14 to %r12
+.Lfloat_rest:
+        call .Lfloat_rest_digits
+        jmp .Lfloat_done
+.Lfloat_rest_digits:
+1:      cmpq %r12, %r14
+        jae 2f
+        movzbl (%rbx,%r14), %edi
+        call rimp_put_char
+        incq %r14
+        jmp 1b
+2:      ret
+
+.Lfloat_nan:
+        leaq rimp_nan(%rip), %rdi
+        call rimp_put_string
+        jmp .Lfloat_done
+.Lfloat_infinity:
+        leaq rimp_infinity(%rip), %rdi
+        call rimp_put_string
+        jmp .Lfloat_done
+.Lfloat_zero:
+        leaq rimp_zero(%rip), %rdi
+        call rimp_put_string
+.Lfloat_done:
+        popq %r14
+        popq %r13
+        popq %r12
+        popq %rbp
+        popq %rbx
+        ret
+
+# prints "<name: %rdi>: <value: %edx>\t [<history> ]", with %rcx printing a value and %esi the variable
+rimp_print:
+        pushq %rbx
+        pushq %rbp
+        pushq %r12
+        pushq %r13
+        pushq %r14
+        movl %esi, %r12d
+        movl %edx, %r13d
+        movq %rcx, %rbp
+        call rimp_put_string
+        leaq rimp_separator(%rip), %rdi
+        call rimp_put_string
+        movl %r13d, %edi
+        call *%rbp
+        leaq rimp_history_open(%rip), %rdi
+        call rimp_put_string
+        movq rimp_history_base(%rip), %rbx
+1:      cmpq %r15, %rbx
+        jae 3f
+        cmpl %r12d, (%rbx)
+        jne 2f
+        movl 4(%rbx), %edi
+        call *%rbp
+        movl $' ', %edi
+        call rimp_put_char
+2:      addq $8, %rbx
+        jmp 1b
+3:      leaq rimp_history_close(%rip), %rdi
+        call rimp_put_string
+        popq %r14
+        popq %r13
+        popq %r12
+        popq %rbp
+        popq %rbx
+        ret
+
+rimp_print_int:
+        leaq rimp_put_int(%rip), %rcx
+        jmp rimp_print
+
+rimp_print_float:
+        leaq rimp_put_float(%rip), %rcx
+        jmp rimp_print
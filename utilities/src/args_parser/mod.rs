@@ -377,7 +377,7 @@ impl CommandLineArguments {
                                             Ok(value) => {
                                                 result
                                                     .arguments
-                                                    .insert(identifier, value.to_string());
+                                                    .insert(id.to_string(), value.to_string());
                                             }
                                             Err(_) => {
                                                 return Err(format!(
@@ -417,7 +417,7 @@ impl CommandLineArguments {
                                             Ok(value) => {
                                                 result
                                                     .arguments
-                                                    .insert(identifier, value.to_string());
+                                                    .insert(id.to_string(), value.to_string());
                                             }
                                             Err(_) => {
                                                 return Err(format!(
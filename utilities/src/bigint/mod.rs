@@ -0,0 +1,447 @@
+/*
+An arbitrary-precision integer, used for RIMP's bigint type.
+
+The magnitude is stored in base 2^32, least significant digit first, without trailing zero digits, so every
+number has exactly one representation: zero is an empty magnitude and is never negative. Division truncates
+towards zero, like i32 and java.math.BigInteger, which the JVM backend compiles bigints to.
+*/
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+fn trim(mut magnitude: Vec<u32>) -> Vec<u32> {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+    magnitude
+}
+
+fn compare_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for index in 0..a.len().max(b.len()) {
+        let sum = *a.get(index).unwrap_or(&0) as u64 + *b.get(index).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+// a - b, where a is at least b
+fn subtract_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (index, digit) in a.iter().enumerate() {
+        let mut difference = *digit as i64 - *b.get(index).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        result.push(difference as u32);
+    }
+    trim(result)
+}
+
+fn multiply_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let product = *x as u64 * *y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+
+        let mut index = i + b.len();
+        while carry > 0 {
+            let sum = result[index] as u64 + carry;
+            result[index] = sum as u32;
+            carry = sum >> 32;
+            index += 1;
+        }
+    }
+    trim(result)
+}
+
+// magnitude * multiplier + addend, in place
+fn multiply_add_digit(magnitude: &mut Vec<u32>, multiplier: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for digit in magnitude.iter_mut() {
+        let value = *digit as u64 * multiplier as u64 + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+// divides in place, returning the remainder
+fn divide_by_digit(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for digit in magnitude.iter_mut().rev() {
+        let current = (remainder << 32) | *digit as u64;
+        *digit = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+    *magnitude = trim(std::mem::take(magnitude));
+    remainder as u32
+}
+
+// the quotient of a / b, a bit at a time, where b isn't zero
+fn divide_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if b.len() == 1 {
+        let mut quotient = a.to_vec();
+        divide_by_digit(&mut quotient, b[0]);
+        return quotient;
+    }
+
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for bit in (0..a.len() * 32).rev() {
+        // remainder = remainder * 2 + the next bit of a
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for digit in remainder.iter_mut() {
+            let shifted = (*digit << 1) | carry;
+            carry = *digit >> 31;
+            *digit = shifted;
+        }
+        if carry > 0 {
+            remainder.push(carry);
+        }
+
+        if compare_magnitudes(&remainder, b) != Ordering::Less {
+            remainder = subtract_magnitudes(&remainder, b);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    trim(quotient)
+}
+
+impl BigInt {
+    fn new(negative: bool, magnitude: Vec<u32>) -> Self {
+        let magnitude = trim(magnitude);
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn zero() -> Self {
+        BigInt::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    // the value, if it fits in an i32
+    pub fn to_i32(&self) -> Option<i32> {
+        match self.magnitude.as_slice() {
+            [] => Some(0),
+            [digit] if self.negative => i32::try_from(-(*digit as i64)).ok(),
+            [digit] => i32::try_from(*digit).ok(),
+            _ => None,
+        }
+    }
+
+    // the lowest 32 bits in two's complement, like BigInteger.intValue
+    pub fn wrapping_i32(&self) -> i32 {
+        let low = *self.magnitude.first().unwrap_or(&0);
+        if self.negative {
+            low.wrapping_neg() as i32
+        } else {
+            low as i32
+        }
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.magnitude.get(index / 32).is_some_and(|digit| (digit >> (index % 32)) & 1 == 1)
+    }
+
+    pub(crate) fn bit_length(&self) -> usize {
+        match self.magnitude.last() {
+            Some(top) => self.magnitude.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    // the nearest float, like BigInteger.floatValue, infinite when it's too large
+    pub fn to_f32(&self) -> f32 {
+        let length = self.bit_length();
+        let shift = length.saturating_sub(64);
+
+        // the top 64 bits, with the lowest one set if any bit below them is, so that rounding them to a float
+        // rounds the whole number
+        let mut top = 0u64;
+        for index in 0..length.min(64) {
+            top |= (self.bit(shift + index) as u64) << index;
+        }
+        if (0..shift).any(|index| self.bit(index)) {
+            top |= 1;
+        }
+
+        let magnitude = (top as f32) as f64 * 2f64.powi(shift as i32);
+        let magnitude = magnitude as f32;
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    // truncates towards zero, none for infinities and NaN
+    pub fn from_f32(value: f32) -> Option<BigInt> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        let value = value.trunc();
+        if value.abs() < 9.0e18 {
+            return Some(BigInt::from(value as i64));
+        }
+
+        // a float this large is its 24 bit mantissa times a power of 2
+        let bits = value.to_bits();
+        let exponent = ((bits >> 23) & 0xff) - 127 - 23;
+        let mantissa = BigInt::from(((bits & 0x7fffff) | 0x800000) as i64);
+        let result = mantissa * BigInt::from(2).pow(exponent);
+        Some(if value < 0.0 { -result } else { result })
+    }
+
+    pub fn pow(&self, exponent: u32) -> BigInt {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    // none when dividing by zero
+    pub fn checked_div(&self, rhs: &BigInt) -> Option<BigInt> {
+        if rhs.is_zero() {
+            return None;
+        }
+
+        Some(BigInt::new(self.negative != rhs.negative, divide_magnitudes(&self.magnitude, &rhs.magnitude)))
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::new(false, self.magnitude.clone())
+    }
+
+    // the greatest common divisor, which is never negative, and 0 only when both are
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let mut a = self.abs();
+        let mut b = other.abs();
+        while !b.is_zero() {
+            let remainder = &a - &(&(&a / &b) * &b);
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(value: i32) -> Self {
+        BigInt::from(value as i64)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        BigInt::new(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+
+    // decimal digits, optionally after a minus sign
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match string.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, string),
+        };
+
+        if digits.is_empty() {
+            return Err(format!("Invalid integer, {}", string));
+        }
+
+        let mut magnitude = Vec::new();
+        for character in digits.chars() {
+            match character.to_digit(10) {
+                Some(digit) => multiply_add_digit(&mut magnitude, 10, digit),
+                None => return Err(format!("Invalid integer, {}", string)),
+            }
+        }
+
+        Ok(BigInt::new(negative, magnitude))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // groups of 9 decimal digits, least significant first
+        let mut magnitude = self.magnitude.clone();
+        let mut groups = Vec::new();
+        while !magnitude.is_empty() {
+            groups.push(divide_by_digit(&mut magnitude, 1_000_000_000));
+        }
+
+        let mut string = String::new();
+        if self.negative {
+            string.push('-');
+        }
+        for (index, group) in groups.iter().rev().enumerate() {
+            if index == 0 {
+                string.push_str(&group.to_string());
+            } else {
+                string.push_str(&format!("{:09}", group));
+            }
+        }
+        write!(f, "{}", string)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude)
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::new(self.negative, add_magnitudes(&self.magnitude, &rhs.magnitude));
+        }
+
+        // the signs differ, so the result has the sign of the larger magnitude
+        match compare_magnitudes(&self.magnitude, &rhs.magnitude) {
+            Ordering::Less => BigInt::new(rhs.negative, subtract_magnitudes(&rhs.magnitude, &self.magnitude)),
+            _ => BigInt::new(self.negative, subtract_magnitudes(&self.magnitude, &rhs.magnitude)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::new(self.negative != rhs.negative, multiply_magnitudes(&self.magnitude, &rhs.magnitude))
+    }
+}
+
+impl Div for &BigInt {
+    type Output = BigInt;
+
+    fn div(self, rhs: &BigInt) -> BigInt {
+        self.checked_div(rhs).expect("attempt to divide by zero")
+    }
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: BigInt) -> BigInt {
+        &self + &rhs
+    }
+}
+
+impl Sub for BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: BigInt) -> BigInt {
+        &self - &rhs
+    }
+}
+
+impl Mul for BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: BigInt) -> BigInt {
+        &self * &rhs
+    }
+}
+
+impl Div for BigInt {
+    type Output = BigInt;
+
+    fn div(self, rhs: BigInt) -> BigInt {
+        &self / &rhs
+    }
+}
@@ -0,0 +1,75 @@
+use super::BigInt;
+
+fn big(string: &str) -> BigInt {
+    string.parse().unwrap()
+}
+
+#[test]
+fn parsing_and_printing() {
+    for string in ["0", "1", "-1", "4294967295", "4294967296", "-18446744073709551616", "123456789012345678901234567890"] {
+        assert_eq!(big(string).to_string(), string);
+    }
+
+    assert_eq!(big("-0"), BigInt::zero());
+    assert_eq!(big("000123").to_string(), "123");
+    assert!("".parse::<BigInt>().is_err());
+    assert!("-".parse::<BigInt>().is_err());
+    assert!("12a".parse::<BigInt>().is_err());
+}
+
+#[test]
+fn arithmetic() {
+    let a = big("123456789012345678901234567890");
+    let b = big("-987654321098765432109876543210");
+
+    assert_eq!(&a + &b, big("-864197532086419753208641975320"));
+    assert_eq!(&a - &b, big("1111111110111111111011111111100"));
+    assert_eq!(&a * &b, big("-121932631137021795226185032733622923332237463801111263526900"));
+    assert_eq!(&b / &a, big("-8"));
+    assert_eq!(&(&a * &b) / &b, a);
+    assert_eq!(&(&a - &a) + &BigInt::zero(), BigInt::zero());
+    assert_eq!(-BigInt::zero(), BigInt::zero());
+
+    // division truncates towards zero, like i32
+    for (x, y) in [(7, 2), (-7, 2), (7, -2), (-7, -2), (1, 3), (-2147483648, -1)] {
+        assert_eq!(&BigInt::from(x as i64) / &BigInt::from(y as i64), BigInt::from(x as i64 / y as i64));
+    }
+    assert_eq!(BigInt::from(1).checked_div(&BigInt::zero()), None);
+
+    assert_eq!(BigInt::from(2).pow(100), big("1267650600228229401496703205376"));
+    assert_eq!(BigInt::from(-3).pow(3), BigInt::from(-27));
+    assert_eq!(BigInt::from(5).pow(0), BigInt::from(1));
+
+    assert_eq!(big("-12").abs(), big("12"));
+    assert_eq!(big("-12").gcd(&big("18")), big("6"));
+    assert_eq!(BigInt::zero().gcd(&big("-7")), big("7"));
+    assert_eq!((&a * &big("1000000007")).gcd(&(&b * &big("1000000007"))), big("9000000063900000006390000000630"));
+}
+
+#[test]
+fn ordering() {
+    let mut numbers = [big("10000000000000000000"), big("-5"), BigInt::zero(), big("-10000000000000000000"), big("3")];
+    numbers.sort();
+
+    let sorted: Vec<String> = numbers.iter().map(|number| number.to_string()).collect();
+    assert_eq!(sorted, ["-10000000000000000000", "-5", "0", "3", "10000000000000000000"]);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(BigInt::from(i32::MIN).to_i32(), Some(i32::MIN));
+    assert_eq!(BigInt::from(i32::MAX as i64 + 1).to_i32(), None);
+    assert_eq!(big("4294967297").wrapping_i32(), 1);
+    assert_eq!(big("-4294967297").wrapping_i32(), -1);
+    assert_eq!(big("2147483648").wrapping_i32(), i32::MIN);
+
+    assert_eq!(BigInt::from(16777217).to_f32(), 16777216.0);
+    assert_eq!(big("-123456789012345678901234567890").to_f32(), -1.2345679e29);
+    assert_eq!(BigInt::from(2).pow(128).to_f32(), f32::INFINITY);
+    // just over half way between two floats, which only the bits below the top 64 decide
+    assert_eq!((&(&BigInt::from(2).pow(100) + &BigInt::from(2).pow(76)) + &BigInt::from(1)).to_f32(), 2f32.powi(100) * (1.0 + 2f32.powi(-23)));
+
+    assert_eq!(BigInt::from_f32(-2.9), Some(BigInt::from(-2)));
+    assert_eq!(BigInt::from_f32(1e30), Some(big("1000000015047466219876688855040")));
+    assert_eq!(BigInt::from_f32(f32::INFINITY), None);
+}
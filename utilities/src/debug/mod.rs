@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
 pub struct Location {
     pub line: usize,
     pub column: usize,
@@ -75,7 +75,7 @@ impl Error {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub struct Meta<T> {
     pub value: T,
     pub location: Location
@@ -1,3 +1,5 @@
 pub mod args_parser;
+pub mod bigint;
+pub mod rational;
 pub mod files;
 pub mod debug;
\ No newline at end of file
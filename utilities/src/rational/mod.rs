@@ -0,0 +1,280 @@
+/*
+An exact rational number, used for RIMP's rational type.
+
+The numerator and denominator are bigints kept in lowest terms with a positive denominator, so every number has
+exactly one representation and the derived equality is equality of values. Multiplying or dividing by a non-zero
+rational can always be undone, which is what makes them useful in a reversible language.
+*/
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+use super::bigint::BigInt;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Rational {
+    numerator: BigInt,
+    denominator: BigInt,
+}
+
+impl Rational {
+    // none when the denominator is zero
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Option<Self> {
+        if denominator.is_zero() {
+            return None;
+        }
+
+        let divisor = numerator.gcd(&denominator);
+        let (numerator, denominator) = if denominator.is_negative() {
+            (-(&numerator / &divisor), -(&denominator / &divisor))
+        } else {
+            (&numerator / &divisor, &denominator / &divisor)
+        };
+        Some(Rational { numerator, denominator })
+    }
+
+    pub fn zero() -> Self {
+        Rational::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        &self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == BigInt::from(1)
+    }
+
+    // rounds towards zero, like converting a float to an int
+    pub fn truncate(&self) -> BigInt {
+        &self.numerator / &self.denominator
+    }
+
+    // the nearest float, infinite when it's too large
+    pub fn to_f32(&self) -> f32 {
+        if self.is_zero() {
+            return 0.0;
+        }
+
+        // scales the numerator so the quotient has at least 64 bits, then keeps whether anything was left over
+        // in the lowest bit, so that rounding the quotient rounds the whole number
+        let shift = 65 + self.denominator.bit_length() as i64 - self.numerator.bit_length() as i64;
+        let two = BigInt::from(2);
+        let (numerator, denominator) = if shift >= 0 {
+            (&self.numerator.abs() * &two.pow(shift as u32), self.denominator.clone())
+        } else {
+            (self.numerator.abs(), &self.denominator * &two.pow(-shift as u32))
+        };
+        let quotient = &numerator / &denominator;
+        let exact = &quotient * &denominator == numerator;
+        let quotient = &(&quotient * &two) + &BigInt::from(!exact as i32);
+
+        let magnitude = (quotient.to_f32() as f64 * 2f64.powi(-(shift as i32) - 1)) as f32;
+        if self.numerator.is_negative() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    // every finite float is exactly a rational, none for infinities and NaN
+    pub fn from_f32(value: f32) -> Option<Rational> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        // a float is its mantissa times a power of 2, with subnormals having no implicit leading bit
+        let bits = value.to_bits();
+        let biased = ((bits >> 23) & 0xff) as i32;
+        let (mantissa, exponent) = match biased {
+            0 => (bits & 0x7fffff, -149),
+            _ => ((bits & 0x7fffff) | 0x800000, biased - 150),
+        };
+        let mantissa = BigInt::from(mantissa as i64);
+        let mantissa = if value < 0.0 { -mantissa } else { mantissa };
+
+        let power = BigInt::from(2).pow(exponent.unsigned_abs());
+        if exponent >= 0 {
+            Some(Rational::from(&mantissa * &power))
+        } else {
+            Rational::new(mantissa, power)
+        }
+    }
+
+    // none when raising zero to a negative power
+    pub fn pow(&self, exponent: i32) -> Option<Rational> {
+        let numerator = self.numerator.pow(exponent.unsigned_abs());
+        let denominator = self.denominator.pow(exponent.unsigned_abs());
+        if exponent >= 0 {
+            Rational::new(numerator, denominator)
+        } else {
+            Rational::new(denominator, numerator)
+        }
+    }
+
+    // none when dividing by zero
+    pub fn checked_div(&self, rhs: &Rational) -> Option<Rational> {
+        Rational::new(&self.numerator * &rhs.denominator, &self.denominator * &rhs.numerator)
+    }
+}
+
+impl Default for Rational {
+    fn default() -> Self {
+        Rational { numerator: BigInt::zero(), denominator: BigInt::from(1) }
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(value: BigInt) -> Self {
+        Rational { numerator: value, denominator: BigInt::from(1) }
+    }
+}
+
+impl From<i32> for Rational {
+    fn from(value: i32) -> Self {
+        Rational::from(BigInt::from(value))
+    }
+}
+
+impl FromStr for Rational {
+    type Err = String;
+
+    // an integer, a decimal like 0.25 or a fraction like -1/3
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid rational, {}", string);
+
+        if let Some((numerator, denominator)) = string.split_once('/') {
+            let numerator = numerator.parse::<BigInt>().map_err(|_| invalid())?;
+            let denominator = denominator.parse::<BigInt>().map_err(|_| invalid())?;
+            return Rational::new(numerator, denominator).ok_or_else(invalid);
+        }
+
+        if let Some((whole, fraction)) = string.split_once('.') {
+            if fraction.is_empty() || fraction.starts_with('-') {
+                return Err(invalid());
+            }
+            let negative = whole.starts_with('-');
+            let digits = format!("{}{}", whole, fraction).parse::<BigInt>().map_err(|_| invalid())?;
+            let digits = if negative && !digits.is_negative() { -digits } else { digits };
+            return Rational::new(digits, BigInt::from(10).pow(fraction.len() as u32)).ok_or_else(invalid);
+        }
+
+        string.parse::<BigInt>().map(Rational::from).map_err(|_| invalid())
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the denominators are positive, so cross multiplying keeps the order
+        (&self.numerator * &other.denominator).cmp(&(&other.numerator * &self.denominator))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational { numerator: -&self.numerator, denominator: self.denominator.clone() }
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        -&self
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: &Rational) -> Rational {
+        let numerator = &(&self.numerator * &rhs.denominator) + &(&rhs.numerator * &self.denominator);
+        Rational::new(numerator, &self.denominator * &rhs.denominator).unwrap()
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: &Rational) -> Rational {
+        self + &-rhs
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.numerator * &rhs.numerator, &self.denominator * &rhs.denominator).unwrap()
+    }
+}
+
+impl Div for &Rational {
+    type Output = Rational;
+
+    fn div(self, rhs: &Rational) -> Rational {
+        self.checked_div(rhs).expect("attempt to divide by zero")
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: Rational) -> Rational {
+        &self + &rhs
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: Rational) -> Rational {
+        &self - &rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: Rational) -> Rational {
+        &self * &rhs
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, rhs: Rational) -> Rational {
+        &self / &rhs
+    }
+}
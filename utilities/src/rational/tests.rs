@@ -0,0 +1,72 @@
+use super::Rational;
+use super::super::bigint::BigInt;
+
+fn rational(string: &str) -> Rational {
+    string.parse().unwrap()
+}
+
+#[test]
+fn parsing_and_printing() {
+    for string in ["0", "-1", "1/3", "-22/7", "123456789012345678901234567890/11"] {
+        assert_eq!(rational(string).to_string(), string);
+    }
+
+    // always in lowest terms, with a positive denominator
+    assert_eq!(rational("6/-4").to_string(), "-3/2");
+    assert_eq!(rational("0/5"), Rational::zero());
+    assert_eq!(rational("0.25").to_string(), "1/4");
+    assert_eq!(rational("-1.5").to_string(), "-3/2");
+    assert_eq!(rational("-0.5").to_string(), "-1/2");
+    assert!("1/0".parse::<Rational>().is_err());
+    assert!("1.".parse::<Rational>().is_err());
+    assert!("1/2/3".parse::<Rational>().is_err());
+}
+
+#[test]
+fn arithmetic() {
+    let third = rational("1/3");
+    let tenth = rational("0.1");
+
+    assert_eq!(&third + &third, rational("2/3"));
+    assert_eq!(&(&tenth + &tenth) + &tenth, rational("0.3"));
+    assert_eq!(&third - &tenth, rational("7/30"));
+    assert_eq!(&third * &rational("3"), Rational::from(1));
+    assert_eq!(&tenth / &third, rational("3/10"));
+    assert_eq!(-&third, rational("-1/3"));
+    assert_eq!(third.checked_div(&Rational::zero()), None);
+
+    // multiplying and dividing by the same rational gets back exactly where it started
+    let mut value = Rational::from(7);
+    for _ in 0..50 {
+        value = &value * &rational("-7/3");
+    }
+    for _ in 0..50 {
+        value = &value / &rational("-7/3");
+    }
+    assert_eq!(value, Rational::from(7));
+
+    assert_eq!(rational("2/3").pow(3), Some(rational("8/27")));
+    assert_eq!(rational("-2/3").pow(-2), Some(rational("9/4")));
+    assert_eq!(Rational::zero().pow(-1), None);
+
+    let mut numbers = [rational("1/2"), rational("-3/4"), rational("1/3"), Rational::zero()];
+    numbers.sort();
+    assert_eq!(numbers, [rational("-3/4"), Rational::zero(), rational("1/3"), rational("1/2")]);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(rational("-7/2").truncate(), BigInt::from(-3));
+    assert_eq!(rational("7/2").truncate(), BigInt::from(3));
+    assert!(rational("4/2").is_integer());
+
+    assert_eq!(rational("1/3").to_f32(), 1.0 / 3.0);
+    assert_eq!(rational("-0.1").to_f32(), -0.1);
+    assert_eq!(rational("123456789012345678901234567890/11").to_f32(), 1.1223345e28);
+    assert_eq!(Rational::from(BigInt::from(2).pow(128)).to_f32(), f32::INFINITY);
+
+    assert_eq!(Rational::from_f32(0.75), Some(rational("3/4")));
+    assert_eq!(Rational::from_f32(-3.0), Some(Rational::from(-3)));
+    assert_eq!(Rational::from_f32(0.1), Some(rational("13421773/134217728")));
+    assert_eq!(Rational::from_f32(f32::NAN), None);
+}